@@ -0,0 +1,54 @@
+use large_table::{RowTable, MMapTable, TableOperations, TableSlice, Row, Value};
+use large_table::testdata::{CsvGenerator, ColumnKind};
+
+use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId};
+
+// runs the same operations against each backend, on the same generated file
+fn criterion_benchmark(c: &mut Criterion) {
+    let path = std::env::temp_dir().join("large_table_backend_bench.csv");
+
+    CsvGenerator::new(2_000)
+        .column("id", ColumnKind::Integer { min: 0, max: 1_000_000 })
+        .column("symbol", ColumnKind::String { cardinality: 10 })
+        .column("price", ColumnKind::Float { min: 1.0, max: 100.0 })
+        .column("date", ColumnKind::Date)
+        .write_to_path(&path)
+        .unwrap();
+
+    let row_table = RowTable::from_csv(&path).unwrap();
+    let mmap_table = MMapTable::new(&path).unwrap();
+    let symbol = row_table.get(0).unwrap().get("symbol");
+
+    let mut group = c.benchmark_group("Backend");
+
+    group.sample_size(10);
+
+    group.bench_function(BenchmarkId::new("load", "RowTable"), |b| b.iter(|| RowTable::from_csv(&path).unwrap()));
+    group.bench_function(BenchmarkId::new("load", "MMapTable"), |b| b.iter(|| MMapTable::new(&path).unwrap()));
+
+    group.bench_function(BenchmarkId::new("sum", "RowTable"), |b| b.iter(|| sum(&row_table)));
+    group.bench_function(BenchmarkId::new("sum", "MMapTable"), |b| b.iter(|| sum(&mmap_table)));
+
+    let price = row_table.column_position("price").unwrap();
+
+    group.bench_function(BenchmarkId::new("par_map sum", "RowTable"), |b| b.iter(|| row_table.par_map(|row| row[price].try_as_float().unwrap_or(0.0)).into_iter().sum::<f64>()));
+    group.bench_function(BenchmarkId::new("par_map sum", "MMapTable"), |b| b.iter(|| mmap_table.par_map(|row| row[price].try_as_float().unwrap_or(0.0)).unwrap().into_iter().sum::<f64>()));
+
+    group.bench_function(BenchmarkId::new("filter", "RowTable"), |b| b.iter(|| row_table.filter("symbol", &symbol).unwrap().len()));
+    group.bench_function(BenchmarkId::new("filter", "MMapTable"), |b| b.iter(|| mmap_table.filter("symbol", &symbol).unwrap().len()));
+
+    group.bench_function(BenchmarkId::new("group_by", "RowTable"), |b| b.iter(|| row_table.group_by("symbol").unwrap().len()));
+    group.bench_function(BenchmarkId::new("group_by", "MMapTable"), |b| b.iter(|| mmap_table.group_by("symbol").unwrap().len()));
+
+    group.bench_function(BenchmarkId::new("sort", "RowTable"), |b| b.iter(|| row_table.filter_by(|_| true).unwrap().sort(&["price"]).unwrap().len()));
+    group.bench_function(BenchmarkId::new("sort", "MMapTable"), |b| b.iter(|| mmap_table.filter_by(|_| true).unwrap().sort(&["price"]).unwrap().len()));
+
+    group.finish();
+}
+
+fn sum<T: TableOperations>(table :&T) -> f64 {
+    table.iter().map(|row| row.get("price")).filter_map(|v :Value| v.try_as_float()).sum()
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
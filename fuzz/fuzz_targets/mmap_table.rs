@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use large_table::{MMapTable, TableOperations, Row};
+
+// Maps arbitrary bytes as a CSV file, then reads every field. Errors are expected, panics are bugs.
+fuzz_target!(|data: &[u8]| {
+    let path = std::env::temp_dir().join(format!("large_table_fuzz_{}.csv", std::process::id()));
+
+    std::fs::write(&path, data).unwrap();
+
+    if let Ok(table) = MMapTable::new(&path) {
+        for row in table.iter() {
+            for column in row.columns() {
+                let _ = row.try_get(&column);
+            }
+        }
+    }
+});
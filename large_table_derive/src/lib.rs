@@ -0,0 +1,69 @@
+//! `#[derive(FromRow)]` for [large_table](https://docs.rs/large_table), enabled with its `derive` feature.
+//!
+//! Each field of the struct is read from the column with the same name, converted with `FromValue`:
+//! ```ignore
+//! #[derive(FromRow)]
+//! struct Trade {
+//!     symbol: String,
+//!     #[from_row(rename = "Close Price")]
+//!     close: f64,
+//!     volume: Option<i64>
+//! }
+//! ```
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+#[proc_macro_derive(FromRow, attributes(from_row))]
+pub fn derive_from_row(input :TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match from_row(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+fn from_row(input :&DeriveInput) -> Result<TokenStream2, syn::Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "FromRow can only be derived for structs with named fields"))
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "FromRow can only be derived for structs"))
+    };
+
+    let mut field_values = Vec::new();
+
+    for field in fields.iter() {
+        let ident = field.ident.as_ref().unwrap();
+        let mut column = ident.to_string().trim_start_matches("r#").to_string();
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("from_row")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    column = meta.value()?.parse::<LitStr>()?.value();
+                    Ok( () )
+                } else {
+                    Err(meta.error("expected `rename = \"column\"`"))
+                }
+            })?;
+        }
+
+        field_values.push(quote! { #ident: ::large_table::row_field(row, #column)? });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::large_table::FromRow for #name #ty_generics #where_clause {
+            fn from_row<R: ::large_table::Row + ?Sized>(row :&R) -> ::core::result::Result<Self, ::large_table::TableError> {
+                ::core::result::Result::Ok(#name { #(#field_values),* })
+            }
+        }
+    })
+}
@@ -0,0 +1,84 @@
+//! Anonymizers for the values of a column, used by [`Table::anonymize`](trait.Table.html#method.anonymize), so a table
+//! can be shared without its original values.
+use chrono::{Datelike, NaiveDate};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use sha2::{Digest, Sha256};
+
+use crate::{TableError, Value};
+
+/// How the values of a column are anonymized; empty values are always left empty
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anonymizer {
+    /// Replaces each value with the hex SHA-256 of the salt followed by the value, so equal values stay equal, and can
+    /// still be joined on, but can't be recovered without the salt
+    Hash(String),
+    /// Moves the values between rows at random, keeping the distribution of the column but not which row has which value
+    Shuffle,
+    /// Adds random noise of up to plus or minus the amount to each number; integers are rounded to stay integers
+    Jitter(f64),
+    /// Replaces dates and date-times with the first day of their month
+    Month
+}
+
+/// The values of a column anonymized by `anonymizer`, in the same order; `seed` is used by the random anonymizers.
+pub(crate) fn anonymized(column :&str, values :Vec<Value>, anonymizer :&Anonymizer, seed :u64) -> Result<Vec<Value>, TableError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let invalid = |value :&Value| {
+        let err_str = format!("Can't anonymize value in column {} with {:?}: {}", column, anonymizer, value);
+        Err(TableError::new(err_str.as_str()))
+    };
+
+    match anonymizer {
+        Anonymizer::Hash(salt) => Ok(values.iter().map(|v| match v {
+            Value::Empty => Value::Empty,
+            v => {
+                let digest = Sha256::new().chain_update(salt.as_bytes()).chain_update(v.as_string().as_bytes()).finalize();
+
+                Value::String(digest.iter().map(|b| format!("{:02x}", b)).collect())
+            }
+        }).collect()),
+        Anonymizer::Shuffle => {
+            // only the non-empty values are moved, so the empty rows stay empty
+            let positions = (0..values.len()).filter(|&i| values[i] != Value::Empty).collect::<Vec<_>>();
+            let mut shuffled = positions.clone();
+            let mut values = values;
+
+            shuffled.shuffle(&mut rng);
+
+            let moved = shuffled.iter().map(|&i| values[i].clone()).collect::<Vec<_>>();
+
+            for (i, value) in positions.into_iter().zip(moved) {
+                values[i] = value;
+            }
+
+            Ok(values)
+        },
+        Anonymizer::Jitter(amount) if !amount.is_finite() || *amount < 0.0 => {
+            let err_str = format!("Invalid jitter amount: {}", amount);
+            Err(TableError::new(err_str.as_str()))
+        },
+        Anonymizer::Jitter(amount) => values.iter().map(|v| {
+            let noise = if *amount > 0.0 { rng.gen_range(-amount, amount) } else { 0.0 };
+
+            match v {
+                Value::Empty => Ok(Value::Empty),
+                Value::Integer(i) => Ok(Value::Integer((*i as f64 + noise).round() as i64)),
+                Value::Float(f) => Ok(Value::Float((f.into_inner() + noise).into())),
+                v => invalid(v)
+            }
+        }).collect(),
+        Anonymizer::Month => values.iter().map(|v| match v {
+            Value::Empty => Ok(Value::Empty),
+            Value::Date(d) => Ok(Value::Date(first_of_month(*d))),
+            Value::DateTime(dt) => Ok(Value::Date(first_of_month(dt.date()))),
+            v => invalid(v)
+        }).collect()
+    }
+}
+
+fn first_of_month(date :NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
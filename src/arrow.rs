@@ -0,0 +1,211 @@
+//! Conversion between tables and Apache Arrow `RecordBatch`es, enabled with the `arrow` feature.
+//!
+//! Columns are converted by their [`dtypes`](trait.TableOperations.html#method.dtypes), inferred from every row
+//! instead of a sample:
+//! ```text
+//! Integer      Int64
+//! Float        Float64 (as is Number, a mix of integers and floats)
+//! Boolean      Boolean
+//! Date         Date32
+//! DateTime     Timestamp(Microsecond)
+//! Time         Time64(Microsecond)
+//! Duration     Duration(Microsecond)
+//! Empty        Null
+//! String       Utf8 (as is any mix of types)
+//! ```
+//! Empty values are nulls. When reading batches, every integer, float, date, time, and string type is supported;
+//! timestamps with a time zone are read in UTC.
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use arrow_array::{Array, ArrayRef, ArrowPrimitiveType, BooleanArray, Date32Array, DurationMicrosecondArray, Float64Array, Int64Array, NullArray, PrimitiveArray, RecordBatch, StringArray, Time64MicrosecondArray, TimestampMicrosecondArray};
+use arrow_array::cast::AsArray;
+use arrow_array::types::*;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{NaiveDate, Timelike};
+
+use crate::{schema, Row, TableError, TableOperations, Value, ValueType};
+
+/// The Arrow type of a column of values of `value_type`
+fn data_type(value_type :&ValueType) -> DataType {
+    match value_type {
+        ValueType::Integer | ValueType::IntegerFormat(_) => DataType::Int64,
+        ValueType::Float | ValueType::FloatFormat(_) | ValueType::Number | ValueType::NumberFormat(_) |
+            ValueType::Currency | ValueType::CurrencyFormat(_) | ValueType::Percent | ValueType::PercentFormat(_) => DataType::Float64,
+        ValueType::Boolean => DataType::Boolean,
+        ValueType::Date | ValueType::DateFormat(_) => DataType::Date32,
+        ValueType::DateTime | ValueType::DateTimeFormat(_) => DataType::Timestamp(TimeUnit::Microsecond, None),
+        ValueType::Time | ValueType::TimeFormat(_) => DataType::Time64(TimeUnit::Microsecond),
+        ValueType::Duration => DataType::Duration(TimeUnit::Microsecond),
+        ValueType::Empty => DataType::Null,
+        ValueType::String => DataType::Utf8
+    }
+}
+
+fn unix_epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+/// Builds the Arrow array of `data_type` from `values`
+fn to_array(data_type :&DataType, values :&[Value]) -> Result<ArrayRef, TableError> {
+    let non_empty = |v :&Value| if *v == Value::Empty { None } else { Some(v.clone()) };
+
+    Ok(match data_type {
+        DataType::Int64 => Arc::new(values.iter().map(|v| non_empty(v).map(|v| v.as_integer())).collect::<Int64Array>()),
+        DataType::Float64 => Arc::new(values.iter().map(|v| non_empty(v).map(|v| v.as_float())).collect::<Float64Array>()),
+        DataType::Boolean => Arc::new(values.iter().map(|v| non_empty(v).map(|v| v.as_boolean())).collect::<BooleanArray>()),
+        DataType::Date32 => Arc::new(values.iter().map(|v| non_empty(v).map(|v| (v.as_date() - unix_epoch()).num_days() as i32)).collect::<Date32Array>()),
+        DataType::Timestamp(_, _) => Arc::new(values.iter().map(|v| non_empty(v).map(|v| v.as_date_time().and_utc().timestamp_micros())).collect::<TimestampMicrosecondArray>()),
+        DataType::Time64(_) => Arc::new(values.iter().map(|v| non_empty(v).map(|v| {
+            let time = v.as_time();
+
+            time.num_seconds_from_midnight() as i64 * 1_000_000 + time.nanosecond() as i64 / 1_000
+        })).collect::<Time64MicrosecondArray>()),
+        DataType::Duration(_) => Arc::new(values.iter().map(|v| match non_empty(v) {
+            Some(v) => v.as_duration().num_microseconds().map(Some).ok_or_else(|| {
+                let err_str = format!("Duration too large for Arrow: {}", v);
+                TableError::new(err_str.as_str())
+            }),
+            None => Ok(None)
+        }).collect::<Result<DurationMicrosecondArray, _>>()?),
+        DataType::Null => Arc::new(NullArray::new(values.len())),
+        _ => Arc::new(values.iter().map(|v| non_empty(v).map(|v| v.as_string())).collect::<StringArray>())
+    })
+}
+
+/// Converts a table to record batches of up to `batch_size` rows; an empty table is a single empty batch.
+pub(crate) fn to_batches<T: TableOperations + ?Sized>(table :&T, batch_size :usize) -> Result<Vec<RecordBatch>, TableError> {
+    if batch_size == 0 {
+        return Err(TableError::new("Batch size must be greater than 0"));
+    }
+
+    let types = schema::column_types(table, None).into_iter().map(|(c, t)| (c, data_type(&t))).collect::<Vec<_>>();
+    let schema = Arc::new(Schema::new(types.iter().map(|(c, t)| Field::new(c.as_str(), t.clone(), true)).collect::<Vec<_>>()));
+
+    let to_batch = |rows :&[Vec<Value>]| -> Result<RecordBatch, TableError> {
+        let arrays = types.iter().enumerate().map(|(i, (_, t))| {
+            to_array(t, rows.iter().map(|row| row[i].clone()).collect::<Vec<_>>().as_slice())
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        RecordBatch::try_new(schema.clone(), arrays).map_err(|e| TableError::new(e.to_string().as_str()))
+    };
+
+    let mut batches = Vec::new();
+    let mut rows = Vec::with_capacity(batch_size);
+
+    for row in table.iter() {
+        rows.push((0..types.len()).map(|i| row.try_at(i)).collect::<Result<Vec<_>, _>>()?);
+
+        if rows.len() == batch_size {
+            batches.push(to_batch(&rows)?);
+            rows.clear();
+        }
+    }
+
+    if !rows.is_empty() || batches.is_empty() {
+        batches.push(to_batch(&rows)?);
+    }
+
+    Ok(batches)
+}
+
+/// Reads the values of a temporal array with `f`, such as `PrimitiveArray::value_as_date`
+fn temporal<T, F>(array :&dyn Array, f :F) -> Vec<Option<Value>>
+    where T: ArrowPrimitiveType, F: Fn(&PrimitiveArray<T>, usize) -> Option<Value>
+{
+    let array = array.as_primitive::<T>();
+
+    (0..array.len()).map(|i| f(array, i)).collect()
+}
+
+/// Reads the values of a numeric array, converted to a `Value` with `f`
+fn numeric<T, F>(array :&dyn Array, f :F) -> Vec<Option<Value>>
+    where T: ArrowPrimitiveType, F: Fn(T::Native) -> Option<Value>
+{
+    let array = array.as_primitive::<T>();
+
+    (0..array.len()).map(|i| f(array.value(i))).collect()
+}
+
+/// Converts an Arrow array to values, with `None` where a value can't be represented
+fn from_array(array :&dyn Array) -> Result<Vec<Option<Value>>, TableError> {
+    let int = |v :i64| Some(Value::Integer(v));
+    let float = |v :f64| Some(Value::Float(v.into()));
+
+    let values = match array.data_type() {
+        DataType::Null => vec![Some(Value::Empty); array.len()],
+        DataType::Boolean => array.as_boolean().iter().map(|v| Some(v.map(Value::Boolean).unwrap_or(Value::Empty))).collect(),
+        DataType::Int8 => numeric::<Int8Type, _>(array, |v| int(v as i64)),
+        DataType::Int16 => numeric::<Int16Type, _>(array, |v| int(v as i64)),
+        DataType::Int32 => numeric::<Int32Type, _>(array, |v| int(v as i64)),
+        DataType::Int64 => numeric::<Int64Type, _>(array, int),
+        DataType::UInt8 => numeric::<UInt8Type, _>(array, |v| int(v as i64)),
+        DataType::UInt16 => numeric::<UInt16Type, _>(array, |v| int(v as i64)),
+        DataType::UInt32 => numeric::<UInt32Type, _>(array, |v| int(v as i64)),
+        DataType::UInt64 => numeric::<UInt64Type, _>(array, |v| i64::try_from(v).ok().and_then(int)),
+        DataType::Float16 => numeric::<Float16Type, _>(array, |v| float(v.to_f64())),
+        DataType::Float32 => numeric::<Float32Type, _>(array, |v| float(v as f64)),
+        DataType::Float64 => numeric::<Float64Type, _>(array, float),
+        DataType::Utf8 => array.as_string::<i32>().iter().map(|v| Some(v.map(|s| Value::String(s.to_string())).unwrap_or(Value::Empty))).collect(),
+        DataType::LargeUtf8 => array.as_string::<i64>().iter().map(|v| Some(v.map(|s| Value::String(s.to_string())).unwrap_or(Value::Empty))).collect(),
+        DataType::Utf8View => array.as_string_view().iter().map(|v| Some(v.map(|s| Value::String(s.to_string())).unwrap_or(Value::Empty))).collect(),
+        DataType::Date32 => temporal::<Date32Type, _>(array, |a, i| a.value_as_date(i).map(Value::Date)),
+        DataType::Date64 => temporal::<Date64Type, _>(array, |a, i| a.value_as_date(i).map(Value::Date)),
+        DataType::Timestamp(TimeUnit::Second, _) => temporal::<TimestampSecondType, _>(array, |a, i| a.value_as_datetime(i).map(Value::DateTime)),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => temporal::<TimestampMillisecondType, _>(array, |a, i| a.value_as_datetime(i).map(Value::DateTime)),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => temporal::<TimestampMicrosecondType, _>(array, |a, i| a.value_as_datetime(i).map(Value::DateTime)),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => temporal::<TimestampNanosecondType, _>(array, |a, i| a.value_as_datetime(i).map(Value::DateTime)),
+        DataType::Time32(TimeUnit::Second) => temporal::<Time32SecondType, _>(array, |a, i| a.value_as_time(i).map(Value::Time)),
+        DataType::Time32(TimeUnit::Millisecond) => temporal::<Time32MillisecondType, _>(array, |a, i| a.value_as_time(i).map(Value::Time)),
+        DataType::Time64(TimeUnit::Microsecond) => temporal::<Time64MicrosecondType, _>(array, |a, i| a.value_as_time(i).map(Value::Time)),
+        DataType::Time64(TimeUnit::Nanosecond) => temporal::<Time64NanosecondType, _>(array, |a, i| a.value_as_time(i).map(Value::Time)),
+        DataType::Duration(TimeUnit::Second) => temporal::<DurationSecondType, _>(array, |a, i| a.value_as_duration(i).map(Value::Duration)),
+        DataType::Duration(TimeUnit::Millisecond) => temporal::<DurationMillisecondType, _>(array, |a, i| a.value_as_duration(i).map(Value::Duration)),
+        DataType::Duration(TimeUnit::Microsecond) => temporal::<DurationMicrosecondType, _>(array, |a, i| a.value_as_duration(i).map(Value::Duration)),
+        DataType::Duration(TimeUnit::Nanosecond) => temporal::<DurationNanosecondType, _>(array, |a, i| a.value_as_duration(i).map(Value::Duration)),
+        t => {
+            let err_str = format!("Unsupported Arrow type: {}", t);
+            return Err(TableError::new(err_str.as_str()));
+        }
+    };
+
+    // nulls are read as whatever the underlying buffer holds, so replace them
+    Ok(values.into_iter().enumerate().map(|(i, v)| if array.is_null(i) { Some(Value::Empty) } else { v }).collect())
+}
+
+/// Converts record batches, which must all have the same columns, to the columns and rows of a table.
+pub(crate) fn from_batches(batches :&[RecordBatch]) -> Result<(Vec<String>, Vec<Vec<Value>>), TableError> {
+    let columns = match batches.first() {
+        Some(batch) => batch.schema().fields().iter().map(|f| f.name().clone()).collect::<Vec<_>>(),
+        None => return Err(TableError::new("At least one record batch is needed for the columns"))
+    };
+
+    let mut rows = Vec::new();
+
+    for batch in batches {
+        let batch_columns = batch.schema().fields().iter().map(|f| f.name().clone()).collect::<Vec<_>>();
+
+        if batch_columns != columns {
+            let err_str = format!("Columns don't match between record batches: {:?} != {:?}", columns, batch_columns);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let arrays = batch.columns().iter().map(|a| from_array(a.as_ref())).collect::<Result<Vec<_>, _>>()?;
+        let start = rows.len();
+
+        rows.extend((0..batch.num_rows()).map(|_| Vec::with_capacity(columns.len())));
+
+        for (column, values) in columns.iter().zip(arrays) {
+            for (i, value) in values.into_iter().enumerate() {
+                let value = value.ok_or_else(|| {
+                    let err_str = format!("Value out of range in column {}, row {}", column, start + i);
+                    TableError::new(err_str.as_str())
+                })?;
+
+                rows[start + i].push(value);
+            }
+        }
+    }
+
+    Ok((columns, rows))
+}
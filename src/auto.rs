@@ -0,0 +1,110 @@
+//! Picks a table backend for a CSV file.
+//!
+//! `RowTable` parses every value into memory, which is fastest once loaded but needs several times the size of the
+//! file in RAM. `MMapTable` only indexes the rows and parses values as they're read, so it works for files larger
+//! than memory, but can't add rows or columns.
+use std::path::Path;
+use std::io::Error as IOError;
+
+use crate::{RowTable, MMapTable};
+
+/// Rough number of bytes of memory a `RowTable` uses for each byte of CSV
+const ROW_TABLE_EXPANSION :u64 = 6;
+
+/// Largest file loaded into a `RowTable` when the available memory is unknown
+const DEFAULT_ROW_TABLE_LIMIT :u64 = 512 * 1024 * 1024;
+
+/// How the table will be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intent {
+    /// Only reading, or updating values in place
+    ReadOnly,
+    /// Appending rows or adding columns, which only a `RowTable` supports
+    Mutable
+}
+
+/// The backends `open_auto` chooses between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    RowTable,
+    MMapTable
+}
+
+/// A table opened by `open_auto`
+pub enum AutoTable {
+    Row(RowTable),
+    MMap(MMapTable)
+}
+
+impl AutoTable {
+    pub fn backend(&self) -> Backend {
+        match self {
+            AutoTable::Row(_) => Backend::RowTable,
+            AutoTable::MMap(_) => Backend::MMapTable
+        }
+    }
+}
+
+/// Chooses the backend `open_auto` would use for a file.
+///
+/// A file is loaded into a `RowTable` when it's expected to fit in half of the available memory, or always when
+/// `intent` is `Intent::Mutable`; otherwise it's memory-mapped.
+pub fn choose_backend<P: AsRef<Path>>(path :P, intent :Intent) -> Result<Backend, IOError> {
+    let file_size = std::fs::metadata(path)?.len();
+
+    if intent == Intent::Mutable {
+        return Ok(Backend::RowTable);
+    }
+
+    let limit = available_memory().map(|m| m / 2 / ROW_TABLE_EXPANSION).unwrap_or(DEFAULT_ROW_TABLE_LIMIT);
+
+    debug!("File size: {} RowTable limit: {}", file_size, limit);
+
+    if file_size <= limit {
+        Ok(Backend::RowTable)
+    } else {
+        Ok(Backend::MMapTable)
+    }
+}
+
+/// Opens a CSV file with the backend picked by `choose_backend`.
+pub fn open_auto<P: AsRef<Path>>(path :P, intent :Intent) -> Result<AutoTable, IOError> {
+    match choose_backend(&path, intent)? {
+        Backend::RowTable => Ok(AutoTable::Row(RowTable::from_csv(path)?)),
+        Backend::MMapTable => Ok(AutoTable::MMap(MMapTable::new(path)?))
+    }
+}
+
+/// Memory available for new allocations, in bytes, if the platform reports it
+#[cfg(target_os = "linux")]
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+
+    // the line looks like: MemAvailable:   12345678 kB
+    let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+
+    Some(kb * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn available_memory() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use crate::auto::{choose_backend, open_auto, Intent, Backend};
+
+    #[test]
+    fn open() {
+        let path = fixtures::copy("stocks.csv");
+
+        assert_eq!(Backend::RowTable, choose_backend(&path, Intent::ReadOnly).unwrap());
+        assert_eq!(Backend::RowTable, choose_backend(&path, Intent::Mutable).unwrap());
+        assert_eq!(Backend::RowTable, open_auto(&path, Intent::ReadOnly).unwrap().backend());
+
+        assert!(open_auto(fixtures::temp_dir().join("missing.csv"), Intent::ReadOnly).is_err());
+    }
+}
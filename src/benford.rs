@@ -0,0 +1,112 @@
+//! The distribution of the leading digits of a column, compared to Benford's law.
+use std::fmt::{Display, Formatter, Error as FmtError};
+
+use crate::{parallel, stats, TableOperations, TableError};
+
+/// The chi-square statistic, with 8 degrees of freedom, above which the digits don't follow Benford's law at the 5%
+/// significance level
+const CHI_SQUARE_CRITICAL :f64 = 15.507;
+
+/// How often each leading digit, 1 to 9, appears in a column of numbers, and how far that is from Benford's law,
+/// created with [`TableOperations::digit_distribution`](trait.TableOperations.html#method.digit_distribution).
+///
+/// Many naturally occurring amounts, such as invoices or populations, start with a 1 about 30% of the time, and a 9
+/// less than 5%; made up numbers tend to be spread more evenly, so a large chi-square statistic is worth a look in an
+/// audit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigitDistribution {
+    counts: [usize; 9]  // counts[d - 1] is the number of values starting with d
+}
+
+impl DigitDistribution {
+    pub(crate) fn new<T: TableOperations + ?Sized>(table :&T, column :&str) -> Result<Self, TableError> {
+        let values = stats::numeric_values(table, column)?;
+        let mut counts = [0; 9];
+
+        for digit in parallel::map(&values, |v| leading_digit(*v)).into_iter().flatten() {
+            counts[digit as usize - 1] += 1;
+        }
+
+        Ok(DigitDistribution { counts })
+    }
+
+    /// The number of values with a leading digit; zeros, and values that aren't finite, don't have one
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// The number of values starting with `digit`, from 1 to 9
+    pub fn count(&self, digit :u8) -> usize {
+        self.counts.get((digit as usize).wrapping_sub(1)).cloned().unwrap_or(0)
+    }
+
+    /// The fraction of the values starting with `digit`, from 1 to 9
+    pub fn observed(&self, digit :u8) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.count(digit) as f64 / total as f64
+        }
+    }
+
+    /// The fraction of values starting with `digit` expected by Benford's law, `log10(1 + 1/digit)`
+    pub fn expected(digit :u8) -> f64 {
+        match digit {
+            1..=9 => (1.0 + 1.0 / digit as f64).log10(),
+            _ => 0.0
+        }
+    }
+
+    /// Pearson's chi-square statistic of the counts against Benford's law, with 8 degrees of freedom; 0 without values
+    pub fn chi_square(&self) -> f64 {
+        let total = self.total() as f64;
+
+        if total == 0.0 {
+            return 0.0;
+        }
+
+        (1..=9).map(|d| {
+            let expected = total * DigitDistribution::expected(d);
+
+            (self.count(d) as f64 - expected).powi(2) / expected
+        }).sum()
+    }
+
+    /// Whether the digits are consistent with Benford's law at the 5% significance level
+    pub fn conforms(&self) -> bool {
+        self.chi_square() < CHI_SQUARE_CRITICAL
+    }
+}
+
+/// The first significant digit of a number, or `None` for 0 and values that aren't finite
+fn leading_digit(value :f64) -> Option<u8> {
+    if value == 0.0 || !value.is_finite() {
+        return None;
+    }
+
+    // scientific notation always starts with the first significant digit
+    format!("{:e}", value.abs()).bytes().next().map(|b| b - b'0')
+}
+
+impl Display for DigitDistribution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        writeln!(f, "digit  {:>10}  {:>8}  {:>8}", "count", "observed", "benford")?;
+
+        for d in 1..=9 {
+            writeln!(f, "{:>5}  {:>10}  {:>8.4}  {:>8.4}", d, self.count(d), self.observed(d), DigitDistribution::expected(d))?;
+        }
+
+        writeln!(f, "chi-square: {:.3} ({})", self.chi_square(), if self.conforms() { "conforms" } else { "doesn't conform" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{leading_digit, DigitDistribution};
+
+    #[test]
+    fn leading_digits() {
+        assert_eq!(vec![Some(1), Some(9), Some(1), Some(4), Some(1), None, None], [1000.0, 0.0009, -12.5, 4.0, 1e-300, 0.0, f64::NAN].iter().map(|v| leading_digit(*v)).collect::<Vec<_>>());
+        assert!(((1..=9).map(DigitDistribution::expected).sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(0.0, DigitDistribution::expected(0));
+    }
+}
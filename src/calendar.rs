@@ -0,0 +1,181 @@
+//! Business-day calendars, for moving dates by trading days and resampling daily data.
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use crate::{TableError, Value};
+#[cfg(feature = "rowtable")]
+use crate::{RowTable, TableOperations, Row};
+
+/// The days that aren't business days: the weekend, Saturday and Sunday unless set, and a list of holidays.
+/// ```
+/// use chrono::NaiveDate;
+/// use large_table::Calendar;
+///
+/// let nyse = Calendar::new().holidays(&[NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()]);
+///
+/// assert!(!nyse.is_business_day(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()));
+/// assert_eq!(Some(NaiveDate::from_ymd_opt(2020, 1, 6).unwrap()), nyse.add_business_days(NaiveDate::from_ymd_opt(2019, 12, 31).unwrap(), 3));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calendar {
+    weekend: [bool; 7],  // by the number of days from Monday
+    holidays: BTreeSet<NaiveDate>
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Calendar { weekend: [false, false, false, false, false, true, true], holidays: BTreeSet::new() }
+    }
+}
+
+impl Calendar {
+    pub fn new() -> Self {
+        Calendar::default()
+    }
+
+    /// The days of the week that aren't business days, instead of Saturday and Sunday.
+    pub fn weekend(mut self, days :&[Weekday]) -> Self {
+        self.weekend = [false; 7];
+
+        for day in days {
+            self.weekend[day.num_days_from_monday() as usize] = true;
+        }
+
+        self
+    }
+
+    /// Adds `dates` to the holidays, which aren't business days.
+    pub fn holidays(mut self, dates :&[NaiveDate]) -> Self {
+        self.holidays.extend(dates.iter().cloned());
+        self
+    }
+
+    pub fn is_business_day(&self, date :NaiveDate) -> bool {
+        !self.weekend[date.weekday().num_days_from_monday() as usize] && !self.holidays.contains(&date)
+    }
+
+    /// Moves `date` forward to the next business day, unless it's one.
+    pub fn roll_forward(&self, date :NaiveDate) -> Option<NaiveDate> {
+        self.add_business_days(date, 0)
+    }
+
+    /// Moves `date` `n` business days, back when `n` is negative, after moving it forward to the next business day
+    /// when it isn't one; so the Saturday before a Monday plus 0 is the Monday, and minus 1 is the Friday.
+    ///
+    /// Returns `None` when the week has no business days, or the date would be out of range.
+    pub fn add_business_days(&self, date :NaiveDate, n :i64) -> Option<NaiveDate> {
+        if self.weekend.iter().all(|&w| w) {
+            return None;
+        }
+
+        let mut date = date;
+
+        while !self.is_business_day(date) {
+            date = date.succ_opt()?;
+        }
+
+        let step = if n < 0 { Duration::days(-1) } else { Duration::days(1) };
+
+        for _ in 0..n.unsigned_abs() {
+            date = date.checked_add_signed(step)?;
+
+            while !self.is_business_day(date) {
+                date = date.checked_add_signed(step)?;
+            }
+        }
+
+        Some(date)
+    }
+
+    /// The business days from `start` to `end`, both included.
+    pub fn business_days(&self, start :NaiveDate, end :NaiveDate) -> Vec<NaiveDate> {
+        start.iter_days().take_while(|d| *d <= end).filter(|d| self.is_business_day(*d)).collect()
+    }
+
+    /// Whether a `Date` or `DateTime` is on a business day; `Empty` stays empty, and any other value is an error.
+    pub(crate) fn is_business_value(&self, value :&Value) -> Result<Value, TableError> {
+        match date_of(value)? {
+            Some(date) => Ok(Value::Boolean(self.is_business_day(date))),
+            None => Ok(Value::Empty)
+        }
+    }
+
+    /// Moves a `Date` or `DateTime` `n` business days, keeping the time of a `DateTime`; `Empty` stays empty, and any
+    /// other value is an error.
+    pub(crate) fn add_business_days_value(&self, value :&Value, n :i64) -> Result<Value, TableError> {
+        let date = match date_of(value)? {
+            Some(date) => date,
+            None => return Ok(Value::Empty)
+        };
+
+        let moved = self.add_business_days(date, n).ok_or_else(|| {
+            let err_str = format!("Cannot move {} by {} business days", value, n);
+            TableError::new(err_str.as_str())
+        })?;
+
+        match value {
+            Value::DateTime(dt) => Ok(Value::DateTime(moved.and_time(dt.time()))),
+            _ => Ok(Value::Date(moved))
+        }
+    }
+
+    /// Resamples `table` to one row per business day, from the first date in `column` to the last, each the last row
+    /// dated on or before that day; a day without rows of its own repeats the row before it. Rows on other days count
+    /// toward the next business day, and the rows are ordered by date, keeping the order of the table for the same date.
+    ///
+    /// The `column` of each row is replaced with its business day, as a `Date`; rows with an empty date are dropped.
+    #[cfg(feature = "rowtable")]
+    pub fn resample<T: TableOperations>(&self, table :&T, column :&str) -> Result<RowTable, TableError> {
+        let pos = table.column_position(column)?;
+        let width = table.width();
+        let mut dated = Vec::new();
+
+        // read the rows in one pass, as getting a row by its index can mean scanning to it
+        for (i, row) in table.iter().enumerate() {
+            if let Some(date) = date_of(&row.try_at(pos)?)? {
+                dated.push((date, i, (0..width).map(|p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?));
+            }
+        }
+
+        dated.sort_by_key(|(date, i, _)| (*date, *i));
+
+        let (first, last) = match (dated.first(), dated.last()) {
+            (Some(first), Some(last)) => (first.0, last.0),
+            _ => return Ok(RowTable::new(&table.columns()))
+        };
+
+        let end = self.roll_forward(last).ok_or_else(|| TableError::new("The calendar has no business days"))?;
+        let mut rows = Vec::new();
+        let mut next = dated.iter().peekable();
+        let mut current = None;
+
+        for day in self.business_days(first, end) {
+            while let Some((_, _, values)) = next.next_if(|(date, _, _)| *date <= day) {
+                current = Some(values);
+            }
+
+            if let Some(values) = current {
+                let mut values = values.clone();
+
+                values[pos] = Value::Date(day);
+                rows.push(values);
+            }
+        }
+
+        Ok(RowTable::from_rows(table.columns(), rows))
+    }
+}
+
+/// The date of a `Date` or `DateTime`, `None` for `Empty`, or an error for any other value
+fn date_of(value :&Value) -> Result<Option<NaiveDate>, TableError> {
+    match value {
+        Value::Date(date) => Ok(Some(*date)),
+        Value::DateTime(dt) => Ok(Some(dt.date())),
+        Value::Empty => Ok(None),
+        v => {
+            let err_str = format!("Not a date: {}", v);
+            Err(TableError::new(err_str.as_str()))
+        }
+    }
+}
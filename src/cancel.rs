@@ -0,0 +1,49 @@
+//! Cancelling long-running operations, such as a query on a huge table, from another thread.
+use std::io::{Error as IOError, ErrorKind};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::TableError;
+
+/// The number of rows between checks of a `CancelToken`
+pub(crate) const CHECK_ROWS :usize = 4 * 1024;
+
+/// A flag shared by its clones, which stops the operations it's passed to once set; they check it periodically,
+/// and return [`TableError::cancelled`](struct.TableError.html#method.cancelled) when it's set.
+/// ```
+/// use large_table::CancelToken;
+///
+/// let token = CancelToken::new();
+/// let other = token.clone();
+///
+/// other.cancel();
+///
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    /// Cancels the operations using this token, or any of its clones.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns `TableError::cancelled` once the token is cancelled
+    pub(crate) fn check(&self) -> Result<(), TableError> {
+        if self.is_cancelled() { Err(TableError::cancelled()) } else { Ok( () ) }
+    }
+
+    /// Returns an `Interrupted` error, wrapping `TableError::cancelled`, once the token is cancelled
+    pub(crate) fn check_io(&self) -> Result<(), IOError> {
+        self.check().map_err(|e| IOError::new(ErrorKind::Interrupted, e))
+    }
+}
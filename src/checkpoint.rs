@@ -0,0 +1,268 @@
+//! Checkpoints for long exports, so an interrupted export can be resumed instead of restarted.
+//!
+//! While [`to_csv_checkpointed`](trait.TableOperations.html#method.to_csv_checkpointed) writes `data.csv`, it
+//! records how far it has got in `data.csv.progress`, a text file with tab-separated fields:
+//! ```text
+//! rows    200000
+//! bytes   11534512
+//! total   5000000
+//! every   100000
+//! ```
+//! `rows` rows have been written, in the first `bytes` bytes of the file, out of `total` rows in the table, with a
+//! checkpoint every `every` rows. The file is removed when the export finishes.
+//!
+//! The `_with_progress` versions report the same counts to a [`Progress`](struct.Progress.html) callback at each
+//! checkpoint.
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Error as IOError, ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{Row, TableError, TableOperations, WriteOptions, Progress};
+
+/// Progress of an export, written by [`to_csv_checkpointed`](trait.TableOperations.html#method.to_csv_checkpointed).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Rows written, not counting the header
+    pub rows: usize,
+    /// Bytes of the file holding the header and those rows
+    pub bytes: u64,
+    /// Rows in the table being exported
+    pub total: usize,
+    /// Rows between checkpoints
+    pub every: usize
+}
+
+impl Checkpoint {
+    /// Path of the progress file for a CSV file
+    pub fn progress_path<P: AsRef<Path>>(csv_path :P) -> PathBuf {
+        let mut path = csv_path.as_ref().as_os_str().to_owned();
+
+        path.push(".progress");
+
+        PathBuf::from(path)
+    }
+
+    /// Writes the progress file for a CSV file, replacing the previous one in a single step.
+    pub fn write<P: AsRef<Path>>(&self, csv_path :P) -> Result<(), IOError> {
+        let path = Checkpoint::progress_path(csv_path);
+        let mut tmp_path = path.clone().into_os_string();
+
+        tmp_path.push(".tmp");
+
+        let contents = format!("rows\t{}\nbytes\t{}\ntotal\t{}\nevery\t{}\n", self.rows, self.bytes, self.total, self.every);
+
+        std::fs::write(&tmp_path, contents)?;
+        std::fs::rename(&tmp_path, path)
+    }
+
+    /// Reads the progress file for a CSV file, or returns `None` when there isn't one.
+    pub fn read<P: AsRef<Path>>(csv_path :P) -> Result<Option<Checkpoint>, IOError> {
+        let contents = match std::fs::read_to_string(Checkpoint::progress_path(csv_path)) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e)
+        };
+
+        let mut checkpoint = Checkpoint { rows: 0, bytes: 0, total: 0, every: 0 };
+
+        for line in contents.lines() {
+            let invalid_line = || {
+                let err_str = format!("Invalid line in progress file: {:?}", line);
+                IOError::new(ErrorKind::InvalidData, err_str.as_str())
+            };
+
+            match line.split('\t').collect::<Vec<_>>().as_slice() {
+                ["rows", rows] => checkpoint.rows = rows.parse().map_err(|_| invalid_line())?,
+                ["bytes", bytes] => checkpoint.bytes = bytes.parse().map_err(|_| invalid_line())?,
+                ["total", total] => checkpoint.total = total.parse().map_err(|_| invalid_line())?,
+                ["every", every] => checkpoint.every = every.parse().map_err(|_| invalid_line())?,
+                _ => return Err(invalid_line())
+            }
+        }
+
+        Ok(Some(checkpoint))
+    }
+}
+
+/// Counts the bytes written through it, so checkpoints know where the rows written so far end
+struct CountingWriter<W> {
+    inner: W,
+    bytes: u64
+}
+
+impl <W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf :&[u8]) -> Result<usize, IOError> {
+        let len = self.inner.write(buf)?;
+
+        self.bytes += len as u64;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<(), IOError> {
+        self.inner.flush()
+    }
+}
+
+/// Writes the table to `csv_path` as described by `options`, from the start or from `checkpoint`, recording a checkpoint
+/// every `every` rows, and calling `progress` after each one, and at the end.
+pub(crate) fn export<T, P>(table :&T, csv_path :P, checkpoint :Option<Checkpoint>, every :usize, options :&WriteOptions, progress :&mut dyn FnMut(Progress)) -> Result<(), TableError>
+    where T: TableOperations + ?Sized, P: AsRef<Path>
+{
+    let io_err = |e :IOError| TableError::new(e.to_string().as_str());
+    let csv_err = |e :csv::Error| TableError::new(e.to_string().as_str());
+
+    if every == 0 {
+        return Err(TableError::new("Rows between checkpoints must be greater than 0"));
+    }
+
+    let total = table.len();
+    let columns = table.columns();
+    let positions = options.positions(&columns)?;
+
+    let (file, start) = match &checkpoint {
+        Some(checkpoint) => {
+            let mut file = OpenOptions::new().write(true).open(&csv_path).map_err(io_err)?;
+
+            // anything after the checkpoint is a partial write, and is written again
+            file.set_len(checkpoint.bytes).map_err(io_err)?;
+            file.seek(SeekFrom::End(0)).map_err(io_err)?;
+
+            (file, checkpoint.clone())
+        },
+        None => (File::create(&csv_path).map_err(io_err)?, Checkpoint { rows: 0, bytes: 0, total, every })
+    };
+
+    let mut writer = CountingWriter { inner: BufWriter::new(file), bytes: start.bytes };
+
+    if checkpoint.is_none() {
+        writer.write_all(options.preamble()).map_err(io_err)?;
+    }
+
+    let mut csv = options.writer(writer);
+
+    if checkpoint.is_none() && options.has_header() {
+        csv.write_record(positions.iter().map(|&i| &columns[i])).map_err(csv_err)?;
+    }
+
+    let mut rows = start.rows;
+
+    for row in table.iter().skip(start.rows) {
+        let record = positions.iter().map(|&i| row.try_at(i).map(|v| v.as_string())).collect::<Result<Vec<_>, _>>()?;
+
+        csv.write_record(record).map_err(csv_err)?;
+        rows += 1;
+
+        if rows % every == 0 {
+            csv.flush().map_err(io_err)?;
+            csv.get_ref().inner.get_ref().sync_data().map_err(io_err)?;
+
+            Checkpoint { rows, bytes: csv.get_ref().bytes, total, every }.write(&csv_path).map_err(io_err)?;
+            progress(export_progress(rows, csv.get_ref().bytes, total));
+        }
+    }
+
+    csv.flush().map_err(io_err)?;
+    progress(export_progress(rows, csv.get_ref().bytes, total));
+
+    match std::fs::remove_file(Checkpoint::progress_path(&csv_path)) {
+        Err(ref e) if e.kind() != ErrorKind::NotFound => Err(TableError::new(e.to_string().as_str())),
+        _ => Ok( () )
+    }
+}
+
+/// The progress of an export that has written `rows` of `total` rows in `bytes`, estimating the bytes of the whole file
+/// from them.
+fn export_progress(rows :usize, bytes :u64, total :usize) -> Progress {
+    let total_bytes = if rows == 0 { bytes } else { (bytes as f64 * total as f64 / rows as f64).round() as u64 };
+
+    Progress { bytes, total_bytes: total_bytes.max(bytes), rows }
+}
+
+/// Resumes an export of `table` to `csv_path`, described by `options`, from its last checkpoint.
+pub(crate) fn resume<T: TableOperations + ?Sized, P: AsRef<Path>>(table :&T, csv_path :P, options :&WriteOptions, progress :&mut dyn FnMut(Progress)) -> Result<(), TableError> {
+    let checkpoint = match Checkpoint::read(&csv_path).map_err(|e| TableError::new(e.to_string().as_str()))? {
+        Some(checkpoint) => checkpoint,
+        None => {
+            let err_str = format!("No export to resume for {}", csv_path.as_ref().display());
+            return Err(TableError::new(err_str.as_str()));
+        }
+    };
+
+    if checkpoint.total != table.len() {
+        let err_str = format!("Export was of a table with {} rows, not {}", checkpoint.total, table.len());
+        return Err(TableError::new(err_str.as_str()));
+    }
+
+    let every = checkpoint.every;
+
+    export(table, csv_path, Some(checkpoint), every, options, progress)
+}
+
+#[cfg(all(test, feature = "rowtable"))]
+mod tests {
+    use crate::{fixtures, RowTable, TableOperations, WriteOptions};
+    use crate::checkpoint::Checkpoint;
+
+    #[test]
+    fn resume() {
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+        let path = fixtures::temp_dir().join("checkpoint_out.csv");
+        let mut expected = Vec::new();
+
+        table.to_csv_writer(&mut expected).unwrap();
+        table.to_csv_checkpointed(&path, 5, &WriteOptions::default()).unwrap();
+
+        assert_eq!(expected, std::fs::read(&path).unwrap());
+        assert!(!Checkpoint::progress_path(&path).exists());
+        assert!(table.resume_export(&path, &WriteOptions::default()).is_err());
+
+        // interrupted part way through the 8th row, after the checkpoint at 5 rows
+        let lines = expected.split_inclusive(|b| *b == b'\n').collect::<Vec<_>>();
+        let bytes = lines[..6].concat();
+        let mut partial = lines[..8].concat();
+
+        partial.extend_from_slice(&lines[8][..4]);
+        std::fs::write(&path, &partial).unwrap();
+
+        let checkpoint = Checkpoint { rows: 5, bytes: bytes.len() as u64, total: table.len(), every: 5 };
+
+        checkpoint.write(&path).unwrap();
+
+        assert_eq!(Some(checkpoint.clone()), Checkpoint::read(&path).unwrap());
+        assert!(table.filter_by(|_| false).unwrap().resume_export(&path, &WriteOptions::default()).is_err());
+
+        table.resume_export(&path, &WriteOptions::default()).unwrap();
+
+        assert_eq!(expected, std::fs::read(&path).unwrap());
+        assert!(!Checkpoint::progress_path(&path).exists());
+        assert!(table.to_csv_checkpointed(&path, 0, &WriteOptions::default()).is_err());
+
+        // progress is reported at each checkpoint, and at the end, counting the rows written before a resume
+        let mut calls = Vec::new();
+
+        table.to_csv_checkpointed_with_progress(&path, 5, &WriteOptions::default(), |p| calls.push(p)).unwrap();
+
+        assert_eq!(vec![5, 10, 12], calls.iter().map(|p| p.rows).collect::<Vec<_>>());
+        assert_eq!((expected.len() as u64, expected.len() as u64), (calls[2].bytes, calls[2].total_bytes));
+        assert!(calls[0].fraction() > 0.3 && calls[0].fraction() < 0.5);
+
+        std::fs::write(&path, &partial).unwrap();
+        checkpoint.write(&path).unwrap();
+        calls.clear();
+
+        table.resume_export_with_progress(&path, &WriteOptions::default(), |p| calls.push(p)).unwrap();
+
+        assert_eq!(vec![10, 12], calls.iter().map(|p| p.rows).collect::<Vec<_>>());
+        assert_eq!(expected, std::fs::read(&path).unwrap());
+
+        // the columns, header, and line endings are the same as any other export
+        let options = WriteOptions::excel().columns(&["close", "date"]);
+        let mut expected = Vec::new();
+
+        table.to_csv_writer_with_options(&mut expected, &options).unwrap();
+        table.to_csv_checkpointed(&path, 5, &options).unwrap();
+
+        assert_eq!(expected, std::fs::read(&path).unwrap());
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+
+use crate::{TableOperations, TableError, Row, Value};
+#[cfg(feature = "rowtable")]
+use crate::RowTable;
+
+/// How the counts of a [`Crosstab`](struct.Crosstab.html) are normalized into proportions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalize {
+    /// Divide by the total number of rows
+    All,
+    /// Divide by the total of each row
+    Rows,
+    /// Divide by the total of each column
+    Columns
+}
+
+/// The number of rows with each combination of values in two columns, created with
+/// [`TableOperations::crosstab`](trait.TableOperations.html#method.crosstab).
+///
+/// The distinct values of each column are kept sorted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crosstab {
+    row_column: String,
+    rows: Vec<Value>,
+    columns: Vec<Value>,
+    counts: Vec<Vec<usize>>  // counts[row][column]
+}
+
+impl Crosstab {
+    pub(crate) fn new<T: TableOperations>(table :&T, row_column :&str, col_column :&str) -> Result<Self, TableError> {
+        // make sure the columns are there
+        table.column_position(row_column)?;
+        table.column_position(col_column)?;
+
+        let mut counts = BTreeMap::new();
+
+        for row in table.iter() {
+            *counts.entry( (row.get(row_column), row.get(col_column)) ).or_insert(0usize) += 1;
+        }
+
+        let mut rows = counts.keys().map(|(r, _)| r.clone()).collect::<Vec<_>>();
+        let mut columns = counts.keys().map(|(_, c)| c.clone()).collect::<Vec<_>>();
+
+        rows.dedup();
+        columns.sort();
+        columns.dedup();
+
+        let counts = rows.iter().map(|r| {
+            columns.iter().map(|c| counts.get(&(r.clone(), c.clone())).cloned().unwrap_or(0)).collect()
+        }).collect();
+
+        Ok(Crosstab { row_column: row_column.to_string(), rows, columns, counts })
+    }
+
+    /// The distinct values of the row column
+    pub fn rows(&self) -> &[Value] {
+        &self.rows
+    }
+
+    /// The distinct values of the column column
+    pub fn columns(&self) -> &[Value] {
+        &self.columns
+    }
+
+    /// The number of rows with `row` in the row column and `column` in the column column
+    pub fn count(&self, row :&Value, column :&Value) -> usize {
+        match (self.rows.binary_search(row), self.columns.binary_search(column)) {
+            (Ok(r), Ok(c)) => self.counts[r][c],
+            _ => 0
+        }
+    }
+
+    /// The total of each row
+    pub fn row_totals(&self) -> Vec<usize> {
+        self.counts.iter().map(|r| r.iter().sum()).collect()
+    }
+
+    /// The total of each column
+    pub fn column_totals(&self) -> Vec<usize> {
+        (0..self.columns.len()).map(|c| self.counts.iter().map(|r| r[c]).sum()).collect()
+    }
+
+    /// The total number of rows in the table
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    /// The counts divided by the totals chosen by `normalize`, with `counts[row][column]` ordering.
+    pub fn normalized(&self, normalize :Normalize) -> Vec<Vec<f64>> {
+        let (row_totals, column_totals, total) = (self.row_totals(), self.column_totals(), self.total());
+
+        self.counts.iter().enumerate().map(|(r, counts)| {
+            counts.iter().enumerate().map(|(c, &count)| {
+                proportion(count, match normalize {
+                    Normalize::All => total,
+                    Normalize::Rows => row_totals[r],
+                    Normalize::Columns => column_totals[c]
+                })
+            }).collect()
+        }).collect()
+    }
+
+    /// Creates a `RowTable` of the counts, or of proportions when `normalize` is set.
+    ///
+    /// The first column has the values of the row column, and the other columns are named after the values of the
+    /// column column. With `margins` an `All` row and column of totals are added.
+    #[cfg(feature = "rowtable")]
+    pub fn to_table(&self, normalize :Option<Normalize>, margins :bool) -> Result<RowTable, TableError> {
+        let (row_totals, column_totals, total) = (self.row_totals(), self.column_totals(), self.total());
+
+        let mut columns = vec![self.row_column.clone()];
+
+        columns.extend(self.columns.iter().map(|c| c.to_string()));
+
+        if margins {
+            columns.push(String::from("All"));
+        }
+
+        // a column named after a value can clash with the row column, or with All
+        if columns.len() != columns.iter().collect::<std::collections::HashSet<_>>().len() {
+            let err_str = format!("Duplicate columns in crosstab: {:?}", columns);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        // each cell is the count, and the row and column totals used to normalize it
+        let cell = |count :usize, row_total :usize, column_total :usize| {
+            match normalize {
+                None => Value::Integer(count as i64),
+                Some(Normalize::All) => Value::Float(proportion(count, total).into()),
+                Some(Normalize::Rows) => Value::Float(proportion(count, row_total).into()),
+                Some(Normalize::Columns) => Value::Float(proportion(count, column_total).into())
+            }
+        };
+
+        let mut rows = Vec::with_capacity(self.rows.len() + 1);
+
+        for (r, row) in self.rows.iter().enumerate() {
+            let mut values = vec![row.clone()];
+
+            values.extend(self.counts[r].iter().enumerate().map(|(c, &count)| cell(count, row_totals[r], column_totals[c])));
+
+            if margins {
+                values.push(cell(row_totals[r], row_totals[r], total));
+            }
+
+            rows.push(values);
+        }
+
+        if margins {
+            let mut values = vec![Value::String(String::from("All"))];
+
+            values.extend(column_totals.iter().map(|&count| cell(count, total, count)));
+            values.push(cell(total, total, total));
+
+            rows.push(values);
+        }
+
+        Ok(RowTable::from_rows(columns, rows))
+    }
+}
+
+fn proportion(count :usize, total :usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+#[cfg(all(test, feature = "rowtable"))]
+mod tests {
+    use crate::{fixtures, RowTable, TableOperations, Row, Value, Normalize};
+
+    #[test]
+    fn crosstab() {
+        let path = fixtures::temp_dir().join("crosstab.csv");
+
+        std::fs::write(&path, "region,product\neast,a\neast,b\nwest,a\neast,a\n").unwrap();
+
+        let table = RowTable::from_csv(&path).unwrap();
+        let crosstab = table.crosstab("region", "product").unwrap();
+
+        let (east, west, a, b) = (Value::new("east"), Value::new("west"), Value::new("a"), Value::new("b"));
+
+        assert_eq!(&[east.clone(), west.clone()], crosstab.rows());
+        assert_eq!(2, crosstab.count(&east, &a));
+        assert_eq!(0, crosstab.count(&west, &b));
+        assert_eq!(0, crosstab.count(&Value::new("north"), &a));
+        assert_eq!(vec![3, 1], crosstab.row_totals());
+        assert_eq!(vec![3, 1], crosstab.column_totals());
+        assert_eq!(4, crosstab.total());
+        assert_eq!(vec![vec![2.0 / 3.0, 1.0 / 3.0], vec![1.0, 0.0]], crosstab.normalized(Normalize::Rows));
+
+        let counts = crosstab.to_table(None, true).unwrap();
+
+        assert_eq!(vec!["region", "a", "b", "All"], counts.columns());
+        assert_eq!(3, counts.len());
+        assert_eq!(Value::Integer(3), counts.get(0).unwrap().get("All"));
+        assert_eq!(Value::Integer(4), counts.get(2).unwrap().get("All"));
+        assert_eq!(Value::Integer(1), counts.get(2).unwrap().get("b"));
+
+        let proportions = crosstab.to_table(Some(Normalize::All), false).unwrap();
+
+        assert_eq!(Value::Float(0.25.into()), proportions.get(1).unwrap().get("a"));
+        assert!(table.crosstab("region", "missing").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! Operations on the dates and times of a column.
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+#[cfg(feature = "timezones")]
+use chrono::TimeZone;
+
+use crate::{TableError, Value};
+
+/// The unit [`dt_truncate`](trait.Table.html#method.dt_truncate) truncates dates and times to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    /// The week starting on Monday
+    Week,
+    Month,
+    Quarter,
+    Year
+}
+
+impl DateUnit {
+    fn truncate_date(&self, date :NaiveDate) -> NaiveDate {
+        match self {
+            DateUnit::Second | DateUnit::Minute | DateUnit::Hour | DateUnit::Day => date,
+            DateUnit::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            DateUnit::Month => date.with_day(1).unwrap(),
+            DateUnit::Quarter => NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1).unwrap(),
+            DateUnit::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap()
+        }
+    }
+
+    fn truncate_date_time(&self, dt :NaiveDateTime) -> NaiveDateTime {
+        let (date, time) = (dt.date(), dt.time());
+
+        let hms = match self {
+            DateUnit::Second => (time.hour(), time.minute(), time.second()),
+            DateUnit::Minute => (time.hour(), time.minute(), 0),
+            DateUnit::Hour => (time.hour(), 0, 0),
+            _ => return self.truncate_date(date).and_hms_opt(0, 0, 0).unwrap()
+        };
+
+        date.and_hms_opt(hms.0, hms.1, hms.2).unwrap()
+    }
+
+    /// Truncates a `DateTime` or `Date` to the start of its unit; `Empty` stays empty, and any other value is an error.
+    pub(crate) fn truncate(&self, value :&Value) -> Result<Value, TableError> {
+        match value {
+            Value::DateTime(dt) => Ok(Value::DateTime(self.truncate_date_time(*dt))),
+            Value::Date(date) => Ok(Value::Date(self.truncate_date(*date))),
+            Value::Empty => Ok(Value::Empty),
+            v => {
+                let err_str = format!("Cannot truncate a value that isn't a date or time: {}", v);
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+}
+
+/// The length of the buckets [`ohlc`](trait.TableOperations.html#method.ohlc) groups times into. Buckets of
+/// seconds, minutes, and hours start again at each midnight, so they line up with the clock when they divide a day
+/// evenly; buckets of days are counted from January 1 of year 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Second(u32),
+    Minute(u32),
+    Hour(u32),
+    Day(u32)
+}
+
+impl Frequency {
+    fn seconds(&self) -> i64 {
+        match self {
+            Frequency::Second(n) => *n as i64,
+            Frequency::Minute(n) => *n as i64 * 60,
+            Frequency::Hour(n) => *n as i64 * 3_600,
+            Frequency::Day(n) => *n as i64 * 86_400
+        }
+    }
+
+    fn bucket_date(&self, date :NaiveDate) -> NaiveDate {
+        match self {
+            Frequency::Day(n) => {
+                let days = date.num_days_from_ce();
+
+                NaiveDate::from_num_days_from_ce_opt(days - days.rem_euclid(*n as i32)).unwrap_or(date)
+            },
+            _ => date
+        }
+    }
+
+    /// The start of the bucket of a `DateTime` or `Date`; a `Date` is its own bucket for a frequency shorter than a
+    /// day. `Empty` stays empty, and any other value is an error.
+    pub(crate) fn bucket(&self, value :&Value) -> Result<Value, TableError> {
+        if self.seconds() == 0 {
+            return Err(TableError::new("The frequency of buckets must be positive"));
+        }
+
+        match (self, value) {
+            (Frequency::Day(_), Value::DateTime(dt)) => Ok(Value::DateTime(self.bucket_date(dt.date()).and_hms_opt(0, 0, 0).unwrap())),
+            (_, Value::DateTime(dt)) => {
+                let seconds = dt.time().num_seconds_from_midnight() as i64;
+                let midnight = dt.date().and_hms_opt(0, 0, 0).unwrap();
+
+                Ok(Value::DateTime(midnight + Duration::seconds(seconds - seconds % self.seconds())))
+            },
+            (_, Value::Date(date)) => Ok(Value::Date(self.bucket_date(*date))),
+            (_, Value::Empty) => Ok(Value::Empty),
+            (_, v) => {
+                let err_str = format!("Cannot put a value that isn't a date or time in a bucket: {}", v);
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+}
+
+/// Looks up a time zone by its IANA name, such as `America/New_York` or `UTC`
+#[cfg(feature = "timezones")]
+pub(crate) fn parse_tz(name :&str) -> Result<chrono_tz::Tz, TableError> {
+    name.parse::<chrono_tz::Tz>().map_err(|_| {
+        let err_str = format!("Unknown time zone: {}", name);
+        TableError::new(err_str.as_str())
+    })
+}
+
+/// Converts a `DateTime` read as a local time in `from` to the local time in `to`. A time that's repeated when the
+/// clocks go back is read as the first one, a time skipped when they go forward becomes `Empty`, and `Empty` stays
+/// empty; any other value is an error.
+#[cfg(feature = "timezones")]
+pub(crate) fn convert_tz(value :&Value, from :chrono_tz::Tz, to :chrono_tz::Tz) -> Result<Value, TableError> {
+    match value {
+        Value::DateTime(dt) => Ok(from.from_local_datetime(dt).earliest().map_or(Value::Empty, |t| Value::DateTime(t.with_timezone(&to).naive_local()))),
+        Value::Empty => Ok(Value::Empty),
+        v => {
+            let err_str = format!("Cannot convert the time zone of a value that isn't a date and time: {}", v);
+            Err(TableError::new(err_str.as_str()))
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Summary statistics of the numeric columns of each group of rows, like Pandas' `df.groupby(column).describe()`.
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Error as FmtError};
+
+use crate::{parallel, schema, stats, TableOperations, TableError, Row, Value, ValueType};
+#[cfg(feature = "rowtable")]
+use crate::RowTable;
+
+/// Summary statistics of the non-empty values of a column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    /// The sample standard deviation, 0 for a single value
+    pub std: f64,
+    pub min: f64,
+    /// The first quartile, interpolated between values like the other quartiles
+    pub q25: f64,
+    pub median: f64,
+    pub q75: f64,
+    pub max: f64
+}
+
+impl Summary {
+    /// Summarizes `values`, or `None` when there aren't any
+    fn new(mut values :Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+
+        let (mean, std) = stats::mean_std(&values);
+
+        Some(Summary {
+            count: values.len(),
+            mean,
+            std,
+            min: values[0],
+            q25: stats::quantile(&values, 0.25),
+            median: stats::quantile(&values, 0.5),
+            q75: stats::quantile(&values, 0.75),
+            max: values[values.len() - 1]
+        })
+    }
+
+    /// The statistics in the order of [`Describe::STATISTICS`](struct.Describe.html#associatedconstant.STATISTICS)
+    pub fn values(&self) -> [f64; 8] {
+        [self.count as f64, self.mean, self.std, self.min, self.q25, self.median, self.q75, self.max]
+    }
+}
+
+/// Summary statistics of the numeric columns of each group of rows with the same value in a column, created with
+/// [`TableOperations::describe_by`](trait.TableOperations.html#method.describe_by).
+///
+/// The numeric columns are those declared with a numeric type, such as `Integer`, `Float`, or `Currency`, or else whose
+/// values, in every row, are all numbers; the groups are sorted by their value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Describe {
+    by: String,
+    columns: Vec<String>,
+    groups: Vec<(Value, Vec<Option<Summary>>)>  // the summary of each column, None without values
+}
+
+impl Describe {
+    /// The names of the statistics of a [`Summary`](struct.Summary.html), in the order of the rows of `to_table`
+    pub const STATISTICS :[&'static str; 8] = ["count", "mean", "std", "min", "25%", "50%", "75%", "max"];
+
+    pub(crate) fn new<T: TableOperations + ?Sized>(table :&T, by :&str) -> Result<Self, TableError> {
+        let by_pos = table.column_position(by)?;
+        // every row is checked, as a sample could miss a value that isn't a number
+        let numeric = schema::column_types(table, None).into_iter().enumerate()
+            .filter(|(i, (_, t))| *i != by_pos && is_numeric(t))
+            .map(|(i, (c, _))| (i, c))
+            .collect::<Vec<_>>();
+
+        // gather the values of every group in one pass over the rows
+        let mut groups :BTreeMap<Value, Vec<Vec<f64>>> = BTreeMap::new();
+
+        for row in table.iter() {
+            let values = groups.entry(row.try_at(by_pos)?).or_insert_with(|| vec![Vec::new(); numeric.len()]);
+
+            for (j, (pos, column)) in numeric.iter().enumerate() {
+                match row.try_at(*pos)? {
+                    Value::Empty => continue,
+                    value => match value.try_as_float() {
+                        Some(f) => values[j].push(f),
+                        None => {
+                            let err_str = format!("Non-numeric value in column {}: {}", column, value);
+                            return Err(TableError::new(err_str.as_str()));
+                        }
+                    }
+                }
+            }
+        }
+
+        let groups = groups.into_iter().collect::<Vec<_>>();
+        let summaries = parallel::map(&groups, |(_, values)| values.iter().map(|v| Summary::new(v.clone())).collect::<Vec<_>>());
+
+        Ok(Describe {
+            by: by.to_string(),
+            columns: numeric.into_iter().map(|(_, c)| c).collect(),
+            groups: groups.into_iter().map(|(k, _)| k).zip(summaries).collect()
+        })
+    }
+
+    /// The numeric columns that are summarized
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// The values of the group column, in order
+    pub fn groups(&self) -> Vec<&Value> {
+        self.groups.iter().map(|(k, _)| k).collect()
+    }
+
+    /// The summary of `column` in the group with `key`, or `None` when there's no such group or column, or the group
+    /// has no values in the column.
+    pub fn get(&self, key :&Value, column :&str) -> Option<&Summary> {
+        let col = self.columns.iter().position(|c| c == column)?;
+        let group = self.groups.binary_search_by(|(k, _)| k.cmp(key)).ok()?;
+
+        self.groups[group].1[col].as_ref()
+    }
+
+    /// The number of groups
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Creates a `RowTable` with a row for each statistic of each group: the group column, then `statistic`, named as
+    /// in `STATISTICS`, then the numeric columns. A column without values in a group is empty, except for its count.
+    #[cfg(feature = "rowtable")]
+    pub fn to_table(&self) -> RowTable {
+        let mut columns = vec![self.by.clone(), String::from("statistic")];
+
+        columns.extend(self.columns.iter().cloned());
+
+        let mut rows = Vec::with_capacity(self.groups.len() * Describe::STATISTICS.len());
+
+        for (key, summaries) in self.groups.iter() {
+            for (s, statistic) in Describe::STATISTICS.iter().enumerate() {
+                let mut row = vec![key.clone(), Value::String(statistic.to_string())];
+
+                row.extend(summaries.iter().map(|summary| match summary {
+                    Some(summary) if s == 0 => Value::Integer(summary.count as i64),
+                    Some(summary) => Value::Float(summary.values()[s].into()),
+                    None if s == 0 => Value::Integer(0),
+                    None => Value::Empty
+                }));
+
+                rows.push(row);
+            }
+        }
+
+        RowTable::from_rows(columns, rows)
+    }
+}
+
+/// Whether the values of a column of `value_type` are numbers
+fn is_numeric(value_type :&ValueType) -> bool {
+    matches!(value_type, ValueType::Number | ValueType::NumberFormat(_) | ValueType::Integer | ValueType::IntegerFormat(_) |
+        ValueType::Float | ValueType::FloatFormat(_) | ValueType::Currency | ValueType::CurrencyFormat(_) | ValueType::Percent | ValueType::PercentFormat(_))
+}
+
+impl Display for Describe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        for (key, summaries) in self.groups.iter() {
+            writeln!(f, "{}: {}", self.by, key)?;
+
+            for (column, summary) in self.columns.iter().zip(summaries.iter()) {
+                match summary {
+                    Some(s) => writeln!(f, "  {}: count {}, mean {:.4}, std {:.4}, min {}, 25% {}, 50% {}, 75% {}, max {}",
+                                        column, s.count, s.mean, s.std, s.min, s.q25, s.median, s.q75, s.max)?,
+                    None => writeln!(f, "  {}: count 0", column)?
+                }
+            }
+        }
+
+        Ok( () )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::describe::Summary;
+
+    #[test]
+    fn quartiles() {
+        let summary = Summary::new(vec![4.0, 1.0, 3.0, 2.0]).unwrap();
+
+        assert_eq!([4.0, 2.5, summary.std, 1.0, 1.75, 2.5, 3.25, 4.0], summary.values());
+        assert!(Summary::new(Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,219 @@
+//! Reports of what changed between two versions of a table, matched on a key column.
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Error as FmtError};
+
+use crate::{TableOperations, TableError, Row, Value};
+#[cfg(feature = "rowtable")]
+use crate::RowTable;
+
+/// A value that changed between the old and new versions of a row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub key: Value,
+    pub column: String,
+    pub old: Value,
+    pub new: Value
+}
+
+/// What changed between an old and a new version of a table, with rows matched by their key, created with
+/// [`TableOperations::diff_report`](trait.TableOperations.html#method.diff_report).
+///
+/// Only the columns both versions have are compared; the keys and changes are sorted by key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffReport {
+    key: String,
+    added: Vec<Value>,
+    removed: Vec<Value>,
+    unchanged: usize,
+    added_columns: Vec<String>,
+    removed_columns: Vec<String>,
+    column_changes: Vec<(String, usize)>,
+    changes: Vec<Change>
+}
+
+/// The values of `columns` of each row of `table`, by the row's value in `key`, which must be unique
+fn keyed_rows<T: TableOperations + ?Sized>(table :&T, key :&str, columns :&[String]) -> Result<BTreeMap<Value, Vec<Value>>, TableError> {
+    let key_pos = table.column_position(key)?;
+    let positions = columns.iter().map(|c| table.column_position(c)).collect::<Result<Vec<_>, _>>()?;
+    let mut rows = BTreeMap::new();
+
+    for row in table.iter() {
+        let key_value = row.try_at(key_pos)?;
+        let values = positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+
+        if rows.insert(key_value.clone(), values).is_some() {
+            let err_str = format!("Key {} repeats in column {}", key_value, key);
+            return Err(TableError::new(err_str.as_str()));
+        }
+    }
+
+    Ok(rows)
+}
+
+impl DiffReport {
+    pub(crate) fn new<O: TableOperations + ?Sized, N: TableOperations>(old :&O, new :&N, key :&str) -> Result<Self, TableError> {
+        let (old_columns, new_columns) = (old.columns(), new.columns());
+
+        // the shared columns, in the order of the old table
+        let columns = old_columns.iter().filter(|c| *c != key && new_columns.contains(c)).cloned().collect::<Vec<_>>();
+
+        let old_rows = keyed_rows(old, key, &columns)?;
+        let mut new_rows = keyed_rows(new, key, &columns)?;
+
+        let mut counts = vec![0; columns.len()];
+        let (mut removed, mut unchanged, mut changes) = (Vec::new(), 0, Vec::new());
+
+        for (key_value, old_values) in old_rows {
+            let new_values = match new_rows.remove(&key_value) {
+                Some(values) => values,
+                None => {
+                    removed.push(key_value);
+                    continue;
+                }
+            };
+
+            let mut changed = false;
+
+            for (i, (old_value, new_value)) in old_values.into_iter().zip(new_values).enumerate() {
+                if old_value != new_value {
+                    counts[i] += 1;
+                    changed = true;
+                    changes.push(Change { key: key_value.clone(), column: columns[i].clone(), old: old_value, new: new_value });
+                }
+            }
+
+            if !changed {
+                unchanged += 1;
+            }
+        }
+
+        Ok(DiffReport {
+            key: key.to_string(),
+            added: new_rows.into_keys().collect(),
+            removed,
+            unchanged,
+            added_columns: new_columns.iter().filter(|c| !old_columns.contains(c)).cloned().collect(),
+            removed_columns: old_columns.iter().filter(|c| !new_columns.contains(c)).cloned().collect(),
+            column_changes: columns.into_iter().zip(counts).collect(),
+            changes
+        })
+    }
+
+    /// Keys only in the new table
+    pub fn added(&self) -> &[Value] {
+        &self.added
+    }
+
+    /// Keys only in the old table
+    pub fn removed(&self) -> &[Value] {
+        &self.removed
+    }
+
+    /// Number of keys in both tables with the same values in every compared column
+    pub fn unchanged(&self) -> usize {
+        self.unchanged
+    }
+
+    /// Number of keys in both tables with a different value in any compared column
+    pub fn changed(&self) -> usize {
+        // the changes are sorted by key
+        let mut keys = self.changes.iter().map(|c| &c.key).collect::<Vec<_>>();
+
+        keys.dedup();
+        keys.len()
+    }
+
+    /// Columns only in the new table
+    pub fn added_columns(&self) -> &[String] {
+        &self.added_columns
+    }
+
+    /// Columns only in the old table
+    pub fn removed_columns(&self) -> &[String] {
+        &self.removed_columns
+    }
+
+    /// The number of changed values in each compared column
+    pub fn column_changes(&self) -> &[(String, usize)] {
+        &self.column_changes
+    }
+
+    /// Every changed value, sorted by key, then in column order
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Whether the tables have the same keys, columns, and values
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changes.is_empty() && self.added_columns.is_empty() && self.removed_columns.is_empty()
+    }
+
+    /// Creates a `RowTable` of the changes, with the key column, `column`, `old`, and `new` columns.
+    #[cfg(feature = "rowtable")]
+    pub fn to_table(&self) -> RowTable {
+        let columns = vec![self.key.clone(), String::from("column"), String::from("old"), String::from("new")];
+        let rows = self.changes.iter().map(|c| {
+            vec![c.key.clone(), Value::String(c.column.clone()), c.old.clone(), c.new.clone()]
+        }).collect();
+
+        RowTable::from_rows(columns, rows)
+    }
+}
+
+impl Display for DiffReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        writeln!(f, "added: {}, removed: {}, changed: {}, unchanged: {}", self.added.len(), self.removed.len(), self.changed(), self.unchanged)?;
+
+        if !self.added_columns.is_empty() || !self.removed_columns.is_empty() {
+            writeln!(f, "added columns: {:?}, removed columns: {:?}", self.added_columns, self.removed_columns)?;
+        }
+
+        let width = self.column_changes.iter().map(|(c, _)| c.chars().count()).max().unwrap_or(0).max("Column".len());
+
+        writeln!(f, "{:<width$}  {:>8}", "Column", "Changes", width = width)?;
+
+        for (column, count) in self.column_changes.iter() {
+            writeln!(f, "{:<width$}  {:>8}", column, count, width = width)?;
+        }
+
+        Ok( () )
+    }
+}
+
+#[cfg(all(test, feature = "rowtable"))]
+mod tests {
+    use crate::{RowTable, TableOperations, Row, Value};
+
+    #[test]
+    fn diff_report() {
+        let yesterday = RowTable::from_csv_bytes(b"id,symbol,close,exchange\n1,AAPL,75.09,N\n2,MSFT,158.62,Q\n3,IBM,135.42,N\n4,GE,11.93,N\n").unwrap();
+        let today = RowTable::from_csv_bytes(b"id,symbol,close,sector\n2,MSFT,159.03,Tech\n1,AAPL,75.09,Tech\n4,GE,11.97,Industrial\n5,XOM,70.90,Energy\n").unwrap();
+
+        let report = yesterday.diff_report(&today, "id").unwrap();
+
+        assert_eq!(&[Value::Integer(5)], report.added());
+        assert_eq!(&[Value::Integer(3)], report.removed());
+        assert_eq!(2, report.changed());
+        assert_eq!(1, report.unchanged());
+        assert_eq!(&["sector".to_string()], report.added_columns());
+        assert_eq!(&["exchange".to_string()], report.removed_columns());
+        assert_eq!(&[("symbol".to_string(), 0), ("close".to_string(), 2)], report.column_changes());
+        assert_eq!(Value::Integer(2), report.changes()[0].key);
+        assert_eq!(Value::Float(159.03.into()), report.changes()[0].new);
+        assert!(!report.is_empty());
+        assert!(report.to_string().contains("added: 1, removed: 1, changed: 2, unchanged: 1"));
+
+        let details = report.to_table();
+
+        assert_eq!(vec!["id", "column", "old", "new"], details.columns());
+        assert_eq!(2, details.len());
+        assert_eq!(Value::Float(11.93.into()), details.get(1).unwrap().get("old"));
+
+        assert!(today.diff_report(&today, "id").unwrap().is_empty());
+        assert!(yesterday.diff_report(&today, "exchange").is_err());
+
+        let repeated = RowTable::from_csv_bytes(b"id,close\n1,1\n1,2\n").unwrap();
+
+        assert!(yesterday.diff_report(&repeated, "id").unwrap_err().to_string().contains("repeats"));
+    }
+}
@@ -0,0 +1,73 @@
+//! Aligned, truncated previews of tables, used by their `Display` implementations.
+use crate::{TableOperations, Row};
+
+/// Rows shown by `Display`: half from the start of the table, half from the end
+pub const DISPLAY_ROWS :usize = 10;
+
+/// Widest value shown by `Display`; longer values are cut off with `...`
+pub const DISPLAY_WIDTH :usize = 20;
+
+/// Formats a preview of `table`: the first and last `rows / 2` rows, with values longer than `max_width` characters
+/// cut off, and a footer with the size of the table.
+pub(crate) fn preview<T: TableOperations + ?Sized>(table :&T, rows :usize, max_width :usize) -> String {
+    let columns = table.columns();
+    let len = table.len();
+
+    // the positions of the rows shown, with None for the gap between the start and the end
+    let positions = if len <= rows {
+        (0..len).map(Some).collect::<Vec<_>>()
+    } else {
+        let head = rows.div_ceil(2);
+
+        (0..head).map(Some).chain(std::iter::once(None)).chain((len - (rows - head)..len).map(Some)).collect()
+    };
+
+    let mut cells = vec![std::iter::once(String::new()).chain(columns.iter().map(|c| truncate(c, max_width))).collect::<Vec<_>>()];
+
+    for pos in positions {
+        let row = match pos.map(|p| table.get(p)) {
+            Some(Ok(row)) => row,
+            Some(Err(_)) => continue,
+            None => {
+                cells.push(vec![String::from("..."); columns.len() + 1]);
+                continue;
+            }
+        };
+
+        let mut line = vec![pos.unwrap().to_string()];
+
+        line.extend(columns.iter().map(|c| match row.try_get(c) {
+            Ok(value) => truncate(&value.to_string(), max_width),
+            Err(_) => String::from("?")
+        }));
+
+        cells.push(line);
+    }
+
+    let widths = (0..=columns.len()).map(|i| cells.iter().map(|line| line[i].chars().count()).max().unwrap_or(0)).collect::<Vec<_>>();
+
+    let mut ret = String::new();
+
+    for line in cells {
+        let line = line.iter().zip(widths.iter()).map(|(cell, &w)| format!("{:>w$}", cell, w=w)).collect::<Vec<_>>();
+
+        ret += line.join("  ").trim_end();
+        ret += "\n";
+    }
+
+    ret += format!("[{} rows x {} columns]", len, columns.len()).as_str();
+
+    ret
+}
+
+/// Cuts `s` off at `max_width` characters, ending it with `...`
+fn truncate(s :&str, max_width :usize) -> String {
+    if s.chars().count() <= max_width {
+        return s.to_string();
+    }
+
+    let mut ret = s.chars().take(max_width.saturating_sub(3)).collect::<String>();
+
+    ret.push_str("...");
+    ret
+}
@@ -0,0 +1,277 @@
+//! Clusters of rows with the same key whose other values are nearly the same, such as a record repeated by two vendors
+//! with a rounding difference or a typo.
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Error as FmtError};
+
+use crate::{parallel, TableOperations, TableError, Row, Value};
+#[cfg(feature = "rowtable")]
+use crate::RowTable;
+
+/// The positions of the rows with a key, and their values in the other columns
+type KeyRows = (Vec<usize>, Vec<Vec<Value>>);
+
+/// Rows with the same key whose other values are within the tolerance of each other
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearDuplicate {
+    /// The values of the key columns
+    pub key: Vec<Value>,
+    /// The positions of the rows in the table, in order
+    pub rows: Vec<usize>,
+    /// The similarity of the least similar pair of rows linking the cluster, from 0 to 1, which is 1 for exact duplicates
+    pub similarity: f64,
+    /// The columns whose values aren't the same in all the rows
+    pub columns: Vec<String>
+}
+
+/// The clusters of near-duplicate rows of a table, created with
+/// [`TableOperations::find_near_duplicates`](trait.TableOperations.html#method.find_near_duplicates).
+///
+/// Two rows with the same key are near duplicates when each of their other values is within the tolerance of the
+/// other: the same, or numbers whose difference is at most the tolerance times the larger of them, or strings whose
+/// edit distance is at most the tolerance times the length of the longer one. The similarity of the rows is the
+/// average of the similarity of their values, 1 minus their relative difference. Rows linked through near duplicates
+/// are in the same cluster; the clusters are sorted by key, then by their first row.
+///
+/// Every pair of rows with the same key is compared, so the time grows with the square of the number of rows per key:
+/// a key such as an id, with a few rows each, is quick, while one such as a date, with thousands, is not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearDuplicates {
+    keys: Vec<String>,
+    clusters: Vec<NearDuplicate>
+}
+
+impl NearDuplicates {
+    pub(crate) fn new<T: TableOperations + ?Sized>(table :&T, keys :&[&str], tolerance :f64) -> Result<Self, TableError> {
+        if keys.is_empty() {
+            return Err(TableError::new("Finding near duplicates needs at least one key column"));
+        }
+
+        if !(0.0..=1.0).contains(&tolerance) {
+            let err_str = format!("Tolerance must be from 0 to 1: {}", tolerance);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let key_pos = keys.iter().map(|c| table.column_position(c)).collect::<Result<Vec<_>, _>>()?;
+        let all_columns = table.columns();
+        let others = (0..all_columns.len()).filter(|p| !key_pos.contains(p)).collect::<Vec<_>>();
+        let columns = others.iter().map(|&p| all_columns[p].clone()).collect::<Vec<_>>();
+        let mut groups :BTreeMap<Vec<Value>, KeyRows> = BTreeMap::new();
+
+        for (i, row) in table.iter().enumerate() {
+            let key = key_pos.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+            let values = others.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+            let group = groups.entry(key).or_default();
+
+            group.0.push(i);
+            group.1.push(values);
+        }
+
+        // only the keys of several rows can have duplicates
+        let groups = groups.into_iter().filter(|(_, (rows, _))| rows.len() > 1).map(|(key, (rows, values))| (key, rows, values)).collect::<Vec<_>>();
+
+        let clusters = parallel::map(&groups, |(key, rows, values)| clusters(key, rows, values, &columns, tolerance));
+
+        Ok(NearDuplicates { keys: keys.iter().map(|k| k.to_string()).collect(), clusters: clusters.into_iter().flatten().collect() })
+    }
+
+    pub fn clusters(&self) -> &[NearDuplicate] {
+        &self.clusters
+    }
+
+    /// The number of clusters
+    pub fn len(&self) -> usize {
+        self.clusters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clusters.is_empty()
+    }
+
+    /// The number of rows that are in a cluster
+    pub fn rows(&self) -> usize {
+        self.clusters.iter().map(|c| c.rows.len()).sum()
+    }
+
+    /// Creates a `RowTable` with a row for each row of each cluster: `cluster`, numbered from 0, `row`, the position of
+    /// the row in the table, the key columns, then `similarity`, and `columns`, the columns that differ, separated by
+    /// commas.
+    #[cfg(feature = "rowtable")]
+    pub fn to_table(&self) -> RowTable {
+        let mut columns = vec![String::from("cluster"), String::from("row")];
+
+        columns.extend(self.keys.iter().cloned());
+        columns.extend(["similarity", "columns"].iter().map(|c| c.to_string()));
+
+        let rows = self.clusters.iter().enumerate().flat_map(|(i, cluster)| {
+            cluster.rows.iter().map(move |&row| {
+                let mut values = vec![Value::Integer(i as i64), Value::Integer(row as i64)];
+
+                values.extend(cluster.key.iter().cloned());
+                values.push(Value::Float(cluster.similarity.into()));
+                values.push(if cluster.columns.is_empty() { Value::Empty } else { Value::String(cluster.columns.join(",")) });
+                values
+            })
+        }).collect();
+
+        RowTable::from_rows(columns, rows)
+    }
+}
+
+/// Links the rows of a key whose values are all within `tolerance` of each other, returning the clusters of linked rows
+fn clusters(key :&[Value], rows :&[usize], values :&[Vec<Value>], columns :&[String], tolerance :f64) -> Vec<NearDuplicate> {
+    let mut parent = (0..rows.len()).collect::<Vec<_>>();
+    let mut links = Vec::new();
+
+    for a in 0..rows.len() {
+        for b in a+1..rows.len() {
+            if let Some(similarity) = row_similarity(&values[a], &values[b], tolerance) {
+                let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+
+                parent[root_a.max(root_b)] = root_a.min(root_b);
+                links.push( (a, similarity) );
+            }
+        }
+    }
+
+    // the members of each cluster, by its root, with the similarity of its weakest link
+    let mut clusters :BTreeMap<usize, (Vec<usize>, f64)> = BTreeMap::new();
+
+    for i in 0..rows.len() {
+        let root = find(&mut parent, i);
+
+        clusters.entry(root).or_insert_with(|| (Vec::new(), 1.0)).0.push(i);
+    }
+
+    for (a, similarity) in links {
+        let cluster = clusters.get_mut(&find(&mut parent, a)).unwrap();
+
+        cluster.1 = cluster.1.min(similarity);
+    }
+
+    clusters.into_values().filter(|(members, _)| members.len() > 1).map(|(members, similarity)| {
+        let differ = |c :&usize| members.iter().any(|&m| values[m][*c] != values[members[0]][*c]);
+
+        NearDuplicate {
+            key: key.to_vec(),
+            rows: members.iter().map(|&m| rows[m]).collect(),
+            similarity,
+            columns: (0..columns.len()).filter(differ).map(|c| columns[c].clone()).collect()
+        }
+    }).collect()
+}
+
+/// The root of the cluster of `i`, pointing `i` at it
+fn find(parent :&mut [usize], i :usize) -> usize {
+    let mut root = i;
+
+    while parent[root] != root {
+        root = parent[root];
+    }
+
+    parent[i] = root;
+    root
+}
+
+/// The average similarity of the values of two rows, or `None` when a pair of values isn't within `tolerance`
+fn row_similarity(a :&[Value], b :&[Value], tolerance :f64) -> Option<f64> {
+    let mut total = 0.0;
+
+    for (x, y) in a.iter().zip(b.iter()) {
+        // strings whose lengths differ by more than the tolerance can't be within it, so skip their edit distance
+        if let (Value::String(x), Value::String(y)) = (x, y) {
+            let (x_len, y_len) = (x.chars().count(), y.chars().count());
+
+            if x_len.abs_diff(y_len) as f64 > tolerance * x_len.max(y_len) as f64 {
+                return None;
+            }
+        }
+
+        let similarity = value_similarity(x, y);
+
+        if similarity < 1.0 - tolerance {
+            return None;
+        }
+
+        total += similarity;
+    }
+
+    Some(if a.is_empty() { 1.0 } else { total / a.len() as f64 })
+}
+
+/// 1 minus the relative difference of two numbers or strings, 1 for the same values, and 0 for any others
+fn value_similarity(a :&Value, b :&Value) -> f64 {
+    match (a, b) {
+        _ if a == b => 1.0,
+        (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => {
+            let (x, y) = (a.as_float(), b.as_float());
+
+            (1.0 - (x - y).abs() / x.abs().max(y.abs())).max(0.0)
+        },
+        (Value::String(x), Value::String(y)) => {
+            let len = x.chars().count().max(y.chars().count());
+
+            1.0 - edit_distance(x, y) as f64 / len as f64
+        },
+        _ => 0.0
+    }
+}
+
+/// The Levenshtein distance between two strings: the fewest characters inserted, removed, or replaced to turn one
+/// into the other
+fn edit_distance(a :&str, b :&str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+
+        for (j, cb) in b.iter().enumerate() {
+            let replace = previous[j] + if ca == *cb { 0 } else { 1 };
+
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+impl Display for NearDuplicates {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        writeln!(f, "near duplicates: {} clusters of {} rows", self.clusters.len(), self.rows())?;
+
+        for cluster in self.clusters.iter() {
+            let key = cluster.key.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+
+            write!(f, "{}: rows {:?}, similarity {:.4}", key, cluster.rows, cluster.similarity)?;
+
+            if cluster.columns.is_empty() {
+                writeln!(f)?;
+            } else {
+                writeln!(f, ", differ in {}", cluster.columns.join(", "))?;
+            }
+        }
+
+        Ok( () )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+    use crate::duplicates::{edit_distance, value_similarity};
+
+    #[test]
+    fn similarity() {
+        assert_eq!(3, edit_distance("kitten", "sitting"));
+        assert_eq!(0, edit_distance("", ""));
+        assert_eq!(2, edit_distance("ab", ""));
+
+        assert_eq!(1.0, value_similarity(&Value::Empty, &Value::Empty));
+        assert_eq!(0.99, value_similarity(&Value::Integer(100), &Value::Float(99.0.into())));
+        assert_eq!(0.0, value_similarity(&Value::Integer(-1), &Value::Integer(1)));
+        assert_eq!(0.75, value_similarity(&Value::new("abcd"), &Value::new("abed")));
+        assert_eq!(0.0, value_similarity(&Value::new("abcd"), &Value::Empty));
+    }
+}
@@ -0,0 +1,429 @@
+//! Test fixtures shared by the table backends.
+//!
+//! Small, hand-checked CSV files live in `tests/fixtures`; large files are generated into a temp directory
+//! with the `testdata` module. The `check_*` functions are the same assertions run against every backend.
+use std::path::PathBuf;
+
+use crate::{Agg, Table, TableOperations, TableSlice, Row, Value, Cmp, CancelToken};
+use crate::testdata::{CsvGenerator, ColumnKind};
+
+/// Path to a checked-in fixture
+pub fn path(name :&str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+/// Directory for files written by tests, unique to this test run
+pub fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("large_table_fixtures_{}", std::process::id()));
+
+    std::fs::create_dir_all(&dir).expect("Error creating fixture temp dir");
+
+    dir
+}
+
+/// Copies a checked-in fixture into the temp dir, for backends that open files read-write
+pub fn copy(name :&str) -> PathBuf {
+    let dest = temp_dir().join(format!("{}_{}", rand::random::<u32>(), name));
+
+    std::fs::copy(path(name), &dest).expect("Error copying fixture");
+
+    dest
+}
+
+/// Generates a CSV file with `rows` rows into the temp dir
+///
+/// The columns are `id` (the row number), `symbol` (10 distinct values), `price`, and `date`.
+pub fn generated(name :&str, rows :usize) -> PathBuf {
+    let dest = temp_dir().join(name);
+
+    CsvGenerator::new(rows)
+        .column("id", ColumnKind::Integer { min: 0, max: i64::MAX })
+        .column("symbol", ColumnKind::String { cardinality: 10 })
+        .column("price", ColumnKind::Float { min: 1.0, max: 1000.0 })
+        .column("date", ColumnKind::Date)
+        .seed(rows as u64)
+        .write_to_path(&dest)
+        .expect("Error generating fixture");
+
+    dest
+}
+
+/// Writes a CSV file with `columns` columns, `c0`, `c1`, ..., into the temp dir, with the value at row `r` and column
+/// `c` being `r * columns + c`, and every tenth one quoted
+pub fn wide(name :&str, columns :usize, rows :usize) -> PathBuf {
+    let dest = temp_dir().join(name);
+    let mut csv = (0..columns).map(|c| format!("c{}", c)).collect::<Vec<_>>().join(",");
+
+    for r in 0..rows {
+        csv.push('\n');
+        csv.push_str(&(0..columns).map(|c| {
+            let value = r * columns + c;
+
+            if value % 10 == 0 { format!("\"{}\"", value) } else { value.to_string() }
+        }).collect::<Vec<_>>().join(","));
+    }
+
+    std::fs::write(&dest, csv).expect("Error writing fixture");
+
+    dest
+}
+
+/// Checks a table loaded from a file made by `wide`
+pub fn check_wide<T: TableOperations>(table :&T, columns :usize, rows :usize) {
+    assert_eq!(rows, table.len());
+    assert_eq!(columns, table.width());
+    assert_eq!(format!("c{}", columns - 1), table.columns()[columns - 1]);
+
+    for (r, row) in table.iter().enumerate() {
+        assert_eq!(columns, row.width());
+        assert_eq!((0..columns).map(|c| Value::Integer((r * columns + c) as i64)).collect::<Vec<_>>(), (0..columns).map(|c| row.at(c)).collect::<Vec<_>>());
+    }
+}
+
+/// Checks a table loaded from `stocks.csv`
+pub fn check_load<T: TableOperations>(table :&T) {
+    assert_eq!(12, table.len());
+    assert_eq!(vec!["date", "symbol", "open", "close", "volume"], table.columns());
+
+    let row = table.get(0).unwrap();
+
+    assert_eq!(Value::new("2020-01-02"), row.get("date"));
+    assert_eq!(Value::String(String::from("AAPL")), row.get("symbol"));
+    assert_eq!(74.06, row.get("open").as_float());
+    assert_eq!(135480400, row.get("volume").as_integer());
+
+    let row = table.get(11).unwrap();
+
+    assert_eq!(Value::String(String::from("IBM")), row.get("symbol"));
+    assert_eq!(134.19, row.get("close").as_float());
+
+    assert!(table.get(12).is_err());
+
+    // the iterator knows its length, and can be walked from either end
+    let mut iter = table.iter();
+
+    assert_eq!(12, iter.len());
+    assert_eq!(Value::String(String::from("IBM")), iter.next_back().unwrap().get("symbol"));
+    assert_eq!(Value::String(String::from("AAPL")), iter.next().unwrap().get("symbol"));
+    assert_eq!((10, Some(10)), iter.size_hint());
+    assert_eq!(vec![Value::new("IBM"), Value::new("MSFT")], iter.rev().skip(8).map(|row| row.get("symbol")).collect::<Vec<_>>());
+}
+
+/// Checks filtering a table loaded from `stocks.csv`
+pub fn check_filter<T: TableOperations>(table :&T) {
+    let aapl = table.filter("symbol", &Value::String(String::from("AAPL"))).unwrap();
+
+    assert_eq!(4, aapl.len());
+    assert!(aapl.iter().all(|row| row.get("symbol") == Value::String(String::from("AAPL"))));
+
+    // borrowed values compare the same as owned ones
+    let symbol = table.column_position("symbol").unwrap();
+    let row = aapl.iter().next().unwrap();
+
+    assert_eq!(Some(true), row.with_ref(symbol, |v| v.as_str().map(|s| s == "AAPL")).unwrap());
+    assert_eq!(row.get("volume"), row.get_ref("volume"));
+    assert_eq!(row.at(symbol), row.at_ref(symbol).into_value());
+    assert!(row.with_ref(table.width(), |_| ()).is_err());
+
+    let big = table.filter_by(|row| row.get("volume").as_integer() > 100_000_000).unwrap();
+
+    assert_eq!(4, big.len());
+    assert_eq!(4, big.iter().len());
+    assert_eq!(108872000, big.iter().next_back().unwrap().get("volume").as_integer());
+    assert_eq!(big.iter().map(|row| row.get("volume")).collect::<Vec<_>>(), big.iter().rev().map(|row| row.get("volume")).rev().collect::<Vec<_>>());
+
+    // filtering a slice
+    assert_eq!(2, big.filter_by(|row| row.get("close").as_float() > 74.9).unwrap().len());
+
+    assert!(table.filter("missing", &Value::Empty).is_err());
+
+    let found = table.find("symbol", &Value::new("AAPL")).unwrap().find("date", &Value::new("2020-01-06")).unwrap();
+
+    assert_eq!(1, found.len());
+    assert_eq!(118387200, found.get(0).unwrap().get("volume").as_integer());
+    assert_eq!(0, aapl.find("symbol", &Value::new("IBM")).unwrap().len());
+    assert!(aapl.find("missing", &Value::Empty).is_err());
+
+    // comparisons and ranges
+    assert_eq!(4, table.filter_cmp("volume", Cmp::Gt, &Value::Integer(100_000_000)).unwrap().len());
+    assert_eq!(8, table.filter_cmp("symbol", Cmp::Ne, &Value::new("AAPL")).unwrap().len());
+    assert_eq!(0, table.filter_cmp("symbol", Cmp::Lt, &Value::Integer(1)).unwrap().len());
+    assert_eq!(6, table.filter_range("date", Value::new("2020-01-03")..Value::new("2020-01-07")).unwrap().len());
+    assert_eq!(9, table.filter_range("date", Value::new("2020-01-03")..).unwrap().len());
+    assert_eq!(3, big.filter_range("open", ..=Value::Float(74.29.into())).unwrap().len());
+    assert!(table.filter_range("missing", ..Value::Empty).is_err());
+
+    assert_eq!(2, table.query("volume > 100000000 && (date == '2020-01-02' || date == '2020-01-03')").unwrap().len());
+    assert_eq!(2, big.query("close > 74.9").unwrap().len());
+    assert!(table.query("volume >").is_err());
+}
+
+/// Checks sorting a table loaded from `stocks.csv`
+pub fn check_sort<T: TableOperations>(table :&T) {
+    let sorted = table.filter_by(|_| true).unwrap().sort(&["symbol", "date"]).unwrap();
+
+    assert_eq!(12, sorted.len());
+
+    let first = sorted.get(0).unwrap();
+    let last = sorted.get(11).unwrap();
+
+    assert_eq!(Value::String(String::from("AAPL")), first.get("symbol"));
+    assert_eq!(Value::new("2020-01-02"), first.get("date"));
+    assert_eq!(Value::String(String::from("MSFT")), last.get("symbol"));
+    assert_eq!(Value::new("2020-01-07"), last.get("date"));
+
+    let by_close = table.filter_by(|_| true).unwrap().sort(&["close"]).unwrap();
+
+    assert_eq!(74.29, by_close.get(0).unwrap().get("open").as_float());
+}
+
+/// Checks that a token stops sorting, grouping, and filtering a table loaded from `stocks.csv` once it's cancelled
+pub fn check_cancel<T: TableOperations>(table :&T) {
+    let token = CancelToken::new();
+
+    assert_eq!(12, table.filter_by_with_cancel(&token, |_| true).unwrap().len());
+    assert_eq!(3, table.group_by_with_cancel("symbol", &token).unwrap().len());
+    assert_eq!(4, table.group_by_with_cancel("symbol", &token).unwrap()[&Value::new("AAPL")].len());
+
+    let sorted = table.filter_by(|_| true).unwrap().sort_with_cancel(&["close"], &token).unwrap();
+
+    assert_eq!(74.29, sorted.get(0).unwrap().get("open").as_float());
+
+    // cancelling a clone cancels the token
+    token.clone().cancel();
+
+    assert!(table.filter_by_with_cancel(&token, |_| true).err().unwrap().is_cancelled());
+    assert!(table.group_by_with_cancel("symbol", &token).err().unwrap().is_cancelled());
+    assert!(table.filter_by(|_| true).unwrap().sort_with_cancel(&["close"], &token).err().unwrap().is_cancelled());
+    assert!(!table.group_by_with_cancel("missing", &token).err().unwrap().is_cancelled());
+}
+
+/// Checks the `str_` methods on a table loaded from `stocks.csv`
+pub fn check_strings<T: TableOperations>(table :&T) {
+    assert_eq!(4, table.str_contains("symbol", "S").unwrap().len());
+    assert_eq!(Value::new("IBM"), table.str_contains("symbol", "B").unwrap().get(0).unwrap().get("symbol"));
+    assert_eq!(8, table.str_startswith("symbol", "A").unwrap().len() + table.str_startswith("symbol", "I").unwrap().len());
+    assert_eq!(0, table.str_startswith("symbol", "a").unwrap().len());
+
+    // values of other types are formatted
+    assert_eq!(3, table.str_contains("date", "-01-02").unwrap().len());
+    assert_eq!(4, table.str_startswith("open", "15").unwrap().len());
+
+    assert_eq!(vec![Value::Integer(4), Value::Integer(4), Value::Integer(3)], table.str_len("symbol").unwrap()[..3].to_vec());
+    assert_eq!(Value::new("aapl"), table.str_lower("symbol").unwrap()[0]);
+    assert_eq!(Value::String("2020/01/02".to_string()), table.str_replace("date", "-", "/").unwrap()[0]);
+    assert_eq!(Value::String("01".to_string()), table.str_split_extract("date", "-", 1).unwrap()[0]);
+    assert_eq!(Value::String("42".to_string()), table.str_split_extract("close", ".", 1).unwrap()[2]);
+    assert_eq!(Value::Empty, table.str_split_extract("symbol", "-", 1).unwrap()[0]);
+    assert_eq!(table.len(), table.str_lower("symbol").unwrap().len());
+
+    assert!(table.str_contains("missing", "A").is_err());
+    assert!(table.str_len("missing").is_err());
+}
+
+/// Checks grouping a table loaded from `stocks.csv`
+pub fn check_group<T: TableOperations>(table :&T) {
+    let groups = table.group_by("symbol").unwrap();
+
+    assert_eq!(3, groups.len());
+    assert!(groups.values().all(|g| g.len() == 4));
+
+    let msft = &groups[&Value::String(String::from("MSFT"))];
+
+    assert!(msft.iter().all(|row| row.get("symbol") == Value::String(String::from("MSFT"))));
+
+    assert!(table.group_by("missing").is_err());
+
+    let by_day = table.group_by_multi(&["symbol", "date"]).unwrap();
+
+    assert_eq!(12, by_day.len());
+    assert_eq!(Value::Integer(20813700), by_day[&vec![Value::new("MSFT"), Value::new("2020-01-06")]].get(0).unwrap().get("volume"));
+
+    let by_symbol = table.group_by_multi(&["symbol"]).unwrap();
+
+    assert_eq!(3, by_symbol.len());
+    assert_eq!(4, by_symbol[&vec![Value::new("IBM")]].len());
+
+    assert!(table.group_by_multi(&["symbol", "missing"]).is_err());
+    assert!(table.group_by_multi(&[]).is_err());
+
+    let groups = table.groups("symbol").unwrap();
+
+    assert_eq!(vec![&Value::new("AAPL"), &Value::new("IBM"), &Value::new("MSFT")], groups.keys());
+    assert!(groups.size().iter().all(|(_, size)| *size == 4));
+
+    let walked = groups.iter_groups().map(|g| g.map(|(key, rows)| (key.clone(), rows.len()))).collect::<Result<Vec<_>, _>>().unwrap();
+
+    assert_eq!(vec![(Value::new("AAPL"), 4), (Value::new("IBM"), 4), (Value::new("MSFT"), 4)], walked);
+
+    let first = groups.first().unwrap();
+
+    assert_eq!(3, first.len());
+    assert_eq!(Value::new("IBM"), first.get(1).unwrap().get("symbol"));
+    assert_eq!(Value::new("2020-01-02"), first.get(1).unwrap().get("date"));
+    assert_eq!(Value::new("2020-01-07"), groups.last().unwrap().get(2).unwrap().get("date"));
+    assert_eq!(Value::new("2020-01-06"), groups.nth(2).unwrap().get(0).unwrap().get("date"));
+    assert_eq!(0, groups.nth(4).unwrap().len());
+
+    assert_eq!(4, groups.get(&Value::new("MSFT")).unwrap().len());
+    assert_eq!(0, groups.get(&Value::new("GOOG")).unwrap().len());
+    assert_eq!(Value::Integer(3148600), groups.agg("volume", Agg::Max).unwrap()[1].1);
+
+    assert!(table.groups("missing").is_err());
+    assert!(groups.agg("missing", Agg::Max).is_err());
+}
+
+/// Checks a table loaded from a file made by `generated`
+pub fn check_generated<T: TableOperations>(table :&T, rows :usize) {
+    assert_eq!(rows, table.len());
+    assert_eq!(vec!["id", "symbol", "price", "date"], table.columns());
+
+    assert_eq!(10, table.unique("symbol").unwrap().len());
+
+    let groups = table.group_by("symbol").unwrap();
+
+    assert_eq!(rows, groups.values().map(|g| g.len()).sum::<usize>());
+
+    let sorted = table.filter_by(|_| true).unwrap().sort(&["price"]).unwrap();
+
+    assert!(sorted.get(0).unwrap().get("price") <= sorted.get(rows - 1).unwrap().get("price"));
+}
+
+/// Checks reading `stocks.csv` through typed column handles
+pub fn check_column_handle<T: TableOperations>(table :&T) {
+    let open = table.column_handle::<f64>("open").unwrap();
+    let volume = table.column_handle::<i64>("volume").unwrap();
+    let symbol = table.column_handle::<String>("symbol").unwrap();
+
+    let high = table.filter_by(|row| row.value(&open) > 150.0).unwrap();
+
+    assert_eq!(4, high.len());
+    assert!(high.iter().all(|row| row.value(&symbol) == "MSFT"));
+    assert_eq!(135480400, table.get(0).unwrap().value(&volume));
+
+    // the wrong type is an error, and so is a column that doesn't exist
+    assert!(table.get(0).unwrap().try_value(&table.column_handle::<bool>("open").unwrap()).is_err());
+    assert!(table.column_handle::<f64>("missing").is_err());
+    assert!(table.get(0).unwrap().try_at(5).is_err());
+}
+
+/// Checks indexing a table loaded from `stocks.csv`
+pub fn check_index<T: TableOperations>(table :&T) {
+    let index = table.create_index("symbol").unwrap();
+
+    assert_eq!(3, index.len());
+    assert_eq!(4, index.lookup(&Value::String(String::from("AAPL"))).unwrap().len());
+    assert_eq!(0, index.lookup(&Value::String(String::from("GOOG"))).unwrap().len());
+
+    let index = table.create_index("volume").unwrap();
+    let range = index.lookup_range(&Value::Integer(3_000_000), &Value::Integer(50_000_000)).unwrap();
+
+    assert_eq!(6, range.len());
+    assert_eq!(Value::String(String::from("MSFT")), range.get(0).unwrap().get("symbol"));
+    assert_eq!(Value::Integer(3148600), range.get(1).unwrap().get("volume"));
+
+    assert!(table.create_index("missing").is_err());
+    assert!(table.take_rows(&[12]).is_err());
+    assert_eq!(Value::String(String::from("IBM")), table.take_rows(&[5, 0]).unwrap().get(0).unwrap().get("symbol"));
+}
+
+pub fn check_split<T: TableOperations>(table :&T) {
+    let (first, second) = table.split_rows_at(4).unwrap();
+
+    assert_eq!(4, first.len());
+    assert_eq!(8, second.len());
+    assert_eq!(table.get(4).unwrap().get("symbol"), second.get(0).unwrap().get("symbol"));
+
+    // splitting a slice keeps the slice's rows
+    let by_close = table.filter_by(|_| true).unwrap().sort(&["close"]).unwrap();
+    let (lowest, rest) = by_close.split_rows_at(1).unwrap();
+
+    assert_eq!(74.29, lowest.get(0).unwrap().get("open").as_float());
+    assert_eq!(by_close.get(1).unwrap().get("open"), rest.get(0).unwrap().get("open"));
+
+    assert!(table.split_rows_at(12).is_err());
+}
+
+/// Checks the statistics, which the caller has computed, of a table loaded from `stocks.csv`
+pub fn check_stats<T: Table>(table :&mut T) {
+    let date = table.column_stats("date").unwrap();
+
+    assert_eq!(Some(Value::new("2020-01-02")), date.min);
+    assert_eq!(Some(Value::new("2020-01-07")), date.max);
+    assert_eq!((12, 0), (date.count, date.null_count));
+    assert_eq!(Some(Value::Float(74.36.into())), table.column_stats("close").unwrap().min);
+    assert!(table.column_stats("missing").is_none());
+
+    // the same rows match with the statistics as without them
+    check_filter(table);
+
+    assert_eq!(0, table.filter_range("date", Value::new("2021-01-01")..).unwrap().len());
+    assert_eq!(0, table.filter_range("date", ..Value::new("2020-01-02")).unwrap().len());
+    assert_eq!(3, table.filter_range("date", ..=Value::new("2020-01-02")).unwrap().len());
+    assert_eq!(0, table.filter_range("close", Value::new("AAPL")..).unwrap().len());
+
+    // changing a value clears the statistics
+    table.get(0).unwrap().set("close", Value::Float(999.99.into())).unwrap();
+
+    assert!(table.column_stats("close").is_none());
+    assert_eq!(1, table.filter_range("close", Value::Float(900.0.into())..).unwrap().len());
+}
+
+/// Checks sampling a table loaded from `stocks.csv`
+#[cfg(feature = "sampling")]
+pub fn check_sample<T: TableOperations>(table :&T) {
+    let dates = |slice :&T::TableSliceType| slice.iter().map(|r| (r.get("date"), r.get("symbol"))).collect::<Vec<_>>();
+
+    let sample = table.sample_n(5, 42).unwrap();
+
+    assert_eq!(5, sample.len());
+    assert_eq!(dates(&sample), dates(&table.sample_n(5, 42).unwrap()));
+    assert!(dates(&sample).windows(2).all(|w| w[0].0 <= w[1].0));
+    assert_eq!(12, table.sample_n(100, 42).unwrap().len());
+
+    assert_eq!(3, table.sample_frac(0.25, 1).unwrap().len());
+    assert_eq!(0, table.sample_frac(0.0, 1).unwrap().len());
+    assert_eq!(12, table.sample_frac(1.0, 1).unwrap().len());
+    assert!(table.sample_frac(1.5, 1).is_err());
+
+    let stratified = table.stratified_sample("symbol", 2, 7).unwrap();
+    let groups = stratified.group_by("symbol").unwrap();
+
+    assert_eq!(6, stratified.len());
+    assert!(groups.values().all(|g| g.len() == 2));
+    assert_eq!(dates(&stratified), dates(&table.stratified_sample("symbol", 2, 7).unwrap()));
+    assert_eq!(12, table.stratified_sample("symbol", 10, 7).unwrap().len());
+    assert!(table.stratified_sample("missing", 2, 7).is_err());
+
+    let count = |folds :&[Value], fold :i64| folds.iter().filter(|f| **f == Value::Integer(fold)).count();
+    let folds = table.kfold(5, 3, None).unwrap();
+
+    assert_eq!(12, folds.len());
+    assert_eq!(vec![3, 3, 2, 2, 2], (0..5).map(|f| count(&folds, f)).collect::<Vec<_>>());
+    assert_eq!(folds, table.kfold(5, 3, None).unwrap());
+
+    let folds = table.kfold(4, 3, Some("symbol")).unwrap();
+
+    // each of the 3 symbols has a row in each fold
+    for fold in 0..4 {
+        let symbols = table.iter().zip(folds.iter()).filter(|(_, f)| **f == Value::Integer(fold)).map(|(r, _)| r.get("symbol")).collect::<std::collections::HashSet<_>>();
+
+        assert_eq!(3, symbols.len());
+    }
+
+    assert!(table.kfold(1, 3, None).is_err());
+    assert!(table.kfold(4, 3, Some("missing")).is_err());
+}
+
+pub fn check_rename<T: Table>(table :&mut T) {
+    let slice = table.filter_by(|_| true).unwrap().rename_column("close", "last").unwrap();
+
+    assert!(slice.columns().contains(&String::from("last")));
+    assert_eq!(table.get(0).unwrap().get("close"), slice.get(0).unwrap().get("last"));
+
+    table.rename_column("volume", "shares").unwrap();
+
+    assert_eq!(vec!["date", "symbol", "open", "close", "shares"], table.columns());
+    assert_eq!(table.get(0).unwrap().get("shares"), slice.get(0).unwrap().get("volume"));
+    assert!(table.rename_column("volume", "shares").is_err());
+}
@@ -0,0 +1,161 @@
+//! Gaps in time series, where rows are missing at the interval they're expected at.
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Error as FmtError};
+
+use chrono::Duration;
+
+use crate::{TableOperations, TableError, Row, Value};
+#[cfg(feature = "rowtable")]
+use crate::RowTable;
+
+/// A run of missing rows in a time series
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gap {
+    /// The value of the group column, or `Value::Empty` without one
+    pub group: Value,
+    /// The first missing time
+    pub start: Value,
+    /// The last missing time
+    pub end: Value,
+    /// The number of missing intervals
+    pub missing: i64
+}
+
+/// The gaps in a time series, and how complete it is, created with
+/// [`TableOperations::find_gaps`](trait.TableOperations.html#method.find_gaps).
+///
+/// A series is expected to have a row every interval from its first time to its last; the gaps are sorted by group,
+/// then by time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gaps {
+    by: Option<String>,
+    gaps: Vec<Gap>,
+    present: usize,
+    expected: i64
+}
+
+impl Gaps {
+    pub(crate) fn new<T: TableOperations + ?Sized>(table :&T, time_column :&str, interval :Duration, by :Option<&str>) -> Result<Self, TableError> {
+        if interval <= Duration::zero() {
+            return Err(TableError::new("The interval of a time series must be positive"));
+        }
+
+        let time_pos = table.column_position(time_column)?;
+        let by_pos = by.map(|b| table.column_position(b)).transpose()?;
+        let mut series :BTreeMap<Value, Vec<Value>> = BTreeMap::new();
+
+        for row in table.iter() {
+            let time = row.try_at(time_pos)?;
+
+            if time == Value::Empty {
+                continue;
+            }
+
+            let group = match by_pos {
+                Some(pos) => row.try_at(pos)?,
+                None => Value::Empty
+            };
+
+            series.entry(group).or_default().push(time);
+        }
+
+        let interval_value = Value::Duration(interval);
+        let (mut gaps, mut present, mut expected) = (Vec::new(), 0, 0);
+
+        for (group, mut times) in series {
+            times.sort();
+            times.dedup();
+
+            present += times.len();
+            expected += intervals(&times[times.len() - 1], &times[0], interval)? + 1;
+
+            for pair in times.windows(2) {
+                let missing = intervals(&pair[1], &pair[0], interval)? - 1;
+
+                if missing > 0 {
+                    gaps.push(Gap {
+                        group: group.clone(),
+                        start: pair[0].try_add(&interval_value)?,
+                        end: pair[0].try_add(&Value::Duration(scaled(interval, missing)?))?,
+                        missing
+                    });
+                }
+            }
+        }
+
+        Ok(Gaps { by: by.map(String::from), gaps, present, expected })
+    }
+
+    pub fn gaps(&self) -> &[Gap] {
+        &self.gaps
+    }
+
+    pub fn len(&self) -> usize {
+        self.gaps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gaps.is_empty()
+    }
+
+    /// The total number of missing intervals
+    pub fn missing(&self) -> i64 {
+        self.gaps.iter().map(|g| g.missing).sum()
+    }
+
+    /// The fraction of the expected times that have a row, 1.0 when nothing is missing
+    pub fn completeness(&self) -> f64 {
+        if self.expected == 0 { 1.0 } else { self.present as f64 / self.expected as f64 }
+    }
+
+    /// Creates a `RowTable` of the gaps, with the group column when there is one, then `start`, `end`, and `missing`.
+    #[cfg(feature = "rowtable")]
+    pub fn to_table(&self) -> RowTable {
+        let mut columns = self.by.iter().cloned().collect::<Vec<_>>();
+
+        columns.extend(["start", "end", "missing"].iter().map(|c| c.to_string()));
+
+        let rows = self.gaps.iter().map(|g| {
+            let mut row = if self.by.is_some() { vec![g.group.clone()] } else { Vec::new() };
+
+            row.extend(vec![g.start.clone(), g.end.clone(), Value::Integer(g.missing)]);
+            row
+        }).collect();
+
+        RowTable::from_rows(columns, rows)
+    }
+}
+
+/// The number of whole `interval`s from `from` to `to`
+fn intervals(to :&Value, from :&Value, interval :Duration) -> Result<i64, TableError> {
+    let elapsed = to.try_sub(from)?.as_duration();
+
+    match (elapsed.num_microseconds(), interval.num_microseconds()) {
+        (Some(e), Some(i)) => Ok(e / i),
+        _ => Ok(elapsed.num_seconds() / interval.num_seconds().max(1))
+    }
+}
+
+/// `interval` times `n`, or an error when it's out of the range of a `Duration`
+fn scaled(interval :Duration, n :i64) -> Result<Duration, TableError> {
+    interval.num_microseconds().and_then(|us| us.checked_mul(n)).map(Duration::microseconds).ok_or_else(|| {
+        let err_str = format!("A gap of {} intervals of {} is out of range", n, interval);
+        TableError::new(err_str.as_str())
+    })
+}
+
+impl Display for Gaps {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        writeln!(f, "gaps: {}, missing: {}, completeness: {:.2}%", self.gaps.len(), self.missing(), self.completeness() * 100.0)?;
+
+        for gap in self.gaps.iter() {
+            if self.by.is_some() {
+                write!(f, "{}: ", gap.group)?;
+            }
+
+            writeln!(f, "{} to {} ({} missing)", gap.start, gap.end, gap.missing)?;
+        }
+
+        Ok( () )
+    }
+}
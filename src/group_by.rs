@@ -0,0 +1,98 @@
+//! Groups of rows walked in key order, with the first, last, or nth row of each and aggregates over them.
+use std::collections::BTreeMap;
+
+use crate::{Agg, TableOperations, TableError, Row, Value};
+
+/// The rows of a table grouped by their value in one column, with the groups in key order, created with
+/// [`TableOperations::groups`](trait.TableOperations.html#method.groups).
+///
+/// Unlike the `HashMap` from `group_by`, the groups can be walked in order, and the rows of each group stay in table
+/// order, so "the first trade of each symbol" is `first()`. The groups are not updated when the table changes.
+pub struct GroupBy<'a, T: TableOperations> {
+    table: &'a T,
+    column: String,
+    groups: Vec<(Value, Vec<usize>)>  // key -> positions of the rows in the table, sorted by key
+}
+
+impl <'a, T: TableOperations> GroupBy<'a, T> {
+    pub(crate) fn new(table :&'a T, column :&str) -> Result<Self, TableError> {
+        let pos = table.column_position(column)?;
+        let mut groups :BTreeMap<Value, Vec<usize>> = BTreeMap::new();
+
+        for (i, row) in table.iter().enumerate() {
+            groups.entry(row.try_at(pos)?).or_default().push(i);
+        }
+
+        Ok(GroupBy { table, column: column.to_string(), groups: groups.into_iter().collect() })
+    }
+
+    /// The column the rows are grouped by
+    pub fn column(&self) -> &str {
+        self.column.as_str()
+    }
+
+    /// The number of groups
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The keys of the groups, in order
+    pub fn keys(&self) -> Vec<&Value> {
+        self.groups.iter().map(|(k, _)| k).collect()
+    }
+
+    /// The number of rows in each group, in key order
+    pub fn size(&self) -> Vec<(&Value, usize)> {
+        self.groups.iter().map(|(k, rows)| (k, rows.len())).collect()
+    }
+
+    /// Returns a `TableSlice` of the rows in the group with `key`, which is empty when there's no such group.
+    pub fn get(&self, key :&Value) -> Result<T::TableSliceType, TableError> {
+        match self.groups.binary_search_by(|(k, _)| k.cmp(key)) {
+            Ok(i) => self.table.take_rows(&self.groups[i].1),
+            Err(_) => self.table.take_rows(&[])
+        }
+    }
+
+    /// Iterates over the groups in key order, as their key and a `TableSlice` of their rows.
+    pub fn iter_groups(&self) -> impl Iterator<Item=Result<(&Value, T::TableSliceType), TableError>> + '_ {
+        self.groups.iter().map(move |(k, rows)| Ok( (k, self.table.take_rows(rows)?) ))
+    }
+
+    /// Returns a `TableSlice` of the first row of each group, in key order.
+    pub fn first(&self) -> Result<T::TableSliceType, TableError> {
+        self.nth(0)
+    }
+
+    /// Returns a `TableSlice` of the last row of each group, in key order.
+    pub fn last(&self) -> Result<T::TableSliceType, TableError> {
+        let rows = self.groups.iter().filter_map(|(_, rows)| rows.last().cloned()).collect::<Vec<_>>();
+
+        self.table.take_rows(&rows)
+    }
+
+    /// Returns a `TableSlice` of the `n`th row, from 0, of each group, in key order; groups with `n` rows or fewer are
+    /// left out.
+    pub fn nth(&self, n :usize) -> Result<T::TableSliceType, TableError> {
+        let rows = self.groups.iter().filter_map(|(_, rows)| rows.get(n).cloned()).collect::<Vec<_>>();
+
+        self.table.take_rows(&rows)
+    }
+
+    /// `agg` of the values of `column` in each group, in key order.
+    pub fn agg(&self, column :&str, agg :Agg) -> Result<Vec<(&Value, Value)>, TableError> {
+        let pos = self.table.column_position(column)?;
+        let mut values = self.table.iter().map(|row| row.try_at(pos)).collect::<Result<Vec<_>, _>>()?;
+
+        // each row is in one group, so its value is moved there
+        self.groups.iter().map(|(k, rows)| {
+            let group = rows.iter().map(|&i| std::mem::replace(&mut values[i], Value::Empty)).collect::<Vec<_>>();
+
+            Ok( (k, agg.apply(&group)?) )
+        }).collect()
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use crate::{TableOperations, TableError, Row, Value};
+
+/// An index of the rows of a table by the values in one column, created with
+/// [`TableOperations::create_index`](trait.TableOperations.html#method.create_index).
+///
+/// Building the index scans the table once; after that `lookup` is a hash lookup instead of a scan of every row.
+/// The index is not updated when the table changes.
+pub struct TableIndex<'a, T: TableOperations> {
+    table: &'a T,
+    column: String,
+    rows: HashMap<Value, Vec<usize>>,  // value -> positions of the rows in the table
+    keys: Vec<Value>                   // the values in the index, sorted for range lookups
+}
+
+impl <'a, T: TableOperations> TableIndex<'a, T> {
+    pub(crate) fn new(table :&'a T, column :&str) -> Result<Self, TableError> {
+        // make sure the column is there
+        table.column_position(column)?;
+
+        let mut rows = HashMap::new();
+
+        for (i, row) in table.iter().enumerate() {
+            rows.entry(row.get(column)).or_insert_with(Vec::new).push(i);
+        }
+
+        let mut keys = rows.keys().cloned().collect::<Vec<_>>();
+
+        keys.sort_unstable();
+
+        Ok(TableIndex { table, column: column.to_string(), rows, keys })
+    }
+
+    /// The column this index was built on
+    pub fn column(&self) -> &str {
+        self.column.as_str()
+    }
+
+    /// The number of distinct values in the column
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns a `TableSlice` of the rows where the column is equal to `value`.
+    pub fn lookup(&self, value :&Value) -> Result<T::TableSliceType, TableError> {
+        match self.rows.get(value) {
+            Some(rows) => self.table.take_rows(rows),
+            None => self.table.take_rows(&[])
+        }
+    }
+
+    /// Returns a `TableSlice` of the rows where the column is in the range `[start, end)`, in table order.
+    ///
+    /// Values are compared using the ordering of [`Value`](enum.Value.html), so this is meant for columns of one type.
+    pub fn lookup_range(&self, start :&Value, end :&Value) -> Result<T::TableSliceType, TableError> {
+        let first = self.keys.partition_point(|k| k < start);
+        let last = self.keys.partition_point(|k| k < end).max(first);
+
+        let mut rows = self.keys[first..last].iter().flat_map(|k| self.rows[k].iter().cloned()).collect::<Vec<_>>();
+
+        rows.sort_unstable();
+
+        self.table.take_rows(&rows)
+    }
+}
@@ -0,0 +1,249 @@
+//! Joins of two tables on key columns.
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{Display, Formatter, Error as FmtError};
+use std::hash::{Hash, Hasher};
+
+use crate::{TableOperations, TableError, Row, Value};
+
+/// The keys of one side of a join, in [`JoinDiagnostics`](struct.JoinDiagnostics.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyStats {
+    /// Number of rows in the table
+    pub rows: usize,
+    /// Number of distinct keys, not counting null keys
+    pub distinct_keys: usize,
+    /// Number of rows with an empty value in any of the key columns, which never match
+    pub null_keys: usize
+}
+
+/// What joining two tables on their key columns would do, computed from the counts of the keys on each side without
+/// running the join, by [`TableOperations::join_diagnostics`](trait.TableOperations.html#method.join_diagnostics).
+///
+/// From [`join_diagnostics_sampled`](trait.TableOperations.html#method.join_diagnostics_sampled), only a fraction of
+/// the keys are counted, and the key and row counts are estimates scaled up from them, except for the number of rows
+/// and null keys, which are exact; `max_fanout` is the most among the counted keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinDiagnostics {
+    pub left: KeyStats,
+    pub right: KeyStats,
+    /// Number of distinct keys found on both sides
+    pub matched_keys: usize,
+    /// Fraction of the rows on the left, with non-null keys, whose key is on the right
+    pub match_rate: f64,
+    /// Number of rows an inner join would have
+    pub inner_rows: usize,
+    /// The most rows on the right with the same key as a row on the left; more than 1 means rows on the left are repeated
+    pub max_fanout: usize,
+    /// The fraction of the keys counted, 1 when the counts are exact
+    pub fraction: f64
+}
+
+impl JoinDiagnostics {
+    /// Whether both sides have repeated keys, so the join multiplies rows, which is usually a mistake in the keys.
+    pub fn is_many_to_many(&self) -> bool {
+        self.left.distinct_keys < self.left.rows - self.left.null_keys && self.right.distinct_keys < self.right.rows - self.right.null_keys
+    }
+}
+
+impl Display for JoinDiagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        writeln!(f, "{:<6}  {:>12}  {:>12}  {:>12}", "", "Rows", "Keys", "Null Keys")?;
+        writeln!(f, "{:<6}  {:>12}  {:>12}  {:>12}", "Left", self.left.rows, self.left.distinct_keys, self.left.null_keys)?;
+        writeln!(f, "{:<6}  {:>12}  {:>12}  {:>12}", "Right", self.right.rows, self.right.distinct_keys, self.right.null_keys)?;
+        writeln!(f, "matched keys: {}, match rate: {:.1}%", self.matched_keys, self.match_rate * 100.0)?;
+        writeln!(f, "inner join rows: {}, max fanout: {}", self.inner_rows, self.max_fanout)?;
+
+        if self.fraction < 1.0 {
+            writeln!(f, "estimated from {:.1}% of the keys", self.fraction * 100.0)?;
+        }
+
+        Ok( () )
+    }
+}
+
+/// The number of rows with each non-null key, and the number of rows with a null key.
+pub(crate) fn key_counts<T: TableOperations + ?Sized>(table :&T, keys :&[&str]) -> Result<(HashMap<Vec<Value>, usize>, usize), TableError> {
+    sampled_key_counts(table, keys, 1.0).map(|counts| (counts.keys, counts.null_keys))
+}
+
+/// The counts of the keys of one side of a join
+struct KeyCounts {
+    keys: HashMap<Vec<Value>, usize>,  // the number of rows with each counted key
+    null_keys: usize,
+    rows: usize
+}
+
+/// The number of rows with each non-null key that's in the `fraction` of keys picked by their hash, which are the same
+/// keys for both sides of a join; and the number of rows, and of rows with a null key.
+fn sampled_key_counts<T: TableOperations + ?Sized>(table :&T, keys :&[&str], fraction :f64) -> Result<KeyCounts, TableError> {
+    let positions = keys.iter().map(|k| table.column_position(k)).collect::<Result<Vec<_>, _>>()?;
+    let mut counts = KeyCounts { keys: HashMap::new(), null_keys: 0, rows: 0 };
+
+    for row in table.iter() {
+        let key = positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+
+        counts.rows += 1;
+
+        if key.contains(&Value::Empty) {
+            counts.null_keys += 1;
+        } else if fraction >= 1.0 || is_sampled(&key, fraction) {
+            *counts.keys.entry(key).or_insert(0) += 1;
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Whether the hash of `key` falls in the first `fraction` of the hashes
+fn is_sampled(key :&[Value], fraction :f64) -> bool {
+    let mut hasher = DefaultHasher::new();
+
+    key.hash(&mut hasher);
+
+    (hasher.finish() as f64) < fraction * u64::MAX as f64
+}
+
+pub(crate) fn diagnostics<L: TableOperations + ?Sized, R: TableOperations>(left :&L, right :&R, keys :&[&str], fraction :f64) -> Result<JoinDiagnostics, TableError> {
+    if keys.is_empty() {
+        return Err(TableError::new("A join needs at least one key column"));
+    }
+
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        let err_str = format!("The fraction of keys sampled must be above 0, and at most 1: {}", fraction);
+        return Err(TableError::new(err_str.as_str()));
+    }
+
+    let left_counts = sampled_key_counts(left, keys, fraction)?;
+    let right_counts = sampled_key_counts(right, keys, fraction)?;
+
+    let (mut matched_keys, mut matched_rows, mut inner_rows, mut max_fanout) = (0, 0, 0, 0);
+
+    for (key, &count) in left_counts.keys.iter() {
+        if let Some(&right_count) = right_counts.keys.get(key) {
+            matched_keys += 1;
+            matched_rows += count;
+            inner_rows += count * right_count;
+            max_fanout = max_fanout.max(right_count);
+        }
+    }
+
+    let counted_rows = left_counts.keys.values().sum::<usize>();
+    let scale = |n :usize| (n as f64 / fraction).round() as usize;
+
+    Ok(JoinDiagnostics {
+        left: KeyStats { rows: left_counts.rows, distinct_keys: scale(left_counts.keys.len()), null_keys: left_counts.null_keys },
+        right: KeyStats { rows: right_counts.rows, distinct_keys: scale(right_counts.keys.len()), null_keys: right_counts.null_keys },
+        matched_keys: scale(matched_keys),
+        match_rate: if counted_rows == 0 { 0.0 } else { matched_rows as f64 / counted_rows as f64 },
+        inner_rows: scale(inner_rows),
+        max_fanout,
+        fraction
+    })
+}
+
+/// Positions of the rows of `left` whose key is, or with `matched` false isn't, in `right`; a null key never matches.
+pub(crate) fn matching_rows<L: TableOperations + ?Sized, R: TableOperations>(left :&L, right :&R, keys :&[&str], matched :bool) -> Result<Vec<usize>, TableError> {
+    if keys.is_empty() {
+        return Err(TableError::new("A join needs at least one key column"));
+    }
+
+    let positions = keys.iter().map(|k| left.column_position(k)).collect::<Result<Vec<_>, _>>()?;
+    let mut rows = Vec::new();
+
+    // a single key declared unique on the right is already indexed
+    if let (&[_], Some(index)) = (keys, right.unique_index(keys[0])) {
+        for (i, row) in left.iter().enumerate() {
+            if index.contains_key(&row.try_at(positions[0])?) == matched {
+                rows.push(i);
+            }
+        }
+
+        return Ok(rows);
+    }
+
+    let (right_keys, _) = key_counts(right, keys)?;
+
+    for (i, row) in left.iter().enumerate() {
+        let key = positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+
+        if right_keys.contains_key(&key) == matched {
+            rows.push(i);
+        }
+    }
+
+    Ok(rows)
+}
+
+#[cfg(all(test, feature = "rowtable"))]
+mod tests {
+    use crate::{RowTable, TableOperations, Row, Value};
+
+    #[test]
+    fn join_diagnostics() {
+        let trades = RowTable::from_csv_bytes(b"exchange,symbol,qty\nN,AAPL,1\nN,AAPL,2\nQ,MSFT,3\nQ,IBM,4\n,AAPL,5\n").unwrap();
+        let quotes = RowTable::from_csv_bytes(b"exchange,symbol,bid\nN,AAPL,75\nN,AAPL,76\nN,AAPL,77\nQ,MSFT,158\nN,GOOG,1400\n").unwrap();
+
+        let diag = trades.join_diagnostics(&quotes, &["exchange", "symbol"]).unwrap();
+
+        assert_eq!((5, 3, 1), (diag.left.rows, diag.left.distinct_keys, diag.left.null_keys));
+        assert_eq!((5, 3, 0), (diag.right.rows, diag.right.distinct_keys, diag.right.null_keys));
+        assert_eq!(2, diag.matched_keys);
+        assert_eq!(0.75, diag.match_rate);
+        assert_eq!(2 * 3 + 1, diag.inner_rows);
+        assert_eq!(3, diag.max_fanout);
+        assert!(diag.is_many_to_many());
+        assert!(diag.to_string().contains("match rate: 75.0%"));
+
+        let by_symbol = quotes.join_diagnostics(&trades.filter_by(|_| true).unwrap(), &["symbol"]).unwrap();
+
+        assert_eq!(3 * 3 + 1, by_symbol.inner_rows);
+
+        assert!(trades.join_diagnostics(&quotes, &["qty"]).is_err());
+        assert!(trades.join_diagnostics(&quotes, &[]).is_err());
+    }
+
+    #[test]
+    fn join_diagnostics_sampled() {
+        let left = RowTable::from_rows(vec!["id".to_string()], (0..20_000).map(|i| vec![Value::Integer(i)]).collect());
+        let right = RowTable::from_rows(vec!["id".to_string()], (0..20_000).filter(|i| i % 4 != 0).map(|i| vec![Value::Integer(i)]).collect());
+
+        let exact = left.join_diagnostics(&right, &["id"]).unwrap();
+        let sampled = left.join_diagnostics_sampled(&right, &["id"], 0.1).unwrap();
+
+        assert_eq!(0.75, exact.match_rate);
+        assert_eq!(1.0, exact.fraction);
+        assert_eq!((20_000, 0), (sampled.left.rows, sampled.left.null_keys));
+        assert!((sampled.match_rate - 0.75).abs() < 0.03, "{}", sampled.match_rate);
+        assert!((sampled.left.distinct_keys as f64 - 20_000.0).abs() < 1_500.0, "{}", sampled.left.distinct_keys);
+        assert!((sampled.inner_rows as f64 - 15_000.0).abs() < 1_500.0, "{}", sampled.inner_rows);
+        assert!(sampled.to_string().contains("estimated from 10.0% of the keys"));
+
+        assert_eq!(exact, left.join_diagnostics_sampled(&right, &["id"], 1.0).unwrap());
+        assert!(left.join_diagnostics_sampled(&right, &["id"], 0.0).is_err());
+        assert!(left.join_diagnostics_sampled(&right, &["id"], 1.5).is_err());
+    }
+
+    #[test]
+    fn semi_anti_join() {
+        let trades = RowTable::from_csv_bytes(b"exchange,symbol,qty\nN,AAPL,1\nN,AAPL,2\nQ,MSFT,3\nQ,IBM,4\n,AAPL,5\n").unwrap();
+        let quotes = RowTable::from_csv_bytes(b"exchange,symbol,bid\nN,AAPL,75\nN,AAPL,76\nQ,MSFT,158\nN,GOOG,1400\n").unwrap();
+
+        let qty = |t :&crate::RowTableSlice| t.iter().map(|r| r.get("qty")).collect::<Vec<_>>();
+
+        // rows on the left aren't repeated for each match
+        let semi = trades.semi_join(&quotes, &["exchange", "symbol"]).unwrap();
+
+        assert_eq!(vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)], qty(&semi));
+
+        // null keys never match, so they're kept by an anti-join
+        let anti = trades.anti_join(&quotes, &["exchange", "symbol"]).unwrap();
+
+        assert_eq!(vec![Value::Integer(4), Value::Integer(5)], qty(&anti));
+        assert_eq!(trades.len(), semi.len() + anti.len());
+
+        assert_eq!(4, trades.semi_join(&quotes, &["symbol"]).unwrap().len());
+        assert!(trades.semi_join(&quotes, &["qty"]).is_err());
+        assert!(trades.anti_join(&quotes, &[]).is_err());
+    }
+}
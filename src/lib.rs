@@ -1,49 +1,219 @@
 //! mem_table is an in-memory table of data, modeled after [Pandas](https://pandas.pydata.org/) for Python.
+//!
+//! Without the `std` feature the crate is `no_std` and only provides the core layer, [`Value`](enum.Value.html),
+//! [`ValueType`](enum.ValueType.html), [`TableError`](struct.TableError.html), and the [`Row`](trait.Row.html) trait,
+//! which only need `alloc`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
 #[macro_use]
 extern crate log;
+extern crate alloc;
+// lets the code generated by `#[derive(FromRow)]` name this crate from inside it, in tests
+extern crate self as large_table;
 
-use std::io::{Error as IOError, Read};
-use std::path::Path;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
 use std::collections::{HashMap, HashSet};
-use std::fmt::{Display, Formatter, Error as FmtError};
-use std::hash::{Hash, Hasher};
-use std::cell::Ref;
-use std::iter::FusedIterator;
-use std::ops::Index;
+#[cfg(feature = "std")]
 use std::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::ops::{Bound, RangeBounds};
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::io::BufWriter;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
-use rayon::prelude::*;
-use csv::{Reader, Writer};
 
+// the core layer, which only needs alloc
 mod value;
 mod row;
 mod table_error;
+
+#[cfg(feature = "rowtable")]
 mod row_table;
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
 mod mmap_table;
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+mod snapshot;
+#[cfg(all(feature = "rowtable", feature = "mmap", not(target_arch = "wasm32")))]
+mod auto;
+#[cfg(feature = "std")]
+mod metadata;
+#[cfg(feature = "std")]
+mod schema;
+#[cfg(feature = "std")]
+mod crosstab;
+#[cfg(feature = "std")]
+mod describe;
+#[cfg(feature = "std")]
+mod duplicates;
+#[cfg(feature = "std")]
+mod strings;
+#[cfg(feature = "std")]
+mod memory_usage;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+mod cancel;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod query;
+#[cfg(feature = "std")]
+mod display;
+#[cfg(feature = "std")]
+mod serde_support;
+#[cfg(feature = "std")]
+mod checkpoint;
+#[cfg(feature = "std")]
+mod read_options;
+#[cfg(feature = "std")]
+mod write_options;
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+#[cfg(feature = "anonymize")]
+mod anonymize;
+#[cfg(feature = "privacy")]
+mod privacy;
+#[cfg(feature = "sampling")]
+mod sampling;
+#[cfg(feature = "pipeline")]
+mod pipeline;
+#[cfg(feature = "std")]
+mod rolling;
+#[cfg(feature = "std")]
+mod window;
+#[cfg(feature = "std")]
+mod datetime;
+#[cfg(feature = "std")]
+mod calendar;
+#[cfg(feature = "std")]
+mod join;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "std")]
+mod gaps;
+#[cfg(feature = "std")]
+mod ohlc;
+#[cfg(feature = "std")]
+mod benford;
+#[cfg(feature = "std")]
+mod index;
+#[cfg(feature = "std")]
+mod group_by;
+#[cfg(feature = "std")]
+mod parallel;
+
+#[cfg(feature = "std")]
+pub mod prelude;
+
+#[cfg(any(all(test, feature = "std"), feature = "testdata"))]
+pub mod testdata;
+
+#[cfg(all(test, feature = "std"))]
+mod fixtures;
 
 // expose some of the underlying structures from other files
-//pub use crate::row_table::RowTable;
-pub use crate::value::{Value, ValueType};
+pub use crate::value::{Value, ValueRef, ValueType, FromValue};
 pub use crate::table_error::TableError;
-pub use crate::row::{Row, RowSlice};
-pub use crate::row_table::{RowTable, RowTableSlice};
-pub use crate::mmap_table::{MMapTable, MMapTableSlice};
+pub use crate::row::{Row, ColumnHandle, FromRow, TypedIter};
+#[doc(hidden)]
+pub use crate::row::row_field;
+#[cfg(feature = "derive")]
+pub use large_table_derive::FromRow;
+#[cfg(feature = "std")]
+pub use crate::row::RowSlice;
+#[cfg(feature = "rowtable")]
+pub use crate::row_table::{concat, Concat, RowTable, RowTableChunks, ParseErrorPolicy, RaggedRowPolicy, RowTableInner, RowTableSlice, RowTableIter, RowTableSliceIter};
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+pub use crate::mmap_table::{MMapTable, MMapTableInner, MMapTableSlice, MMapTableIter, MMapTableSliceIter};
+#[cfg(all(feature = "rowtable", feature = "mmap", not(target_arch = "wasm32")))]
+pub use crate::auto::{open_auto, choose_backend, AutoTable, Backend, Intent};
+#[cfg(feature = "std")]
+pub use crate::metadata::Metadata;
+#[cfg(feature = "std")]
+pub use crate::schema::{Schema, DTYPES_SAMPLE_ROWS};
+#[cfg(feature = "std")]
+pub use crate::checkpoint::Checkpoint;
+#[cfg(feature = "std")]
+pub use crate::read_options::ReadOptions;
+#[cfg(feature = "std")]
+pub use crate::write_options::{LineEnding, Quoting, WriteOptions};
+#[cfg(feature = "std")]
+pub use crate::serde_support::DeserializeIter;
+#[cfg(feature = "std")]
+pub use crate::crosstab::{Crosstab, Normalize};
+#[cfg(feature = "std")]
+pub use crate::describe::{Describe, Summary};
+#[cfg(feature = "std")]
+pub use crate::duplicates::{NearDuplicate, NearDuplicates};
+#[cfg(feature = "std")]
+pub use crate::memory_usage::MemoryUsage;
+#[cfg(feature = "std")]
+pub use crate::progress::Progress;
+#[cfg(feature = "std")]
+pub use crate::cancel::CancelToken;
+#[cfg(feature = "std")]
+pub use crate::stats::{Agg, ColumnStats};
+#[cfg(feature = "anonymize")]
+pub use crate::anonymize::Anonymizer;
+#[cfg(feature = "privacy")]
+pub use crate::privacy::{Noise, Privacy};
+#[cfg(feature = "pipeline")]
+pub use crate::pipeline::{Pipeline, Step};
+#[cfg(feature = "std")]
+pub use crate::query::Query;
+#[cfg(feature = "std")]
+pub use crate::display::{DISPLAY_ROWS, DISPLAY_WIDTH};
+#[cfg(feature = "std")]
+pub use crate::rolling::{Rolling, RowWindows};
+#[cfg(feature = "std")]
+pub use crate::window::Window;
+#[cfg(feature = "std")]
+pub use crate::datetime::{DateUnit, Frequency};
+#[cfg(feature = "std")]
+pub use crate::calendar::Calendar;
+#[cfg(feature = "std")]
+pub use crate::join::{JoinDiagnostics, KeyStats};
+#[cfg(feature = "std")]
+pub use crate::diff::{Change, DiffReport};
+#[cfg(feature = "std")]
+pub use crate::gaps::{Gap, Gaps};
+#[cfg(feature = "std")]
+pub use crate::ohlc::{Bar, Ohlc};
+#[cfg(feature = "std")]
+pub use crate::benford::DigitDistribution;
+#[cfg(feature = "std")]
+pub use crate::index::TableIndex;
+#[cfg(feature = "std")]
+pub use crate::group_by::GroupBy;
+#[cfg(feature = "std")]
+pub use crate::parallel::{set_parallelism, parallelism, install};
 
 // Playground: https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=98ca951a70269d44cb48230359857f60
 
 /// The main interface into the mem_table library
+#[cfg(feature = "std")]
 pub trait Table: TableOperations {
     fn update_by<F :FnMut(&mut Self::RowType)>(&mut self, update :F);
 
+    /// Appends the rows of `table`, which must have the same columns, in the same order.
     fn append(&mut self, table :impl TableOperations) -> Result<(), TableError> {
         // make sure the columns are the same
-        if !self.columns().iter().zip(table.columns().iter()).all(|(a, b)| a == b) {
+        if self.columns() != table.columns() {
             let err_str = format!("Columns don't match between tables: {:?} != {:?}", self.columns(), table.columns());
             return Err(TableError::new(err_str.as_str()));
         }
 
         for row in table.iter() {
-            self.append_row(row);
+            self.append_row(row)?;
         }
 
         Ok( () )
@@ -51,6 +221,14 @@ pub trait Table: TableOperations {
 
     fn append_row<R>(&mut self, row: R) -> Result<(), TableError>  where R: Row;
 
+    /// Appends a struct, or map, as a row, with each field going to the column with the same name.
+    ///
+    /// The fields are serialized as they would be written to a CSV file by the `csv` crate, then parsed like the fields
+    /// of a CSV file. Fields without a column are ignored, and columns without a field are an error.
+    fn append_struct<T: serde::Serialize>(&mut self, value :&T) -> Result<(), TableError> {
+        self.append_row(serde_support::SerializedRow::new(value)?)
+    }
+
     /// Adds a column with `column_name` to the end of the table filling in all rows with `value`.
     /// This method works in parallel and is therefore usually faster than `add_column_with`
     fn add_column(&mut self, column_name :&str, value :&Value) -> Result<(), TableError> {
@@ -61,8 +239,470 @@ pub trait Table: TableOperations {
     /// This method works a row-at-a-time and therefore can be slower than `add_column`.
     fn add_column_with<F: FnMut() -> Value>(&mut self, column_name :&str, f :F) -> Result<(), TableError>;
 
+    /// Adds a column with `column_name` to the end of the table using `values` for the rows, in order.
+    fn add_column_values(&mut self, column_name :&str, values :Vec<Value>) -> Result<(), TableError> {
+        if values.len() != self.len() {
+            let err_str = format!("Number of values does not match table length: {} != {}", values.len(), self.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let mut values = values.into_iter();
+
+        self.add_column_with(column_name, || values.next().unwrap())
+    }
+
+    /// Adds a column with `column_name` to the end of the table using `f` to compute the value from each existing row.
+    fn add_column_from<F: FnMut(&Self::RowType) -> Value>(&mut self, column_name :&str, mut f :F) -> Result<(), TableError> {
+        let mut values = self.iter().map(|row| f(&row)).collect::<Vec<_>>().into_iter();
+
+        self.add_column_with(column_name, || values.next().unwrap_or(Value::Empty))
+    }
+
     fn rename_column(&mut self, old_col :&str, new_col :&str) -> Result<(), TableError>;
 
+    /// Replaces every value in `column` with the result of calling `f` on it.
+    fn map_column<F: Fn(&Value) -> Value + Sync + Send>(&mut self, column :&str, f :F) -> Result<(), TableError> {
+        self.column_position(column)?;
+
+        let mut ret = Ok( () );
+
+        self.update_by(|row| {
+            if ret.is_ok() {
+                ret = row.set(column, f(&row.get(column))).map(|_| ());
+            }
+        });
+
+        ret
+    }
+
+    /// Converts the values of `column` to `value_type`, by parsing their text with it, such as to read a column of
+    /// numbers as `ValueType::String`, or one of strings as `ValueType::Integer`; a table that declares the types of its
+    /// columns, like an `MMapTable`, declares the new type, which [`dtypes`](trait.TableOperations.html#method.dtypes)
+    /// then reports.
+    ///
+    /// Empty values stay empty. Returns an error, without changing the column, naming the first row with a value that
+    /// can't be converted.
+    fn astype(&mut self, column :&str, value_type :ValueType) -> Result<(), TableError> {
+        let values = schema::convert(self, column, &value_type)?.into_iter().collect::<Result<Vec<_>, _>>()?;
+        let mut values = values.into_iter();
+        let mut ret = Ok( () );
+
+        self.update_by(|row| {
+            if let (true, Some(value)) = (ret.is_ok(), values.next()) {
+                ret = row.set(column, value).map(|_| ());
+            }
+        });
+
+        ret
+    }
+
+    /// Converts the values of `column` to `value_type`, the same as [`astype`](#method.astype), except the values that
+    /// can't be converted become empty; returns the rows of those values, in order.
+    fn try_astype(&mut self, column :&str, value_type :ValueType) -> Result<Vec<usize>, TableError> {
+        let values = schema::convert(self, column, &value_type)?;
+        let failed = values.iter().enumerate().filter(|(_, v)| v.is_err()).map(|(i, _)| i).collect();
+        let mut values = values.into_iter().map(|v| v.unwrap_or(Value::Empty));
+        let mut ret = Ok( () );
+
+        self.update_by(|row| {
+            if let (true, Some(value)) = (ret.is_ok(), values.next()) {
+                ret = row.set(column, value).map(|_| ());
+            }
+        });
+
+        ret.map(|_| failed)
+    }
+
+    /// Replaces the values of `column` with values anonymized by `anonymizer`, such as before sharing a production
+    /// dataset; the random anonymizers give the same values for the same `seed`.
+    ///
+    /// Returns an error, without changing the column, if it has a value the anonymizer doesn't apply to, such as text
+    /// for `Anonymizer::Jitter`.
+    #[cfg(feature = "anonymize")]
+    fn anonymize(&mut self, column :&str, anonymizer :&Anonymizer, seed :u64) -> Result<(), TableError> {
+        self.column_position(column)?;
+
+        let values = self.iter().map(|row| row.get(column)).collect::<Vec<_>>();
+        let mut values = anonymize::anonymized(column, values, anonymizer, seed)?.into_iter();
+        let mut ret = Ok( () );
+
+        self.update_by(|row| {
+            if let (true, Some(value)) = (ret.is_ok(), values.next()) {
+                ret = row.set(column, value).map(|_| ());
+            }
+        });
+
+        ret
+    }
+
+    /// Replaces the values of each of `columns` with their z-score: the difference from the mean of the column,
+    /// divided by its sample standard deviation.
+    ///
+    /// Empty values are left empty, and a column with no variation becomes all 0. Returns an error, without changing
+    /// any column, if a column has a value that isn't a number.
+    fn standardize(&mut self, columns :&[&str]) -> Result<(), TableError> {
+        let stats = columns.iter().map(|c| stats::numeric_values(self, c).map(|v| stats::mean_std(&v))).collect::<Result<Vec<_>, _>>()?;
+
+        for (column, (mean, std)) in columns.iter().zip(stats) {
+            let std = if std == 0.0 { 1.0 } else { std };
+
+            self.map_column(column, |v| match v.try_as_float() {
+                Some(f) => Value::Float(((f - mean) / std).into()),
+                None => Value::Empty
+            })?;
+        }
+
+        Ok( () )
+    }
+
+    /// Replaces the values of each of `columns` with their position between the minimum and maximum of the column,
+    /// from 0 to 1.
+    ///
+    /// Empty values are left empty, and a column with no variation becomes all 0. Returns an error, without changing
+    /// any column, if a column has a value that isn't a number.
+    fn min_max_scale(&mut self, columns :&[&str]) -> Result<(), TableError> {
+        let ranges = columns.iter().map(|c| {
+            stats::numeric_values(self, c).map(|v| {
+                (v.iter().cloned().fold(f64::INFINITY, f64::min), v.iter().cloned().fold(f64::NEG_INFINITY, f64::max))
+            })
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        for (column, (min, max)) in columns.iter().zip(ranges) {
+            let range = if max > min { max - min } else { 1.0 };
+
+            self.map_column(column, |v| match v.try_as_float() {
+                Some(f) => Value::Float(((f - min) / range).into()),
+                None => Value::Empty
+            })?;
+        }
+
+        Ok( () )
+    }
+
+    /// Caps the values of `column` at its `lower_q` and `upper_q` quantiles, from 0 to 1, such as 0.01 and 0.99, so a few
+    /// extreme values don't dominate, a standard cleanup for heavy-tailed data like returns. The quantiles are exact,
+    /// interpolated between the values on either side of them.
+    ///
+    /// Capped values become floats, while the others, and empty values, are unchanged. Returns an error, without
+    /// changing the column, if it has a value that isn't a number.
+    fn winsorize(&mut self, column :&str, lower_q :f64, upper_q :f64) -> Result<(), TableError> {
+        let (lower, upper) = stats::quantile_bounds(self, column, lower_q, upper_q)?;
+
+        self.map_column(column, |v| stats::cap(v, lower, upper))
+    }
+
+    /// Adds `new_column` with the values of `column` capped at its quantiles, the same as
+    /// [`winsorize`](#method.winsorize), leaving `column` as it is.
+    fn winsorize_to(&mut self, column :&str, lower_q :f64, upper_q :f64, new_column :&str) -> Result<(), TableError> {
+        let (lower, upper) = stats::quantile_bounds(self, column, lower_q, upper_q)?;
+        let values = self.iter().map(|row| stats::cap(&row.get(column), lower, upper)).collect();
+
+        self.add_column_values(new_column, values)
+    }
+
+    /// Sets `column` to `if_true` in the rows matching `predicate`, and to `if_false` in the others, adding the column
+    /// when the table doesn't have it.
+    fn where_assign<P: FnMut(&Self::RowType) -> bool>(&mut self, mut predicate :P, column :&str, if_true :&Value, if_false :&Value) -> Result<(), TableError> {
+        let values = self.iter().map(|row| if predicate(&row) { if_true.clone() } else { if_false.clone() }).collect::<Vec<_>>();
+
+        if self.column_position(column).is_err() {
+            return self.add_column_values(column, values);
+        }
+
+        let mut values = values.into_iter();
+        let mut ret = Ok( () );
+
+        self.update_by(|row| {
+            if let (true, Some(value)) = (ret.is_ok(), values.next()) {
+                ret = row.set(column, value).map(|_| ());
+            }
+        });
+
+        ret
+    }
+
+    /// Limits the values of `column` to `min` and `max`; an integer stays an integer when the bound it's clipped to is
+    /// whole.
+    ///
+    /// Empty values are left empty. Returns an error, without changing the column, if it has a value that isn't a number.
+    fn clip(&mut self, column :&str, min :f64, max :f64) -> Result<(), TableError> {
+        stats::numeric_values(self, column)?;
+
+        let bound = |v :&Value, b :f64| match v {
+            Value::Integer(_) if b.fract() == 0.0 => Value::Integer(b as i64),
+            _ => Value::Float(b.into())
+        };
+
+        self.map_column(column, |v| match v.try_as_float() {
+            Some(f) if f < min => bound(v, min),
+            Some(f) if f > max => bound(v, max),
+            _ => v.clone()
+        })
+    }
+
+    /// Rounds the values of `column` to `digits` decimal places, or to tens, hundreds, etc. when `digits` is negative.
+    ///
+    /// Integers are only changed by a negative `digits`. Returns an error, without changing the column, if it has a
+    /// value that isn't a number.
+    fn round(&mut self, column :&str, digits :i32) -> Result<(), TableError> {
+        stats::numeric_values(self, column)?;
+
+        let scale = 10f64.powi(digits);
+
+        self.map_column(column, |v| match v {
+            Value::Integer(i) if digits < 0 => Value::Integer(((*i as f64 * scale).round() / scale) as i64),
+            Value::Float(f) => Value::Float(((f.0 * scale).round() / scale).into()),
+            v => v.clone()
+        })
+    }
+
+    /// Rounds the floats of `column` down to a whole number. Returns an error, without changing the column, if it has a
+    /// value that isn't a number.
+    fn floor(&mut self, column :&str) -> Result<(), TableError> {
+        stats::numeric_values(self, column)?;
+
+        self.map_column(column, |v| match v {
+            Value::Float(f) => Value::Float(f.0.floor().into()),
+            v => v.clone()
+        })
+    }
+
+    /// Rounds the floats of `column` up to a whole number. Returns an error, without changing the column, if it has a
+    /// value that isn't a number.
+    fn ceil(&mut self, column :&str) -> Result<(), TableError> {
+        stats::numeric_values(self, column)?;
+
+        self.map_column(column, |v| match v {
+            Value::Float(f) => Value::Float(f.0.ceil().into()),
+            v => v.clone()
+        })
+    }
+
+    /// Pads the values of `column` on the left with zeros to `width` characters, after any sign, such as to restore
+    /// the leading zeros of codes that were read as integers.
+    ///
+    /// The values become strings, except empty values, which are left empty.
+    fn zfill(&mut self, column :&str, width :usize) -> Result<(), TableError> {
+        self.map_column(column, |v| string_op(v, |s| {
+            let (sign, digits) = match s.strip_prefix('-').or_else(|| s.strip_prefix('+')) {
+                Some(digits) => (&s[..1], digits),
+                None => ("", s)
+            };
+
+            let zeros = width.saturating_sub(s.chars().count());
+
+            format!("{}{}{}", sign, "0".repeat(zeros), digits)
+        }))
+    }
+
+    /// Replaces the values of `column` with `len` characters starting at character `start`, or fewer when the value
+    /// is too short.
+    ///
+    /// The values become strings, except empty values, which are left empty.
+    fn substring(&mut self, column :&str, start :usize, len :usize) -> Result<(), TableError> {
+        self.map_column(column, |v| string_op(v, |s| s.chars().skip(start).take(len).collect()))
+    }
+
+    /// Pads the values of `column` on the left with `fill` to `width` characters.
+    ///
+    /// The values become strings, except empty values, which are left empty.
+    fn pad_left(&mut self, column :&str, width :usize, fill :char) -> Result<(), TableError> {
+        self.map_column(column, |v| string_op(v, |s| {
+            let mut ret = std::iter::repeat(fill).take(width.saturating_sub(s.chars().count())).collect::<String>();
+
+            ret.push_str(s);
+            ret
+        }))
+    }
+
+    /// Pads the values of `column` on the right with `fill` to `width` characters.
+    ///
+    /// The values become strings, except empty values, which are left empty.
+    fn pad_right(&mut self, column :&str, width :usize, fill :char) -> Result<(), TableError> {
+        self.map_column(column, |v| string_op(v, |s| {
+            let mut ret = s.to_string();
+
+            ret.extend(std::iter::repeat(fill).take(width.saturating_sub(s.chars().count())));
+            ret
+        }))
+    }
+
+    /// Replaces every `Value::Empty` in `column` with `value`.
+    fn fillna(&mut self, column :&str, value :&Value) -> Result<(), TableError> {
+        // make sure the column is valid
+        self.column_position(column)?;
+
+        let mut ret = Ok( () );
+
+        self.update_by(|row| {
+            if ret.is_ok() && row.get(column) == Value::Empty {
+                ret = row.set(column, value.clone()).map(|_| ());
+            }
+        });
+
+        ret
+    }
+
+    /// Replaces every `Value::Empty` in the table with `value`.
+    fn fillna_all(&mut self, value :&Value) -> Result<(), TableError> {
+        for column in self.columns() {
+            self.fillna(column.as_str(), value)?;
+        }
+
+        Ok( () )
+    }
+
+    /// Adds a column with `column_name` with the aggregate of `value_column` over each group of `group_column`, so
+    /// every row has the aggregate of its group, like Pandas' `groupby().transform()`.
+    fn group_transform(&mut self, group_column :&str, value_column :&str, agg :Agg, column_name :&str) -> Result<(), TableError> {
+        self.column_position(group_column)?;
+        self.column_position(value_column)?;
+
+        let mut groups :HashMap<Value, (Vec<usize>, Vec<Value>)> = HashMap::new();
+
+        for (i, row) in self.iter().enumerate() {
+            let group = groups.entry(row.get(group_column)).or_default();
+
+            group.0.push(i);
+            group.1.push(row.get(value_column));
+        }
+
+        let mut values = vec![Value::Empty; self.len()];
+
+        for (rows, group_values) in groups.into_values() {
+            let aggregate = agg.apply(&group_values)?;
+
+            for row in rows {
+                values[row] = aggregate.clone();
+            }
+        }
+
+        self.add_column_values(column_name, values)
+    }
+
+    /// Adds a column with `column_name` with the percentile rank of each value in `column`, within its group of `by`
+    /// when given.
+    ///
+    /// The rank is from 0 to 1: the average rank of a value among the non-empty values of its group, divided by their
+    /// count. Empty values get `Value::Empty`.
+    fn percent_rank(&mut self, column_name :&str, column :&str, by :Option<&str>) -> Result<(), TableError> {
+        self.column_position(column)?;
+
+        if let Some(by) = by {
+            self.column_position(by)?;
+        }
+
+        let mut groups :HashMap<Value, Vec<(usize, Value)>> = HashMap::new();
+
+        for (i, row) in self.iter().enumerate() {
+            let value = row.get(column);
+
+            if value != Value::Empty {
+                groups.entry(by.map(|b| row.get(b)).unwrap_or(Value::Empty)).or_default().push( (i, value) );
+            }
+        }
+
+        let mut ranks = vec![Value::Empty; self.len()];
+
+        for group in groups.into_values() {
+            let (rows, values) :(Vec<usize>, Vec<Value>) = group.into_iter().unzip();
+
+            for (row, rank) in rows.into_iter().zip(stats::average_ranks(&values)) {
+                ranks[row] = Value::Float((rank / values.len() as f64).into());
+            }
+        }
+
+        self.add_column_values(column_name, ranks)
+    }
+
+    /// Adds a column with `column_name` numbering the sessions in `time_column`, such as the visits in a clickstream.
+    ///
+    /// The rows are ordered by `by`, when given, then by time; a new session starts with each value of `by`, and
+    /// whenever the time since the row before is more than `gap`. Sessions are numbered from 0 in that order, and
+    /// rows without a time get `Value::Empty`.
+    fn sessionize(&mut self, column_name :&str, time_column :&str, gap :chrono::Duration, by :Option<&str>) -> Result<(), TableError> {
+        self.column_position(time_column)?;
+
+        if let Some(by) = by {
+            self.column_position(by)?;
+        }
+
+        let keys = self.iter().map(|row| (by.map(|b| row.get(b)).unwrap_or(Value::Empty), row.get(time_column))).collect::<Vec<_>>();
+        let mut order = (0..keys.len()).collect::<Vec<_>>();
+
+        order.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+
+        let mut sessions = vec![Value::Empty; keys.len()];
+        let mut session = -1;
+        let mut prev :Option<&(Value, Value)> = None;
+
+        for i in order {
+            let (user, time) = &keys[i];
+
+            if *time == Value::Empty {
+                continue;
+            }
+
+            let new_session = match prev {
+                Some((prev_user, prev_time)) if prev_user == user => {
+                    let elapsed = time.try_sub(prev_time)?;
+
+                    elapsed.try_as_duration().map_or(true, |d| d > gap)
+                },
+                _ => true
+            };
+
+            if new_session {
+                session += 1;
+            }
+
+            sessions[i] = Value::Integer(session);
+            prev = Some(&keys[i]);
+        }
+
+        self.add_column_values(column_name, sessions)
+    }
+
+    /// Adds `column_name` with the dates and times of `column` truncated to the start of their `unit`, such as the hour,
+    /// or the Monday of the week. Dates are only changed by units of a day or longer, and empty values stay empty.
+    fn dt_truncate(&mut self, column_name :&str, column :&str, unit :DateUnit) -> Result<(), TableError> {
+        let pos = self.column_position(column)?;
+        let values = self.iter().map(|row| unit.truncate(&row.try_at(pos)?)).collect::<Result<Vec<_>, _>>()?;
+
+        self.add_column_values(column_name, values)
+    }
+
+    /// Adds `column_name` with whether each date, or date and time, of `column` is a business day of `calendar`.
+    fn is_business_day(&mut self, column_name :&str, column :&str, calendar :&Calendar) -> Result<(), TableError> {
+        let pos = self.column_position(column)?;
+        let values = self.iter().map(|row| calendar.is_business_value(&row.try_at(pos)?)).collect::<Result<Vec<_>, _>>()?;
+
+        self.add_column_values(column_name, values)
+    }
+
+    /// Adds `column_name` with the dates, or dates and times, of `column` moved `n` business days of `calendar`, as
+    /// [`Calendar::add_business_days`](struct.Calendar.html#method.add_business_days) does; times are kept.
+    fn add_business_days(&mut self, column_name :&str, column :&str, n :i64, calendar :&Calendar) -> Result<(), TableError> {
+        let pos = self.column_position(column)?;
+        let values = self.iter().map(|row| calendar.add_business_days_value(&row.try_at(pos)?, n)).collect::<Result<Vec<_>, _>>()?;
+
+        self.add_column_values(column_name, values)
+    }
+
+    /// Adds `column_name` with the dates and times of `column`, which are local times in the time zone `from`, converted
+    /// to local times in `to`; the zones are IANA names, such as `UTC` or `America/New_York`.
+    ///
+    /// A time repeated when the clocks go back is read as the first one, and a time skipped when they go forward
+    /// becomes `Value::Empty`.
+    #[cfg(feature = "timezones")]
+    fn dt_convert_tz(&mut self, column_name :&str, column :&str, from :&str, to :&str) -> Result<(), TableError> {
+        let pos = self.column_position(column)?;
+        let (from, to) = (datetime::parse_tz(from)?, datetime::parse_tz(to)?);
+        let values = self.iter().map(|row| datetime::convert_tz(&row.try_at(pos)?, from, to)).collect::<Result<Vec<_>, _>>()?;
+
+        self.add_column_values(column_name, values)
+    }
+
 
 //    /// Sorts the rows in the table, in an unstable way, in ascending order, by the columns provided, in the order they're provided.
 //    ///
@@ -131,15 +771,22 @@ pub trait Table: TableOperations {
 }
 
 /// Operations that can be performed on `Table`s or `TableSlice`s.
+#[cfg(feature = "std")]
 pub trait TableOperations {
     type TableSliceType: TableSlice;
     type RowType: Row;
-    type Iter: Iterator<Item=Self::RowType>;
+    type Iter: ExactSizeIterator<Item=Self::RowType> + DoubleEndedIterator;
 
     fn iter(&self) -> Self::Iter;
 
     fn get(&self, index :usize) -> Result<Self::RowType, TableError>;
 
+    /// Consumes the table, returning the values of its rows when it owns them and can give them up without copying,
+    /// or the table itself when it can't, such as a slice, or a table shared with another handle.
+    fn into_rows(self) -> Result<Vec<Vec<Value>>, Self> where Self: Sized {
+        Err(self)
+    }
+
     fn columns(&self) -> Vec<String>;
 
     fn column_position(&self, column :&str) -> Result<usize, TableError> {
@@ -150,9 +797,26 @@ pub trait TableOperations {
         }
     }
 
+    /// The statistics of `column` stored by the table's `compute_stats`, if they've been computed since the table was
+    /// last changed.
+    fn column_stats(&self, _column :&str) -> Option<ColumnStats> {
+        None
+    }
+
+    /// The row of each non-empty value of `column`, when the table keeps them because the column is declared unique,
+    /// such as with [`RowTable::declare_unique`](struct.RowTable.html#method.declare_unique).
+    fn unique_index(&self, _column :&str) -> Option<Arc<HashMap<Value, usize>>> {
+        None
+    }
+
+    /// Looks up a column once, returning a handle to read its values as `T` with [`Row::value`](trait.Row.html#method.value).
+    fn column_handle<T: FromValue>(&self, column :&str) -> Result<ColumnHandle<T>, TableError> {
+        Ok(ColumnHandle::new(self.column_position(column)?, column))
+    }
+
     #[inline]
     fn len(&self) -> usize {
-        self.iter().count()
+        self.iter().len()
     }
 
     #[inline]
@@ -160,25 +824,350 @@ pub trait TableOperations {
         self.columns().len()
     }
 
-    /// Write a table out to a CSV file
+    /// Returns the `ValueType` of each column: its [declared type](#method.declared_type), or else the type inferred
+    /// from the values of up to [`DTYPES_SAMPLE_ROWS`](constant.DTYPES_SAMPLE_ROWS.html) rows, spread evenly through
+    /// the table, so it's quick for large tables; like Pandas' `df.dtypes`.
+    ///
+    /// A column with both integers and floats is reported as `ValueType::Number`, and a column with any other mix of types
+    /// is reported as `ValueType::String`. `Value::Empty` does not affect the type of a column.
+    fn dtypes(&self) -> Vec<(String, ValueType)> {
+        schema::column_types(self, Some(DTYPES_SAMPLE_ROWS))
+    }
+
+    /// The type `column` is declared with, such as by a [`Metadata`](struct.Metadata.html) sidecar file, or `None`
+    /// when its values are inferred.
+    fn declared_type(&self, _column :&str) -> Option<ValueType> {
+        None
+    }
+
+    /// Returns a `Rolling` window of `window` rows, used to compute moving statistics of a column.
+    fn rolling(&self, window :usize) -> Rolling<'_, Self> where Self: Sized {
+        Rolling::new(self, window)
+    }
+
+    /// Returns a `Window` over the groups of rows with the same values in `by`, or over all the rows when `by` is
+    /// empty, used to compute running totals, ranks, and lagged values within each group.
+    fn window(&self, by :&[&str]) -> Result<Window<'_, Self>, TableError> where Self: Sized {
+        Window::new(self, by)
+    }
+
+    /// Returns an iterator over the rows converted to `T`, such as a struct with `#[derive(FromRow)]`.
+    fn typed_iter<T: FromRow>(&self) -> TypedIter<Self::Iter, T> {
+        TypedIter::new(self.iter())
+    }
+
+    /// Returns an iterator over the rows deserialized to `T`, mapping each column to the struct field with the same name.
+    ///
+    /// Values are deserialized from their string form, as the `csv` crate would, so an empty value is `None`.
+    fn iter_as<T: serde::de::DeserializeOwned>(&self) -> DeserializeIter<Self::Iter, T> {
+        DeserializeIter::new(self.iter(), &self.columns())
+    }
+
+    /// Converts the table to Arrow record batches of up to `batch_size` rows, with each column's type from `dtypes`.
+    #[cfg(feature = "arrow")]
+    fn to_arrow(&self, batch_size :usize) -> Result<Vec<arrow_array::RecordBatch>, TableError> {
+        arrow::to_batches(self, batch_size)
+    }
+
+    /// Returns an iterator over each run of `size` consecutive rows, such as pairs of a row and the row before it.
+    ///
+    /// There are no windows when `size` is 0, or larger than the table.
+    fn iter_windows(&self, size :usize) -> RowWindows<'_, Self> where Self: Sized {
+        RowWindows::new(self, size)
+    }
+
+    /// Returns the memory used by the table, and an estimate of the memory used by each column.
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage { index: 0, mapped: 0, columns: memory_usage::column_memory(self) }
+    }
+
+    /// Returns an aligned preview of the first and last `rows / 2` rows, with values cut off at `max_width` characters.
+    ///
+    /// Tables print a preview of `DISPLAY_ROWS` rows and `DISPLAY_WIDTH` characters with `Display`.
+    fn preview(&self, rows :usize, max_width :usize) -> String {
+        display::preview(self, rows, max_width)
+    }
+
+    /// Returns a report of the table: the type, non-empty count, and estimated memory of each column.
+    fn info(&self) -> String {
+        let columns = self.columns();
+        let mut types = vec![ValueType::Empty; columns.len()];
+        let mut non_null = vec![0usize; columns.len()];
+        let mut memory = vec![0usize; columns.len()];
+        let mut len = 0;
+
+        for row in self.iter() {
+            for (i, col) in columns.iter().enumerate() {
+                let val = row.get(col);
+
+                if val != Value::Empty {
+                    non_null[i] += 1;
+                }
+
+                memory[i] += val.mem_size();
+
+                let t = std::mem::replace(&mut types[i], ValueType::Empty);
+                types[i] = t.merge(val.value_type());
+            }
+
+            len += 1;
+        }
+
+        let type_strs = types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        let col_width = columns.iter().map(|c| c.len()).chain(std::iter::once("Column".len())).max().unwrap();
+        let type_width = type_strs.iter().map(|t| t.len()).chain(std::iter::once("Type".len())).max().unwrap();
+
+        let mut ret = format!("{} rows, {} columns\n", len, columns.len());
+
+        ret += format!("{:>4}  {:<cw$}  {:<tw$}  {:>10}  {:>12}\n", "#", "Column", "Type", "Non-Null", "Memory", cw=col_width, tw=type_width).as_str();
+
+        for (i, col) in columns.iter().enumerate() {
+            ret += format!("{:>4}  {:<cw$}  {:<tw$}  {:>10}  {:>12}\n", i, col, type_strs[i], non_null[i], memory[i], cw=col_width, tw=type_width).as_str();
+        }
+
+        ret += format!("memory usage: {} bytes\n", memory.iter().sum::<usize>()).as_str();
+
+        ret
+    }
+
+    /// Write a table out to a CSV file, with a header, returning an error if any row can't be written
     fn to_csv<P: AsRef<Path>>(&self, csv_path :P) -> Result<(), TableError> {
-        let mut csv = Writer::from_path(csv_path).map_err(|e| TableError::new(e.to_string().as_str()))?;
+        self.to_csv_with_options(csv_path, &WriteOptions::default())
+    }
+
+    /// Write a table out as CSV to `writer`, such as a socket, or an encoder that compresses the data
+    fn to_csv_writer<W: Write>(&self, writer :W) -> Result<(), TableError> {
+        self.to_csv_writer_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Write a table out as CSV to `writer`, with the columns, header, quoting, and line endings given by `options`
+    fn to_csv_writer_with_options<W: Write>(&self, mut writer :W, options :&WriteOptions) -> Result<(), TableError> {
+        let csv_err = |e :csv::Error| TableError::new(e.to_string().as_str());
+        let columns = self.columns();
+        let positions = options.positions(&columns)?;
+
+        writer.write_all(options.preamble()).map_err(|e| TableError::new(e.to_string().as_str()))?;
 
-        // write out the headers first
-        csv.write_record(self.columns());
+        let mut csv = options.writer(writer);
+
+        if options.has_header() {
+            csv.write_record(positions.iter().map(|&i| &columns[i])).map_err(csv_err)?;
+        }
 
-        // go through each row, writing the records converted to Strings
         for row in self.iter() {
-            csv.write_record(self.columns().iter().map(|c| {
-                row.get(c).as_string()
-            }));
+            let record = positions.iter().map(|&i| row.try_at(i).map(|v| v.as_string())).collect::<Result<Vec<_>, _>>()?;
+
+            csv.write_record(record).map_err(csv_err)?;
         }
 
-        Ok( () )
+        csv.flush().map_err(|e| TableError::new(e.to_string().as_str()))
+    }
+
+    /// Write a table out to a CSV file, with the columns and header given by `options`
+    fn to_csv_with_options<P: AsRef<Path>>(&self, csv_path :P, options :&WriteOptions) -> Result<(), TableError> {
+        let file = File::create(csv_path).map_err(|e| TableError::new(e.to_string().as_str()))?;
+
+        self.to_csv_writer_with_options(BufWriter::new(file), options)
+    }
+
+    /// Write a table out to a gzip-compressed CSV file, compressing it as it's written, at a `level` from 0 (none) to 9
+    /// (best), with the columns and header given by `options`.
+    #[cfg(feature = "gzip")]
+    fn to_csv_gz<P: AsRef<Path>>(&self, csv_path :P, level :u32, options :&WriteOptions) -> Result<(), TableError> {
+        if level > 9 {
+            let err_str = format!("Invalid gzip compression level: {}", level);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let io_err = |e :std::io::Error| TableError::new(e.to_string().as_str());
+        let file = File::create(csv_path).map_err(io_err)?;
+        let mut encoder = flate2::write::GzEncoder::new(BufWriter::new(file), flate2::Compression::new(level));
+
+        self.to_csv_writer_with_options(&mut encoder, options)?;
+
+        encoder.finish().and_then(|mut w| w.flush()).map_err(io_err)
+    }
+
+    /// Write a table out to a zstd-compressed CSV file, compressing it as it's written, at a `level` from 1 to 22, or 0
+    /// for zstd's default, with the columns and header given by `options`. The data is compressed in chunks on
+    /// [`parallelism`](fn.parallelism.html) threads.
+    #[cfg(feature = "zstd")]
+    fn to_csv_zst<P: AsRef<Path>>(&self, csv_path :P, level :i32, options :&WriteOptions) -> Result<(), TableError> {
+        if level != 0 && !zstd::compression_level_range().contains(&level) {
+            let err_str = format!("Invalid zstd compression level: {}", level);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let io_err = |e :std::io::Error| TableError::new(e.to_string().as_str());
+        let file = File::create(csv_path).map_err(io_err)?;
+        let mut encoder = zstd::Encoder::new(BufWriter::new(file), level).map_err(io_err)?;
+
+        if parallelism() > 1 {
+            encoder.multithread(parallelism() as u32).map_err(io_err)?;
+        }
+
+        self.to_csv_writer_with_options(&mut encoder, options)?;
+
+        encoder.finish().and_then(|mut w| w.flush()).map_err(io_err)
+    }
+
+    /// Write a table out to a CSV file, with the columns and header given by `options`, recording a
+    /// [`Checkpoint`](struct.Checkpoint.html) every `every` rows, so an interrupted export can be continued with
+    /// `resume_export` instead of restarted.
+    fn to_csv_checkpointed<P: AsRef<Path>>(&self, csv_path :P, every :usize, options :&WriteOptions) -> Result<(), TableError> {
+        checkpoint::export(self, csv_path, None, every, options, &mut |_| ())
+    }
+
+    /// Write a table out the same as [`to_csv_checkpointed`](#method.to_csv_checkpointed), calling `progress` with the
+    /// bytes and rows written so far after each checkpoint, and once more when the export finishes, such as to show a
+    /// progress bar; `total_bytes` is estimated from the rows written so far.
+    fn to_csv_checkpointed_with_progress<P: AsRef<Path>, F: FnMut(Progress)>(&self, csv_path :P, every :usize, options :&WriteOptions, mut progress :F) -> Result<(), TableError> {
+        checkpoint::export(self, csv_path, None, every, options, &mut progress)
+    }
+
+    /// Continues an export started with `to_csv_checkpointed` from its last checkpoint; the table must have the same
+    /// rows, in the same order, as when the export started, and `options` must be the same.
+    fn resume_export<P: AsRef<Path>>(&self, csv_path :P, options :&WriteOptions) -> Result<(), TableError> {
+        checkpoint::resume(self, csv_path, options, &mut |_| ())
+    }
+
+    /// Continues an export the same as [`resume_export`](#method.resume_export), calling `progress` like
+    /// [`to_csv_checkpointed_with_progress`](#method.to_csv_checkpointed_with_progress), counting the rows written
+    /// before the export was interrupted.
+    fn resume_export_with_progress<P: AsRef<Path>, F: FnMut(Progress)>(&self, csv_path :P, options :&WriteOptions, mut progress :F) -> Result<(), TableError> {
+        checkpoint::resume(self, csv_path, options, &mut progress)
+    }
+
+    /// Write a table out to several CSV files in `dir`, each with the header, split as given by `split`. The files are named
+    /// `part-00000.csv`, `part-00001.csv`, and so on, and their paths are returned in order. The columns, and whether
+    /// there is a header, are given by `options`.
+    ///
+    /// There's always at least one file, so an empty table is written as a file with only the header. The names of the
+    /// parts are also written to a `_parts` file in `dir`. Returns an error if `dir` already has part files, unless
+    /// `options` sets [`overwrite`](struct.WriteOptions.html#method.overwrite); then the parts listed in its `_parts`
+    /// file, such as from an earlier split into more files, are removed first, so every part in `dir` is from this
+    /// table. Other files are left alone.
+    fn to_csv_split<P: AsRef<Path>>(&self, dir :P, split :Split, options :&WriteOptions) -> Result<Vec<PathBuf>, TableError> {
+        let rows_per_file = match split {
+            Split::Files(0) | Split::Rows(0) | Split::Bytes(0) => {
+                let err_str = format!("Invalid split: {:?}", split);
+                return Err(TableError::new(err_str.as_str()));
+            },
+            Split::Files(n) => self.len().div_ceil(n).max(1),
+            Split::Rows(n) => n,
+            Split::Bytes(_) => usize::MAX
+        };
+        let max_bytes = if let Split::Bytes(n) = split { n } else { usize::MAX };
+
+        let io_err = |e :std::io::Error| TableError::new(e.to_string().as_str());
+        let csv_err = |e :csv::Error| TableError::new(e.to_string().as_str());
+
+        std::fs::create_dir_all(&dir).map_err(io_err)?;
+
+        let manifest = dir.as_ref().join(PARTS_MANIFEST);
+
+        if options.can_overwrite() {
+            // only the parts an earlier split listed are removed
+            let listed = match std::fs::read_to_string(&manifest) {
+                Ok(listed) => listed,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(io_err(e))
+            };
+
+            for name in listed.lines().filter(|n| is_part_name(n)) {
+                match std::fs::remove_file(dir.as_ref().join(name)) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(io_err(e)),
+                    _ => ()
+                }
+            }
+        } else {
+            for entry in std::fs::read_dir(&dir).map_err(io_err)? {
+                let name = entry.map_err(io_err)?.file_name();
+
+                if is_part_name(&name.to_string_lossy()) {
+                    let err_str = format!("{} already has part files, such as {}; set WriteOptions::overwrite to replace them", dir.as_ref().display(), name.to_string_lossy());
+                    return Err(TableError::new(err_str.as_str()));
+                }
+            }
+        }
+
+        // records are encoded on their own first, so the size of each file is known
+        let encode = |record :Vec<String>| -> Result<Vec<u8>, TableError> {
+            let mut csv = options.writer(Vec::new());
+
+            csv.write_record(record).map_err(csv_err)?;
+            csv.into_inner().map_err(|e| TableError::new(e.to_string().as_str()))
+        };
+
+        let columns = self.columns();
+        let positions = options.positions(&columns)?;
+        let mut header = options.preamble().to_vec();
+
+        if options.has_header() {
+            header.extend(encode(positions.iter().map(|&i| columns[i].clone()).collect())?);
+        }
+
+        let mut paths = Vec::new();
+        let mut file :Option<BufWriter<File>> = None;
+        let (mut rows, mut bytes) = (0, 0);
+
+        for row in self.iter() {
+            let record = encode(positions.iter().map(|&i| row.try_at(i).map(|v| v.as_string())).collect::<Result<Vec<_>, _>>()?)?;
+
+            // start a new file when this one is full, but never leave a file without any rows
+            if file.is_some() && (rows >= rows_per_file || bytes + record.len() > max_bytes) {
+                file.take().unwrap().flush().map_err(io_err)?;
+            }
+
+            if file.is_none() {
+                let path = dir.as_ref().join(format!("part-{:05}.csv", paths.len()));
+                let mut part = BufWriter::new(File::create(&path).map_err(io_err)?);
+
+                part.write_all(&header).map_err(io_err)?;
+                paths.push(path);
+                file = Some(part);
+                rows = 0;
+                bytes = header.len();
+            }
+
+            file.as_mut().unwrap().write_all(&record).map_err(io_err)?;
+            rows += 1;
+            bytes += record.len();
+        }
+
+        match file {
+            Some(mut file) => file.flush().map_err(io_err)?,
+            None => {
+                let path = dir.as_ref().join("part-00000.csv");
+
+                std::fs::write(&path, &header).map_err(io_err)?;
+                paths.push(path);
+            }
+        }
+
+        let names = paths.iter().filter_map(|p| p.file_name()).map(|n| format!("{}\n", n.to_string_lossy())).collect::<String>();
+
+        std::fs::write(&manifest, names).map_err(io_err)?;
+
+        Ok(paths)
+    }
+
+    /// Write a table out to a CSV file, with the columns given by `options`, and a [`Metadata`](struct.Metadata.html)
+    /// sidecar file recording the type of each column written. Loading the file picks up the sidecar, instead of
+    /// inferring the types again; it's matched to the header, so `options` must write one.
+    fn to_csv_with_metadata<P: AsRef<Path>>(&self, csv_path :P, options :&WriteOptions) -> Result<(), TableError> {
+        if !options.has_header() {
+            return Err(TableError::new("A metadata sidecar needs the file to have a header"));
+        }
+
+        let positions = options.positions(&self.columns())?;
+
+        self.to_csv_with_options(&csv_path, options)?;
+
+        Metadata::from_table(self).select(&positions).write(csv_path).map_err(|e| TableError::new(e.to_string().as_str()))
     }
 
     fn group_by(&self, column :&str) -> Result<HashMap<Value, Self::TableSliceType>, TableError> {
-        // TODO: make sure the column name is valid
         let col_vals = self.unique(column)?;
         let mut ret = HashMap::with_capacity(col_vals.len());
 
@@ -189,9 +1178,49 @@ pub trait TableOperations {
         Ok(ret)
     }
 
+    /// Groups the rows by their value in `column`, the same as [`group_by`](#method.group_by), checking `token`
+    /// every few thousand rows; returns [`TableError::cancelled`](struct.TableError.html#method.cancelled) once it's
+    /// cancelled.
+    fn group_by_with_cancel(&self, column :&str, token :&CancelToken) -> Result<HashMap<Value, Self::TableSliceType>, TableError> {
+        let pos = self.column_position(column)?;
+        let mut groups :HashMap<Value, Vec<usize>> = HashMap::new();
+
+        for (i, row) in self.iter().enumerate() {
+            if i % cancel::CHECK_ROWS == 0 {
+                token.check()?;
+            }
+
+            groups.entry(row.try_at(pos)?).or_default().push(i);
+        }
+
+        token.check()?;
+
+        groups.into_iter().map(|(key, rows)| self.take_rows(&rows).map(|slice| (key, slice))).collect()
+    }
+
+    /// Groups the rows by their values in all of `columns`, such as `&["exchange", "symbol"]`; each key has the values
+    /// in the same order as `columns`.
+    fn group_by_multi(&self, columns :&[&str]) -> Result<HashMap<Vec<Value>, Self::TableSliceType>, TableError> {
+        if columns.is_empty() {
+            return Err(TableError::new("Grouping needs at least one column"));
+        }
+
+        let positions = columns.iter().map(|c| self.column_position(c)).collect::<Result<Vec<_>, _>>()?;
+        let mut groups :HashMap<Vec<Value>, Vec<usize>> = HashMap::new();
+
+        for (i, row) in self.iter().enumerate() {
+            let key = positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+
+            groups.entry(key).or_default().push(i);
+        }
+
+        groups.into_iter().map(|(key, rows)| self.take_rows(&rows).map(|slice| (key, slice))).collect()
+    }
+
     /// Get a set of unique values for a given column
     fn unique(&self, column :&str) -> Result<HashSet<Value>, TableError>  {
-        //TODO: make sure the column name is valid
+        // make sure the column name is valid
+        self.column_position(column)?;
 
         // insert the values into the HashSet
         // TODO: use Rayon to make this go in parallel
@@ -203,15 +1232,441 @@ pub trait TableOperations {
         // get the position in the underlying table
         let pos = self.column_position(column)?;
 
-        self.filter_by(|row| row.get(column) == *value)
+        self.filter_by(|row| row.with_ref(pos, |v| v == *value).unwrap())
+    }
+
+    /// Returns a `TableSlice` of the rows with `value` in `column`, the same as [`filter`](#method.filter); for tables
+    /// and slices alike, so `table.find("symbol", &aapl)?.find("date", &day)` narrows a slice further.
+    fn find(&self, column :&str, value :&Value) -> Result<Self::TableSliceType, TableError> {
+        self.filter(column, value)
     }
 
+    /// Returns a `TableSlice` of the rows matching `predicate`, which is called on each row in order; the rows of the
+    /// slice are always in the order of this table.
     fn filter_by<P: FnMut(&Self::RowType) -> bool>(&self, predicate :P) -> Result<Self::TableSliceType, TableError>;
 
+    /// Returns a `TableSlice` of the rows matching `predicate`, the same as [`filter_by`](#method.filter_by), checking
+    /// `token` every few thousand rows; returns [`TableError::cancelled`](struct.TableError.html#method.cancelled)
+    /// once it's cancelled, without calling `predicate` again.
+    fn filter_by_with_cancel<P: FnMut(&Self::RowType) -> bool>(&self, token :&CancelToken, mut predicate :P) -> Result<Self::TableSliceType, TableError> {
+        let (mut index, mut cancelled) = (0usize, false);
+
+        let slice = self.filter_by(|row| {
+            cancelled = cancelled || (index % cancel::CHECK_ROWS == 0 && token.is_cancelled());
+            index += 1;
+
+            !cancelled && predicate(row)
+        })?;
+
+        if cancelled {
+            return Err(TableError::cancelled());
+        }
+
+        Ok(slice)
+    }
+
+    /// Like `filter_by`, but the predicate is also passed the position of the row in this table, which can be
+    /// used with `get` to look at the rows around it.
+    fn filter_by_indexed<P: FnMut(usize, &Self::RowType) -> bool>(&self, mut predicate :P) -> Result<Self::TableSliceType, TableError> {
+        let mut index = 0;
+
+        // filter_by calls the predicate on each row in order
+        self.filter_by(|row| {
+            index += 1;
+            predicate(index - 1, row)
+        })
+    }
+
+    /// Returns a `TableSlice` without the rows that have a `Value::Empty` in any of the `subset` columns,
+    /// or in any column when `subset` is `None`.
+    fn dropna(&self, subset :Option<&[&str]>) -> Result<Self::TableSliceType, TableError> {
+        let columns = subset_columns(self, subset)?;
+
+        self.filter_by(|row| columns.iter().all(|c| row.get(c) != Value::Empty))
+    }
+
+    /// `agg` of the non-empty values of `column` with differentially private noise added, calibrated by `privacy`;
+    /// only `Agg::Count`, `Agg::Sum`, and `Agg::Mean` are supported.
+    ///
+    /// Returns an error if a sum or mean is asked for without bounds for the column, or the column has a value that
+    /// isn't a number.
+    #[cfg(feature = "privacy")]
+    fn agg_with_privacy(&self, column :&str, agg :Agg, privacy :&Privacy) -> Result<f64, TableError> {
+        self.column_position(column)?;
+
+        let values = self.iter().map(|row| row.get(column)).filter(|v| *v != Value::Empty).collect::<Vec<_>>();
+
+        privacy.apply(column, agg, &values)
+    }
+
+    /// Reports what joining this table with `other` on the `keys` columns, which both must have, would do: the number
+    /// of distinct and null keys on each side, how many rows match, and how many rows the join would have. Checking
+    /// this first catches keys that don't line up, or repeat on both sides, before running an expensive join.
+    fn join_diagnostics<T: TableOperations>(&self, other :&T, keys :&[&str]) -> Result<JoinDiagnostics, TableError> {
+        join::diagnostics(self, other, keys, 1.0)
+    }
+
+    /// Estimates what joining this table with `other` on the `keys` columns would do, like
+    /// [`join_diagnostics`](#method.join_diagnostics), from only the `fraction` of the keys, above 0 and at most 1,
+    /// whose hash falls in the same range on both sides, so the keys counted are the ones that would match. For huge
+    /// tables, this keeps only that fraction of the keys in memory; the counts are scaled up from them.
+    fn join_diagnostics_sampled<T: TableOperations>(&self, other :&T, keys :&[&str], fraction :f64) -> Result<JoinDiagnostics, TableError> {
+        join::diagnostics(self, other, keys, fraction)
+    }
+
+    /// Returns a `TableSlice` of the rows whose values in the `keys` columns are also found in `other`, such as the
+    /// trades of the symbols in a watch list. Each row is kept once, however many rows of `other` it matches, and
+    /// rows with an empty key never match.
+    fn semi_join<T: TableOperations>(&self, other :&T, keys :&[&str]) -> Result<Self::TableSliceType, TableError> {
+        self.take_rows(&join::matching_rows(self, other, keys, true)?)
+    }
+
+    /// Returns a `TableSlice` of the rows whose values in the `keys` columns aren't found in `other`, the rows
+    /// [`semi_join`](#method.semi_join) leaves out; rows with an empty key are kept.
+    fn anti_join<T: TableOperations>(&self, other :&T, keys :&[&str]) -> Result<Self::TableSliceType, TableError> {
+        self.take_rows(&join::matching_rows(self, other, keys, false)?)
+    }
+
+    /// Spearman's rank correlation of columns `a` and `b`, which is the Pearson correlation of the ranks of their values.
+    ///
+    /// Rows where either value is empty are skipped. Returns an error if there are fewer than two rows left, or if
+    /// either column has the same value in every row.
+    fn spearman_corr(&self, a :&str, b :&str) -> Result<f64, TableError> {
+        self.column_position(a)?;
+        self.column_position(b)?;
+
+        let (x, y) :(Vec<Value>, Vec<Value>) = self.iter()
+            .map(|row| (row.get(a), row.get(b)))
+            .filter(|(x, y)| *x != Value::Empty && *y != Value::Empty)
+            .unzip();
+
+        if x.len() < 2 {
+            let err_str = format!("Not enough rows to correlate {} and {}: {}", a, b, x.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        stats::pearson(&stats::average_ranks(&x), &stats::average_ranks(&y)).ok_or_else(|| {
+            let err_str = format!("Correlation of {} and {} is undefined, a column is constant", a, b);
+            TableError::new(err_str.as_str())
+        })
+    }
+
+    /// Returns a `TableSlice` of the rows where the value in `column` compares to `value` with `cmp`.
+    ///
+    /// Only values [comparable](enum.Value.html#method.is_comparable) with `value` can match, so empty values never do,
+    /// and neither do strings when `value` is a number.
+    fn filter_cmp(&self, column :&str, cmp :Cmp, value :&Value) -> Result<Self::TableSliceType, TableError> {
+        self.column_position(column)?;
+
+        self.filter_by(|row| cmp.matches(&row.get(column), value))
+    }
+
+    /// Returns a `TableSlice` of the rows where the value in `column` is in `range`, such as `start..end`.
+    ///
+    /// Only values comparable with the bounds of the range can match, see [`filter_cmp`](#method.filter_cmp).
+    ///
+    /// When the table has statistics from `compute_stats`, and they show no value of the column can be in the range,
+    /// the rows aren't read.
+    fn filter_range<R: RangeBounds<Value>>(&self, column :&str, range :R) -> Result<Self::TableSliceType, TableError> {
+        self.column_position(column)?;
+
+        if self.column_stats(column).is_some_and(|stats| stats.excludes(&range)) {
+            return self.filter_by(|_| false);
+        }
+
+        self.filter_by(|row| in_range(&row.get(column), &range))
+    }
+
+    /// Returns a `TableSlice` of the rows matching a [`Query`](struct.Query.html) string, such as
+    /// `price > 100 && symbol == 'AAPL'`.
+    fn query(&self, query :&str) -> Result<Self::TableSliceType, TableError> {
+        let query = Query::new(query, &self.columns())?;
+        let mut ret = Ok( () );
+
+        let slice = self.filter_by(|row| {
+            match query.matches(row) {
+                Ok(m) => m,
+                Err(e) => {
+                    ret = Err(e);
+                    false
+                }
+            }
+        })?;
+
+        ret.map(|_| slice)
+    }
+
+    /// Returns a `TableSlice` without duplicate rows, comparing the values in `subset`, or in every column when `None`.
+    ///
+    /// `keep` chooses which of a set of duplicates is kept; the rows stay in table order.
+    fn drop_duplicates(&self, subset :Option<&[&str]>, keep :Keep) -> Result<Self::TableSliceType, TableError> {
+        let columns = subset_columns(self, subset)?;
+        let mut kept :HashMap<Vec<Value>, usize> = HashMap::new();
+
+        for (i, row) in self.iter().enumerate() {
+            let key = columns.iter().map(|c| row.get(c)).collect::<Vec<_>>();
+
+            match keep {
+                Keep::First => { kept.entry(key).or_insert(i); },
+                Keep::Last => { kept.insert(key, i); }
+            }
+        }
+
+        let mut rows = kept.into_values().collect::<Vec<_>>();
+
+        rows.sort_unstable();
+
+        self.take_rows(&rows)
+    }
+
     fn split_rows_at(&self, mid :usize) -> Result<(Self::TableSliceType, Self::TableSliceType), TableError>;
+
+    /// Returns a `TableSlice` of the rows at `indices`, in the order given.
+    fn take_rows(&self, indices :&[usize]) -> Result<Self::TableSliceType, TableError>;
+
+    /// Returns a `TableSlice` of `n` rows chosen at random, or every row when the table has fewer; the rows stay in table
+    /// order, and the same `seed` chooses the same rows.
+    #[cfg(feature = "sampling")]
+    fn sample_n(&self, n :usize, seed :u64) -> Result<Self::TableSliceType, TableError> {
+        self.take_rows(&sampling::sample_n(self, n, seed))
+    }
+
+    /// Returns a `TableSlice` of a fraction `frac`, from 0 to 1, of the rows chosen at random, like
+    /// [`sample_n`](#method.sample_n).
+    #[cfg(feature = "sampling")]
+    fn sample_frac(&self, frac :f64, seed :u64) -> Result<Self::TableSliceType, TableError> {
+        self.take_rows(&sampling::sample_frac(self, frac, seed)?)
+    }
+
+    /// Returns a `TableSlice` of `n_per_group` rows chosen at random for each value of `column`, or every row of the
+    /// values with fewer, so rare values are as well represented as common ones. The rows stay in table order.
+    #[cfg(feature = "sampling")]
+    fn stratified_sample(&self, column :&str, n_per_group :usize, seed :u64) -> Result<Self::TableSliceType, TableError> {
+        self.take_rows(&sampling::stratified(self, column, n_per_group, seed)?)
+    }
+
+    /// Assigns each row to one of `n` folds for cross-validation, at random, returning the fold of each row, from 0 to
+    /// `n - 1`, in table order, to add as a column with
+    /// [`add_column_values`](trait.Table.html#method.add_column_values) or to filter on, without copying the rows.
+    ///
+    /// The folds differ in size by at most one row; with a `stratify` column, such as the label, each fold also has
+    /// about the same share of each of its values. The same `seed` gives the same folds.
+    #[cfg(feature = "sampling")]
+    fn kfold(&self, n :usize, seed :u64, stratify :Option<&str>) -> Result<Vec<Value>, TableError> {
+        sampling::kfold(self, n, seed, stratify)
+    }
+
+    /// Summary statistics, the count, mean, standard deviation, quartiles, and extremes, of each numeric column for each
+    /// group of rows with the same value in `group_column`, computed in one pass over the rows, with the groups
+    /// summarized in parallel; [`to_table`](struct.Describe.html#method.to_table) has a row for each group and statistic.
+    fn describe_by(&self, group_column :&str) -> Result<Describe, TableError> {
+        Describe::new(self, group_column)
+    }
+
+    /// Finds clusters of rows with the same values in `keys` whose other values differ by at most `tolerance`, from 0
+    /// to 1, relative to the larger number or the longer string, such as the same record from two vendor files with a
+    /// rounded price or a misspelled name. Exact duplicates are clusters too, with a similarity of 1.
+    ///
+    /// The rows of each key are compared pairwise, so use keys that leave few rows per value.
+    fn find_near_duplicates(&self, keys :&[&str], tolerance :f64) -> Result<NearDuplicates, TableError> {
+        NearDuplicates::new(self, keys, tolerance)
+    }
+
+    /// Returns a `TableSlice` of the rows whose value in `column` contains `pattern`; like the other `str_` methods,
+    /// it reads the fields of a memory-mapped table as they are in the file, formats the values of in-memory tables,
+    /// and skips empty values.
+    fn str_contains(&self, column :&str, pattern :&str) -> Result<Self::TableSliceType, TableError> {
+        strings::filter(self, column, |s| s.contains(pattern))
+    }
+
+    /// Returns a `TableSlice` of the rows whose value in `column` starts with `prefix`.
+    fn str_startswith(&self, column :&str, prefix :&str) -> Result<Self::TableSliceType, TableError> {
+        strings::filter(self, column, |s| s.starts_with(prefix))
+    }
+
+    /// The number of characters of each value of `column`, in table order, to add as a column with
+    /// [`add_column_values`](trait.Table.html#method.add_column_values); empty values stay empty.
+    fn str_len(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        strings::map(self, column, |s| Value::Integer(s.chars().count() as i64))
+    }
+
+    /// Each value of `column` in lowercase, as a string.
+    fn str_lower(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        strings::map(self, column, |s| Value::String(s.to_lowercase()))
+    }
+
+    /// Each value of `column` with every `from` replaced by `to`, as a string.
+    fn str_replace(&self, column :&str, from :&str, to :&str) -> Result<Vec<Value>, TableError> {
+        strings::map(self, column, |s| Value::String(s.replace(from, to)))
+    }
+
+    /// The part at `index`, from 0, of each value of `column` split on `separator`, as a string, or empty when the
+    /// value has fewer parts; [`astype`](trait.Table.html#method.astype) converts a column of them to another type.
+    fn str_split_extract(&self, column :&str, separator :&str, index :usize) -> Result<Vec<Value>, TableError> {
+        strings::map(self, column, |s| s.split(separator).nth(index).map_or(Value::Empty, |p| Value::String(p.to_string())))
+    }
+
+    /// Counts the rows with each combination of values in `row_column` and `col_column`.
+    fn crosstab(&self, row_column :&str, col_column :&str) -> Result<Crosstab, TableError> where Self: Sized {
+        Crosstab::new(self, row_column, col_column)
+    }
+
+    /// Reports what changed from this table to `other`, a newer version of it, matching rows by their value in `key`,
+    /// which must be unique in both: the keys added and removed, the number of changed values in each column, and
+    /// each change.
+    fn diff_report<T: TableOperations>(&self, other :&T, key :&str) -> Result<DiffReport, TableError> {
+        DiffReport::new(self, other, key)
+    }
+
+    /// Finds the gaps in the time series in `time_column`, where the rows more than `interval` apart are missing the
+    /// times between them, such as the missing bars of a file of minute bars; with `by`, each of its values is a
+    /// separate series. Rows without a time are skipped, and any other value that isn't a time is an error.
+    fn find_gaps(&self, time_column :&str, interval :chrono::Duration, by :Option<&str>) -> Result<Gaps, TableError> {
+        Gaps::new(self, time_column, interval, by)
+    }
+
+    /// Computes the open, high, low, and close of `price_column` in each bucket of `frequency` in `time_column`, in one
+    /// pass, such as the minute bars of a file of trades, with the total of `volume_column` when given. Rows without a
+    /// time or price are skipped, and a price that isn't a number is an error.
+    fn ohlc(&self, time_column :&str, price_column :&str, frequency :Frequency, volume_column :Option<&str>) -> Result<Ohlc, TableError> {
+        Ohlc::new(self, time_column, price_column, frequency, volume_column)
+    }
+
+    /// Counts the leading digits of the numbers in `column`, and compares them to Benford's law, such as to audit
+    /// invoice amounts for made up numbers. Empty values, and zeros, are skipped, and any other non-numeric value is an
+    /// error.
+    fn digit_distribution(&self, column :&str) -> Result<DigitDistribution, TableError> {
+        DigitDistribution::new(self, column)
+    }
+
+    /// Builds a [`TableIndex`](struct.TableIndex.html) on `column`, for fast lookups of rows by value.
+    fn create_index(&self, column :&str) -> Result<TableIndex<'_, Self>, TableError> where Self: Sized {
+        TableIndex::new(self, column)
+    }
+
+    /// Groups the rows by their value in `column` into a [`GroupBy`](struct.GroupBy.html), with the groups in key
+    /// order and the rows of each group in table order.
+    fn groups(&self, column :&str) -> Result<GroupBy<'_, Self>, TableError> where Self: Sized {
+        GroupBy::new(self, column)
+    }
+}
+
+/// The file listing the parts written by [`to_csv_split`](trait.TableOperations.html#method.to_csv_split)
+#[cfg(feature = "std")]
+const PARTS_MANIFEST :&str = "_parts";
+
+/// Whether `name` is one [`to_csv_split`](trait.TableOperations.html#method.to_csv_split) gives its parts: `part-`,
+/// then at least 5 digits, then `.csv`
+#[cfg(feature = "std")]
+fn is_part_name(name :&str) -> bool {
+    let digits = name.strip_prefix("part-").and_then(|n| n.strip_suffix(".csv")).unwrap_or_default();
+
+    digits.len() >= 5 && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// How [`to_csv_split`](trait.TableOperations.html#method.to_csv_split) splits a table into files
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// Into this many files, with about the same number of rows each
+    Files(usize),
+    /// Into files of at most this many rows
+    Rows(usize),
+    /// Into files of at most this many bytes, or a single row when a row is larger
+    Bytes(usize)
+}
+
+/// Which of a set of duplicate rows [`drop_duplicates`](trait.TableOperations.html#method.drop_duplicates) keeps
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    First,
+    Last
+}
+
+/// How [`RowTable::align`](struct.RowTable.html#method.align) fills the times a table has no row at
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    /// Leave the other columns empty
+    Empty,
+    /// Use the last row before the time
+    FFill,
+    /// Use the first row after the time
+    BFill
+}
+
+/// Comparisons used by [`filter_cmp`](trait.TableOperations.html#method.filter_cmp)
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge
+}
+
+#[cfg(feature = "std")]
+impl Cmp {
+    /// Whether `value` compares to `other` with this comparison; values that aren't comparable never match.
+    pub fn matches(&self, value :&Value, other :&Value) -> bool {
+        if !value.is_comparable(other) || *value == Value::Empty {
+            return false;
+        }
+
+        let ordering = value.cmp(other);
+
+        match self {
+            Cmp::Eq => ordering == Ordering::Equal,
+            Cmp::Ne => ordering != Ordering::Equal,
+            Cmp::Lt => ordering == Ordering::Less,
+            Cmp::Le => ordering != Ordering::Greater,
+            Cmp::Gt => ordering == Ordering::Greater,
+            Cmp::Ge => ordering != Ordering::Less
+        }
+    }
+}
+
+/// Whether `value` is in `range`, and comparable with its bounds
+#[cfg(feature = "std")]
+fn in_range<R: RangeBounds<Value>>(value :&Value, range :&R) -> bool {
+    let comparable = |bound :Bound<&Value>| match bound {
+        Bound::Included(b) | Bound::Excluded(b) => value.is_comparable(b),
+        Bound::Unbounded => true
+    };
+
+    *value != Value::Empty && comparable(range.start_bound()) && comparable(range.end_bound()) && range.contains(value)
+}
+
+/// Applies `op` to the value as a string, leaving empty values empty
+#[cfg(feature = "std")]
+fn string_op<F: Fn(&str) -> String>(value :&Value, op :F) -> Value {
+    match value {
+        Value::Empty => Value::Empty,
+        value => Value::String(op(value.as_string().as_str()))
+    }
+}
+
+/// The columns in `subset`, making sure they're all in the table, or all the columns when `None`
+#[cfg(feature = "std")]
+fn subset_columns<T: TableOperations + ?Sized>(table :&T, subset :Option<&[&str]>) -> Result<Vec<String>, TableError> {
+    match subset {
+        Some(subset) => {
+            // make sure all the columns are there
+            for col in subset {
+                table.column_position(col)?;
+            }
+
+            Ok(subset.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+        },
+        None => Ok(table.columns())
+    }
 }
 
 /// A `TableSlice` is a view into a `Table`.
+#[cfg(feature = "std")]
 pub trait TableSlice: TableOperations {
     fn column_position(&self, column :&str) -> Result<usize, TableError> {
         if self.columns().iter().find(|c| c.as_str() == column).is_none() {
@@ -228,29 +1683,40 @@ pub trait TableSlice: TableOperations {
     ///
     /// If the columns passed are `A`, `B`, `C`, then the rows will be sored by column `A` first, then `B`, then `C`.
     fn sort(&self, columns :&[&str]) -> Result<Self::TableSliceType, TableError> {
+        self.sort_with_cancel(columns, &CancelToken::new())
+    }
+
+    /// Sorts the rows the same as [`sort`](#method.sort), checking `token` while the values of the columns are read,
+    /// and between sorting each chunk of rows; returns [`TableError::cancelled`](struct.TableError.html#method.cancelled)
+    /// once it's cancelled.
+    fn sort_with_cancel(&self, columns :&[&str], token :&CancelToken) -> Result<Self::TableSliceType, TableError> {
         // make sure columns were passed
         if columns.is_empty() {
             return Err(TableError::new("No columns passed to sort"));
         }
 
         // make sure all the columns are there
-        for col in columns {
-            TableSlice::column_position(self, col)?;
-        }
+        let positions = columns.iter().map(|col| TableSlice::column_position(self, col)).collect::<Result<Vec<_>, _>>()?;
+        let mut keys = Vec::with_capacity(self.len());
+
+        for (i, row) in self.iter().enumerate() {
+            if i % cancel::CHECK_ROWS == 0 {
+                token.check()?;
+            }
 
-        self.sort_by(|a, b| {
-            let mut ret = Ordering::Equal;
+            keys.push( (positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?, i) );
+        }
 
-            for col in columns {
-                ret = a.get(*col).cmp(&b.get(*col));
+        // sort the chunks, then merge their runs, which can't be stopped part way, but is quick once they're sorted
+        for chunk in keys.chunks_mut(cancel::CHECK_ROWS) {
+            token.check()?;
+            chunk.sort_unstable();
+        }
 
-                if ret != Ordering::Equal {
-                    return ret;
-                }
-            }
+        token.check()?;
+        keys.sort();
 
-            ret
-        })
+        self.take_rows(&keys.into_iter().map(|(_, i)| i).collect::<Vec<_>>())
     }
 
     /// Sorts the rows in the table, in an unstable way, in ascending order using the `compare` function to compare values.
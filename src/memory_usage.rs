@@ -0,0 +1,56 @@
+use std::fmt::{Display, Formatter, Error as FmtError};
+
+use crate::{TableOperations, Row};
+
+/// The memory used by a table, created with [`TableOperations::memory_usage`](trait.TableOperations.html#method.memory_usage).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Bytes used to find the rows, such as the offsets of the rows in a memory-mapped file
+    pub index: usize,
+    /// Bytes of the memory-mapped file; the OS only pages in the parts that are read
+    pub mapped: usize,
+    /// Estimated bytes used by the values of each column; for a memory-mapped table, the bytes they'd use if loaded
+    /// into a `RowTable`
+    pub columns: Vec<(String, usize)>
+}
+
+impl MemoryUsage {
+    /// Estimated bytes used by the values of every column
+    pub fn values(&self) -> usize {
+        self.columns.iter().map(|(_, m)| m).sum()
+    }
+
+    /// The index plus the values, not counting the memory-mapped file
+    pub fn total(&self) -> usize {
+        self.index + self.values()
+    }
+}
+
+impl Display for MemoryUsage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        let width = self.columns.iter().map(|(c, _)| c.len()).chain(std::iter::once("Index".len())).max().unwrap();
+
+        writeln!(f, "{:<w$}  {:>12}", "Index", self.index, w=width)?;
+        writeln!(f, "{:<w$}  {:>12}", "Mapped", self.mapped, w=width)?;
+
+        for (column, memory) in self.columns.iter() {
+            writeln!(f, "{:<w$}  {:>12}", column, memory, w=width)?;
+        }
+
+        writeln!(f, "total: {} bytes", self.total())
+    }
+}
+
+/// Estimated bytes used by the values of each column, reading every row
+pub(crate) fn column_memory<T: TableOperations + ?Sized>(table :&T) -> Vec<(String, usize)> {
+    let columns = table.columns();
+    let mut memory = vec![0usize; columns.len()];
+
+    for row in table.iter() {
+        for (i, col) in columns.iter().enumerate() {
+            memory[i] += row.get(col).mem_size();
+        }
+    }
+
+    columns.into_iter().zip(memory).collect()
+}
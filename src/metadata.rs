@@ -0,0 +1,196 @@
+//! Metadata sidecar files, written next to a CSV file so it can be re-opened with the same column types.
+//!
+//! The sidecar for `data.csv` is `data.csv.meta`, a text file with tab-separated fields:
+//! ```text
+//! rows    12
+//! column  date    Date
+//! column  price   Number
+//! column  notes
+//! ```
+//! A column without a type has values of several types, and is inferred value-by-value when loaded.
+use std::path::{Path, PathBuf};
+use std::io::{Error as IOError, ErrorKind};
+
+use crate::{TableOperations, Row, ValueType};
+
+/// Column types and row count of a CSV file written by [`to_csv_with_metadata`](trait.TableOperations.html#method.to_csv_with_metadata).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    /// The name of each column, with its type, or `None` when the type is inferred
+    pub columns: Vec<(String, Option<ValueType>)>,
+    pub rows: usize
+}
+
+impl Metadata {
+    /// Computes the metadata of a table.
+    pub fn from_table<T: TableOperations + ?Sized>(table :&T) -> Self {
+        let columns = table.columns();
+        let mut types = vec![ValueType::Empty; columns.len()];
+        let mut strings_only = vec![true; columns.len()];
+        let mut rows = 0;
+
+        for row in table.iter() {
+            for (i, col) in columns.iter().enumerate() {
+                let value_type = row.get(col).value_type();
+
+                // a string column might be a mix of types, and those have to be inferred
+                strings_only[i] &= value_type == ValueType::String || value_type == ValueType::Empty;
+
+                let t = std::mem::replace(&mut types[i], ValueType::Empty);
+                types[i] = t.merge(value_type);
+            }
+
+            rows += 1;
+        }
+
+        let columns = columns.into_iter().zip(types).zip(strings_only).map(|((c, t), strings_only)| {
+            if t == ValueType::String && !strings_only {
+                (c, None)
+            } else {
+                (c, Some(t))
+            }
+        }).collect();
+
+        Metadata { columns, rows }
+    }
+
+    /// The metadata of only the columns at `positions`, in that order, such as for the columns of an export.
+    pub(crate) fn select(mut self, positions :&[usize]) -> Self {
+        self.columns = positions.iter().map(|&i| self.columns[i].clone()).collect();
+        self
+    }
+
+    /// Path of the sidecar file for a CSV file
+    pub fn sidecar_path<P: AsRef<Path>>(csv_path :P) -> PathBuf {
+        let mut path = csv_path.as_ref().as_os_str().to_owned();
+
+        path.push(".meta");
+
+        PathBuf::from(path)
+    }
+
+    /// Writes the sidecar file for a CSV file.
+    pub fn write<P: AsRef<Path>>(&self, csv_path :P) -> Result<(), IOError> {
+        let mut contents = format!("rows\t{}\n", self.rows);
+
+        for (column, value_type) in self.columns.iter() {
+            if column.contains('\t') || column.contains('\n') {
+                let err_str = format!("Column names in metadata cannot contain tabs or newlines: {:?}", column);
+                return Err(IOError::new(ErrorKind::InvalidInput, err_str.as_str()));
+            }
+
+            match value_type {
+                Some(t) => contents += format!("column\t{}\t{}\n", column, t).as_str(),
+                None => contents += format!("column\t{}\n", column).as_str()
+            }
+        }
+
+        std::fs::write(Metadata::sidecar_path(csv_path), contents)
+    }
+
+    /// Reads the sidecar file for a CSV file.
+    ///
+    /// Returns `None` when there isn't a sidecar, or the CSV file was modified after the sidecar was written.
+    pub fn read<P: AsRef<Path>>(csv_path :P) -> Result<Option<Metadata>, IOError> {
+        let sidecar_path = Metadata::sidecar_path(&csv_path);
+
+        let sidecar_modified = match std::fs::metadata(&sidecar_path) {
+            Ok(m) => m.modified()?,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e)
+        };
+
+        if std::fs::metadata(&csv_path)?.modified()? > sidecar_modified {
+            debug!("Ignoring stale metadata: {}", sidecar_path.display());
+            return Ok(None);
+        }
+
+        let contents = std::fs::read_to_string(&sidecar_path)?;
+        let mut metadata = Metadata { columns: Vec::new(), rows: 0 };
+
+        for line in contents.lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+
+            match fields.as_slice() {
+                ["rows", rows] => metadata.rows = rows.parse().map_err(|_| invalid_line(line))?,
+                ["column", column] => metadata.columns.push((column.to_string(), None)),
+                ["column", column, value_type] => {
+                    let value_type = value_type.parse::<ValueType>().map_err(|_| invalid_line(line))?;
+
+                    metadata.columns.push((column.to_string(), Some(value_type)))
+                },
+                _ => return Err(invalid_line(line))
+            }
+        }
+
+        Ok(Some(metadata))
+    }
+
+    /// The type of each of `columns`, or `None` if they aren't the columns in the metadata.
+    pub(crate) fn types_for(&self, columns :&[String]) -> Option<Vec<Option<ValueType>>> {
+        if columns.len() != self.columns.len() || columns.iter().zip(self.columns.iter()).any(|(a, (b, _))| a != b) {
+            return None;
+        }
+
+        Some(self.columns.iter().map(|(_, t)| t.clone()).collect())
+    }
+}
+
+fn invalid_line(line :&str) -> IOError {
+    let err_str = format!("Invalid line in metadata: {:?}", line);
+    IOError::new(ErrorKind::InvalidData, err_str.as_str())
+}
+
+#[cfg(all(test, feature = "rowtable"))]
+mod tests {
+    use crate::{fixtures, Table, TableOperations, Row, RowTable, Value, ValueType, WriteOptions};
+    use crate::metadata::Metadata;
+
+    #[test]
+    fn round_trip() {
+        let path = fixtures::temp_dir().join("metadata_in.csv");
+
+        std::fs::write(&path, "code,mixed,price,empty\nabc,1,1.5,\nxyz,x,2,\n").unwrap();
+
+        let mut table = RowTable::from_csv(&path).unwrap();
+
+        // a string that would be inferred as a number
+        table.update_by(|row| { row.set("code", Value::String("007".to_string())).unwrap(); });
+
+        let out = fixtures::temp_dir().join("metadata_out.csv");
+
+        table.to_csv_with_metadata(&out, &WriteOptions::default()).unwrap();
+
+        let metadata = Metadata::read(&out).unwrap().unwrap();
+
+        assert_eq!(2, metadata.rows);
+        assert_eq!(vec![
+            ("code".to_string(), Some(ValueType::String)),
+            ("mixed".to_string(), None),
+            ("price".to_string(), Some(ValueType::Number)),
+            ("empty".to_string(), Some(ValueType::Empty))
+        ], metadata.columns);
+
+        let reopened = RowTable::from_csv(&out).unwrap();
+
+        assert_eq!(Value::String("007".to_string()), reopened.get(0).unwrap().get("code"));
+        assert_eq!(Value::Integer(1), reopened.get(0).unwrap().get("mixed"));
+        assert_eq!(Value::Empty, reopened.get(1).unwrap().get("empty"));
+        assert_eq!(table.dtypes(), reopened.dtypes());
+
+        #[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+        {
+            let mapped = crate::MMapTable::new(&out).unwrap();
+
+            assert_eq!(Value::String("007".to_string()), mapped.get(1).unwrap().get("code"));
+            assert_eq!(Some(ValueType::Number), mapped.declared_type("price"));
+            assert_eq!(None, mapped.declared_type("mixed"));
+            assert_eq!(table.dtypes(), mapped.dtypes());
+        }
+
+        // without the sidecar the codes are inferred as integers
+        std::fs::remove_file(Metadata::sidecar_path(&out)).unwrap();
+
+        assert_eq!(Value::Integer(7), RowTable::from_csv(&out).unwrap().get(0).unwrap().get("code"));
+    }
+}
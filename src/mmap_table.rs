@@ -1,100 +1,628 @@
 use std::path::Path;
-use std::collections::hash_map::RandomState;
-use std::collections::{HashMap, HashSet};
-use std::fs::OpenOptions;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
 use std::io::{Error as IOError, ErrorKind, Cursor};
 use std::sync::{Mutex, Arc};
+use std::thread::{self, JoinHandle};
 use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Error as FmtError};
+use std::borrow::Cow;
+use std::str::Utf8Error;
 
 use memmap::{MmapMut, MmapOptions};
-use csv_core::{Reader as CsvCoreReader, ReadRecordResult};
-use csv::Reader;
+use csv_core::{Reader as CsvCoreReader, ReadRecordResult, ReadFieldResult};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-use crate::{Table, TableOperations, Value, TableError, Row, RowSlice, TableSlice};
+use crate::snapshot::Snapshot;
+use crate::{parallel, schema, stats, ColumnStats, Table, TableOperations, Value, ValueRef, ValueType, TableError, Row, RowSlice, TableSlice, Metadata, ReadOptions, WriteOptions, MemoryUsage, Progress, CancelToken, memory_usage, DISPLAY_ROWS, DISPLAY_WIDTH};
 use std::borrow::Borrow;
 
+/// Bytes of the file read by `estimate_len`
+const ESTIMATE_SAMPLE_SIZE :usize = 4 * 1024 * 1024;
+
+/// Rows indexed between calls to the progress callback of `with_progress`
+const PROGRESS_ROWS :usize = 64 * 1024;
+
 pub struct MMapTableInner {
     columns: Vec<String>,
     mmap: MmapMut,
     rows: Vec<usize>,
+    types: Vec<Option<ValueType>>,          // type of each column from the metadata sidecar, if any
+    edits: HashMap<(usize, usize), Value>,  // (row, column) edits that don't fit in the file
+    fields: Option<(usize, Vec<(usize, usize)>)>, // start and end of the fields of the last row read, see `fields`
+    stats: Option<Vec<ColumnStats>>,        // statistics of each column from `compute_stats`, cleared by edits
+    nulls: Vec<String>,                     // tokens read as `Value::Empty`, from the `ReadOptions`
+    indexer: Option<JoinHandle<Vec<usize>>> // thread indexing the rows, for tables opened with `open_header`
 }
 
-pub struct MMapTable (Arc<Mutex<MMapTableInner>>);
+impl MMapTableInner {
+    /// Waits for the background indexing, if any, to finish and takes its rows.
+    fn wait_for_index(&mut self) {
+        if let Some(indexer) = self.indexer.take() {
+            self.rows = indexer.join().expect("Indexing thread panicked");
+        }
+    }
 
-impl MMapTable {
-    pub fn new<P: AsRef<Path>>(file :P) -> Result<Self, IOError> {
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(&file)?;
+    /// Finds the start and end of each field of a row in the file, including any quotes.
+    fn find_fields(&self, row :usize) -> Result<Vec<(usize, usize)>, TableError> {
+        let mut reader = CsvCoreReader::new();
+        // only how much of the file each field takes is needed, so the unquoted field is thrown away
+        let mut output = [0u8; 4096];
+        let mut fields = Vec::new();
+        let (mut start, mut read) = (self.rows[row], 0);
+
+        loop {
+            // read the field, finishing it at the end of the file if needed
+            let (res, r, _) = reader.read_field(&self.mmap[start+read..], &mut output);
+
+            read += r;
+
+            let record_end = match res {
+                ReadFieldResult::InputEmpty | ReadFieldResult::OutputFull => continue,
+                ReadFieldResult::Field { record_end } => record_end,
+                ReadFieldResult::End => {
+                    let err_str = format!("Row {} is past the end of the file", row);
+                    return Err(TableError::new(err_str.as_str()));
+                }
+            };
+
+            let mut end = start + read;
+
+            // drop the delimiter or line terminator, which can only be inside a field when it's quoted, and
+            // then the field ends with the closing quote
+            while end > start && [b',', b'\r', b'\n'].contains(&self.mmap[end-1]) {
+                end -= 1;
+            }
+
+            fields.push( (start, end) );
+
+            if record_end {
+                return Ok(fields);
+            }
+
+            start += read;
+            read = 0;
+        }
+    }
+
+    /// Returns the start and end of each field of a row in the file, keeping them for the last row read, so reading
+    /// the other fields of the row doesn't parse it again.
+    fn fields(&mut self, row :usize) -> Result<&[(usize, usize)], TableError> {
+        if self.fields.as_ref().map(|(r, _)| *r) != Some(row) {
+            self.fields = Some( (row, self.find_fields(row)?) );
+        }
+
+        Ok(&self.fields.as_ref().unwrap().1)
+    }
+
+    /// Reads the values of `columns`, each a name and a position in the file, of a row, without the cache of `fields`,
+    /// so several threads can read rows at once.
+    fn row_values(&self, row :usize, columns :&[(String, usize)]) -> Result<Vec<Value>, TableError> {
+        let fields = self.find_fields(row)?;
+
+        columns.iter().map(|(column, pos)| {
+            if let Some(value) = self.edits.get(&(row, *pos)) {
+                return Ok(value.clone());
+            }
+
+            let &(start, end) = fields.get(*pos).ok_or_else(|| {
+                let err_str = format!("Could not parse column {}: row {} only has {} fields", column, row, fields.len());
+                TableError::new(err_str.as_str())
+            })?;
+
+            match self.field_text(start, end) {
+                Ok(field) => Value::with_type_or_infer(&field, self.types.get(*pos).and_then(|t| t.as_ref()), &self.nulls),
+                Err(e) => {
+                    let err_str = format!("Could not parse column {}: {}", column, e);
+                    Err(TableError::new(err_str.as_str()))
+                }
+            }
+        }).collect()
+    }
+
+    /// Returns the start and end of field `pos` of `row`, named `column` in errors.
+    fn field_span(&mut self, row :usize, pos :usize, column :&str) -> Result<(usize, usize), TableError> {
+        let fields = self.fields(row)?;
+
+        match fields.get(pos) {
+            Some(&span) => Ok(span),
+            None => {
+                let err_str = format!("Could not parse column {}: row {} only has {} fields", column, row, fields.len());
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
 
-        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+    /// Returns the text of the field from `start` to `end` in the file, borrowed unless it's quoted.
+    fn field_text(&self, start :usize, end :usize) -> Result<Cow<'_, str>, Utf8Error> {
+        let raw = &self.mmap[start..end];
+
+        if raw.first() != Some(&b'"') {
+            return std::str::from_utf8(raw).map(Cow::Borrowed);
+        }
 
+        // the field without its quotes is never longer than with them
         let mut reader = CsvCoreReader::new();
-        let mut rows = vec![0usize];
-        let mut pos = 0;
-        let mut output = [0u8; 1024*1024];
+        let mut output = vec![0u8; raw.len()];
+        let (_, _, written) = reader.read_field(raw, &mut output);
+        let (_, _, rest) = reader.read_field(&[], &mut output[written..]);
 
-        loop {
-            let mut ends = [0usize; 100];
+        output.truncate(written + rest);
+
+        String::from_utf8(output).map(Cow::Owned).map_err(|e| e.utf8_error())
+    }
+
+    /// Sets a field, in the file when the new value is the same length as the old one.
+    fn set_field(&mut self, row :usize, pos :usize, value :Value) -> Result<(), TableError> {
+        self.stats = None;
 
-            let (res, read, written, num_ends) = reader.read_record(&mmap[pos..], &mut output, &mut ends);
+        let mut field = value.to_string();
 
-//            println!("POS: {} RES: {:?} READ: {} WRITTEN: {} NUM_ENDS: {}", pos, res, read, written, num_ends);
+        if field.bytes().any(|b| [b',', b'"', b'\r', b'\n'].contains(&b)) {
+            field = format!("\"{}\"", field.replace('"', "\"\""));
+        }
 
-            if let ReadRecordResult::End = res {
-                break;
+        let (start, end) = match self.fields(row)?.get(pos) {
+            Some(&span) => span,
+            None => {
+                let err_str = format!("Row {} only has {} fields", row, self.fields(row)?.len());
+                return Err(TableError::new(err_str.as_str()));
             }
+        };
+
+        if end - start == field.len() {
+            self.mmap[start..end].copy_from_slice(field.as_bytes());
+            self.edits.remove(&(row, pos));
+
+            return Ok( () );
+        }
 
-            pos += read;
+        self.edits.insert((row, pos), value);
 
-            if let ReadRecordResult::Record = res {
-                rows.push(pos);
+        Ok( () )
+    }
+
+    /// Declares the type of the column at `pos`, so the values in the file are parsed with it, and converts the edits
+    /// of the column to it. The values that can't be parsed with the type are set to empty, returning their rows,
+    /// unless `strict`, when nothing is changed and the first of them is an error.
+    fn retype(&mut self, column :&str, pos :usize, value_type :ValueType, strict :bool) -> Result<Vec<usize>, TableError> {
+        self.wait_for_index();
+
+        let (mut failed, mut edits) = (Vec::new(), Vec::new());
+
+        for row in 0..self.rows.len() {
+            let text = match self.edits.get(&(row, pos)) {
+                Some(Value::Empty) => continue,
+                Some(value) => value.as_string(),
+                None => {
+                    let (start, end) = self.fields(row)?.get(pos).cloned().unwrap_or((0, 0));
+
+                    self.field_text(start, end).map_err(|e| TableError::new(e.to_string().as_str()))?.into_owned()
+                }
+            };
+
+            match Value::with_type_or_infer(&text, Some(&value_type), &self.nulls) {
+                Ok(value) if self.edits.contains_key(&(row, pos)) => edits.push( ((row, pos), value) ),
+                Ok(_) => (),
+                Err(_) if strict => return Err(schema::conversion_error(row, column, &text, &value_type)),
+                Err(_) => failed.push(row)
             }
         }
 
-        rows.pop();
-        rows.shrink_to_fit();
+        self.edits.extend(edits);
+
+        for &row in failed.iter() {
+            self.set_field(row, pos, Value::Empty)?;
+        }
+
+        self.types.resize(self.columns.len(), None);
+        self.types[pos] = Some(value_type);
+        self.stats = None;
+
+        Ok(failed)
+    }
+}
 
-//        println!("ROWS: {}", rows.len());
+/// Parses the header, returning the columns and the offset of the first row.
+fn read_header(data :&[u8], options :&ReadOptions) -> Result<(Vec<String>, usize), IOError> {
+    let mut reader = CsvCoreReader::new();
+    let (mut output, mut ends) = record_buffers();
+    let mut rest = data;
 
-        let mut header_buffer = vec![0u8; rows[1]];
+    options.skip_lines(&mut rest)?;
 
-        header_buffer.copy_from_slice(&mmap[0..rows[1]]);
+    let start = skip_line_ends(data, data.len() - rest.len(), options.comment_char());
+    let mut pos = start;
 
-        let mut header_reader = Reader::from_reader(header_buffer.as_slice());
+    let header_end = loop {
+        let (res, read, _, _) = reader.read_record(&data[pos..], &mut output, &mut ends);
 
-        let columns = header_reader.headers()?.iter().map(|h| String::from(h)).collect::<Vec<_>>();
+        pos += read;
 
-        if columns.iter().collect::<HashSet<_>>().len() != columns.len() {
-            return Err(IOError::new(ErrorKind::InvalidData, "Duplicate columns detected in the file"));
+        match res {
+            ReadRecordResult::Record => break skip_line_ends(data, pos, options.comment_char()),
+            ReadRecordResult::End => return Err(IOError::new(ErrorKind::InvalidData, "No header found in the file")),
+            ReadRecordResult::InputEmpty | ReadRecordResult::OutputFull | ReadRecordResult::OutputEndsFull => continue
         }
+    };
+
+    let columns = options.columns(&mut options.reader(&data[start..header_end]))?;
+
+    // without a header, the first record is the first row
+    Ok( (columns, if options.has_header() { header_end } else { start }) )
+}
+
+/// Buffers for finding where records end with `read_record`, which only need the positions in the file; a record
+/// with more fields, or more bytes, than fit is read by calling it again, with the same buffers, until it ends.
+fn record_buffers() -> (Vec<u8>, Vec<usize>) {
+    (vec![0u8; 64 * 1024], vec![0usize; 1024])
+}
+
+/// Skips the rest of a line break, such as the `\n` of `\r\n`, and any blank lines, or lines starting with `comment`,
+/// after the end of a record, so the offset of a row is the first byte of its first field. Records can't start with a
+/// line break, so this never skips part of a record.
+fn skip_line_ends(data :&[u8], mut pos :usize, comment :Option<u8>) -> usize {
+    loop {
+        while pos < data.len() && (data[pos] == b'\r' || data[pos] == b'\n') {
+            pos += 1;
+        }
+
+        match comment {
+            Some(c) if pos < data.len() && data[pos] == c => {
+                pos += data[pos..].iter().position(|&b| b == b'\n').unwrap_or(data.len() - pos);
+            },
+            _ => return pos
+        }
+    }
+}
+
+/// Finds the offset of every row after the header, skipping lines that start with `comment`, calling `report` with
+/// the rows and bytes indexed every `PROGRESS_ROWS` rows, and stopping at the first error it returns.
+fn index_rows(data :&[u8], header_end :usize, comment :Option<u8>, report :&mut dyn FnMut(usize, usize) -> Result<(), IOError>) -> Result<Vec<usize>, IOError> {
+    let mut reader = CsvCoreReader::new();
+    let mut rows = vec![header_end];
+    let mut pos = header_end;
+    let (mut output, mut ends) = record_buffers();
+
+    loop {
+        let (res, read, _, _) = reader.read_record(&data[pos..], &mut output, &mut ends);
+
+        if let ReadRecordResult::End = res {
+            break;
+        }
+
+        pos += read;
+
+        if let ReadRecordResult::Record = res {
+            pos = skip_line_ends(data, pos, comment);
+            rows.push(pos);
+
+            // the last offset pushed is the start of the next row
+            if (rows.len() - 1) % PROGRESS_ROWS == 0 {
+                report(rows.len() - 1, pos)?;
+            }
+        }
+    }
+
+    // the last offset is the end of the file
+    rows.pop();
+    rows.shrink_to_fit();
+
+    Ok(rows)
+}
+
+/// Estimates the number of rows after the header from the average length of the records in the first
+/// `sample_size` bytes.
+fn estimate_rows(data :&[u8], sample_size :usize) -> usize {
+    let mut reader = CsvCoreReader::new();
+    let (mut output, mut ends) = record_buffers();
+    let (mut pos, mut header_end, mut records) = (0, None, 0);
+
+    // an empty input at the end of the file finishes its last record
+    while pos <= data.len() && pos < header_end.unwrap_or(0) + sample_size {
+        let (res, read, _, _) = reader.read_record(&data[pos..], &mut output, &mut ends);
+
+        pos += read;
+
+        match res {
+            ReadRecordResult::Record if header_end.is_none() => header_end = Some(pos),
+            ReadRecordResult::Record => records += 1,
+            ReadRecordResult::End => break,
+            ReadRecordResult::InputEmpty | ReadRecordResult::OutputFull | ReadRecordResult::OutputEndsFull => continue
+        }
+    }
+
+    let header_end = header_end.unwrap_or(pos);
+
+    // the whole file was read, so the count is exact
+    if pos >= data.len() || records == 0 {
+        return records;
+    }
+
+    ((data.len() - header_end) as f64 / (pos - header_end) as f64 * records as f64).round() as usize
+}
+
+pub struct MMapTable (Arc<Mutex<MMapTableInner>>);
+
+impl MMapTable {
+    /// Maps a CSV file; the column types are taken from the file's [`Metadata`](struct.Metadata.html) sidecar
+    /// when it has one.
+    pub fn new<P: AsRef<Path>>(path :P) -> Result<Self, IOError> {
+        MMapTable::with_options(path, &ReadOptions::default())
+    }
+
+    /// Maps a CSV file as described by `options`, such as one without a header row.
+    pub fn with_options<P: AsRef<Path>>(path :P, options :&ReadOptions) -> Result<Self, IOError> {
+        MMapTable::with_options_and_progress(path, options, &CancelToken::new(), |_| ())
+    }
+
+    /// Maps a CSV file, the same as [`new`](#method.new), calling `progress` with the bytes and rows indexed so far
+    /// every `PROGRESS_ROWS` rows, and once more when the whole file is indexed, such as to show a progress bar.
+    pub fn with_progress<P: AsRef<Path>, F: FnMut(Progress)>(path :P, progress :F) -> Result<Self, IOError> {
+        MMapTable::with_options_and_progress(path, &ReadOptions::default(), &CancelToken::new(), progress)
+    }
+
+    /// Maps a CSV file, the same as [`new`](#method.new), checking `token` every `PROGRESS_ROWS` rows while the file
+    /// is indexed.
+    ///
+    /// Once it's cancelled, returns an `Interrupted` error wrapping
+    /// [`TableError::cancelled`](struct.TableError.html#method.cancelled).
+    pub fn with_cancel<P: AsRef<Path>>(path :P, token :&CancelToken) -> Result<Self, IOError> {
+        MMapTable::with_progress_and_cancel(path, token, |_| ())
+    }
+
+    /// Maps a CSV file, calling `progress` like [`with_progress`](#method.with_progress), and checking `token` after
+    /// each call like [`with_cancel`](#method.with_cancel), so the indexing can be cancelled from `progress`.
+    pub fn with_progress_and_cancel<P: AsRef<Path>, F: FnMut(Progress)>(path :P, token :&CancelToken, progress :F) -> Result<Self, IOError> {
+        MMapTable::with_options_and_progress(path, &ReadOptions::default(), token, progress)
+    }
+
+    fn with_options_and_progress<P: AsRef<Path>, F: FnMut(Progress)>(path :P, options :&ReadOptions, token :&CancelToken, mut progress :F) -> Result<Self, IOError> {
+        token.check_io()?;
+
+        let total_bytes = std::fs::metadata(&path)?.len();
+        let table = MMapTable::map_and_index(path, options, &mut |rows, bytes| {
+            progress(Progress { bytes: bytes as u64, total_bytes, rows });
+            token.check_io()
+        })?;
+
+        progress(Progress { bytes: total_bytes, total_bytes, rows: table.len() });
+
+        Ok(table)
+    }
+
+    /// Maps a CSV file and finds its rows, calling `report` with the rows and bytes indexed every `PROGRESS_ROWS` rows
+    fn map_and_index<P: AsRef<Path>>(path :P, options :&ReadOptions, report :&mut dyn FnMut(usize, usize) -> Result<(), IOError>) -> Result<Self, IOError> {
+        let (_file, mmap) = MMapTable::map_file(&path)?;
+        let (columns, header_end) = read_header(&mmap, options)?;
+        let mut rows = index_rows(&mmap, header_end, options.comment_char(), report)?;
+
+        options.trim_footer(&mut rows);
+
+        MMapTable::from_parts(&path, columns, mmap, rows, options.nulls().to_vec(), None)
+    }
+
+    /// Maps a CSV file using the index saved by [`save_index`](#method.save_index), instead of finding its rows,
+    /// so re-opening a large file is near-instant.
+    ///
+    /// Returns an error if the file's length, or a hash of its contents, changed since the index was saved.
+    pub fn open_with_index<P: AsRef<Path>, I: AsRef<Path>>(csv_path :P, index_path :I) -> Result<Self, IOError> {
+        MMapTable::open_with_index_and_options(csv_path, index_path, &ReadOptions::default())
+    }
+
+    /// Maps a CSV file using the index saved by [`save_index`](#method.save_index), like
+    /// [`open_with_index`](#method.open_with_index), reading the null tokens of `options` as empty values; the columns
+    /// and rows are the ones saved with the index.
+    pub fn open_with_index_and_options<P: AsRef<Path>, I: AsRef<Path>>(csv_path :P, index_path :I, options :&ReadOptions) -> Result<Self, IOError> {
+        let (_file, mmap) = MMapTable::map_file(&csv_path)?;
+        let snapshot = Snapshot::read(index_path, &mmap)?;
+        let table = MMapTable::from_parts(&csv_path, snapshot.columns, mmap, snapshot.rows, options.nulls().to_vec(), None)?;
+
+        // the types saved with the index win over the metadata sidecar's
+        if !snapshot.types.is_empty() {
+            table.0.lock().unwrap().types = snapshot.types;
+        }
+
+        Ok(table)
+    }
+
+    /// Maps a CSV file, only reading its header before returning; the rows are indexed in a background thread.
+    ///
+    /// The columns are available right away, while anything that reads rows blocks until the indexing finishes.
+    pub fn open_header<P: AsRef<Path>>(path :P) -> Result<Self, IOError> {
+        MMapTable::open_header_with_options(path, &ReadOptions::default())
+    }
+
+    /// Maps a CSV file as described by `options`, only reading its header before returning, like
+    /// [`open_header`](#method.open_header).
+    pub fn open_header_with_options<P: AsRef<Path>>(path :P, options :&ReadOptions) -> Result<Self, IOError> {
+        let (file, mmap) = MMapTable::map_file(&path)?;
+        let (columns, header_end) = read_header(&mmap, options)?;
+
+        // index a second, read-only, mapping so the table can be used while the thread runs
+        let index_mmap = unsafe { MmapOptions::new().map(&file)? };
+        let thread_options = options.clone();
+        let indexer = thread::spawn(move || {
+            // indexing only fails when reporting does
+            let mut rows = index_rows(&index_mmap, header_end, thread_options.comment_char(), &mut |_, _| Ok( () )).unwrap();
+
+            thread_options.trim_footer(&mut rows);
+            rows
+        });
+
+        MMapTable::from_parts(&path, columns, mmap, Vec::new(), options.nulls().to_vec(), Some(indexer))
+    }
+
+    fn map_file<P: AsRef<Path>>(path :P) -> Result<(File, MmapMut), IOError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&path)?;
+
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        Ok( (file, mmap) )
+    }
+
+    fn from_parts<P: AsRef<Path>>(path :P, columns :Vec<String>, mmap :MmapMut, rows :Vec<usize>, nulls :Vec<String>, indexer :Option<JoinHandle<Vec<usize>>>) -> Result<Self, IOError> {
+        let metadata = Metadata::read(&path)?;
+        let types = metadata.and_then(|m| m.types_for(&columns)).unwrap_or_default();
 
         Ok(MMapTable (
             Arc::new(Mutex::new(MMapTableInner{
                 columns,
                 mmap,
-                rows
+                rows,
+                types,
+                edits: HashMap::new(),
+                fields: None,
+                stats: None,
+                nulls,
+                indexer
         }))))
     }
+
+    /// Returns true once all the rows are indexed, which is always the case for a table opened with `new`.
+    pub fn is_indexed(&self) -> bool {
+        self.0.lock().unwrap().indexer.as_ref().map_or(true, |h| h.is_finished())
+    }
+
+    /// Returns the number of rows once they're indexed, otherwise an estimate from the average length of
+    /// the rows in the first few MB of the file.
+    pub fn estimate_len(&self) -> usize {
+        let table = self.0.lock().unwrap();
+
+        if table.indexer.is_none() {
+            return table.rows.len();
+        }
+
+        estimate_rows(&table.mmap, ESTIMATE_SAMPLE_SIZE)
+    }
+
+    /// Returns a rayon parallel iterator over the rows of the table.
+    ///
+    /// It runs in the current rayon thread pool; use it inside [`install`](fn.install.html) to run it in the pool set
+    /// with `set_parallelism`. Each value read from a row locks the table, and the fields of only the last row read
+    /// are kept, so the threads take turns reading, and parse rows again; it suits work that takes longer than reading
+    /// the values, while [`par_map`](#method.par_map) reads them in parallel.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item=RowSlice<MMapTableInner>> {
+        let column_map :Arc<Vec<(String, usize)>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect());
+        let table = self.0.clone();
+
+        (0..self.len()).into_par_iter().map(move |row| RowSlice { column_map: column_map.clone(), table: table.clone(), row })
+    }
+
+    /// Calls `f` on the rows in parallel, with the values of each row in the order of `columns`, returning the results
+    /// in the order of the rows. The table is locked once, rather than for each value, so don't use the table from `f`;
+    /// each thread parses the rows it reads.
+    pub fn par_map<R: Send, F: Fn(&[Value]) -> R + Sync + Send>(&self, f :F) -> Result<Vec<R>, TableError> {
+        let mut inner = self.0.lock().unwrap();
+
+        inner.wait_for_index();
+
+        let columns = inner.columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect::<Vec<_>>();
+        let rows = (0..inner.rows.len()).collect::<Vec<_>>();
+
+        par_map_rows(&inner, &rows, &columns, f)
+    }
+
+    /// Computes the minimum, maximum, and number of empty values of every column, in one pass over the file, and keeps
+    /// them until the table is edited. `filter_range` uses them to skip files that can't have matching rows.
+    pub fn compute_stats(&self) {
+        let stats = stats::column_stats(self);
+
+        self.0.lock().unwrap().stats = Some(stats);
+    }
+
+    /// Blocks until all the rows are indexed.
+    pub fn wait_for_index(&self) {
+        self.0.lock().unwrap().wait_for_index();
+    }
+
+    /// Saves the columns, their types, and the offsets of the rows to `index_path`, with a hash of the file to check
+    /// it wasn't changed, for [`open_with_index`](#method.open_with_index). Pending edits aren't saved.
+    pub fn save_index<P: AsRef<Path>>(&self, index_path :P) -> Result<(), IOError> {
+        let mut table = self.0.lock().unwrap();
+
+        table.wait_for_index();
+
+        let snapshot = Snapshot { columns: table.columns.clone(), types: table.types.clone(), rows: table.rows.clone() };
+
+        snapshot.write(index_path, &table.mmap)
+    }
+
+    /// Number of edits that could not be made in the file, because the new value is a different length
+    /// than the old one, or needs quoting. These are only saved by `rewrite`.
+    pub fn pending_edits(&self) -> usize {
+        self.0.lock().unwrap().edits.len()
+    }
+
+    /// Flushes the edits made in the file to disk.
+    pub fn flush(&self) -> Result<(), IOError> {
+        self.0.lock().unwrap().mmap.flush()
+    }
+
+    /// Writes the table, including pending edits, to a new CSV file.
+    ///
+    /// The path must not be the file backing this table; open the new file to continue working with it.
+    pub fn rewrite<P: AsRef<Path>>(&self, path :P) -> Result<(), IOError> {
+        self.rewrite_with_options(path, &WriteOptions::default())
+    }
+
+    /// Writes the table, including pending edits, to a new CSV file, with the columns, header, quoting, and line
+    /// endings given by `options`.
+    pub fn rewrite_with_options<P: AsRef<Path>>(&self, path :P, options :&WriteOptions) -> Result<(), IOError> {
+        self.to_csv_with_options(path, options).map_err(|e| IOError::new(ErrorKind::InvalidData, e.to_string()))
+    }
 }
 
 impl Table for MMapTable {
-    fn update_by<F: FnMut(&mut Self::RowType)>(&mut self, update: F) {
-        unimplemented!()
+    fn update_by<F: FnMut(&mut Self::RowType)>(&mut self, mut update: F) {
+        for mut row in self.iter() {
+            update(&mut row);
+        }
     }
 
     fn append_row<R>(&mut self, row: R) -> Result<(), TableError> where R: Row {
-        unimplemented!("You can only modify the contents of memory-mapped table, not change it's size")
+        Err(TableError::new("You can only modify the contents of memory-mapped table, not change it's size"))
     }
 
     fn add_column_with<F: FnMut() -> Value>(&mut self, column_name: &str, f: F) -> Result<(), TableError> {
-        unimplemented!("You can only modify the contents of memory-mapped table, not change it's size")
+        Err(TableError::new("You can only modify the contents of memory-mapped table, not change it's size"))
     }
 
+    /// Renames a column; the file's header is only changed by `rewrite`.
     fn rename_column(&mut self, old_col :&str, new_col :&str) -> Result<(), TableError> {
-        unimplemented!()
+        let pos = self.column_position(old_col)?;
+
+        self.0.lock().unwrap().columns[pos] = new_col.to_string();
+
+        Ok( () )
+    }
+
+    /// Declares the type of `column`, parsing the text of its values in the file with it, so `"007"` read as
+    /// `ValueType::String` stays `"007"`; the file is unchanged.
+    fn astype(&mut self, column :&str, value_type :ValueType) -> Result<(), TableError> {
+        let pos = self.column_position(column)?;
+
+        self.0.lock().unwrap().retype(column, pos, value_type, true).map(|_| ())
+    }
+
+    /// Declares the type of `column`, the same as [`astype`](#method.astype), setting the values that can't be parsed
+    /// with it to empty.
+    fn try_astype(&mut self, column :&str, value_type :ValueType) -> Result<Vec<usize>, TableError> {
+        let pos = self.column_position(column)?;
+
+        self.0.lock().unwrap().retype(column, pos, value_type, false)
+    }
+}
+
+impl Display for MMapTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        write!(f, "{}", self.preview(DISPLAY_ROWS, DISPLAY_WIDTH))
     }
 }
 
@@ -104,10 +632,30 @@ impl TableOperations for MMapTable {
     type Iter = MMapTableIter;
 
     fn iter(&self) -> Self::Iter {
+        let mut table = self.0.lock().unwrap();
+
+        table.wait_for_index();
+
         MMapTableIter {
             table: self.0.clone(),
-            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
-            cur_pos: 0
+            column_map: Arc::new(table.columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
+            cur_pos: 0,
+            end: table.rows.len()
+        }
+    }
+
+    /// The index is the offsets of the rows, plus the edits that don't fit in the file, and the offsets of the fields
+    /// of the last row read; the values of the columns are estimated by reading every row.
+    fn memory_usage(&self) -> MemoryUsage {
+        let columns = memory_usage::column_memory(self);
+        let table = self.0.lock().unwrap();
+        let edits = table.edits.values().map(|v| v.mem_size() + std::mem::size_of::<(usize, usize)>()).sum::<usize>();
+        let fields = table.fields.as_ref().map_or(0, |(_, f)| f.capacity() * std::mem::size_of::<(usize, usize)>());
+
+        MemoryUsage {
+            index: table.rows.capacity() * std::mem::size_of::<usize>() + edits + fields,
+            mapped: table.mmap.len(),
+            columns
         }
     }
 
@@ -128,8 +676,16 @@ impl TableOperations for MMapTable {
         self.0.lock().unwrap().borrow().columns.clone()
     }
 
-    fn group_by(&self, column: &str) -> Result<HashMap<Value, Self::TableSliceType, RandomState>, TableError> {
-        unimplemented!()
+    fn column_stats(&self, column :&str) -> Option<ColumnStats> {
+        let pos = self.column_position(column).ok()?;
+
+        self.0.lock().unwrap().stats.as_ref().map(|stats| stats[pos].clone())
+    }
+
+    fn declared_type(&self, column :&str) -> Option<ValueType> {
+        let pos = self.column_position(column).ok()?;
+
+        self.0.lock().unwrap().types.get(pos).cloned().flatten()
     }
 
     fn filter_by<P: FnMut(&Self::RowType) -> bool>(&self, mut predicate: P) -> Result<Self::TableSliceType, TableError> {
@@ -149,31 +705,93 @@ impl TableOperations for MMapTable {
     }
 
     fn split_rows_at(&self, mid: usize) -> Result<(Self::TableSliceType, Self::TableSliceType), TableError> {
-        unimplemented!()
+        let len = self.len();
+
+        if mid >= len {
+            let err_str = format!("Midpoint too large: {} >= {}", mid, len);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let column_map :Arc<Vec<(String, usize)>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect());
+
+        Ok( (
+            MMapTableSlice { column_map: column_map.clone(), rows: Arc::new((0..mid).collect()), table: self.0.clone() },
+            MMapTableSlice { column_map, rows: Arc::new((mid..len).collect()), table: self.0.clone() }
+            )
+        )
+    }
+
+    fn take_rows(&self, indices :&[usize]) -> Result<Self::TableSliceType, TableError> {
+        let len = self.len();
+
+        if let Some(index) = indices.iter().find(|&&i| i >= len) {
+            let err_str = format!("Index {} is beyond table length {}", index, len);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok(MMapTableSlice {
+            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
+            rows: Arc::new(indices.to_vec()),
+            table: self.0.clone()
+        })
     }
 }
 
-/// `Iterator` for rows in a table.
+/// `Iterator` for rows in a table; creating it waits for the rows to be indexed, and it covers the rows in the table
+/// when it was created.
 pub struct MMapTableIter {
     table: Arc<Mutex<MMapTableInner>>,
     column_map: Arc<Vec<(String, usize)>>,
-    cur_pos: usize
+    cur_pos: usize,
+    end: usize
+}
+
+impl MMapTableIter {
+    fn row(&self, row :usize) -> RowSlice<MMapTableInner> {
+        RowSlice { table: self.table.clone(), column_map: self.column_map.clone(), row }
+    }
 }
 
 impl Iterator for MMapTableIter {
     type Item=RowSlice<MMapTableInner>;
 
     fn next(&mut self) -> Option<Self::Item> {
-         if self.cur_pos >= self.table.lock().unwrap().rows.len() {
-             None
-         } else {
-             self.cur_pos += 1;
-             Some(RowSlice {
-                 table: self.table.clone(),
-                 column_map: self.column_map.clone(),
-                 row: self.cur_pos-1
-             })
-         }
+        if self.cur_pos >= self.end {
+            None
+        } else {
+            self.cur_pos += 1;
+            Some(self.row(self.cur_pos-1))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.end - self.cur_pos, Some(self.end - self.cur_pos))
+    }
+}
+
+impl DoubleEndedIterator for MMapTableIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.cur_pos >= self.end {
+            None
+        } else {
+            self.end -= 1;
+            Some(self.row(self.end))
+        }
+    }
+}
+
+impl ExactSizeIterator for MMapTableIter {}
+
+impl RowSlice<MMapTableInner> {
+    /// The name of the column at `index` in the row, and its position in the file
+    fn column_at(&self, index :usize) -> Result<(&str, usize), TableError> {
+        match self.column_map.get(index) {
+            Some((column, pos)) => Ok( (column.as_str(), *pos) ),
+            None => {
+                let err_str = format!("Index {} is beyond row width {}", index, self.column_map.len());
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
     }
 }
 
@@ -186,33 +804,77 @@ impl Row for RowSlice<MMapTableInner> {
             return Err(TableError::new(err_str.as_str()));
         }
 
-        let pos = self.column_map[pos.unwrap()].1;
+        self.try_at(pos.unwrap())
+    }
 
-        // get the offset into the file
-        let table = self.table.lock().unwrap();
-        let offset = table.rows[self.row];
+    fn try_at(&self, index :usize) -> Result<Value, TableError> {
+        self.with_ref(index, |v| v.into_value())
+    }
 
-        // parse the row
-        let mut reader = CsvCoreReader::new();
-        let mut output = [0u8; 1024*1024];
-        let mut ends = [0usize; 100];
+    fn with_ref<T, F: FnOnce(ValueRef<'_>) -> T>(&self, index :usize, f :F) -> Result<T, TableError> {
+        let (column, pos) = self.column_at(index)?;
+        let mut table = self.table.lock().unwrap();
 
-        let (res, read, written, num_ends) = reader.read_record(&table.mmap[offset..], &mut output, &mut ends);
+        table.wait_for_index();
 
-        if let ReadRecordResult::Record = res {
-            let (s, e) = if pos == 0 {
-                (0, ends[0])
-            } else {
-                (ends[pos-1], ends[pos])
-            };
+        // edits that didn't fit in the file take precedence
+        if let Some(value) = table.edits.get(&(self.row, pos)) {
+            return Ok(f(ValueRef::from(value)));
+        }
 
-            Ok(Value::new(String::from_utf8(output[s..e].to_vec()).unwrap().as_str()))
-        } else {
-            let err_str = format!("Could not parse column {}: {:?}", column, res);
-            Err(TableError::new(err_str.as_str()))
+        let (start, end) = table.field_span(self.row, pos, column)?;
+
+        match table.field_text(start, end) {
+            Ok(field) => ValueRef::with_type_or_infer(&field, table.types.get(pos).and_then(|t| t.as_ref()), &table.nulls).map(f),
+            Err(e) => {
+                let err_str = format!("Could not parse column {}: {}", column, e);
+                Err(TableError::new(err_str.as_str()))
+            }
         }
     }
 
+    fn with_text<T, F: FnOnce(Option<&str>) -> T>(&self, index :usize, f :F) -> Result<T, TableError> {
+        let (column, pos) = self.column_at(index)?;
+        let mut table = self.table.lock().unwrap();
+
+        table.wait_for_index();
+
+        // edits are values, so they're formatted; the fields in the file are passed as they are, without parsing them
+        match table.edits.get(&(self.row, pos)) {
+            Some(Value::Empty) => return Ok(f(None)),
+            Some(Value::String(s)) => return Ok(f(Some(s.as_str()))),
+            Some(value) => return Ok(f(Some(value.to_string().as_str()))),
+            None => ()
+        }
+
+        let (start, end) = table.field_span(self.row, pos, column)?;
+
+        match table.field_text(start, end) {
+            Ok(field) if field.is_empty() || table.nulls.iter().any(|n| *n == field) => Ok(f(None)),
+            Ok(field) => Ok(f(Some(&field))),
+            Err(e) => {
+                let err_str = format!("Could not parse column {}: {}", column, e);
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+
+    fn set(&mut self, column :&str, value :Value) -> Result<Value, TableError> {
+        let pos = self.column_map.iter().position(|(c, _)| c == column);
+
+        if pos.is_none() {
+            let err_str = format!("Could not find column in RowSlice: {}", column);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let pos = self.column_map[pos.unwrap()].1;
+        let old = self.try_get(column)?;
+
+        self.table.lock().unwrap().set_field(self.row, pos, value)?;
+
+        Ok(old)
+    }
+
     fn columns(&self) -> Vec<String> {
         self.column_map.iter().map(|(c,i)| c.clone()).collect()
     }
@@ -224,6 +886,40 @@ pub struct MMapTableSlice {
     table: Arc<Mutex<MMapTableInner>>       // reference to the underlying table
 }
 
+impl MMapTableSlice {
+    /// Returns a rayon parallel iterator over the rows of the slice.
+    ///
+    /// It runs in the current rayon thread pool; use it inside [`install`](fn.install.html) to run it in the pool set
+    /// with `set_parallelism`.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item=RowSlice<MMapTableInner>> {
+        let (column_map, table, rows) = (self.column_map.clone(), self.table.clone(), self.rows.clone());
+
+        (0..rows.len()).into_par_iter().map(move |i| RowSlice { column_map: column_map.clone(), table: table.clone(), row: rows[i] })
+    }
+
+    /// Calls `f` on the rows of the slice in parallel, like [`MMapTable::par_map`](struct.MMapTable.html#method.par_map),
+    /// with the values of each row in the order of the slice's `columns`.
+    pub fn par_map<R: Send, F: Fn(&[Value]) -> R + Sync + Send>(&self, f :F) -> Result<Vec<R>, TableError> {
+        let mut inner = self.table.lock().unwrap();
+
+        inner.wait_for_index();
+
+        par_map_rows(&inner, &self.rows, &self.column_map, f)
+    }
+}
+
+/// Calls `f` on the values of `columns` of each of `rows` in parallel, returning the results in the order of `rows`
+fn par_map_rows<R: Send, F: Fn(&[Value]) -> R + Sync + Send>(table :&MMapTableInner, rows :&[usize], columns :&[(String, usize)], f :F) -> Result<Vec<R>, TableError> {
+    parallel::map(rows, |&row| table.row_values(row, columns).map(|values| f(&values))).into_iter().collect()
+}
+
+impl Display for MMapTableSlice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        write!(f, "{}", self.preview(DISPLAY_ROWS, DISPLAY_WIDTH))
+    }
+}
+
 impl TableOperations for MMapTableSlice {
     type TableSliceType = MMapTableSlice;
     type RowType = RowSlice<MMapTableInner>;
@@ -234,10 +930,17 @@ impl TableOperations for MMapTableSlice {
             column_map: self.column_map.clone(),
             rows: self.rows.clone(),
             table: self.table.clone(),
-            cur_pos: 0
+            cur_pos: 0,
+            end: self.rows.len()
         }
     }
 
+    fn declared_type(&self, column :&str) -> Option<ValueType> {
+        let pos = self.column_map.iter().find(|(c, _)| c == column)?.1;
+
+        self.table.lock().unwrap().types.get(pos).cloned().flatten()
+    }
+
     fn get(&self, index: usize) -> Result<Self::RowType, TableError> {
         if index >= self.len() {
             let err_str = format!("Index {} is beyond table length {}", index, self.len());
@@ -255,10 +958,6 @@ impl TableOperations for MMapTableSlice {
         self.column_map.iter().map(|(c,i)| c.clone()).collect()
     }
 
-    fn group_by(&self, column: &str) -> Result<HashMap<Value, Self::TableSliceType, RandomState>, TableError> {
-        unimplemented!()
-    }
-
     fn filter_by<P: FnMut(&Self::RowType) -> bool>(&self, mut predicate: P) -> Result<Self::TableSliceType, TableError> {
         let mut slice_rows = Vec::new();
 
@@ -279,17 +978,62 @@ impl TableOperations for MMapTableSlice {
     }
 
     fn split_rows_at(&self, mid: usize) -> Result<(Self::TableSliceType, Self::TableSliceType), TableError> {
-        unimplemented!()
+        if mid >= self.rows.len() {
+            let err_str = format!("Midpoint too large: {} >= {}", mid, self.rows.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok( (
+            MMapTableSlice { column_map: self.column_map.clone(), rows: Arc::new(self.rows[..mid].to_vec()), table: self.table.clone() },
+            MMapTableSlice { column_map: self.column_map.clone(), rows: Arc::new(self.rows[mid..].to_vec()), table: self.table.clone() }
+            )
+        )
+    }
+
+    fn take_rows(&self, indices :&[usize]) -> Result<Self::TableSliceType, TableError> {
+        if let Some(index) = indices.iter().find(|&&i| i >= self.rows.len()) {
+            let err_str = format!("Index {} is beyond table length {}", index, self.rows.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok(MMapTableSlice {
+            column_map: self.column_map.clone(),
+            rows: Arc::new(indices.iter().map(|&i| self.rows[i]).collect()),
+            table: self.table.clone()
+        })
     }
 }
 
 impl TableSlice for MMapTableSlice {
-    fn sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, compare: F) -> Result<Self::TableSliceType, TableError> {
-        unimplemented!()
+    fn sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, mut compare: F) -> Result<Self::TableSliceType, TableError> {
+        let mut rows = self.rows.iter().cloned().collect::<Vec<_>>();
+
+        rows.sort_unstable_by(|&a, &b| {
+            let a_row = RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: a };
+            let b_row = RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: b };
+
+            compare(a_row, b_row)
+        });
+
+        Ok(MMapTableSlice {
+            column_map: self.column_map.clone(),
+            rows: Arc::new(rows),
+            table: self.table.clone()
+        })
     }
 
     fn rename_column(&self, old_col :&str, new_col :&str) -> Result<Self::TableSliceType, TableError> {
-        unimplemented!()
+        let pos = TableSlice::column_position(self, old_col)?;
+
+        let mut column_map = self.column_map.as_ref().clone();
+
+        column_map[pos].0 = new_col.to_string();
+
+        Ok( MMapTableSlice {
+            column_map: Arc::new(column_map),
+            rows: self.rows.clone(),
+            table: self.table.clone()
+        })
     }
 }
 
@@ -297,14 +1041,15 @@ pub struct MMapTableSliceIter {
     column_map: Arc<Vec<(String, usize)>>,
     rows: Arc<Vec<usize>>,
     table: Arc<Mutex<MMapTableInner>>,
-    cur_pos: usize
+    cur_pos: usize,
+    end: usize
 }
 
 impl Iterator for MMapTableSliceIter {
     type Item=RowSlice<MMapTableInner>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.cur_pos >= self.rows.len() {
+        if self.cur_pos >= self.end {
             None
         } else {
             self.cur_pos += 1;
@@ -313,30 +1058,546 @@ impl Iterator for MMapTableSliceIter {
             Some(RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: row_index})
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.end - self.cur_pos, Some(self.end - self.cur_pos))
+    }
 }
 
+impl DoubleEndedIterator for MMapTableSliceIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.cur_pos >= self.end {
+            None
+        } else {
+            self.end -= 1;
+            let row_index = self.rows[self.end];
+
+            Some(RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: row_index})
+        }
+    }
+}
+
+impl ExactSizeIterator for MMapTableSliceIter {}
+
 #[cfg(test)]
 mod tests {
     use log::Level;
-    use chrono::Duration;
 
     use std::time::Instant;
 
     use crate::LOGGER_INIT;
 
-    use crate::TableOperations;
-    use crate::mmap_table::MMapTable;
+    use crate::{fixtures, Table, TableOperations, Row, Value, ValueType, ReadOptions, WriteOptions, TableError, CancelToken};
+    use crate::mmap_table::{MMapTable, estimate_rows};
 
     #[test]
     fn new() {
         LOGGER_INIT.call_once(|| simple_logger::init_with_level(Level::Debug).unwrap()); // this will panic on error
 
         let start = Instant::now();
-        let table = MMapTable::new("/export/stock_stuff/199x_100_sample.csv").unwrap();
+        let table = MMapTable::new(fixtures::copy("stocks.csv")).unwrap();
         let end = Instant::now();
 
         println!("COLS: {:?}", table.columns());
 
         println!("TIME: {}ms", (end-start).as_millis());
+
+        fixtures::check_load(&table);
+    }
+
+    #[test]
+    fn astype() {
+        let path = fixtures::temp_dir().join("mmap_astype.csv");
+
+        std::fs::write(&path, "code,price,flag\n007,1.5,yes\n012,x,no\n,2,yes\n").unwrap();
+
+        let mut table = MMapTable::new(&path).unwrap();
+
+        // the text in the file is parsed with the type, so the leading zeros are kept
+        table.astype("code", ValueType::String).unwrap();
+        table.astype("flag", ValueType::Boolean).unwrap();
+
+        assert_eq!(Value::String("007".to_string()), table.get(0).unwrap().get("code"));
+        assert_eq!(Value::Empty, table.get(2).unwrap().get("code"));
+        assert_eq!(Value::Boolean(true), table.get(0).unwrap().get("flag"));
+        assert_eq!(Some(ValueType::String), table.declared_type("code"));
+
+        // nothing changes when a value can't be converted
+        assert!(table.astype("price", ValueType::Float).is_err());
+        assert_eq!(None, table.declared_type("price"));
+        assert_eq!(Value::String("x".to_string()), table.get(1).unwrap().get("price"));
+
+        assert_eq!(vec![1], table.try_astype("price", ValueType::Float).unwrap());
+        assert_eq!(vec![Value::Float(1.5.into()), Value::Empty, Value::Float(2.0.into())], table.iter().map(|r| r.get("price")).collect::<Vec<_>>());
+
+        // edits that don't fit in the file are converted too
+        table.get(0).unwrap().set("code", Value::String("12345".to_string())).unwrap();
+        table.astype("code", ValueType::Integer).unwrap();
+
+        assert_eq!(vec![Value::Integer(12345), Value::Integer(12), Value::Empty], table.iter().map(|r| r.get("code")).collect::<Vec<_>>());
+        assert_eq!(vec![ValueType::Integer, ValueType::Float, ValueType::Boolean], table.dtypes().into_iter().map(|(_, t)| t).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn headerless() {
+        let path = fixtures::temp_dir().join("mmap_headerless.csv");
+
+        std::fs::write(&path, "1,abc\r\n2,def\r\n").unwrap();
+
+        let table = MMapTable::with_options(&path, &ReadOptions::new().has_headers(false)).unwrap();
+
+        assert_eq!(vec!["column_0", "column_1"], table.columns());
+        assert_eq!(2, table.len());
+        assert_eq!(Value::Integer(1), table.get(0).unwrap().get("column_0"));
+        assert_eq!(Value::String("def".to_string()), table.get(1).unwrap().get("column_1"));
+
+        let named = MMapTable::with_options(&path, &ReadOptions::new().has_headers(false).column_names(&["id", "name"])).unwrap();
+
+        assert_eq!(Value::String("abc".to_string()), named.get(0).unwrap().get("name"));
+    }
+
+    #[test]
+    fn skip_lines() {
+        let path = fixtures::temp_dir().join("mmap_skip_lines.csv");
+
+        std::fs::write(&path, "Daily report\r\n\r\n# columns\r\nid,name\r\n1,abc\r\n# 2,ghi\r\n3,def\r\n#\r\nTotal 2\r\n").unwrap();
+
+        let table = MMapTable::with_options(&path, &ReadOptions::new().skip_rows(1).comment(b'#').skip_footer(1)).unwrap();
+
+        assert_eq!(vec!["id", "name"], table.columns());
+        assert_eq!(2, table.len());
+        assert_eq!(Value::Integer(1), table.get(0).unwrap().get("id"));
+        assert_eq!(Value::String("def".to_string()), table.get(1).unwrap().get("name"));
+
+        let headerless = MMapTable::with_options(&path, &ReadOptions::new().skip_rows(3).has_headers(false).comment(b'#')).unwrap();
+
+        assert_eq!(4, headerless.len());
+        assert_eq!(Value::String("id".to_string()), headerless.get(0).unwrap().get("column_0"));
+    }
+
+    #[test]
+    fn null_values() {
+        let path = fixtures::temp_dir().join("mmap_null_values.csv");
+
+        std::fs::write(&path, "symbol,close\nAAPL,75.09\nNULL,74.36\nN/A,-\n").unwrap();
+
+        let table = MMapTable::with_options(&path, &ReadOptions::new().null_values(&["NULL", "N/A", "-"])).unwrap();
+
+        assert_eq!(Value::Empty, table.get(1).unwrap().get("symbol"));
+        assert_eq!(Value::Empty, table.get(2).unwrap().get("close"));
+        assert_eq!(2, table.group_by("symbol").unwrap().len());
+        assert_eq!(Value::String("NULL".to_string()), MMapTable::new(&path).unwrap().get(1).unwrap().get("symbol"));
+
+        // the tokens are also read as empty values when the rows are indexed in the background, or from a saved index
+        let options = ReadOptions::new().null_values(&["NULL", "N/A", "-"]).skip_footer(1);
+        let header = MMapTable::open_header_with_options(&path, &options).unwrap();
+        let index_path = fixtures::temp_dir().join("mmap_null_values.idx");
+
+        assert_eq!(2, header.len());
+        assert_eq!(Value::Empty, header.get(1).unwrap().get("symbol"));
+
+        header.save_index(&index_path).unwrap();
+        drop(header);
+
+        let indexed = MMapTable::open_with_index_and_options(&path, &index_path, &options).unwrap();
+
+        assert_eq!(Value::Empty, indexed.get(1).unwrap().get("symbol"));
+        assert_eq!(Value::String("NULL".to_string()), MMapTable::open_with_index(&path, &index_path).unwrap().get(1).unwrap().get("symbol"));
+    }
+
+    #[test]
+    fn quoted_fields() {
+        let path = fixtures::temp_dir().join("mmap_quoted.csv");
+
+        std::fs::write(&path, "id,name,note\r\n1,\"Smith, John\",\"said \"\"hi\"\"\"\r\n2,\"two\r\nlines\",plain\r\n3,abc,\"x,y\"\r\n").unwrap();
+
+        let table = MMapTable::new(&path).unwrap();
+
+        assert_eq!(vec!["id", "name", "note"], table.columns());
+        assert_eq!(3, table.len());
+        assert_eq!(Value::String("Smith, John".to_string()), table.get(0).unwrap().get("name"));
+        assert_eq!(Value::String("said \"hi\"".to_string()), table.get(0).unwrap().get("note"));
+        assert_eq!(Value::String("two\r\nlines".to_string()), table.get(1).unwrap().get("name"));
+        assert_eq!(Value::String("plain".to_string()), table.get(1).unwrap().get("note"));
+        assert_eq!(Value::Integer(3), table.get(2).unwrap().get("id"));
+        assert_eq!(Value::String("x,y".to_string()), table.get(2).unwrap().get("note"));
+
+        // same length edits go in the file, at the right place after quoted fields and line breaks
+        table.get(1).unwrap().set("id", Value::Integer(7)).unwrap();
+        table.get(1).unwrap().set("note", Value::String("PLAIN".to_string())).unwrap();
+        table.get(2).unwrap().set("id", Value::Integer(8)).unwrap();
+        table.get(2).unwrap().set("name", Value::String("xyz".to_string())).unwrap();
+        table.get(0).unwrap().set("name", Value::String("Smith, Jane".to_string())).unwrap();
+
+        assert_eq!(0, table.pending_edits());
+
+        // a quoted value of a different length is kept as an edit
+        table.get(0).unwrap().set("note", Value::String("said \"bye\"".to_string())).unwrap();
+
+        assert_eq!(1, table.pending_edits());
+
+        table.flush().unwrap();
+
+        let reopened = MMapTable::new(&path).unwrap();
+
+        assert_eq!(Value::Integer(7), reopened.get(1).unwrap().get("id"));
+        assert_eq!(Value::String("PLAIN".to_string()), reopened.get(1).unwrap().get("note"));
+        assert_eq!(Value::String("two\r\nlines".to_string()), reopened.get(1).unwrap().get("name"));
+        assert_eq!(Value::Integer(8), reopened.get(2).unwrap().get("id"));
+        assert_eq!(Value::String("xyz".to_string()), reopened.get(2).unwrap().get("name"));
+        assert_eq!(Value::String("x,y".to_string()), reopened.get(2).unwrap().get("note"));
+        assert_eq!(Value::String("Smith, Jane".to_string()), reopened.get(0).unwrap().get("name"));
+        assert_eq!(Value::String("said \"hi\"".to_string()), reopened.get(0).unwrap().get("note"));
+        assert_eq!(Value::String("said \"bye\"".to_string()), table.get(0).unwrap().get("note"));
+    }
+
+    #[test]
+    fn field_offsets() {
+        let path = fixtures::temp_dir().join("mmap_field_offsets.csv");
+        let long = "x".repeat(10_000);
+
+        std::fs::write(&path, format!("a,b,c\n{},\"{}\"\"\",1\n2,,\n3,4", long, long)).unwrap();
+
+        let table = MMapTable::new(&path).unwrap();
+        let row = table.get(0).unwrap();
+
+        // fields longer than the parse buffer, read again from the cached offsets
+        for _ in 0..2 {
+            assert_eq!(Value::String(long.clone()), row.get("a"));
+            assert_eq!(Value::String(format!("{}\"", long)), row.get("b"));
+            assert_eq!(Value::Integer(1), row.get("c"));
+        }
+
+        assert!(row.with_ref(0, |v| v.as_str() == Some(long.as_str())).unwrap());
+        assert_eq!(vec![Value::Integer(2), Value::Empty, Value::Empty], (0..3).map(|p| table.get(1).unwrap().at(p)).collect::<Vec<_>>());
+        assert_eq!(vec![Value::Integer(3), Value::Integer(4)], (0..2).map(|p| table.get(2).unwrap().at(p)).collect::<Vec<_>>());
+        assert!(table.get(2).unwrap().try_get("c").is_err());
+    }
+
+    #[test]
+    fn with_progress() {
+        let mut calls = Vec::new();
+        let table = MMapTable::with_progress(fixtures::generated("mmap_progress.csv", 70_000), |p| calls.push(p)).unwrap();
+
+        assert_eq!(vec![65_536, 70_000], calls.iter().map(|p| p.rows).collect::<Vec<_>>());
+        assert!(calls[0].bytes < calls[1].bytes);
+        assert_eq!(calls[1].total_bytes, calls[1].bytes);
+        assert_eq!(70_000, table.len());
+
+        let token = CancelToken::new();
+        let err = MMapTable::with_progress_and_cancel(fixtures::temp_dir().join("mmap_progress.csv"), &token, |_| token.cancel()).err().unwrap();
+
+        assert_eq!(std::io::ErrorKind::Interrupted, err.kind());
+    }
+
+    #[test]
+    fn cancel() {
+        let path = fixtures::copy("stocks.csv");
+        let token = CancelToken::new();
+
+        fixtures::check_cancel(&MMapTable::with_cancel(&path, &token).unwrap());
+
+        token.cancel();
+
+        let err = MMapTable::with_cancel(&path, &token).err().unwrap();
+
+        assert_eq!(std::io::ErrorKind::Interrupted, err.kind());
+        assert!(err.get_ref().unwrap().downcast_ref::<TableError>().unwrap().is_cancelled());
+    }
+
+    #[test]
+    fn wide() {
+        let path = fixtures::wide("mmap_wide.csv", 500, 20);
+
+        fixtures::check_wide(&MMapTable::new(&path).unwrap(), 500, 20);
+
+        let table = MMapTable::open_header(&path).unwrap();
+
+        assert_eq!(20, table.estimate_len());
+        fixtures::check_wide(&table, 500, 20);
+    }
+
+    #[test]
+    fn open_header() {
+        let table = MMapTable::open_header(fixtures::generated("mmap_open_header.csv", 1_000)).unwrap();
+
+        assert!(!table.columns().is_empty());
+
+        // reading rows blocks until they're indexed
+        fixtures::check_generated(&table, 1_000);
+
+        assert!(table.is_indexed());
+        assert_eq!(1_000, table.estimate_len());
+
+        let usage = table.memory_usage();
+
+        // the offsets of the rows, and of the 4 fields of the last row read
+        assert_eq!(1_000 * std::mem::size_of::<usize>() + 4 * std::mem::size_of::<(usize, usize)>(), usage.index);
+        assert_eq!(std::fs::metadata(fixtures::temp_dir().join("mmap_open_header.csv")).unwrap().len() as usize, usage.mapped);
+        assert_eq!(4, usage.columns.len());
+        assert!(usage.total() > usage.index);
+        assert!(MMapTable::open_header(fixtures::temp_dir().join("mmap_header_empty.csv")).is_err());
+    }
+
+    #[test]
+    fn open_with_index() {
+        let path = fixtures::generated("mmap_index.csv", 1_000);
+        let index_path = fixtures::temp_dir().join("mmap_index.idx");
+
+        MMapTable::open_header(&path).unwrap().save_index(&index_path).unwrap();
+
+        let table = MMapTable::open_with_index(&path, &index_path).unwrap();
+
+        assert!(table.is_indexed());
+        fixtures::check_generated(&table, 1_000);
+
+        // an index of another file, or of the same file after it changed, isn't used
+        let other = fixtures::generated("mmap_index_other.csv", 10);
+
+        assert_eq!(std::io::ErrorKind::InvalidData, MMapTable::open_with_index(&other, &index_path).err().unwrap().kind());
+
+        drop(table);
+
+        // an edit in the middle that keeps the length of the file
+        let data = std::fs::read(&path).unwrap();
+        let mut edited = data.clone();
+        let middle = edited.len() / 2;
+
+        edited[middle] = if edited[middle] == b'0' { b'1' } else { b'0' };
+        std::fs::write(&path, &edited).unwrap();
+
+        assert!(MMapTable::open_with_index(&path, &index_path).is_err());
+
+        std::fs::write(&path, [data, b"1,A,1.0,2020-01-01\n".to_vec()].concat()).unwrap();
+
+        assert!(MMapTable::open_with_index(&path, &index_path).is_err());
+        assert!(MMapTable::open_with_index(&path, &path).is_err());
+    }
+
+    #[test]
+    fn estimate_len() {
+        let data = std::fs::read(fixtures::generated("mmap_estimate.csv", 1_000)).unwrap();
+
+        assert_eq!(1_000, estimate_rows(&data, data.len()));
+
+        // the generated rows are close to the same length
+        let estimate = estimate_rows(&data, data.len() / 10);
+
+        assert!(estimate > 900 && estimate < 1_100, "{}", estimate);
+        assert_eq!(0, estimate_rows(b"A,B\n", 100));
+        assert_eq!(0, estimate_rows(b"", 100));
+    }
+
+    #[test]
+    fn filter() {
+        fixtures::check_filter(&MMapTable::new(fixtures::copy("stocks.csv")).unwrap());
+    }
+
+    #[cfg(feature = "sampling")]
+    #[test]
+    fn sample() {
+        fixtures::check_sample(&MMapTable::new(fixtures::copy("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn stats() {
+        let mut table = MMapTable::new(fixtures::copy("stocks.csv")).unwrap();
+
+        table.compute_stats();
+        fixtures::check_stats(&mut table);
+    }
+
+    #[test]
+    fn sort() {
+        fixtures::check_sort(&MMapTable::new(fixtures::copy("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn group() {
+        fixtures::check_group(&MMapTable::new(fixtures::copy("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn strings() {
+        let table = MMapTable::new(fixtures::copy("stocks.csv")).unwrap();
+
+        fixtures::check_strings(&table);
+        fixtures::check_strings(&table.filter_by(|_| true).unwrap());
+
+        // quoted fields, edits, and null values
+        let path = fixtures::temp_dir().join("mmap_strings.csv");
+
+        std::fs::write(&path, "id,name\n1,\"Smith, John\"\n2,NULL\n3,Jones\n").unwrap();
+
+        let mut table = MMapTable::with_options(&path, &ReadOptions::new().null_values(&["NULL"])).unwrap();
+
+        table.update_by(|row| if row.get("id") == Value::Integer(3) { row.set("name", Value::new("Jones-Smith")).unwrap(); });
+
+        assert_eq!(vec![1, 3], table.str_contains("name", "Smith").unwrap().iter().map(|r| r.get("id").as_integer()).collect::<Vec<_>>());
+        assert_eq!(vec![Value::new("John"), Value::Empty, Value::Empty], table.str_split_extract("name", " ", 1).unwrap());
+        assert_eq!(vec![Value::new("smith, john"), Value::Empty, Value::new("jones-smith")], table.str_lower("name").unwrap());
+        assert_eq!(vec![Value::Integer(11), Value::Empty, Value::Integer(11)], table.str_len("name").unwrap());
+
+        // the fields are used as they are in the file, not as the values they're parsed to
+        let path = fixtures::temp_dir().join("mmap_strings_raw.csv");
+
+        std::fs::write(&path, "code,price\n007,1.50\n42,2.05\n").unwrap();
+
+        let table = MMapTable::new(&path).unwrap();
+
+        assert_eq!(Value::Integer(7), table.get(0).unwrap().get("code"));
+        assert_eq!(vec![Value::Integer(3), Value::Integer(2)], table.str_len("code").unwrap());
+        assert_eq!(1, table.str_startswith("code", "0").unwrap().len());
+        assert_eq!(Value::String("50".to_string()), table.str_split_extract("price", ".", 1).unwrap()[0]);
+    }
+
+    #[test]
+    fn index() {
+        fixtures::check_index(&MMapTable::new(fixtures::copy("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn column_handle() {
+        fixtures::check_column_handle(&MMapTable::new(fixtures::copy("stocks.csv")).unwrap());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn par_iter() {
+        use rayon::prelude::*;
+
+        let table = MMapTable::new(fixtures::copy("stocks.csv")).unwrap();
+
+        let volume :i64 = table.par_iter().map(|row| row.get("volume").as_integer()).sum();
+
+        assert_eq!(table.iter().map(|row| row.get("volume").as_integer()).sum::<i64>(), volume);
+
+        let slice = table.filter("symbol", &Value::String("IBM".to_string())).unwrap();
+        let dates = slice.par_iter().map(|row| row.get("date")).collect::<Vec<_>>();
+
+        assert_eq!(slice.iter().map(|row| row.get("date")).collect::<Vec<_>>(), dates);
+    }
+
+    #[test]
+    fn par_map() {
+        let table = MMapTable::new(fixtures::copy("stocks.csv")).unwrap();
+        let volume = table.column_position("volume").unwrap();
+
+        assert_eq!(table.iter().map(|row| row.get("volume").as_integer()).sum::<i64>(), table.par_map(|row| row[volume].as_integer()).unwrap().into_iter().sum::<i64>());
+
+        // edits that didn't fit in the file are read too
+        table.get(2).unwrap().set("symbol", Value::String("a much longer symbol".to_string())).unwrap();
+
+        let slice = table.filter_by(|row| row.get("volume").as_integer() > 0).unwrap();
+        let symbol = slice.column_position("symbol").unwrap();
+
+        assert_eq!(slice.iter().map(|row| row.get("symbol")).collect::<Vec<_>>(), slice.par_map(|row| row[symbol].clone()).unwrap());
+        assert_eq!(Value::String("a much longer symbol".to_string()), table.par_map(|row| row[symbol].clone()).unwrap()[2]);
+    }
+
+    #[test]
+    fn split_rename() {
+        let mut table = MMapTable::new(fixtures::copy("stocks.csv")).unwrap();
+
+        fixtures::check_split(&table);
+        fixtures::check_rename(&mut table);
+
+        assert!(table.append_row(table.get(0).unwrap()).is_err());
+        assert!(table.add_column("extra", &Value::Empty).is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        let files :&[&[u8]] = &[
+            b"A,B\n",
+            b"A,B",
+            b"A,B\n1,2\n3\n",
+            b"A,B\n1,\xff\xfe\n",
+            b"A,B\n1,\"2\n",
+            b"\n\n\n",
+            b"A\n1,2,3,4\n\"",
+        ];
+
+        for (i, contents) in files.iter().enumerate() {
+            let path = fixtures::temp_dir().join(format!("mmap_malformed_{}.csv", i));
+
+            std::fs::write(&path, contents).unwrap();
+
+            // errors are fine, panics are not
+            if let Ok(table) = MMapTable::new(&path) {
+                for row in table.iter() {
+                    for column in row.columns() {
+                        let _ = row.try_get(&column);
+                    }
+                }
+            }
+        }
+
+        let path = fixtures::temp_dir().join("mmap_no_newline.csv");
+
+        std::fs::write(&path, b"A,B\n1,2\n3,4").unwrap();
+
+        let table = MMapTable::new(&path).unwrap();
+
+        assert_eq!(2, table.len());
+        assert_eq!(Value::Integer(4), table.get(1).unwrap().get("B"));
+        assert!(MMapTable::new(fixtures::temp_dir().join("mmap_empty.csv")).is_err());
+    }
+
+    #[test]
+    fn generated() {
+        fixtures::check_generated(&MMapTable::new(fixtures::generated("mmap_generated.csv", 1_000)).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn update() {
+        let path = fixtures::copy("stocks.csv");
+        let mut table = MMapTable::new(&path).unwrap();
+
+        // same length, so it's written to the file; then longer, and quoted
+        table.update_by(|row| {
+            if row.get("symbol") == Value::String("AAPL".to_string()) {
+                row.set("symbol", Value::String("AAPX".to_string())).unwrap();
+                row.set("volume", Value::String("a, b".to_string())).unwrap();
+            }
+        });
+
+        assert_eq!(Value::String("AAPX".to_string()), table.get(0).unwrap().get("symbol"));
+        assert_eq!(Value::String("a, b".to_string()), table.get(0).unwrap().get("volume"));
+        assert_eq!(Value::Float(74.06.into()), table.get(0).unwrap().get("open"));
+        assert_eq!(4, table.pending_edits());
+
+        // setting a pending edit back to the original length writes it to the file
+        table.get(0).unwrap().set("volume", Value::Integer(999999999)).unwrap();
+
+        assert_eq!(3, table.pending_edits());
+
+        table.flush().unwrap();
+
+        let reopened = MMapTable::new(&path).unwrap();
+
+        assert_eq!(Value::String("AAPX".to_string()), reopened.get(0).unwrap().get("symbol"));
+        assert_eq!(Value::Integer(999999999), reopened.get(0).unwrap().get("volume"));
+        assert_eq!(Value::Integer(22622100), reopened.get(1).unwrap().get("volume"));
+
+        let rewritten = fixtures::temp_dir().join("mmap_rewrite.csv");
+
+        table.rewrite(&rewritten).unwrap();
+
+        let rewritten = MMapTable::new(&rewritten).unwrap();
+
+        assert_eq!(table.len(), rewritten.len());
+        assert_eq!(Value::String("a, b".to_string()), rewritten.get(3).unwrap().get("volume"));
+        assert_eq!(Value::Integer(3148600), rewritten.get(2).unwrap().get("volume"));
+
+        let path = fixtures::temp_dir().join("mmap_rewrite_options.csv");
+
+        table.rewrite_with_options(&path, &WriteOptions::new().columns(&["volume"]).header(false)).unwrap();
+
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with("999999999\n"));
+
+        table.map_column("symbol", |v| Value::String(v.as_string().to_lowercase())).unwrap();
+
+        assert_eq!(Value::String("msft".to_string()), table.get(1).unwrap().get("symbol"));
     }
 }
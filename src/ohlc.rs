@@ -0,0 +1,149 @@
+//! Open, high, low, and close prices of each bucket of time, the candlesticks of a chart.
+use std::collections::BTreeMap;
+
+use crate::{TableOperations, TableError, Row, Value, Frequency};
+#[cfg(feature = "rowtable")]
+use crate::RowTable;
+
+/// The prices of one bucket of time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar {
+    /// The start of the bucket
+    pub time: Value,
+    /// The price at the earliest time in the bucket
+    pub open: Value,
+    pub high: Value,
+    pub low: Value,
+    /// The price at the latest time in the bucket
+    pub close: Value,
+    /// The total volume, or `Value::Empty` without a volume column
+    pub volume: Value
+}
+
+/// The open, high, low, and close prices of each bucket of time with a price, created with
+/// [`TableOperations::ohlc`](trait.TableOperations.html#method.ohlc); the bars are sorted by time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ohlc {
+    volume: bool,
+    bars: Vec<Bar>
+}
+
+impl Ohlc {
+    pub(crate) fn new<T: TableOperations + ?Sized>(table :&T, time_column :&str, price_column :&str, frequency :Frequency, volume_column :Option<&str>) -> Result<Self, TableError> {
+        let time_pos = table.column_position(time_column)?;
+        let price_pos = table.column_position(price_column)?;
+        let volume_pos = volume_column.map(|c| table.column_position(c)).transpose()?;
+        let mut bars :BTreeMap<Value, (Value, Value, Bar)> = BTreeMap::new();  // bucket -> (open time, close time, bar)
+
+        for row in table.iter() {
+            let (time, price) = (row.try_at(time_pos)?, row.try_at(price_pos)?);
+
+            if time == Value::Empty || price == Value::Empty {
+                continue;
+            }
+
+            if price.try_as_float().is_none() {
+                let err_str = format!("Not a price: {}", price);
+                return Err(TableError::new(err_str.as_str()));
+            }
+
+            let volume = match volume_pos {
+                Some(pos) => row.try_at(pos)?,
+                None => Value::Empty
+            };
+
+            let bucket = frequency.bucket(&time)?;
+
+            match bars.get_mut(&bucket) {
+                Some((open_time, close_time, bar)) => {
+                    // the earliest price opens, and the latest closes, keeping the order of the table for equal times
+                    if time < *open_time {
+                        *open_time = time.clone();
+                        bar.open = price.clone();
+                    }
+
+                    if time >= *close_time {
+                        *close_time = time;
+                        bar.close = price.clone();
+                    }
+
+                    bar.high = bar.high.clone().max(price.clone());
+                    bar.low = bar.low.clone().min(price);
+                    bar.volume = add_volume(&bar.volume, &volume)?;
+                },
+                None => {
+                    let bar = Bar {
+                        time: bucket.clone(),
+                        open: price.clone(),
+                        high: price.clone(),
+                        low: price.clone(),
+                        close: price,
+                        volume: add_volume(&Value::Integer(0), &volume)?
+                    };
+
+                    bars.insert(bucket, (time.clone(), time, bar));
+                }
+            }
+        }
+
+        let bars = bars.into_values().map(|(_, _, mut bar)| {
+            if volume_pos.is_none() {
+                bar.volume = Value::Empty;
+            }
+
+            bar
+        }).collect();
+
+        Ok(Ohlc { volume: volume_pos.is_some(), bars })
+    }
+
+    pub fn bars(&self) -> &[Bar] {
+        &self.bars
+    }
+
+    pub fn len(&self) -> usize {
+        self.bars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bars.is_empty()
+    }
+
+    /// Creates a `RowTable` of the bars, with the columns `time`, `open`, `high`, `low`, and `close`, then `volume`
+    /// when there's a volume column.
+    #[cfg(feature = "rowtable")]
+    pub fn to_table(&self) -> RowTable {
+        let mut columns = ["time", "open", "high", "low", "close"].iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        if self.volume {
+            columns.push(String::from("volume"));
+        }
+
+        let rows = self.bars.iter().map(|b| {
+            let mut row = vec![b.time.clone(), b.open.clone(), b.high.clone(), b.low.clone(), b.close.clone()];
+
+            if self.volume {
+                row.push(b.volume.clone());
+            }
+
+            row
+        }).collect();
+
+        RowTable::from_rows(columns, rows)
+    }
+}
+
+/// Adds a volume to a total, keeping it an integer while the volumes are, and skipping empty volumes
+fn add_volume(total :&Value, volume :&Value) -> Result<Value, TableError> {
+    match (total, volume) {
+        (_, Value::Empty) => Ok(total.clone()),
+        (Value::Integer(a), Value::Integer(b)) if a.checked_add(*b).is_some() => Ok(Value::Integer(a + b)),
+        (_, v) => match v.try_as_float() {
+            Some(f) => Ok(Value::Float((total.as_float() + f).into())),
+            None => {
+                let err_str = format!("Not a volume: {}", v);
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+}
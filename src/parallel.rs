@@ -0,0 +1,205 @@
+//! Controls the threads used by parallel table operations.
+//!
+//! With the `parallel` feature operations run in a rayon thread pool; without it, or after `set_parallelism(1)`,
+//! they run sequentially on the calling thread.
+//!
+//! Parallel operations collect their results by row index, and merge the groups found by each thread in row order,
+//! so they produce the same rows, in the same order, as the sequential implementation.
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "parallel")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+static PARALLELISM: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of rows each task groups before the groups are merged
+#[cfg(feature = "parallel")]
+const GROUP_CHUNK_SIZE :usize = 16 * 1024;
+
+#[cfg(feature = "parallel")]
+static POOL: Mutex<Option<Arc<ThreadPool>>> = Mutex::new(None);
+
+/// Sets the number of threads used by parallel table operations.
+///
+/// `1` runs every operation sequentially on the calling thread without spawning any threads, which makes debugging
+/// deterministic. `0`, the default, uses rayon's global thread pool. Any other value uses a pool of that many threads.
+pub fn set_parallelism(threads :usize) {
+    #[cfg(feature = "parallel")]
+    {
+        let mut pool = POOL.lock().unwrap();
+
+        *pool = if threads > 1 {
+            Some(Arc::new(ThreadPoolBuilder::new().num_threads(threads).build().expect("Error building thread pool")))
+        } else {
+            None
+        };
+    }
+
+    PARALLELISM.store(threads, Ordering::SeqCst);
+}
+
+/// The number of threads set with `set_parallelism`; always `1` without the `parallel` feature.
+pub fn parallelism() -> usize {
+    if cfg!(feature = "parallel") {
+        PARALLELISM.load(Ordering::SeqCst)
+    } else {
+        1
+    }
+}
+
+/// Runs `op` in the thread pool set with `set_parallelism`, so the rayon iterators it uses, such as
+/// [`RowTable::par_iter`](struct.RowTable.html#method.par_iter), run on that pool's threads; without a pool of its own,
+/// after `set_parallelism(0)` or `1`, it runs `op` on the calling thread, where rayon uses its global pool.
+/// ```
+/// use large_table::{install, set_parallelism};
+///
+/// set_parallelism(2);
+///
+/// assert_eq!(10, install(|| (1..=4).sum::<i32>()));
+/// ```
+#[cfg(feature = "parallel")]
+pub fn install<R: Send, OP: FnOnce() -> R + Send>(op :OP) -> R {
+    let pool = POOL.lock().unwrap().clone();
+
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op()
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn install<R: Send, OP: FnOnce() -> R + Send>(op :OP) -> R {
+    op()
+}
+
+/// Calls `f` on every item, in parallel when allowed.
+#[cfg(feature = "parallel")]
+pub(crate) fn for_each_mut<T: Send, F: Fn(&mut T) + Sync + Send>(items :&mut [T], f :F) {
+    if parallelism() == 1 {
+        return items.iter_mut().for_each(f);
+    }
+
+    install(|| items.par_iter_mut().for_each(f))
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn for_each_mut<T: Send, F: Fn(&mut T) + Sync + Send>(items :&mut [T], f :F) {
+    items.iter_mut().for_each(f)
+}
+
+/// Calls `f` on every item, in parallel when allowed, returning the results in the order of `items`.
+#[cfg(feature = "parallel")]
+pub(crate) fn map<T: Sync, R: Send, F: Fn(&T) -> R + Sync + Send>(items :&[T], f :F) -> Vec<R> {
+    if parallelism() == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    install(|| items.par_iter().map(f).collect())
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map<T: Sync, R: Send, F: Fn(&T) -> R + Sync + Send>(items :&[T], f :F) -> Vec<R> {
+    items.iter().map(f).collect()
+}
+
+/// Returns the positions of the items matching `predicate`, in ascending order.
+#[cfg(feature = "parallel")]
+pub(crate) fn filter_indices<T: Sync, F: Fn(&T) -> bool + Sync + Send>(items :&[T], predicate :F) -> Vec<usize> {
+    if parallelism() == 1 {
+        return items.iter().enumerate().filter(|(_, item)| predicate(item)).map(|(i, _)| i).collect();
+    }
+
+    install(|| items.par_iter().enumerate().filter(|(_, item)| predicate(item)).map(|(i, _)| i).collect())
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn filter_indices<T: Sync, F: Fn(&T) -> bool + Sync + Send>(items :&[T], predicate :F) -> Vec<usize> {
+    items.iter().enumerate().filter(|(_, item)| predicate(item)).map(|(i, _)| i).collect()
+}
+
+/// Groups the positions of the items by `key`.
+///
+/// The positions in each group are in ascending order.
+#[cfg(feature = "parallel")]
+pub(crate) fn group_indices<T: Sync, K: Hash + Eq + Send, F: Fn(&T) -> K + Sync + Send>(items :&[T], key :F) -> HashMap<K, Vec<usize>> {
+    if parallelism() == 1 {
+        return group_chunk(items, 0, key);
+    }
+
+    // rayon reduces neighbouring chunks, in parallel, keeping their order, so each group's positions stay ascending
+    install(|| {
+        items.par_chunks(GROUP_CHUNK_SIZE)
+            .enumerate()
+            .map(|(c, chunk)| group_chunk(chunk, c * GROUP_CHUNK_SIZE, &key))
+            .reduce(HashMap::new, merge_groups)
+    })
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn group_indices<T: Sync, K: Hash + Eq + Send, F: Fn(&T) -> K + Sync + Send>(items :&[T], key :F) -> HashMap<K, Vec<usize>> {
+    group_chunk(items, 0, key)
+}
+
+/// Groups the positions of the items in `chunk`, which starts at position `offset`
+fn group_chunk<T, K: Hash + Eq, F: Fn(&T) -> K>(chunk :&[T], offset :usize, key :F) -> HashMap<K, Vec<usize>> {
+    let mut groups :HashMap<K, Vec<usize>> = HashMap::new();
+
+    for (i, item) in chunk.iter().enumerate() {
+        groups.entry(key(item)).or_default().push(offset + i);
+    }
+
+    groups
+}
+
+#[cfg(feature = "parallel")]
+fn merge_groups<K: Hash + Eq>(mut groups :HashMap<K, Vec<usize>>, other :HashMap<K, Vec<usize>>) -> HashMap<K, Vec<usize>> {
+    for (k, v) in other {
+        groups.entry(k).or_default().extend(v);
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::parallel::{map, group_indices, filter_indices, set_parallelism, parallelism};
+
+    #[test]
+    fn sequential() {
+        set_parallelism(1);
+
+        assert_eq!(1, parallelism());
+
+        let caller = thread::current().id();
+        let threads = map(&(0..1000).collect::<Vec<_>>(), |_| thread::current().id());
+
+        assert!(threads.iter().all(|&t| t == caller));
+
+        set_parallelism(0);
+
+        assert_eq!((0..1000).map(|i| i * 2).collect::<Vec<_>>(), map(&(0..1000).collect::<Vec<_>>(), |i| i * 2));
+    }
+
+    #[test]
+    fn ordered() {
+        let items = (0..100_000).map(|i| i % 7).collect::<Vec<_>>();
+
+        let groups = group_indices(&items, |i| *i);
+
+        assert_eq!(7, groups.len());
+        assert!(groups.values().all(|g| g.windows(2).all(|w| w[0] < w[1])));
+        assert_eq!(items.len(), groups.values().map(|g| g.len()).sum::<usize>());
+
+        let matches = filter_indices(&items, |i| *i == 3);
+
+        assert_eq!(groups[&3], matches);
+    }
+}
@@ -0,0 +1,263 @@
+//! Pipelines of table operations recorded as data, so they can be saved to JSON and re-applied to each new file.
+//!
+//! A pipeline is a list of steps, saved as:
+//! ```text
+//! {
+//!   "version": 1,
+//!   "columns": ["date", "symbol", "close"],
+//!   "steps": [
+//!     {"op": "query", "query": "close > 100"},
+//!     {"op": "aggregate", "by": ["symbol"], "column": "close", "agg": "Mean", "name": "mean_close"}
+//!   ]
+//! }
+//! ```
+//! `columns`, when set, are the columns the input must have, in order, so a file with a different layout is rejected
+//! instead of processed differently.
+use std::fs;
+use std::io::{Error as IOError, ErrorKind};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Agg, RowTable, Row, Table, TableOperations, TableSlice, TableError, Value};
+
+/// The version of the pipeline format written by [`Pipeline::to_json`](struct.Pipeline.html#method.to_json)
+const VERSION :u32 = 1;
+
+/// A step of a [`Pipeline`](struct.Pipeline.html); each one is applied to the table produced by the step before it.
+///
+/// Steps that look up another table, such as `SemiJoin`, read it from the CSV file at `path` when they're applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Step {
+    /// Keeps the rows matching a [`query`](trait.TableOperations.html#method.query)
+    Query { query: String },
+    /// Keeps only `columns`, in the order given
+    Select { columns: Vec<String> },
+    Rename { from: String, to: String },
+    /// Sorts the rows by `columns`
+    Sort { columns: Vec<String> },
+    /// Drops the rows with an empty value in any of `columns`, or in any column when it's empty
+    DropEmpty { columns: Vec<String> },
+    /// Keeps the rows whose `keys` are in the table at `path`
+    SemiJoin { path: String, keys: Vec<String> },
+    /// Keeps the rows whose `keys` aren't in the table at `path`
+    AntiJoin { path: String, keys: Vec<String> },
+    /// Adds `columns` of the lookup table at `path`, by `key`, see [`RowTable::enrich`](struct.RowTable.html#method.enrich)
+    Enrich { path: String, key: String, columns: Vec<String> },
+    /// Replaces the rows with one per group of `by`, with the `by` columns and `agg` of `column` as `name`, sorted by `by`
+    Aggregate { by: Vec<String>, column: String, agg: Agg, name: String }
+}
+
+/// A recipe of table operations, built step-by-step, that can be saved and re-applied to new files with the same
+/// columns, such as a daily drop of the same export.
+/// ```
+/// use large_table::{Agg, Pipeline, Step};
+///
+/// let pipeline = Pipeline::new()
+///     .expect_columns(&["date", "symbol", "close"])
+///     .step(Step::Query { query: "close > 100".to_string() })
+///     .step(Step::Aggregate { by: vec!["symbol".to_string()], column: "close".to_string(), agg: Agg::Mean, name: "mean_close".to_string() });
+///
+/// assert_eq!(pipeline, Pipeline::from_json(&pipeline.to_json().unwrap()).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pipeline {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    columns: Option<Vec<String>>,
+    steps: Vec<Step>
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline::new()
+    }
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Pipeline { version: VERSION, columns: None, steps: Vec::new() }
+    }
+
+    /// Adds a step to the end of the pipeline.
+    pub fn step(mut self, step :Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// The columns the input table must have, in order, for the pipeline to be applied.
+    pub fn expect_columns<S: ToString>(mut self, columns :&[S]) -> Self {
+        self.columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn to_json(&self) -> Result<String, TableError> {
+        serde_json::to_string_pretty(self).map_err(|e| TableError::new(e.to_string().as_str()))
+    }
+
+    pub fn from_json(json :&str) -> Result<Self, TableError> {
+        let pipeline :Pipeline = serde_json::from_str(json).map_err(|e| TableError::new(e.to_string().as_str()))?;
+
+        if pipeline.version != VERSION {
+            let err_str = format!("Unsupported pipeline version: {}", pipeline.version);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok(pipeline)
+    }
+
+    /// Writes the pipeline to a JSON file.
+    pub fn save<P: AsRef<Path>>(&self, path :P) -> Result<(), IOError> {
+        let json = self.to_json().map_err(|e| IOError::new(ErrorKind::InvalidData, e.to_string()))?;
+
+        fs::write(path, json)
+    }
+
+    /// Reads a pipeline from a JSON file written by [`save`](#method.save).
+    pub fn load<P: AsRef<Path>>(path :P) -> Result<Self, IOError> {
+        Pipeline::from_json(&fs::read_to_string(path)?).map_err(|e| IOError::new(ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Applies the steps, in order, to `table`, returning the table produced by the last step.
+    pub fn apply<T: TableOperations>(&self, table :&T) -> Result<RowTable, TableError> {
+        let columns = table.columns();
+
+        if let Some(expected) = &self.columns {
+            if *expected != columns {
+                let err_str = format!("Columns do not match the pipeline: expected {:?}, found {:?}", expected, columns);
+                return Err(TableError::new(err_str.as_str()));
+            }
+        }
+
+        let mut table = collect(table, &columns)?;
+
+        for (i, step) in self.steps.iter().enumerate() {
+            table = apply_step(&table, step).map_err(|e| {
+                let err_str = format!("Step {} ({:?}): {}", i, step, e);
+                TableError::new(err_str.as_str())
+            })?;
+        }
+
+        Ok(table)
+    }
+}
+
+/// `columns` of the rows of `table`, copied into a new `RowTable`
+fn collect<T: TableOperations>(table :&T, columns :&[String]) -> Result<RowTable, TableError> {
+    let positions = columns.iter().map(|c| table.column_position(c)).collect::<Result<Vec<_>, _>>()?;
+    let rows = table.iter().map(|row| positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(RowTable::from_rows(columns.to_vec(), rows))
+}
+
+fn read_table(path :&str) -> Result<RowTable, TableError> {
+    RowTable::from_csv(path).map_err(|e| {
+        let err_str = format!("Could not read {}: {}", path, e);
+        TableError::new(err_str.as_str())
+    })
+}
+
+fn strs(strings :&[String]) -> Vec<&str> {
+    strings.iter().map(String::as_str).collect()
+}
+
+fn apply_step(table :&RowTable, step :&Step) -> Result<RowTable, TableError> {
+    match step {
+        Step::Query { query } => collect(&table.query(query)?, &table.columns()),
+        Step::Select { columns } => collect(table, columns),
+        Step::Rename { from, to } => {
+            let mut table = collect(table, &table.columns())?;
+
+            table.rename_column(from, to)?;
+
+            Ok(table)
+        },
+        Step::Sort { columns } => collect(&table.filter_by(|_| true)?.sort(&strs(columns))?, &table.columns()),
+        Step::DropEmpty { columns } if columns.is_empty() => collect(&table.dropna(None)?, &table.columns()),
+        Step::DropEmpty { columns } => collect(&table.dropna(Some(&strs(columns)))?, &table.columns()),
+        Step::SemiJoin { path, keys } => collect(&table.semi_join(&read_table(path)?, &strs(keys))?, &table.columns()),
+        Step::AntiJoin { path, keys } => collect(&table.anti_join(&read_table(path)?, &strs(keys))?, &table.columns()),
+        Step::Enrich { path, key, columns } => {
+            let mut table = collect(table, &table.columns())?;
+
+            table.enrich(&read_table(path)?, key, &strs(columns))?;
+
+            Ok(table)
+        },
+        Step::Aggregate { by, column, agg, name } => {
+            let pos = table.column_position(column)?;
+            let mut groups = table.group_by_multi(&strs(by))?.into_iter().collect::<Vec<_>>();
+
+            groups.sort_by(|a, b| a.0.cmp(&b.0));
+
+            let rows = groups.into_iter().map(|(mut key, group)| {
+                let values = group.iter().map(|row| row.try_at(pos)).collect::<Result<Vec<Value>, _>>()?;
+
+                key.push(agg.apply(&values)?);
+
+                Ok(key)
+            }).collect::<Result<Vec<_>, TableError>>()?;
+
+            let mut columns = by.clone();
+
+            columns.push(name.clone());
+
+            Ok(RowTable::from_rows(columns, rows))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixtures, Agg, Pipeline, RowTable, Step, TableOperations, Row, Value};
+
+    #[test]
+    fn pipeline() {
+        let symbols = fixtures::temp_dir().join("pipeline_symbols.csv");
+
+        std::fs::write(&symbols, "symbol,sector\nAAPL,Tech\nMSFT,Tech\n").unwrap();
+
+        let pipeline = Pipeline::new()
+            .expect_columns(&["symbol", "close", "volume"])
+            .step(Step::Query { query: "volume > 10".to_string() })
+            .step(Step::Enrich { path: symbols.to_str().unwrap().to_string(), key: "symbol".to_string(), columns: vec!["sector".to_string()] })
+            .step(Step::Aggregate { by: vec!["sector".to_string(), "symbol".to_string()], column: "close".to_string(), agg: Agg::Sum, name: "total".to_string() })
+            .step(Step::Rename { from: "total".to_string(), to: "close".to_string() });
+
+        let path = fixtures::temp_dir().join("pipeline.json");
+
+        pipeline.save(&path).unwrap();
+
+        let loaded = Pipeline::load(&path).unwrap();
+
+        assert_eq!(pipeline, loaded);
+
+        let day1 = RowTable::from_csv_bytes(b"symbol,close,volume\nAAPL,75,100\nMSFT,158,5\nAAPL,76,20\nIBM,135,50\n").unwrap();
+        let result = loaded.apply(&day1).unwrap();
+
+        assert_eq!(vec!["sector", "symbol", "close"], result.columns());
+        assert_eq!(2, result.len());
+
+        let rows = result.iter().map(|row| (0..3).map(|i| row.at(i)).collect::<Vec<_>>()).collect::<Vec<_>>();
+
+        assert!(rows.contains(&vec![Value::Empty, Value::String("IBM".to_string()), Value::Integer(135)]));
+        assert!(rows.contains(&vec![Value::String("Tech".to_string()), Value::String("AAPL".to_string()), Value::Integer(151)]));
+
+        // a file with a different layout is rejected
+        let day2 = RowTable::from_csv_bytes(b"symbol,volume,close\nAAPL,100,75\n").unwrap();
+
+        assert!(loaded.apply(&day2).unwrap_err().to_string().contains("Columns do not match"));
+
+        let missing = Pipeline::new().step(Step::Select { columns: vec!["open".to_string()] });
+
+        assert!(missing.apply(&day1).unwrap_err().to_string().starts_with("Step 0"));
+        assert!(Pipeline::from_json("{\"version\": 2, \"steps\": []}").is_err());
+        assert_eq!(Pipeline::default(), Pipeline::from_json(&Pipeline::default().to_json().unwrap()).unwrap());
+        assert!(Pipeline::from_json("{\"version\": 1, \"steps\": [{\"op\": \"explode\"}]}").is_err());
+    }
+}
@@ -0,0 +1,12 @@
+//! The traits and types needed to work with tables.
+//!
+//! ```
+//! use large_table::prelude::*;
+//! ```
+pub use crate::{Table, TableOperations, TableSlice, Row, ColumnHandle, FromValue, FromRow};
+pub use crate::RowSlice;
+#[cfg(feature = "rowtable")]
+pub use crate::{RowTable, RowTableSlice};
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+pub use crate::{MMapTable, MMapTableSlice};
+pub use crate::{Value, ValueRef, ValueType, TableError};
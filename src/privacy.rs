@@ -0,0 +1,155 @@
+//! Differentially private aggregates, used by [`TableOperations::agg_with_privacy`](trait.TableOperations.html#method.agg_with_privacy).
+//!
+//! Noise calibrated to `epsilon`, and to how much one row can change the aggregate, is added to counts, sums, and
+//! means, so they can be exported without revealing whether any one row was in the table.
+use std::collections::HashMap;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::{Agg, TableError, Value};
+
+/// The distribution the noise is drawn from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Noise {
+    /// Laplace noise, for pure epsilon-differential privacy
+    Laplace,
+    /// Gaussian noise, for (epsilon, delta)-differential privacy with the given delta; its calibration only holds for an
+    /// epsilon below 1, so larger ones are an error
+    Gaussian(f64)
+}
+
+/// The privacy parameters of an aggregate: the budget `epsilon`, the noise, and the bounds of each column's values.
+///
+/// Sums and means need bounds for their column, since the noise depends on how much one value can change them; values
+/// outside the bounds are clamped to them. Counts don't need bounds.
+/// ```
+/// use large_table::{Noise, Privacy};
+///
+/// let privacy = Privacy::new(0.5).bounds("age", 0.0, 120.0).noise(Noise::Gaussian(1e-6));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Privacy {
+    epsilon: f64,
+    noise: Noise,
+    bounds: HashMap<String, (f64, f64)>,
+    seed: Option<u64>
+}
+
+impl Privacy {
+    /// Laplace noise for the privacy budget `epsilon`; smaller is more private, and noisier.
+    pub fn new(epsilon :f64) -> Self {
+        Privacy { epsilon, noise: Noise::Laplace, bounds: HashMap::new(), seed: None }
+    }
+
+    /// The distribution the noise is drawn from.
+    pub fn noise(mut self, noise :Noise) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// The smallest and largest values of `column`; this sets the sensitivity of its sums and means.
+    pub fn bounds(mut self, column :&str, lower :f64, upper :f64) -> Self {
+        self.bounds.insert(column.to_string(), (lower, upper));
+        self
+    }
+
+    /// Seeds the noise, so it's the same on every run. Only for tests: noise that can be predicted isn't private.
+    pub fn seed(mut self, seed :u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// `agg` of `values`, the non-empty values of `column`, with noise added.
+    pub(crate) fn apply(&self, column :&str, agg :Agg, values :&[Value]) -> Result<f64, TableError> {
+        if !self.epsilon.is_finite() || self.epsilon <= 0.0 {
+            let err_str = format!("Invalid epsilon: {}", self.epsilon);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        if let Noise::Gaussian(delta) = self.noise {
+            if delta.is_nan() || delta <= 0.0 || delta >= 1.0 {
+                let err_str = format!("Invalid delta: {}", delta);
+                return Err(TableError::new(err_str.as_str()));
+            }
+
+            if self.epsilon >= 1.0 {
+                let err_str = format!("Gaussian noise needs an epsilon below 1: {}", self.epsilon);
+                return Err(TableError::new(err_str.as_str()));
+            }
+        }
+
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        let count = values.len() as f64;
+
+        let bounded_sum = |epsilon :f64, rng :&mut StdRng| -> Result<f64, TableError> {
+            let (lower, upper) = self.bounds.get(column).cloned().ok_or_else(|| {
+                let err_str = format!("No bounds for column {}, needed for a private {:?}", column, agg);
+                TableError::new(err_str.as_str())
+            })?;
+
+            if lower.is_nan() || upper.is_nan() || lower > upper {
+                let err_str = format!("Invalid bounds for column {}: {} > {}", column, lower, upper);
+                return Err(TableError::new(err_str.as_str()));
+            }
+
+            let mut sum = 0.0;
+
+            for value in values {
+                let f = value.try_as_float().ok_or_else(|| {
+                    let err_str = format!("Non-numeric value in column {}: {}", column, value);
+                    TableError::new(err_str.as_str())
+                })?;
+
+                sum += f.clamp(lower, upper);
+            }
+
+            Ok(sum + self.sample(lower.abs().max(upper.abs()), epsilon, rng))
+        };
+
+        match agg {
+            Agg::Count => Ok(count + self.sample(1.0, self.epsilon, &mut rng)),
+            Agg::Sum => bounded_sum(self.epsilon, &mut rng),
+            // half the budget goes to the sum, and half to the count
+            Agg::Mean => {
+                let sum = bounded_sum(self.epsilon / 2.0, &mut rng)?;
+                let count = count + self.sample(1.0, self.epsilon / 2.0, &mut rng);
+
+                Ok(sum / count.max(1.0))
+            },
+            agg => {
+                let err_str = format!("{:?} can't be computed privately; only Count, Sum, and Mean can", agg);
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+
+    /// Noise for an aggregate that one row can change by up to `sensitivity`.
+    fn sample(&self, sensitivity :f64, epsilon :f64, rng :&mut StdRng) -> f64 {
+        match self.noise {
+            Noise::Laplace => {
+                let scale = sensitivity / epsilon;
+                let mut u :f64 = rng.gen_range(-0.5, 0.5);
+
+                // -0.5 would take the log of 0, so sample from the open interval
+                while u == -0.5 {
+                    u = rng.gen_range(-0.5, 0.5);
+                }
+
+                -scale * u.signum() * (1.0 - 2.0 * u.abs()).ln()
+            },
+            Noise::Gaussian(delta) => {
+                let sigma = sensitivity * (2.0 * (1.25 / delta).ln()).sqrt() / epsilon;
+
+                // Box-Muller transform
+                let (u1, u2) :(f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+
+                sigma * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Progress of loading or exporting a file, reported to a callback while the file is read or written.
+
+/// How much of a file has been loaded, passed to the callback of
+/// [`RowTable::from_csv_with_progress`](struct.RowTable.html#method.from_csv_with_progress) or
+/// [`MMapTable::with_progress`](struct.MMapTable.html#method.with_progress); the last call has all the bytes of the
+/// file, and all of its rows. Exports, such as
+/// [`to_csv_checkpointed_with_progress`](trait.TableOperations.html#method.to_csv_checkpointed_with_progress), pass
+/// how much has been written instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes of the file read so far
+    pub bytes: u64,
+    /// Bytes in the file
+    pub total_bytes: u64,
+    /// Rows read so far
+    pub rows: usize
+}
+
+impl Progress {
+    /// The fraction of the file read, from 0.0 to 1.0, which is 1.0 for an empty file
+    pub fn fraction(&self) -> f64 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            (self.bytes as f64 / self.total_bytes as f64).min(1.0)
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! String queries over the rows of a table, used by [`TableOperations::query`](trait.TableOperations.html#method.query).
+//!
+//! A query compares columns with values, or other columns, and combines the comparisons:
+//! ```text
+//! price > 100 && (symbol == 'AAPL' || symbol == "MSFT") && !(date < '2020-01-03')
+//! ```
+//! Quoted values and numbers are parsed like the values of a CSV file, so `'2020-01-03'` is a date; `true`,
+//! `false`, and `null` (an empty value) are keywords. Column names that aren't identifiers are quoted with backticks.
+//! Comparisons follow [`Cmp`](enum.Cmp.html), except that `== null` and `!= null` test for empty values.
+use crate::{Cmp, Row, TableError, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Column(String),
+    Value(Value),
+    Cmp(Cmp),
+    And,
+    Or,
+    Not,
+    Open,
+    Close
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Column(usize),
+    Value(Value)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Compare(Operand, Cmp, Operand),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>)
+}
+
+/// A query parsed, with its columns looked up in the columns of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Expr
+}
+
+impl Query {
+    /// Parses `query`, for rows with `columns`.
+    pub fn new(query :&str, columns :&[String]) -> Result<Query, TableError> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens: &tokens, pos: 0, columns };
+
+        let expr = parser.or()?;
+
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            let err_str = format!("Unexpected {:?} in query", token);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok(Query { expr })
+    }
+
+    /// Whether the row matches the query; the row must have the columns the query was parsed with.
+    pub fn matches<R: Row>(&self, row :&R) -> Result<bool, TableError> {
+        eval(&self.expr, row)
+    }
+}
+
+fn eval<R: Row>(expr :&Expr, row :&R) -> Result<bool, TableError> {
+    let operand = |op :&Operand| match op {
+        Operand::Column(i) => row.try_at(*i),
+        Operand::Value(v) => Ok(v.clone())
+    };
+
+    Ok(match expr {
+        Expr::Compare(a, cmp, b) => {
+            let (a, b) = (operand(a)?, operand(b)?);
+
+            match cmp {
+                Cmp::Eq if a == Value::Empty || b == Value::Empty => a == b,
+                Cmp::Ne if a == Value::Empty || b == Value::Empty => a != b,
+                cmp => cmp.matches(&a, &b)
+            }
+        },
+        Expr::And(a, b) => eval(a, row)? && eval(b, row)?,
+        Expr::Or(a, b) => eval(a, row)? || eval(b, row)?,
+        Expr::Not(a) => !eval(a, row)?
+    })
+}
+
+fn tokenize(query :&str) -> Result<Vec<Token>, TableError> {
+    let chars = query.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).cloned();
+
+        let (token, len) = match (c, next) {
+            (c, _) if c.is_whitespace() => { i += 1; continue; },
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Cmp(Cmp::Eq), 2),
+            ('!', Some('=')) => (Token::Cmp(Cmp::Ne), 2),
+            ('<', Some('=')) => (Token::Cmp(Cmp::Le), 2),
+            ('>', Some('=')) => (Token::Cmp(Cmp::Ge), 2),
+            ('<', _) => (Token::Cmp(Cmp::Lt), 1),
+            ('>', _) => (Token::Cmp(Cmp::Gt), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            ('\'', _) | ('"', _) | ('`', _) => {
+                let end = chars[i+1..].iter().position(|&e| e == c).ok_or_else(|| {
+                    let err_str = format!("Unterminated {} in query", c);
+                    TableError::new(err_str.as_str())
+                })?;
+                let s = chars[i+1..i+1+end].iter().collect::<String>();
+
+                let token = if c == '`' { Token::Column(s) } else { Token::Value(Value::new(s.as_str())) };
+
+                (token, end + 2)
+            },
+            (c, _) if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let len = chars[i..].iter().position(|&e| !(e.is_alphanumeric() || e == '_' || e == '-' || e == '.')).unwrap_or(chars.len() - i);
+                let word = chars[i..i+len].iter().collect::<String>();
+
+                let token = match word.as_str() {
+                    "true" => Token::Value(Value::Boolean(true)),
+                    "false" => Token::Value(Value::Boolean(false)),
+                    "null" => Token::Value(Value::Empty),
+                    _ if c.is_ascii_digit() || c == '-' || c == '.' => match Value::new(word.as_str()) {
+                        Value::Integer(i) => Token::Value(Value::Integer(i)),
+                        Value::Float(f) => Token::Value(Value::Float(f)),
+                        _ => {
+                            let err_str = format!("Invalid number in query: {}", word);
+                            return Err(TableError::new(err_str.as_str()));
+                        }
+                    },
+                    _ => Token::Column(word)
+                };
+
+                (token, len)
+            },
+            (c, _) => {
+                let err_str = format!("Unexpected character in query: {}", c);
+                return Err(TableError::new(err_str.as_str()));
+            }
+        };
+
+        tokens.push(token);
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, from lowest to highest precedence: `||`, `&&`, `!`, then comparisons and parentheses
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    columns: &'a [String]
+}
+
+impl <'a> Parser<'a> {
+    fn next(&mut self) -> Option<&'a Token> {
+        self.pos += 1;
+        self.tokens.get(self.pos - 1)
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<Expr, TableError> {
+        let mut expr = self.and()?;
+
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, TableError> {
+        let mut expr = self.not()?;
+
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, TableError> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+
+            let expr = self.or()?;
+
+            return match self.next() {
+                Some(Token::Close) => Ok(expr),
+                _ => Err(TableError::new("Missing ) in query"))
+            };
+        }
+
+        let a = self.operand()?;
+
+        let cmp = match self.next() {
+            Some(Token::Cmp(cmp)) => *cmp,
+            token => {
+                let err_str = format!("Expected a comparison in query, found {:?}", token);
+                return Err(TableError::new(err_str.as_str()));
+            }
+        };
+
+        Ok(Expr::Compare(a, cmp, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand, TableError> {
+        match self.next() {
+            Some(Token::Value(v)) => Ok(Operand::Value(v.clone())),
+            Some(Token::Column(c)) => match self.columns.iter().position(|col| col == c) {
+                Some(i) => Ok(Operand::Column(i)),
+                None => {
+                    let err_str = format!("Column not found: {}", c);
+                    Err(TableError::new(err_str.as_str()))
+                }
+            },
+            token => {
+                let err_str = format!("Expected a column or value in query, found {:?}", token);
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Query, Value, Row, TableError};
+
+    struct TestRow(Vec<(String, Value)>);
+
+    impl Row for TestRow {
+        fn try_get(&self, column :&str) -> Result<Value, TableError> {
+            self.0.iter().find(|(c, _)| c == column).map(|(_, v)| v.clone()).ok_or_else(|| TableError::new(column))
+        }
+
+        fn columns(&self) -> Vec<String> {
+            self.0.iter().map(|(c, _)| c.clone()).collect()
+        }
+    }
+
+    #[test]
+    fn query() {
+        let row = TestRow(vec![
+            ("price".to_string(), Value::Integer(120)),
+            ("symbol".to_string(), Value::new("AAPL")),
+            ("date".to_string(), Value::new("2020-01-03")),
+            ("low price".to_string(), Value::Float(99.5.into())),
+            ("note".to_string(), Value::Empty)
+        ]);
+        let columns = row.columns();
+        let matches = |q :&str| Query::new(q, &columns).unwrap().matches(&row).unwrap();
+
+        assert!(matches("price > 100 && symbol == 'AAPL'"));
+        assert!(matches("price >= 120 && price <= 120.0 && price != 121"));
+        assert!(!matches("price > 100 && symbol == \"MSFT\""));
+        assert!(matches("symbol == 'MSFT' || (date >= '2020-01-03' && date < '2020-01-04')"));
+        assert!(matches("!(price < 100) && `low price` < price"));
+        assert!(matches("note == null && symbol != null && !note > 1"));
+        assert!(!matches("symbol > 1"));
+        assert!(matches("-1.5 < price"));
+
+        for bad in &["price >", "price > 1 &&", "(price > 1", "price > 1)", "missing > 1", "price = 1", "symbol == 'AAPL", "1x > 1", "price > 1 price"] {
+            assert!(Query::new(bad, &columns).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Options for reading CSV files, for files that don't follow the usual layout of a header row followed by the rows.
+use std::collections::HashSet;
+use std::io::{BufRead, Error as IOError, ErrorKind, Read};
+
+use csv::{Reader, ReaderBuilder};
+
+/// How a CSV file is read, such as by [`RowTable::from_csv_with_options`](struct.RowTable.html#method.from_csv_with_options).
+///
+/// By default the first record of the file is a header with the column names. For machine-generated files without
+/// one, the columns are named `column_0`, `column_1`, ... unless names are given. Banners before the header, comment
+/// lines, and summary lines at the end can be skipped, instead of editing the file first:
+/// ```
+/// use large_table::ReadOptions;
+///
+/// let options = ReadOptions::new().has_headers(false).column_names(&["date", "close"]);
+/// let report = ReadOptions::new().skip_rows(2).comment(b'#').skip_footer(1);
+/// let export = ReadOptions::new().null_values(&["NULL", "N/A", "-"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadOptions {
+    has_headers: bool,
+    column_names: Option<Vec<String>>,
+    skip_rows: usize,
+    comment: Option<u8>,
+    skip_footer: usize,
+    nulls: Vec<String>
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions { has_headers: true, column_names: None, skip_rows: 0, comment: None, skip_footer: 0, nulls: Vec::new() }
+    }
+}
+
+impl ReadOptions {
+    pub fn new() -> Self {
+        ReadOptions::default()
+    }
+
+    /// Whether the first record of the file is a header; when it isn't, it's read as a row.
+    pub fn has_headers(mut self, has_headers :bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Names the columns, in order, instead of the header or the synthesized `column_N` names.
+    pub fn column_names<S: ToString>(mut self, column_names :&[S]) -> Self {
+        self.column_names = Some(column_names.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    /// Skips the first `skip_rows` lines of the file, before the header, such as a banner.
+    pub fn skip_rows(mut self, skip_rows :usize) -> Self {
+        self.skip_rows = skip_rows;
+        self
+    }
+
+    /// Skips the lines that start with `comment`.
+    pub fn comment(mut self, comment :u8) -> Self {
+        self.comment = Some(comment);
+        self
+    }
+
+    /// Skips the last `skip_footer` records of the file, such as a line of totals; they don't need the file's number
+    /// of fields.
+    pub fn skip_footer(mut self, skip_footer :usize) -> Self {
+        self.skip_footer = skip_footer;
+        self
+    }
+
+    /// Reads fields that are exactly one of `tokens`, such as `"NULL"` or `"N/A"`, as `Value::Empty`, like empty fields,
+    /// instead of as strings.
+    pub fn null_values<S: ToString>(mut self, tokens :&[S]) -> Self {
+        self.nulls = tokens.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    pub fn has_header(&self) -> bool {
+        self.has_headers
+    }
+
+    pub(crate) fn comment_char(&self) -> Option<u8> {
+        self.comment
+    }
+
+    pub(crate) fn nulls(&self) -> &[String] {
+        &self.nulls
+    }
+
+    /// Reads past the lines skipped by [`skip_rows`](#method.skip_rows), leaving `reader` at the start of the header.
+    pub(crate) fn skip_lines<R: BufRead>(&self, reader :&mut R) -> Result<(), IOError> {
+        let mut line = Vec::new();
+
+        for _ in 0..self.skip_rows {
+            line.clear();
+
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+        }
+
+        Ok( () )
+    }
+
+    /// A CSV reader for `reader`, which must already be past the skipped lines, configured by the options.
+    pub(crate) fn reader<R: Read>(&self, reader :R) -> Reader<R> {
+        ReaderBuilder::new()
+            .has_headers(self.has_headers)
+            .comment(self.comment)
+            .flexible(self.skip_footer > 0)
+            .from_reader(reader)
+    }
+
+    /// Removes the footer records from the end of `rows`.
+    pub(crate) fn trim_footer<T>(&self, rows :&mut Vec<T>) {
+        rows.truncate(rows.len().saturating_sub(self.skip_footer));
+    }
+
+    /// The columns of a CSV file read with [`reader`](#method.reader), from its header or the width of its first record.
+    pub(crate) fn columns<R: Read>(&self, csv :&mut Reader<R>) -> Result<Vec<String>, IOError> {
+        let first = csv.headers()?;
+
+        let columns = match &self.column_names {
+            Some(names) if !first.is_empty() && names.len() != first.len() => {
+                let err_str = format!("Expected {} column names, found {}", first.len(), names.len());
+                return Err(IOError::new(ErrorKind::InvalidInput, err_str.as_str()));
+            },
+            Some(names) => names.clone(),
+            None if self.has_headers => first.iter().map(String::from).collect::<Vec<_>>(),
+            None => (0..first.len()).map(|i| format!("column_{}", i)).collect::<Vec<_>>()
+        };
+
+        if columns.iter().collect::<HashSet<_>>().len() != columns.len() {
+            return Err(IOError::new(ErrorKind::InvalidData, "Duplicate columns detected in the file"));
+        }
+
+        Ok(columns)
+    }
+}
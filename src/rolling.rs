@@ -0,0 +1,108 @@
+use ordered_float::OrderedFloat;
+
+use crate::{TableOperations, TableError, Row, Value};
+
+/// Moving-window statistics over the rows of a table, created with [`TableOperations::rolling`](trait.TableOperations.html#method.rolling).
+///
+/// Each statistic returns one `Value` per row: the statistic over the `window` rows ending at that row.
+/// The first `window - 1` rows, and any window containing a non-numeric value, are `Value::Empty`.
+/// The rows are used in the order of the table, so sort it first for time-series data.
+pub struct Rolling<'a, T: TableOperations> {
+    table: &'a T,
+    window: usize
+}
+
+impl <'a, T: TableOperations> Rolling<'a, T> {
+    pub(crate) fn new(table :&'a T, window :usize) -> Self {
+        Rolling { table, window }
+    }
+
+    /// Sum of the values in each window
+    pub fn sum(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        self.apply(column, |w| w.iter().sum())
+    }
+
+    /// Mean of the values in each window
+    pub fn mean(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        self.apply(column, |w| w.iter().sum::<f64>() / w.len() as f64)
+    }
+
+    /// Minimum value in each window
+    pub fn min(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        self.apply(column, |w| w.iter().cloned().fold(f64::INFINITY, f64::min))
+    }
+
+    /// Maximum value in each window
+    pub fn max(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        self.apply(column, |w| w.iter().cloned().fold(f64::NEG_INFINITY, f64::max))
+    }
+
+    /// Sample standard deviation of the values in each window
+    pub fn std(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        if self.window < 2 {
+            return Err(TableError::new("Window must be at least 2 rows to compute the standard deviation"));
+        }
+
+        self.apply(column, |w| {
+            let mean = w.iter().sum::<f64>() / w.len() as f64;
+            let var = w.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (w.len() - 1) as f64;
+
+            var.sqrt()
+        })
+    }
+
+    fn apply<F: Fn(&[f64]) -> f64>(&self, column :&str, f :F) -> Result<Vec<Value>, TableError> {
+        if self.window == 0 {
+            return Err(TableError::new("Window must be at least 1 row"));
+        }
+
+        // make sure the column is there
+        self.table.column_position(column)?;
+
+        let values = self.table.iter().map(|row| row.get(column).try_as_float()).collect::<Vec<_>>();
+        let mut ret = vec![Value::Empty; values.len().min(self.window - 1)];
+
+        for window in values.windows(self.window) {
+            if window.iter().all(|v| v.is_some()) {
+                let window = window.iter().map(|v| v.unwrap()).collect::<Vec<_>>();
+
+                ret.push(Value::Float(OrderedFloat(f(&window))));
+            } else {
+                ret.push(Value::Empty);
+            }
+        }
+
+        Ok(ret)
+    }
+}
+
+/// `Iterator` over each run of `size` consecutive rows of a table, created with
+/// [`TableOperations::iter_windows`](trait.TableOperations.html#method.iter_windows).
+///
+/// The windows overlap, like `slice::windows`, so a table of `n` rows has `n - size + 1` of them.
+pub struct RowWindows<'a, T: TableOperations> {
+    table: &'a T,
+    size: usize,
+    len: usize,
+    cur_pos: usize
+}
+
+impl <'a, T: TableOperations> RowWindows<'a, T> {
+    pub(crate) fn new(table :&'a T, size :usize) -> Self {
+        RowWindows { table, size, len: table.len(), cur_pos: 0 }
+    }
+}
+
+impl <'a, T: TableOperations> Iterator for RowWindows<'a, T> {
+    type Item = Vec<T::RowType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 || self.cur_pos + self.size > self.len {
+            return None;
+        }
+
+        self.cur_pos += 1;
+
+        (self.cur_pos-1..self.cur_pos-1+self.size).map(|i| self.table.get(i)).collect::<Result<Vec<_>, _>>().ok()
+    }
+}
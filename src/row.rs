@@ -1,17 +1,19 @@
-use std::rc::Rc;
-use std::cell::RefCell;
+#[cfg(feature = "std")]
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
+use core::marker::PhantomData;
 
-use crate::value::Value;
+use crate::value::{Value, ValueRef, FromValue};
 use crate::table_error::TableError;
-use crate::Table;
 
 
 // playground: https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=fbac8bab1dc26bc89edf35e6d62b3170
 
 // playground for Row & Iterators: https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=5b1ead8cdf0cbaac2941ec9e15a942d5
 
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct RowSlice<T> {
     pub(crate) column_map: Arc<Vec<(String, usize)>>,
@@ -27,6 +29,68 @@ pub trait Row {
 
     fn try_get(&self, column :&str) -> Result<Value, TableError>;
 
+    /// Returns the value at a position in `columns`.
+    fn at(&self, index :usize) -> Value {
+        self.try_at(index).unwrap()
+    }
+
+    fn try_at(&self, index :usize) -> Result<Value, TableError> {
+        match self.columns().get(index) {
+            Some(column) => self.try_get(column),
+            None => {
+                let err_str = format!("Index {} is beyond row width {}", index, self.width());
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+
+    /// Returns the value of `column` as a [`ValueRef`](enum.ValueRef.html); see [`at_ref`](#method.at_ref).
+    fn get_ref(&self, column :&str) -> ValueRef<'_> {
+        self.try_get_ref(column).unwrap()
+    }
+
+    fn try_get_ref(&self, column :&str) -> Result<ValueRef<'_>, TableError> {
+        self.try_get(column).map(ValueRef::from)
+    }
+
+    /// Returns the value at a position in `columns` as a [`ValueRef`](enum.ValueRef.html), borrowing its string from
+    /// the row when the row owns its values, and owning it otherwise.
+    ///
+    /// The rows of a table only hold their values while the table is locked, so they can't lend them; use
+    /// [`with_ref`](#method.with_ref) to compare their values without copying them.
+    fn at_ref(&self, index :usize) -> ValueRef<'_> {
+        self.try_at_ref(index).unwrap()
+    }
+
+    fn try_at_ref(&self, index :usize) -> Result<ValueRef<'_>, TableError> {
+        self.try_at(index).map(ValueRef::from)
+    }
+
+    /// Calls `f` with the value at a position in `columns` as a [`ValueRef`](enum.ValueRef.html), borrowed from the
+    /// table for as long as `f` runs, so filtering on a string doesn't copy each one.
+    fn with_ref<T, F: FnOnce(ValueRef<'_>) -> T>(&self, index :usize, f :F) -> Result<T, TableError> {
+        self.try_at_ref(index).map(f)
+    }
+
+    /// Calls `f` with the text of the value at a position in `columns`, or `None` when it's empty: the field as it is
+    /// in the file for tables that read their values from one, so "007" keeps its zeros, or else the value formatted.
+    fn with_text<T, F: FnOnce(Option<&str>) -> T>(&self, index :usize, f :F) -> Result<T, TableError> {
+        self.with_ref(index, |v| match v {
+            ValueRef::Empty => f(None),
+            ValueRef::String(s) => f(Some(s.as_ref())),
+            other => f(Some(other.to_string().as_str()))
+        })
+    }
+
+    /// Returns the value of the handle's column, converted to the handle's type.
+    fn value<T: FromValue>(&self, handle :&ColumnHandle<T>) -> T {
+        self.try_value(handle).unwrap()
+    }
+
+    fn try_value<T: FromValue>(&self, handle :&ColumnHandle<T>) -> Result<T, TableError> {
+        T::from_value(self.try_at(handle.index)?)
+    }
+
     fn set(&mut self, column :&str, value :Value) -> Result<Value, TableError> {
         unimplemented!()
     }
@@ -39,3 +103,83 @@ pub trait Row {
     fn columns(&self) -> Vec<String>;
 }
 
+/// Types built from the values of a row, by column name.
+///
+/// With the `derive` feature, `#[derive(FromRow)]` implements this for structs with named fields, reading each field
+/// from the column with the same name, or the name given with `#[from_row(rename = "column")]`, using `FromValue`.
+pub trait FromRow: Sized {
+    fn from_row<R: Row + ?Sized>(row :&R) -> Result<Self, TableError>;
+}
+
+/// Reads a column of a row as `T`, with the column in the error; used by `#[derive(FromRow)]`.
+#[doc(hidden)]
+pub fn row_field<R: Row + ?Sized, T: FromValue>(row :&R, column :&str) -> Result<T, TableError> {
+    T::from_value(row.try_get(column)?).map_err(|e| {
+        let err_str = format!("Column {}: {}", column, e);
+        TableError::new(err_str.as_str())
+    })
+}
+
+/// Iterator over the rows of a table converted to `T`, returned by
+/// [`TableOperations::typed_iter`](trait.TableOperations.html#method.typed_iter).
+pub struct TypedIter<I, T> {
+    iter: I,
+    row_type: PhantomData<fn() -> T>
+}
+
+impl <I, T> TypedIter<I, T> {
+    pub(crate) fn new(iter :I) -> Self {
+        TypedIter { iter, row_type: PhantomData }
+    }
+}
+
+impl <I: Iterator, T: FromRow> Iterator for TypedIter<I, T> where I::Item: Row {
+    type Item = Result<T, TableError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|row| T::from_row(&row))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl <I: DoubleEndedIterator, T: FromRow> DoubleEndedIterator for TypedIter<I, T> where I::Item: Row {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|row| T::from_row(&row))
+    }
+}
+
+impl <I: ExactSizeIterator, T: FromRow> ExactSizeIterator for TypedIter<I, T> where I::Item: Row {}
+
+/// A column of a table, with the position of the column looked up once, and the type its values are read as.
+///
+/// Created with [`TableOperations::column_handle`](trait.TableOperations.html#method.column_handle), and only
+/// valid for rows of that table, or of slices of it.
+#[derive(Debug)]
+pub struct ColumnHandle<T> {
+    index: usize,
+    column: String,
+    value_type: PhantomData<fn() -> T>
+}
+
+impl <T> ColumnHandle<T> {
+    pub fn new(index :usize, column :&str) -> Self {
+        ColumnHandle { index, column: String::from(column), value_type: PhantomData }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn column(&self) -> &str {
+        self.column.as_str()
+    }
+}
+
+impl <T> Clone for ColumnHandle<T> {
+    fn clone(&self) -> Self {
+        ColumnHandle::new(self.index, &self.column)
+    }
+}
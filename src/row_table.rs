@@ -1,7 +1,8 @@
 use std::collections::{HashMap, HashSet};
 use std::cmp::Ordering;
 use std::path::Path;
-use std::io::{Error as IOError, ErrorKind};
+use std::fs::File;
+use std::io::{BufReader, Error as IOError, ErrorKind, Read};
 use std::ops::Index;
 use std::collections::hash_map::RandomState;
 use std::iter::Map;
@@ -10,17 +11,113 @@ use std::fmt::{Display, Formatter, Error as FmtError};
 
 
 use csv::{Reader, StringRecord, ByteRecord, ReaderBuilder, Trim};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-use crate::{Table, TableOperations, TableSlice, TableError, ValueType};
-use crate::value::Value;
+use crate::{parallel, stats, ColumnStats, Table, TableOperations, TableSlice, TableError, Fill, ValueType, Metadata, Schema, ReadOptions, MemoryUsage, Progress, CancelToken, DISPLAY_ROWS, DISPLAY_WIDTH};
+use crate::value::{Value, ValueRef};
 use crate::row::{Row, RowSlice};
 
+/// Number of records read before their values are parsed in parallel
+const PARSE_BATCH_SIZE :usize = 64 * 1024;
+
 /// A table with row-oriented data
 #[derive(Debug, Clone)]
 pub struct RowTableInner {
     columns: Vec<String>,
-    rows: Vec<Vec<Value>>
+    rows: Vec<Vec<Value>>,
+    stats: Option<Vec<ColumnStats>>, // statistics of each column from `compute_stats`, cleared when the table changes
+    unique: Vec<UniqueKey>,           // columns declared unique with `declare_unique`, kept up to date by edits
+    types: Vec<Option<ValueType>>     // type of each column from the schema or metadata it's loaded with, if any
+}
+
+/// A column declared unique, with the row of each of its non-empty values
+#[derive(Debug, Clone)]
+struct UniqueKey {
+    pos: usize,
+    rows: Arc<HashMap<Value, usize>>
+}
+
+/// The row of each non-empty value in `values`, or an error naming a value of `column` that repeats
+fn unique_rows<'a, I: Iterator<Item=&'a Value>>(values :I, column :&str) -> Result<HashMap<Value, usize>, TableError> {
+    let mut rows = HashMap::new();
+
+    for (i, value) in values.enumerate() {
+        if *value == Value::Empty {
+            continue;
+        }
+
+        if let Some(prev) = rows.insert(value.clone(), i) {
+            let err_str = format!("Value {} repeats in unique column {}, in rows {} and {}", value, column, prev, i);
+            return Err(TableError::new(err_str.as_str()));
+        }
+    }
+
+    Ok(rows)
+}
+
+impl RowTableInner {
+    /// Checks that setting column `pos` of `row` to `value` doesn't repeat a value of a unique column
+    fn check_unique(&self, row :usize, pos :usize, value :&Value) -> Result<(), TableError> {
+        for key in self.unique.iter().filter(|k| k.pos == pos) {
+            match key.rows.get(value) {
+                Some(&other) if other != row => {
+                    let err_str = format!("Value {} is already in unique column {}, in row {}", value, self.columns[pos], other);
+                    return Err(TableError::new(err_str.as_str()));
+                },
+                _ => ()
+            }
+        }
+
+        Ok( () )
+    }
+
+    /// Checks that `rows`, added after the existing rows, don't repeat a value of a unique column, among themselves or
+    /// with the existing rows
+    fn check_unique_rows(&self, rows :&[Vec<Value>]) -> Result<(), TableError> {
+        for key in self.unique.iter() {
+            let column = &self.columns[key.pos];
+            let added = unique_rows(rows.iter().map(|row| &row[key.pos]), column)?;
+
+            if let Some((value, &other)) = added.iter().find_map(|(v, _)| key.rows.get_key_value(v)) {
+                let err_str = format!("Value {} is already in unique column {}, in row {}", value, column, other);
+                return Err(TableError::new(err_str.as_str()));
+            }
+        }
+
+        Ok( () )
+    }
+
+    /// Forgets the declared type of column `pos`, when its values are replaced
+    fn forget_type(&mut self, pos :usize) {
+        if let Some(declared) = self.types.get_mut(pos) {
+            *declared = None;
+        }
+    }
+
+    /// Forgets the declared type of column `pos` when `value` isn't empty, or of that type
+    fn check_type(&mut self, pos :usize, value :&Value) {
+        if let Some(declared) = self.types.get_mut(pos) {
+            if declared.as_ref().is_some_and(|t| !holds(t, value)) {
+                *declared = None;
+            }
+        }
+    }
+
+    /// Moves `row` from `old` to `new` in the index of column `pos`, when it's unique
+    fn reindex(&mut self, row :usize, pos :usize, old :&Value, new :&Value) {
+        for key in self.unique.iter_mut().filter(|k| k.pos == pos) {
+            let rows = Arc::make_mut(&mut key.rows);
+
+            if rows.get(old) == Some(&row) {
+                rows.remove(old);
+            }
+
+            if *new != Value::Empty {
+                rows.insert(new.clone(), row);
+            }
+        }
+    }
 }
 
 //https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=85a1c46e9e455bba144e442cdf0e57b3 - Arc<Mutex<>> Playground
@@ -32,478 +129,3235 @@ impl RowTable {
     pub fn new<S: ToString>(columns :&[S]) -> Self {
         RowTable(Arc::new(Mutex::new(RowTableInner {
             columns: columns.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
-            rows: Vec::new()
+            rows: Vec::new(),
+            stats: None,
+            unique: Vec::new(),
+            types: Vec::new()
         })))
     }
 
-    /// Read in a CSV file, and construct a RowTable
-    pub fn from_csv<P: AsRef<Path>>(path :P) -> Result<Self, IOError> {
-//        let mut csv = ReaderBuilder::new().trim(Trim::All).from_path(path)?;
-        let mut csv = Reader::from_path(path)?;
+    /// Creates a RowTable from rows that each have a value for every column
+    pub(crate) fn from_rows(columns :Vec<String>, rows :Vec<Vec<Value>>) -> Self {
+        RowTable::from_typed_rows(columns, rows, Vec::new())
+    }
 
-        // get the headers from the CSV file
-        let columns = csv.headers()?.iter().map(|h| String::from(h)).collect::<Vec<_>>();
+    /// Creates a RowTable from rows that each have a value for every column, declaring the `types` of the columns
+    fn from_typed_rows(columns :Vec<String>, rows :Vec<Vec<Value>>, types :Vec<Option<ValueType>>) -> Self {
+        RowTable(Arc::new(Mutex::new(RowTableInner { columns, rows, stats: None, unique: Vec::new(), types })))
+    }
 
-        if columns.iter().collect::<HashSet<_>>().len() != columns.len() {
-            return Err(IOError::new(ErrorKind::InvalidData, "Duplicate columns detected in the file"));
-        }
+    /// Computes the minimum, maximum, and number of empty values of every column, in one pass over the rows, and keeps
+    /// them until the table is changed. `filter_range` uses them to skip tables that can't have matching rows.
+    pub fn compute_stats(&self) {
+        let stats = stats::column_stats(self);
 
-        let mut rows = Vec::new();
-////        let mut record = ByteRecord::new();
-        let mut record = StringRecord::new();
-//
-////        while csv.read_byte_record(&mut record).map_err(|e| IOError::new(ErrorKind::Other, e))? {
-        while csv.read_record(&mut record).map_err(|e| IOError::new(ErrorKind::Other, e))? {
-//            let row = record.iter().map(|s| Value::String(s.to_string())).collect::<Vec<_>>();
-            let row = record.iter().map(|s| Value::new(s)).collect::<Vec<_>>();
+        self.0.lock().unwrap().stats = Some(stats);
+    }
 
-            rows.push(row);
-        }
+    /// Checks that the non-empty values of `column` are unique, and keeps the row of each one, so [`loc`](#method.loc)
+    /// and [`upsert`](#method.upsert) find rows by it without a scan, and `enrich`, `semi_join`, and `anti_join` with
+    /// this table as the lookup side reuse it instead of building their own.
+    ///
+    /// While it's declared, edits that would repeat a value of `column`, such as `append_row` or `Row::set`, are
+    /// rejected with an error.
+    pub fn declare_unique(&self, column :&str) -> Result<(), TableError> {
+        let pos = self.column_position(column)?;
+        let mut inner = self.0.lock().unwrap();
+        let rows = unique_rows(inner.rows.iter().map(|row| &row[pos]), column)?;
 
-        // shrink the vector down so we're not chewing up more memory than needed
-        rows.shrink_to_fit();
+        inner.unique.retain(|k| k.pos != pos);
+        inner.unique.push(UniqueKey { pos, rows: Arc::new(rows) });
 
-        Ok(RowTable(Arc::new(Mutex::new(RowTableInner { columns, rows }))))
+        Ok( () )
     }
 
-    pub fn from_csv_with_schema<P: AsRef<Path>>(path :P, schema :&[ValueType]) -> Result<Self, IOError> {
-        let mut csv = Reader::from_path(path)?;
+    /// The columns declared unique with [`declare_unique`](#method.declare_unique)
+    pub fn unique_columns(&self) -> Vec<String> {
+        let inner = self.0.lock().unwrap();
 
-        // get the headers from the CSV file
-        let columns = csv.headers()?.iter().map(|h| String::from(h)).collect::<Vec<_>>();
+        inner.unique.iter().map(|k| inner.columns[k.pos].clone()).collect()
+    }
 
-        if columns.iter().collect::<HashSet<_>>().len() != columns.len() {
-            return Err(IOError::new(ErrorKind::InvalidData, "Duplicate columns detected in the file"));
-        }
+    /// Returns the row with `key` in `column`, which must be declared unique with
+    /// [`declare_unique`](#method.declare_unique), or `None` when no row has it.
+    pub fn loc(&self, column :&str, key :&Value) -> Result<Option<RowSlice<RowTableInner>>, TableError> {
+        let index = self.unique_index(column).ok_or_else(|| {
+            let err_str = format!("Column {} is not declared unique", column);
+            TableError::new(err_str.as_str())
+        })?;
 
-        if columns.len() != schema.len() {
-            let err_str = format!("Column count and schema length do not match: {} != {}", columns.len(), schema.len());
-            return Err(IOError::new(ErrorKind::InvalidInput, err_str.as_str()));
+        index.get(key).map(|&row| self.get(row)).transpose()
+    }
+
+    /// Replaces the values of the row with the same value in `column` as `row`, or appends `row` when there's no such
+    /// row; `column` must be declared unique with [`declare_unique`](#method.declare_unique).
+    pub fn upsert<R: Row>(&mut self, column :&str, row :R) -> Result<(), TableError> {
+        let key = row.try_get(column)?;
+        let existing = self.loc(column, &key)?.map(|r| r.row);
+
+        let existing = match existing {
+            Some(existing) => existing,
+            None => return self.append_row(row)
+        };
+
+        let values = self.columns().iter().map(|c| row.try_get(c)).collect::<Result<Vec<_>, _>>()?;
+        let mut inner = self.0.lock().unwrap();
+
+        // check every unique column before changing anything
+        for (pos, value) in values.iter().enumerate() {
+            inner.check_unique(existing, pos, value)?;
         }
 
-        let mut rows = Vec::new();
-        let mut record = StringRecord::new();
+        inner.stats = None;
 
-        while csv.read_record(&mut record).map_err(|e| IOError::new(ErrorKind::Other, e))? {
-            let row = record.iter().enumerate().map(|(i, s)| Value::with_type(s, &schema[i])).collect::<Vec<_>>();
+        for (pos, value) in values.into_iter().enumerate() {
+            inner.check_type(pos, &value);
 
-            rows.push(row);
+            let old = std::mem::replace(&mut inner.rows[existing][pos], value.clone());
+
+            inner.reindex(existing, pos, &old, &value);
         }
 
-        // shrink the vector down so we're not chewing up more memory than needed
-        rows.shrink_to_fit();
+        Ok( () )
+    }
 
-        Ok(RowTable(Arc::new(Mutex::new(RowTableInner { columns, rows }))))
+    /// Read in a CSV file, and construct a RowTable
+    ///
+    /// The column types are taken from the file's [`Metadata`](struct.Metadata.html) sidecar when it has one.
+    pub fn from_csv<P: AsRef<Path>>(path :P) -> Result<Self, IOError> {
+        RowTable::from_csv_with_progress(path, |_| ())
     }
-}
 
-impl Table for RowTable {
-    fn update_by<F: FnMut(&mut Self::RowType)>(&mut self, mut update: F) {
-        for mut row in self.iter() {
-            update(&mut row);
-        }
+    /// Read in a CSV file, the same as [`from_csv`](#method.from_csv), calling `progress` with the bytes and rows read
+    /// so far after each batch of rows, and once more when the whole file is read, such as to show a progress bar.
+    pub fn from_csv_with_progress<P: AsRef<Path>, F: FnMut(Progress)>(path :P, progress :F) -> Result<Self, IOError> {
+        RowTable::from_csv_with_progress_and_cancel(path, &CancelToken::new(), progress)
     }
 
-    fn append_row<R>(&mut self, row: R) -> Result<(), TableError>  where R: Row {
-        // go through each column, and get the corresponding column from the row
-        let mut row_vec = Vec::new();
+    /// Read in a CSV file, the same as [`from_csv`](#method.from_csv), checking `token` after each batch of rows.
+    ///
+    /// Once it's cancelled, returns an `Interrupted` error wrapping
+    /// [`TableError::cancelled`](struct.TableError.html#method.cancelled).
+    pub fn from_csv_with_cancel<P: AsRef<Path>>(path :P, token :&CancelToken) -> Result<Self, IOError> {
+        RowTable::from_csv_with_progress_and_cancel(path, token, |_| ())
+    }
 
-        for column in self.0.lock().unwrap().columns.iter() {
-            let val = row.try_get(column);
+    /// Read in a CSV file, calling `progress` like [`from_csv_with_progress`](#method.from_csv_with_progress), and
+    /// checking `token` after each call like [`from_csv_with_cancel`](#method.from_csv_with_cancel), so the load can be
+    /// cancelled from `progress`, such as when the user closes a progress bar.
+    pub fn from_csv_with_progress_and_cancel<P: AsRef<Path>, F: FnMut(Progress)>(path :P, token :&CancelToken, mut progress :F) -> Result<Self, IOError> {
+        token.check_io()?;
 
-            if let Err(e) = val {
-                return Err(e);
-            }
+        let total_bytes = std::fs::metadata(&path)?.len();
+        let mut report = |rows, bytes| {
+            progress(Progress { bytes, total_bytes, rows });
+            token.check_io()
+        };
 
-            row_vec.push(val.unwrap());
-        }
+        let table = RowTable::load_csv(path, &ReadOptions::default(), &mut report)?;
 
-        Ok(Arc::get_mut(&mut self.0).unwrap().get_mut().unwrap().rows.push(row_vec))
+        report(table.len(), total_bytes)?;
+
+        Ok(table)
     }
 
-    fn add_column_with<F: FnMut() -> Value>(&mut self, column_name :&str, mut f :F) -> Result<(), TableError> {
-        // make sure we're not duplicating column names
-        if let Ok(_) = self.column_position(column_name) {
-            let err_str = format!("Attempting to add duplicate column: {} already exists", column_name);
-            return Err(TableError::new(err_str.as_str()));
+    /// Reads a CSV file as described by `options`, using its metadata when it has one, calling `report` with the rows
+    /// and bytes read after each batch of rows
+    fn load_csv<P: AsRef<Path>>(path :P, options :&ReadOptions, report :&mut dyn FnMut(usize, u64) -> Result<(), IOError>) -> Result<Self, IOError> {
+        match Metadata::read(&path)? {
+            Some(metadata) => RowTable::from_csv_with_metadata(path, &metadata, options, report),
+            None => RowTable::read_csv(File::open(path)?, options, report)
         }
+    }
 
-        // add the column name to our list of columns
-        Arc::get_mut(&mut self.0).unwrap().get_mut().unwrap().columns.push(String::from(column_name));
+    fn from_csv_with_metadata<P: AsRef<Path>>(path :P, metadata :&Metadata, options :&ReadOptions, report :&mut dyn FnMut(usize, u64) -> Result<(), IOError>) -> Result<Self, IOError> {
+        let mut csv = Reader::from_path(&path)?;
+        let columns = read_columns(&mut csv)?;
 
-        // add the default value for the column
-        Arc::get_mut(&mut self.0).unwrap().get_mut().unwrap().rows.iter_mut().for_each(|row| row.push(f()));
+        // metadata for different columns is ignored
+        let types = match metadata.types_for(&columns) {
+            Some(types) => types,
+            None => return RowTable::read_csv(File::open(path)?, options, report)
+        };
 
-        Ok( () )
+        let mut rows = Vec::with_capacity(metadata.rows);
+
+        loop {
+            let batch = read_typed_rows(&mut csv, PARSE_BATCH_SIZE, &types, options.nulls())?;
+
+            if batch.is_empty() {
+                break;
+            }
+
+            rows.extend(batch);
+            report(rows.len(), csv.position().byte())?;
+        }
+
+        rows.shrink_to_fit();
+
+        Ok(RowTable::from_typed_rows(columns, rows, types))
     }
 
-    fn rename_column(&mut self, old_col :&str, new_col :&str) -> Result<(), TableError> {
-        let pos = self.column_position(old_col)?;
+    /// Construct a RowTable from the bytes of a CSV file, such as a file uploaded in a browser
+    pub fn from_csv_bytes(bytes :&[u8]) -> Result<Self, IOError> {
+        RowTable::from_csv_reader(bytes)
+    }
 
-        self.0.lock().unwrap().columns[pos] = new_col.to_string();
+    /// Read in a sheet of an Excel (xlsx) workbook, and construct a RowTable; the first row of the sheet has the columns.
+    #[cfg(feature = "xlsx")]
+    pub fn from_xlsx<P: AsRef<Path>>(path :P, sheet :&str) -> Result<Self, IOError> {
+        let (columns, rows) = crate::xlsx::read_sheet(path, sheet)?;
 
-        Ok( () )
+        Ok(RowTable::from_rows(columns, rows))
     }
-}
 
-impl TableOperations for RowTable {
-    type TableSliceType = RowTableSlice;
-    type RowType = RowSlice<RowTableInner>;
-    type Iter = RowTableIter;
+    /// Construct a RowTable from Arrow record batches, which must all have the same columns
+    #[cfg(feature = "arrow")]
+    pub fn from_arrow(batches :&[arrow_array::RecordBatch]) -> Result<Self, TableError> {
+        let (columns, rows) = crate::arrow::from_batches(batches)?;
 
-    fn iter(&self) -> RowTableIter {
-        RowTableIter {
-            table: self.0.clone(),
-            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
-            cur_pos: 0
-        }
+        Ok(RowTable::from_rows(columns, rows))
     }
 
-    fn get(&self, index :usize) -> Result<Self::RowType, TableError> {
-        if index >= self.len() {
-            let err_str = format!("Index {} is beyond table length {}", index, self.len());
-            return Err(TableError::new(err_str.as_str()));
-        }
+    /// Read in CSV data from `reader`, and construct a RowTable
+    pub fn from_csv_reader<R: Read>(reader :R) -> Result<Self, IOError> {
+        RowTable::from_csv_reader_with_options(reader, &ReadOptions::default())
+    }
 
-        Ok(RowSlice {
-            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
-            table: self.0.clone(),
-            row: index
-        })
+    /// Read in a CSV file as described by `options`, such as one without a header row, and construct a RowTable
+    pub fn from_csv_with_options<P: AsRef<Path>>(path :P, options :&ReadOptions) -> Result<Self, IOError> {
+        RowTable::from_csv_reader_with_options(File::open(path)?, options)
     }
 
-    #[inline]
-    fn columns(&self) -> Vec<String> {
-        self.0.lock().unwrap().columns.clone()
+    /// Read in CSV data from `reader` as described by `options`, and construct a RowTable
+    pub fn from_csv_reader_with_options<R: Read>(reader :R, options :&ReadOptions) -> Result<Self, IOError> {
+        RowTable::read_csv(reader, options, &mut |_, _| Ok( () ))
     }
 
-    fn group_by(&self, column: &str) -> Result<HashMap<Value, RowTableSlice>, TableError> {
-        // get the position in the row we're concerned with
-        let pos = self.column_position(column)?;
+    /// Reads CSV data from `reader` as described by `options`, calling `report` with the rows and bytes read after
+    /// each batch of rows
+    fn read_csv<R: Read>(reader :R, options :&ReadOptions, report :&mut dyn FnMut(usize, u64) -> Result<(), IOError>) -> Result<Self, IOError> {
+//        let mut csv = ReaderBuilder::new().trim(Trim::All).from_path(path)?;
+        let mut reader = BufReader::new(reader);
+
+        options.skip_lines(&mut reader)?;
+
+        let mut csv = options.reader(reader);
+        let columns = options.columns(&mut csv)?;
+        let mut rows = Vec::new();
 
-        let mut row_map = HashMap::new();
+        loop {
+            let batch = read_rows(&mut csv, PARSE_BATCH_SIZE, options.nulls())?;
 
-        // go through each row, and add them to our result
-        for (i, row) in self.0.lock().unwrap().rows.iter().enumerate() {
-            // get the slice, or create a new one
-            let slice = row_map.entry(row[pos].clone()).or_insert(Vec::new());
+            if batch.is_empty() {
+                break;
+            }
 
-            // insert this row
-            slice.push(i);
+            rows.extend(batch);
+            report(rows.len(), csv.position().byte())?;
         }
 
-        let column_map :Arc<Vec<(String, usize)>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect());
+        options.trim_footer(&mut rows);
 
-        Ok(row_map.into_iter().map(|(k, v)| (k, RowTableSlice {
-            column_map: column_map.clone(),
-            rows: Arc::new(v),
-            table: self.0.clone()
-        })).collect())
+        // the reader doesn't check the number of fields when there's a footer, since it usually has fewer
+        if let Some((i, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != columns.len()) {
+            let err_str = format!("Row {}: expected {} fields, found {}", i, columns.len(), row.len());
+            return Err(IOError::new(ErrorKind::InvalidData, err_str.as_str()));
+        }
+
+        // shrink the vector down so we're not chewing up more memory than needed
+        rows.shrink_to_fit();
+
+        Ok(RowTable::from_rows(columns, rows))
     }
 
-    fn filter_by<P: FnMut(&RowSlice<RowTableInner>) -> bool>(&self, mut predicate :P) -> Result<RowTableSlice, TableError> {
-        let mut slice_rows = Vec::new();
+    /// Creates a new `RowTable` with the rows of this table followed by the rows of `other`.
+    ///
+    /// Columns are aligned by name, see [`concat`](fn.concat.html).
+    pub fn concat<T: TableOperations>(&self, other :&T) -> Result<RowTable, TableError> {
+        let columns = union_columns(vec![self.columns(), other.columns()].into_iter());
+        let mut rows = aligned_rows(&columns, self);
 
-        for (i, row) in self.iter().enumerate() {
-            if predicate(&row) {
-                slice_rows.push(i);
-            }
-        }
+        rows.extend(aligned_rows(&columns, other));
 
-        Ok(RowTableSlice {
-            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
-            rows: Arc::new(slice_rows),
-            table: self.0.clone()
-        })
+        Ok(RowTable::from_rows(columns, rows))
     }
 
-//    fn sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, mut compare: F) -> Result<RowTableSlice, TableError> {
-//        let column_map :Arc<HashMap<String, usize>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i,s)| (s.clone(), i)).collect());
-//
-//        let slice = RowTableSlice {
-//            column_map,
-//            rows: Arc::new((0..self.len()).collect()),
-//            table: self.0.clone()
-//        };
-//
-//        slice.sort_by(compare)
-//    }
-//
-//    fn stable_sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, mut compare: F) -> Result<Self::TableSliceType, TableError> {
-//        let column_map :Arc<HashMap<String, usize>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i,s)| (s.clone(), i)).collect());
-//
-//        let slice = RowTableSlice {
-//            column_map,
-//            rows: Arc::new((0..self.len()).collect()),
-//            table: self.0.clone()
-//        };
-//
-//        slice.stable_sort_by(compare)
-//    }
+    /// Aligns this table and `other` onto the same times in their `on` column, for comparing two time series row by
+    /// row, such as the spread or correlation of two prices; returns a new `RowTable` for each, with their own
+    /// columns, and a row for each time.
+    ///
+    /// The times are those of both tables together or, with `interval`, every `interval` from the first time to the
+    /// last. A table with more than one row at a time uses its last one, and a time it has no row at is filled with
+    /// `fill`; the rows before a table's first time, with `Fill::FFill`, or after its last, with `Fill::BFill`, are
+    /// left empty. Rows with an empty time are dropped.
+    pub fn align<T: TableOperations>(&self, other :&T, on :&str, fill :Fill, interval :Option<chrono::Duration>) -> Result<(RowTable, RowTable), TableError> {
+        let (pos, other_pos) = (self.column_position(on)?, other.column_position(on)?);
+        let (series, other_series) = (time_series(self, pos)?, time_series(other, other_pos)?);
+
+        let mut times = series.iter().chain(other_series.iter()).map(|(t, _)| t.clone()).collect::<Vec<_>>();
+
+        times.sort();
+        times.dedup();
+
+        if let (Some(interval), Some(first), Some(last)) = (interval, times.first().cloned(), times.last().cloned()) {
+            if interval <= chrono::Duration::zero() {
+                return Err(TableError::new("The interval to align tables on must be positive"));
+            }
 
-    fn split_rows_at(&self, mid: usize) -> Result<(Self::TableSliceType, Self::TableSliceType), TableError> {
-        if mid >= self.0.lock().unwrap().rows.len() {
-            let err_str = format!("Midpoint too large: {} >= {}", mid, self.0.lock().unwrap().rows.len());
-            return Err(TableError::new(err_str.as_str()));
+            let step = Value::Duration(interval);
+            let mut time = first;
+
+            times.clear();
+
+            while time <= last {
+                let next = time.try_add(&step)?;
+
+                times.push(time);
+                time = next;
+            }
         }
 
         Ok( (
-            RowTableSlice {
-                column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
-                rows: Arc::new((0..mid).collect::<Vec<_>>()),
-                table: self.0.clone()
-            },
-            RowTableSlice {
-                column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
-                rows: Arc::new((mid..self.0.lock().unwrap().rows.len()).collect::<Vec<_>>()),
-                table: self.0.clone()
-            }
-            )
-        )
+            RowTable::from_rows(self.columns(), align_series(&series, pos, &times, fill, self.width())),
+            RowTable::from_rows(other.columns(), align_series(&other_series, other_pos, &times, fill, other.width()))
+        ) )
     }
-}
 
-
-impl Row for RowSlice<RowTableInner> {
-    fn try_get(&self, column: &str) -> Result<Value, TableError> {
-        let pos = self.column_map.iter().position(|(c, i)| c == column);
-
-        if pos.is_none() {
-            let err_str = format!("Could not find column in RowSlice: {}", column);
+    /// Creates a new `RowTable` with the columns of this table followed by the columns of `other`, side-by-side.
+    ///
+    /// Both tables must have the same number of rows. Columns of `other` whose names clash with a column
+    /// of this table have `suffix` appended, so it must not be empty when any do.
+    pub fn hconcat<T: TableOperations>(&self, other :&T, suffix :&str) -> Result<RowTable, TableError> {
+        if self.len() != other.len() {
+            let err_str = format!("Row counts do not match: {} != {}", self.len(), other.len());
             return Err(TableError::new(err_str.as_str()));
         }
 
-        let pos = self.column_map[pos.unwrap()].1;
+        let other_columns = other.columns();
+        let columns = side_by_side_columns(self.columns(), &other_columns, suffix)?;
 
-        let row = &self.table.lock().unwrap().rows[self.row];
+        let rows = self.iter().zip(other.iter()).map(|(a, b)| {
+            let mut row = a.columns().iter().map(|c| a.get(c)).collect::<Vec<_>>();
 
-        Ok(row[pos].clone())
-    }
+            row.extend(other_columns.iter().map(|c| b.get(c)));
 
-    fn columns(&self) -> Vec<String> {
-        self.column_map.iter().map(|(c,i)| c.clone()).collect()
-    }
-}
+            row
+        }).collect::<Vec<_>>();
 
-impl Display for RowSlice<RowTableInner> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
-        // TODO: Fix this
-        write!(f, "{:?}", self.table.lock().unwrap().rows[self.row])
+        Ok(RowTable::from_rows(columns, rows))
     }
-}
 
+    /// Creates a new `RowTable` with every row of this table followed by every row of `other`, the cartesian product,
+    /// such as for a grid of parameters.
+    ///
+    /// Columns of `other` whose names clash with a column of this table have `suffix` appended, as with
+    /// [`hconcat`](#method.hconcat), so it must not be empty when any do. Returns an error, without building anything, if the product would have more than
+    /// `max_rows` rows.
+    pub fn cross_join<T: TableOperations>(&self, other :&T, suffix :&str, max_rows :usize) -> Result<RowTable, TableError> {
+        let (len, other_len) = (self.len(), other.len());
+
+        match len.checked_mul(other_len) {
+            Some(rows) if rows <= max_rows => (),
+            _ => {
+                let err_str = format!("Cross join of {} and {} rows is more than the limit of {} rows", len, other_len, max_rows);
+                return Err(TableError::new(err_str.as_str()));
+            }
+        }
 
-// Iterator for RowTable
-//https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=856142d55cbca5cfda7bc97a744a0c4e - Iterator/Row Playground
-
-/// `Iterator` for rows in a table.
-pub struct RowTableIter {
-    table: Arc<Mutex<RowTableInner>>,
-    column_map: Arc<Vec<(String, usize)>>,
-    cur_pos: usize
-}
+        let other_columns = other.columns();
+        let columns = side_by_side_columns(self.columns(), &other_columns, suffix)?;
+        let other_rows = other.iter().map(|b| other_columns.iter().map(|c| b.get(c)).collect::<Vec<_>>()).collect::<Vec<_>>();
+        let mut rows = Vec::with_capacity(len * other_len);
 
-impl Iterator for RowTableIter {
-    type Item=RowSlice<RowTableInner>;
+        for a in self.iter() {
+            let row = a.columns().iter().map(|c| a.get(c)).collect::<Vec<_>>();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.cur_pos >= self.table.lock().unwrap().rows.len() {
-            None
-        } else {
-            self.cur_pos += 1;
-            Some(RowSlice {
-                table: self.table.clone(),
-                column_map: self.column_map.clone(),
-                row: self.cur_pos-1
-            })
+            for other_row in other_rows.iter() {
+                rows.push(row.iter().chain(other_row.iter()).cloned().collect());
+            }
         }
+
+        Ok(RowTable::from_rows(columns, rows))
     }
-}
 
-//impl IntoIterator for RowTable {
-//    type Item=OwnedRow;
-//    type IntoIter=RowTableIntoIter;
-//
-//    fn into_iter(self) -> Self::IntoIter {
-//        let columns = self.0.lock().unwrap().columns.clone();
-//
-//        RowTableIntoIter{ columns: Arc::new(columns), iter: self.0.lock().unwrap().rows.into_iter() }
-//    }
-//}
+    /// Adds `columns` of the lookup table `other` to this table, taking each row's values from the row of `other` with
+    /// the same value in the `key` column, like a spreadsheet's VLOOKUP. Rows with no match, or an empty key, get
+    /// `Value::Empty`.
+    ///
+    /// This is cheaper than a join when `other` is a small dimension table, such as the name and sector of each
+    /// symbol: the lookup is built once, and the columns are filled in parallel. The keys of `other` must be unique.
+    pub fn enrich<T: TableOperations>(&mut self, other :&T, key :&str, columns :&[&str]) -> Result<(), TableError> {
+        let pos = self.column_position(key)?;
+        let other_pos = other.column_position(key)?;
+        let positions = columns.iter().map(|c| other.column_position(c)).collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(column) = columns.iter().find(|c| self.column_position(c).is_ok()) {
+            let err_str = format!("Attempting to add duplicate column: {} already exists", column);
+            return Err(TableError::new(err_str.as_str()));
+        }
 
-#[derive(Clone)]
-pub struct RowTableSlice {
-    column_map: Arc<Vec<(String, usize)>>,   // mapping of column names to row offsets
-    rows: Arc<Vec<usize>>,                   // index of the corresponding row in the Table
-    table: Arc<Mutex<RowTableInner>>       // reference to the underlying table
-}
+        let mut lookup = HashMap::new();
 
-impl Display for RowTableSlice {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
-        for row in self.rows.iter() {
-            writeln!(f, "{:?}", self.table.lock().unwrap().rows[*row]);
+        if let Some(index) = other.unique_index(key) {
+            // the keys are known to be unique, so read the rows in one pass and look up their values directly
+            let mut rows = other.iter().map(|row| positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()).collect::<Result<Vec<_>, _>>()?;
+
+            for (key_value, &i) in index.iter() {
+                lookup.insert(key_value.clone(), std::mem::take(&mut rows[i]));
+            }
+        } else {
+            for row in other.iter() {
+                let key_value = row.try_at(other_pos)?;
+
+                if key_value == Value::Empty {
+                    continue;
+                }
+
+                let values = positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+
+                if lookup.insert(key_value.clone(), values).is_some() {
+                    let err_str = format!("Key {} repeats in the lookup table, in column {}", key_value, key);
+                    return Err(TableError::new(err_str.as_str()));
+                }
+            }
         }
 
+        let missing = vec![Value::Empty; columns.len()];
+        let mut inner = self.0.lock().unwrap();
+
+        inner.columns.extend(columns.iter().map(|c| c.to_string()));
+        inner.stats = None;
+
+        parallel::for_each_mut(&mut inner.rows, |row| {
+            let values = lookup.get(&row[pos]).unwrap_or(&missing);
+
+            row.extend(values.iter().cloned());
+        });
+
         Ok( () )
     }
-}
 
+    /// Read in a CSV file in chunks of `chunk_rows` rows, without loading the whole file into memory
+    pub fn from_csv_chunked<P: AsRef<Path>>(path :P, chunk_rows :usize) -> Result<RowTableChunks<File>, IOError> {
+        if chunk_rows == 0 {
+            return Err(IOError::new(ErrorKind::InvalidInput, "Chunks must have at least 1 row"));
+        }
 
-impl TableOperations for RowTableSlice {
-    type TableSliceType = RowTableSlice;
-    type RowType = RowSlice<RowTableInner>;
-    type Iter = RowTableSliceIter;
+        let mut csv = Reader::from_path(path)?;
+        let columns = read_columns(&mut csv)?;
 
-    fn iter(&self) -> RowTableSliceIter{
-        RowTableSliceIter {
-            column_map: self.column_map.clone(),
-            rows: self.rows.clone(),
-            table: self.table.clone(),
-            cur_pos: 0
-        }
+        Ok(RowTableChunks { csv, columns, chunk_rows, done: false })
     }
 
-    fn get(&self, index :usize) -> Result<Self::RowType, TableError> {
-        if index >= self.len() {
-            let err_str = format!("Index {} is beyond table length {}", index, self.len());
-            return Err(TableError::new(err_str.as_str()));
+    /// Read in a CSV file whose rows might not have as many fields as the header, handling them according to `policy`.
+    ///
+    /// Other loaders stop with an error at the first such row.
+    pub fn from_csv_ragged<P: AsRef<Path>>(path :P, policy :RaggedRowPolicy) -> Result<Self, IOError> {
+        let mut csv = ReaderBuilder::new().flexible(true).from_path(path)?;
+        let columns = read_columns(&mut csv)?;
+        let mut rows = Vec::new();
+        let mut record = StringRecord::new();
+
+        while csv.read_record(&mut record).map_err(|e| IOError::new(ErrorKind::Other, e))? {
+            let mut row = record.iter().map(Value::new).collect::<Vec<_>>();
+
+            match policy {
+                _ if row.len() == columns.len() => (),
+                RaggedRowPolicy::Pad if row.len() < columns.len() => row.resize(columns.len(), Value::Empty),
+                RaggedRowPolicy::Skip => continue,
+                _ => {
+                    let line = record.position().map(|p| p.line()).unwrap_or_default();
+                    let err_str = format!("Line {}: expected {} fields, found {}", line, columns.len(), row.len());
+                    return Err(IOError::new(ErrorKind::InvalidData, err_str.as_str()));
+                }
+            }
+
+            rows.push(row);
         }
 
-        Ok(RowSlice {
-            column_map: self.column_map.clone(),
-            table: self.table.clone(),
-            row: self.rows[index]
-        })
+        rows.shrink_to_fit();
+
+        Ok(RowTable::from_rows(columns, rows))
     }
 
-    #[inline]
-    fn columns(&self) -> Vec<String> {
-        self.column_map.iter().map(|(c,i)| c.clone()).collect()
+    /// Read in a CSV file, parsing each column with the type at the same position in `schema`.
+    ///
+    /// Loading stops with an error at the first value that doesn't parse; see
+    /// [`from_csv_with_schema_policy`](#method.from_csv_with_schema_policy) to skip or empty those instead.
+    pub fn from_csv_with_schema<P: AsRef<Path>>(path :P, schema :&[ValueType]) -> Result<Self, IOError> {
+        RowTable::from_csv_with_schema_policy(path, schema, ParseErrorPolicy::Error)
     }
 
-    fn filter_by<P: FnMut(&RowSlice<RowTableInner>) -> bool>(&self, mut predicate: P) -> Result<RowTableSlice, TableError> {
-        let mut slice_rows = Vec::new();
+    /// Read in a CSV file like [`from_csv_with_schema`](#method.from_csv_with_schema), handling values that don't
+    /// parse according to `policy`.
+    pub fn from_csv_with_schema_policy<P: AsRef<Path>>(path :P, schema :&[ValueType], policy :ParseErrorPolicy) -> Result<Self, IOError> {
+        let mut csv = Reader::from_path(path)?;
+        let columns = read_columns(&mut csv)?;
 
-        for &row_index in self.rows.iter() {
-            let row = RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: row_index };
+        if columns.len() != schema.len() {
+            let err_str = format!("Column count and schema length do not match: {} != {}", columns.len(), schema.len());
+            return Err(IOError::new(ErrorKind::InvalidInput, err_str.as_str()));
+        }
 
-            // run the predicate against the row
-            if predicate(&row) {
-                slice_rows.push(row_index);
+        let types = schema.iter().cloned().map(Some).collect::<Vec<_>>();
+
+        RowTable::from_typed_records(csv, columns, types, &[], policy)
+    }
+
+    /// Read in a CSV file, parsing the columns named in `schema` with their types, and inferring the others.
+    ///
+    /// Values that don't parse are handled according to `policy`.
+    pub fn from_csv_typed<P: AsRef<Path>>(path :P, schema :&Schema, policy :ParseErrorPolicy) -> Result<Self, IOError> {
+        let mut csv = Reader::from_path(path)?;
+        let columns = read_columns(&mut csv)?;
+        let types = schema.types_for(&columns)?;
+
+        RowTable::from_typed_records(csv, columns, types, schema.null_values(), policy)
+    }
+
+    fn from_typed_records<R: Read>(mut csv :Reader<R>, columns :Vec<String>, types :Vec<Option<ValueType>>, nulls :&[String], policy :ParseErrorPolicy) -> Result<Self, IOError> {
+        let mut rows = Vec::new();
+        let mut record = StringRecord::new();
+
+        while csv.read_record(&mut record).map_err(|e| IOError::new(ErrorKind::Other, e))? {
+            if let Some(row) = parse_record(&record, &types, nulls, policy)? {
+                rows.push(row);
             }
         }
 
-        Ok(RowTableSlice {
-            column_map: self.column_map.clone(),
-            table: self.table.clone(),
-            rows: Arc::new(slice_rows),
-        })
+        // shrink the vector down so we're not chewing up more memory than needed
+        rows.shrink_to_fit();
+
+        Ok(RowTable::from_typed_rows(columns, rows, types))
     }
 
-    fn split_rows_at(&self, mid: usize) -> Result<(Self::TableSliceType, Self::TableSliceType), TableError> {
-        if mid >= self.rows.len() {
-            let err_str = format!("Midpoint too large: {} >= {}", mid, self.rows.len());
-            return Err(TableError::new(err_str.as_str()));
-        }
+    /// Returns a rayon parallel iterator over the rows of the table.
+    ///
+    /// It runs in the current rayon thread pool; use it inside [`install`](fn.install.html) to run it in the pool set
+    /// with `set_parallelism`. Each value read from a row locks the table, so the threads take turns reading; it suits
+    /// work that takes longer than reading the values, while [`par_map`](#method.par_map) reads them in parallel.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item=RowSlice<RowTableInner>> {
+        let column_map :Arc<Vec<(String, usize)>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect());
+        let table = self.0.clone();
 
-        Ok( (
-            RowTableSlice { column_map: self.column_map.clone(), rows: Arc::new((0..mid).collect()), table: self.table.clone() },
-            RowTableSlice { column_map: self.column_map.clone(), rows: Arc::new((mid..self.rows.len()).collect()), table: self.table.clone() }
-            )
-        )
+        (0..self.len()).into_par_iter().map(move |row| RowSlice { column_map: column_map.clone(), table: table.clone(), row })
+    }
+
+    /// Like `filter_by`, but calls `predicate` on the rows in parallel, with the values of each row in the order of
+    /// `columns`, so look up the positions it needs with `column_position` first; the table is locked once, rather than
+    /// for each value. The rows of the slice are still in the order of this table.
+    pub fn par_filter_by<P: Fn(&[Value]) -> bool + Sync + Send>(&self, predicate :P) -> Result<RowTableSlice, TableError> {
+        let inner = self.0.lock().unwrap();
+        let column_map :Arc<Vec<(String, usize)>> = Arc::new(inner.columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect());
+        let slice_rows = parallel::filter_indices(&inner.rows, |row| predicate(row));
+
+        Ok(RowTableSlice { column_map, rows: Arc::new(slice_rows), table: self.0.clone() })
+    }
+
+    /// Calls `f` on the rows in parallel, with the values of each row in the order of `columns`, returning the results
+    /// in the order of the rows; like `par_filter_by`, the table is locked once, rather than for each value, so don't
+    /// use the table from `f`.
+    pub fn par_map<R: Send, F: Fn(&[Value]) -> R + Sync + Send>(&self, f :F) -> Vec<R> {
+        let inner = self.0.lock().unwrap();
+
+        parallel::map(&inner.rows, |row| f(row))
     }
 }
 
-impl TableSlice for RowTableSlice {
-    fn sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, mut compare: F) -> Result<Self::TableSliceType, TableError> {
-        let mut rows = self.rows.iter().cloned().collect::<Vec<_>>();
+/// What a loader does with a value that doesn't parse as the type of its column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorPolicy {
+    /// Stop loading, and return the error
+    Error,
+    /// Leave the row out of the table
+    SkipRow,
+    /// Use `Value::Empty` for the value
+    Empty
+}
 
-        rows.sort_unstable_by(|&a, &b| {
-            let a_row = RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: a };
-            let b_row = RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: b };
+/// What [`RowTable::from_csv_ragged`](struct.RowTable.html#method.from_csv_ragged) does with a row that doesn't have as
+/// many fields as the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaggedRowPolicy {
+    /// Stop loading, and return the error
+    Error,
+    /// Leave the row out of the table
+    Skip,
+    /// Fill in the missing values of a short row with `Value::Empty`; a row with extra fields is still an error
+    Pad
+}
 
-            compare(a_row, b_row)
-        });
+/// Reads the headers from the CSV file, making sure there are no duplicates
+fn read_columns<R: Read>(csv :&mut Reader<R>) -> Result<Vec<String>, IOError> {
+    let columns = csv.headers()?.iter().map(String::from).collect::<Vec<_>>();
 
-        Ok(RowTableSlice {
-            column_map: self.column_map.clone(),
-            rows: Arc::new(rows),
-            table: self.table.clone()
-        })
+    if columns.iter().collect::<HashSet<_>>().len() != columns.len() {
+        return Err(IOError::new(ErrorKind::InvalidData, "Duplicate columns detected in the file"));
     }
 
-//    fn stable_sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, compare: F) -> Result<Self::TableSliceType, TableError> {
-//        unimplemented!()
-//        let columns = self.columns.clone();
-//        let table = self.table.clone();
-//
-//        Ok(self.rows.sort_by(|a, b| {
-//            let a_row = RowSlice<RowTableInner> { columns: &columns, row: &table.lock().unwrap().rows[*a] };
-//            let b_row = RowSlice<RowTableInner> { columns: &columns, row: &table.lock().unwrap().rows[*b] };
-//
-//            compare(a_row, b_row)
-//        }))
-//    }
+    Ok(columns)
+}
 
-    fn rename_column(&self, old_col: &str, new_col: &str) -> Result<Self::TableSliceType, TableError> {
-        let pos = TableSlice::column_position(self, old_col)?;
+/// Reads up to `count` records from the CSV file, inferring the type of each value
+fn read_rows<R: Read>(csv :&mut Reader<R>, count :usize, nulls :&[String]) -> Result<Vec<Vec<Value>>, IOError> {
+    let batch = csv.records().take(count).collect::<Result<Vec<_>, _>>().map_err(|e| IOError::new(ErrorKind::Other, e))?;
 
-        let new_val = (new_col.to_string(), self.column_map[pos].1);
+    // inferring the type of each value is the slow part, so do it in parallel
+    Ok(parallel::map(&batch, |record| record.iter().map(|s| if nulls.iter().any(|n| n == s) { Value::Empty } else { Value::new(s) }).collect::<Vec<_>>()))
+}
 
-        let mut new_column_map = Vec::with_capacity(self.column_map.len());
+/// Reads up to `count` records from the CSV file, parsing each value with the type of its column, when known, and
+/// reading the `nulls` tokens as empty values
+fn read_typed_rows<R: Read>(csv :&mut Reader<R>, count :usize, types :&[Option<ValueType>], nulls :&[String]) -> Result<Vec<Vec<Value>>, IOError> {
+    let batch = csv.records().take(count).collect::<Result<Vec<_>, _>>().map_err(|e| IOError::new(ErrorKind::Other, e))?;
 
-        for i in 0..self.column_map.len() {
-            if i == pos {
-                new_column_map.push((new_col.to_string(), self.column_map[i].1));
-            } else {
-                new_column_map.push(self.column_map[i].clone());
+    parallel::map(&batch, |record| parse_record(record, types, nulls, ParseErrorPolicy::Error))
+        .into_iter()
+        .filter_map(|row| row.transpose())
+        .collect()
+}
+
+/// Parses the values of a record with the type of its column, when known, returning `None` if the row is skipped
+/// Whether `value` is empty, or one that parsing with `value_type` gives
+fn holds(value_type :&ValueType, value :&Value) -> bool {
+    matches!((value_type, value),
+        (_, Value::Empty) | (ValueType::String, Value::String(_)) | (ValueType::Boolean, Value::Boolean(_)) | (ValueType::Duration, Value::Duration(_)) |
+        (ValueType::DateTime | ValueType::DateTimeFormat(_), Value::DateTime(_)) |
+        (ValueType::Date | ValueType::DateFormat(_), Value::Date(_)) |
+        (ValueType::Time | ValueType::TimeFormat(_), Value::Time(_)) |
+        (ValueType::Integer | ValueType::IntegerFormat(_), Value::Integer(_)) |
+        (ValueType::Float | ValueType::FloatFormat(_) | ValueType::Currency | ValueType::CurrencyFormat(_) | ValueType::Percent | ValueType::PercentFormat(_), Value::Float(_)) |
+        (ValueType::Number | ValueType::NumberFormat(_), Value::Integer(_) | Value::Float(_)))
+}
+
+fn parse_record(record :&StringRecord, types :&[Option<ValueType>], nulls :&[String], policy :ParseErrorPolicy) -> Result<Option<Vec<Value>>, IOError> {
+    let mut row = Vec::with_capacity(record.len());
+
+    for (i, s) in record.iter().enumerate() {
+        match Value::with_type_or_infer(s, types.get(i).and_then(|t| t.as_ref()), nulls) {
+            Ok(value) => row.push(value),
+            Err(_) if policy == ParseErrorPolicy::Empty => row.push(Value::Empty),
+            Err(_) if policy == ParseErrorPolicy::SkipRow => return Ok(None),
+            Err(e) => {
+                let line = record.position().map(|p| p.line()).unwrap_or_default();
+                let err_str = format!("Line {}: {}", line, e);
+                return Err(IOError::new(ErrorKind::InvalidData, err_str.as_str()));
             }
         }
-
-        Ok( RowTableSlice {
-            column_map: Arc::new(new_column_map),
-            rows: self.rows.clone(),
-            table: self.table.clone()
-        })
     }
 
+    Ok(Some(row))
 }
 
-/// Reference `Iterator` for rows in a table.
-pub struct RowTableSliceIter {
-    column_map: Arc<Vec<(String, usize)>>,
-    rows: Arc<Vec<usize>>,
-    table: Arc<Mutex<RowTableInner>>,
-    cur_pos: usize
+/// `Iterator` over the chunks of a CSV file, created with [`RowTable::from_csv_chunked`](struct.RowTable.html#method.from_csv_chunked).
+pub struct RowTableChunks<R: Read> {
+    csv: Reader<R>,
+    columns: Vec<String>,
+    chunk_rows: usize,
+    done: bool
 }
 
-impl Iterator for RowTableSliceIter {
-    type Item=RowSlice<RowTableInner>;
+impl <R: Read> Iterator for RowTableChunks<R> {
+    type Item=Result<RowTable, IOError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.cur_pos >= self.rows.len() {
-            None
-        } else {
-            self.cur_pos += 1;
-            let row_index = self.rows[self.cur_pos-1];
+        if self.done {
+            return None;
+        }
 
-            Some(RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: row_index})
+        match read_rows(&mut self.csv, self.chunk_rows, &[]) {
+            Ok(rows) if rows.is_empty() => {
+                self.done = true;
+                None
+            },
+            Ok(rows) => Some(Ok(RowTable::from_rows(self.columns.clone(), rows))),
+            Err(e) => {
+                // stop after the first error
+                self.done = true;
+                Some(Err(e))
+            }
         }
     }
 }
 
+/// A table that [`concat`](fn.concat.html) can take the rows of. It's implemented for every `TableOperations`, and
+/// is its own trait so tables of different types, such as a `RowTable` and an `MMapTable`, can be passed together.
+pub trait Concat {
+    /// The columns of the table
+    fn concat_columns(&self) -> Vec<String>;
 
-#[cfg(test)]
-mod tests {
-    use crate::{RowTable, TableOperations, Table, Row, Value};
+    /// The rows of the table with values in the order of `columns`, and `Value::Empty` for missing columns
+    fn concat_rows(&self, columns :&[String]) -> Vec<Vec<Value>>;
+}
 
-    #[test]
-    fn to_from_csv() {
-        let mut table :RowTable = RowTable::new(&["B"]);
+impl <T: TableOperations> Concat for T {
+    fn concat_columns(&self) -> Vec<String> {
+        self.columns()
+    }
 
-        table.filter_by(|r| { r.get("B"); true });
-//        table.find_by(|r| { r.set("B", Value::Integer(7)); true });
-        table.update_by(|r| { r.set("B", Value::Integer(7));} );
+    fn concat_rows(&self, columns :&[String]) -> Vec<Vec<Value>> {
+        aligned_rows(columns, self)
+    }
+}
+
+/// Concatenates the rows of `tables`, which can be of different types, into a new `RowTable`.
+///
+/// The columns of the result are the columns of all the tables, in the order they're first seen.
+/// Rows from a table without one of those columns get `Value::Empty` for it.
+pub fn concat(tables :&[&dyn Concat]) -> Result<RowTable, TableError> {
+    if tables.is_empty() {
+        return Err(TableError::new("No tables passed to concat"));
+    }
+
+    let columns = union_columns(tables.iter().map(|t| t.concat_columns()));
+    let mut rows = Vec::new();
+
+    for table in tables {
+        rows.extend(table.concat_rows(&columns));
+    }
+
+    Ok(RowTable::from_rows(columns, rows))
+}
+
+/// All of the columns, in the order they're first seen
+fn union_columns<I: Iterator<Item=Vec<String>>>(column_lists :I) -> Vec<String> {
+    let mut columns = Vec::new();
+
+    for column in column_lists.flatten() {
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+
+    columns
+}
+
+/// The rows of `table` with values in the order of `columns`, and `Value::Empty` for missing columns
+fn aligned_rows<T: TableOperations>(columns :&[String], table :&T) -> Vec<Vec<Value>> {
+    table.iter().map(|row| {
+        columns.iter().map(|c| row.try_get(c).unwrap_or(Value::Empty)).collect::<Vec<_>>()
+    }).collect()
+}
+
+/// The columns of two tables side-by-side, with `suffix` appended to the columns of the second that clash with the
+/// first; an empty suffix can't make a clashing name unique, so it's an error
+fn side_by_side_columns(mut columns :Vec<String>, other_columns :&[String], suffix :&str) -> Result<Vec<String>, TableError> {
+    for column in other_columns.iter() {
+        let mut column = column.clone();
+
+        while columns.contains(&column) {
+            if suffix.is_empty() {
+                let err_str = format!("Column {} is in both tables, and the suffix to tell them apart is empty", column);
+                return Err(TableError::new(err_str.as_str()));
+            }
+
+            column.push_str(suffix);
+        }
+
+        columns.push(column);
+    }
+
+    Ok(columns)
+}
+
+/// The rows of `table` with a value in the `pos` column, keyed by it, sorted by it and keeping the last row of each
+fn time_series<T: TableOperations>(table :&T, pos :usize) -> Result<Vec<(Value, Vec<Value>)>, TableError> {
+    let mut series = Vec::with_capacity(table.len());
+
+    for row in table.iter() {
+        let values = (0..table.width()).map(|p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+
+        if values[pos] != Value::Empty {
+            series.push( (values[pos].clone(), values) );
+        }
+    }
+
+    // stable, so the last row of each time is last among the rows with that time
+    series.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut deduped :Vec<(Value, Vec<Value>)> = Vec::with_capacity(series.len());
+
+    for entry in series {
+        match deduped.last_mut() {
+            Some(last) if last.0 == entry.0 => *last = entry,
+            _ => deduped.push(entry)
+        }
+    }
+
+    Ok(deduped)
+}
+
+/// A row of `series` for each of the sorted `times`, filled with `fill` when it has no row at the time, with the time
+/// in the `pos` column
+fn align_series(series :&[(Value, Vec<Value>)], pos :usize, times :&[Value], fill :Fill, width :usize) -> Vec<Vec<Value>> {
+    times.iter().map(|time| {
+        // the first row at or after the time
+        let i = series.partition_point(|(t, _)| t < time);
+
+        let found = match series.get(i) {
+            Some((t, values)) if t == time => Some(values),
+            _ => match fill {
+                Fill::Empty => None,
+                Fill::FFill => i.checked_sub(1).map(|i| &series[i].1),
+                Fill::BFill => series.get(i).map(|(_, values)| values)
+            }
+        };
+
+        let mut row = found.cloned().unwrap_or_else(|| vec![Value::Empty; width]);
+
+        row[pos] = time.clone();
+        row
+    }).collect()
+}
+
+impl Table for RowTable {
+    /// Appends the rows of `table`, which must have the same columns, in the same order. When `table` is a `RowTable`
+    /// that isn't shared with another handle, its rows are moved instead of copied.
+    fn append(&mut self, table :impl TableOperations) -> Result<(), TableError> {
+        let columns = self.columns();
+
+        if columns != table.columns() {
+            let err_str = format!("Columns don't match between tables: {:?} != {:?}", columns, table.columns());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let rows = match table.into_rows() {
+            Ok(rows) => rows,
+            Err(table) => aligned_rows(&columns, &table)
+        };
+
+        let mut inner = self.0.lock().unwrap();
+
+        inner.check_unique_rows(&rows)?;
+
+        let start = inner.rows.len();
+
+        for (i, row) in rows.iter().enumerate() {
+            for (pos, value) in row.iter().enumerate() {
+                inner.reindex(start + i, pos, &Value::Empty, value);
+                inner.check_type(pos, value);
+            }
+        }
+
+        inner.stats = None;
+        inner.rows.extend(rows);
+
+        Ok( () )
+    }
+
+    fn update_by<F: FnMut(&mut Self::RowType)>(&mut self, mut update: F) {
+        for mut row in self.iter() {
+            update(&mut row);
+        }
+    }
+
+    fn append_row<R>(&mut self, row: R) -> Result<(), TableError>  where R: Row {
+        // go through each column, and get the corresponding column from the row
+        let mut row_vec = Vec::new();
+
+        // the row can be one of this table's, so don't hold the lock while reading it
+        let columns = self.0.lock().unwrap().columns.clone();
+
+        for column in columns.iter() {
+            let val = row.try_get(column);
+
+            if let Err(e) = val {
+                return Err(e);
+            }
+
+            row_vec.push(val.unwrap());
+        }
+
+        // lock, like `append`, as slices and rows of the table can share it
+        let mut inner = self.0.lock().unwrap();
+        let row = inner.rows.len();
+
+        for (pos, value) in row_vec.iter().enumerate() {
+            inner.check_unique(row, pos, value)?;
+        }
+
+        for (pos, value) in row_vec.iter().enumerate() {
+            inner.reindex(row, pos, &Value::Empty, value);
+            inner.check_type(pos, value);
+        }
+
+        inner.stats = None;
+        inner.rows.push(row_vec);
+
+        Ok( () )
+    }
+
+    fn add_column(&mut self, column_name :&str, value :&Value) -> Result<(), TableError> {
+        // make sure we're not duplicating column names
+        if self.column_position(column_name).is_ok() {
+            let err_str = format!("Attempting to add duplicate column: {} already exists", column_name);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let mut inner = self.0.lock().unwrap();
+
+        inner.columns.push(String::from(column_name));
+        inner.stats = None;
+
+        parallel::for_each_mut(&mut inner.rows, |row| row.push(value.clone()));
+
+        Ok( () )
+    }
+
+    /// Maps the values in place, in parallel.
+    fn map_column<F: Fn(&Value) -> Value + Sync + Send>(&mut self, column :&str, f :F) -> Result<(), TableError> {
+        let pos = self.column_position(column)?;
+
+        let mut inner = self.0.lock().unwrap();
+
+        if inner.unique.iter().any(|k| k.pos == pos) {
+            // check the new values are still unique before changing anything
+            let values = inner.rows.iter().map(|row| f(&row[pos])).collect::<Vec<_>>();
+            let rows = Arc::new(unique_rows(values.iter(), column)?);
+
+            for (row, value) in inner.rows.iter_mut().zip(values) {
+                row[pos] = value;
+            }
+
+            inner.unique.iter_mut().filter(|k| k.pos == pos).for_each(|k| k.rows = rows.clone());
+            inner.stats = None;
+            inner.forget_type(pos);
+
+            return Ok( () );
+        }
+
+        inner.stats = None;
+        inner.forget_type(pos);
+
+        parallel::for_each_mut(&mut inner.rows, |row| row[pos] = f(&row[pos]));
+
+        Ok( () )
+    }
+
+    fn add_column_with<F: FnMut() -> Value>(&mut self, column_name :&str, mut f :F) -> Result<(), TableError> {
+        // make sure we're not duplicating column names
+        if let Ok(_) = self.column_position(column_name) {
+            let err_str = format!("Attempting to add duplicate column: {} already exists", column_name);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let mut inner = self.0.lock().unwrap();
+
+        // add the column name to our list of columns
+        inner.columns.push(String::from(column_name));
+        inner.stats = None;
+
+        // add the default value for the column
+        inner.rows.iter_mut().for_each(|row| row.push(f()));
+
+        Ok( () )
+    }
+
+    fn rename_column(&mut self, old_col :&str, new_col :&str) -> Result<(), TableError> {
+        let pos = self.column_position(old_col)?;
+
+        self.0.lock().unwrap().columns[pos] = new_col.to_string();
+
+        Ok( () )
+    }
+}
+
+impl Display for RowTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        write!(f, "{}", self.preview(DISPLAY_ROWS, DISPLAY_WIDTH))
+    }
+}
+
+impl TableOperations for RowTable {
+    type TableSliceType = RowTableSlice;
+    type RowType = RowSlice<RowTableInner>;
+    type Iter = RowTableIter;
+
+    fn iter(&self) -> RowTableIter {
+        let table = self.0.lock().unwrap();
+
+        RowTableIter {
+            table: self.0.clone(),
+            column_map: Arc::new(table.columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
+            cur_pos: 0,
+            end: table.rows.len()
+        }
+    }
+
+    /// The index is the vector of rows, and the values are measured without reading them through a `Row`.
+    fn memory_usage(&self) -> MemoryUsage {
+        let inner = self.0.lock().unwrap();
+        let mut columns = inner.columns.iter().map(|c| (c.clone(), 0)).collect::<Vec<_>>();
+
+        for row in inner.rows.iter() {
+            for (i, value) in row.iter().enumerate() {
+                columns[i].1 += value.mem_size();
+            }
+        }
+
+        MemoryUsage { index: inner.rows.capacity() * std::mem::size_of::<Vec<Value>>(), mapped: 0, columns }
+    }
+
+    fn get(&self, index :usize) -> Result<Self::RowType, TableError> {
+        if index >= self.len() {
+            let err_str = format!("Index {} is beyond table length {}", index, self.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok(RowSlice {
+            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
+            table: self.0.clone(),
+            row: index
+        })
+    }
+
+    #[inline]
+    fn columns(&self) -> Vec<String> {
+        self.0.lock().unwrap().columns.clone()
+    }
+
+    fn column_stats(&self, column :&str) -> Option<ColumnStats> {
+        let pos = self.column_position(column).ok()?;
+
+        self.0.lock().unwrap().stats.as_ref().map(|stats| stats[pos].clone())
+    }
+
+    fn declared_type(&self, column :&str) -> Option<ValueType> {
+        let pos = self.column_position(column).ok()?;
+
+        self.0.lock().unwrap().types.get(pos).cloned().flatten()
+    }
+
+    fn into_rows(self) -> Result<Vec<Vec<Value>>, Self> {
+        match Arc::try_unwrap(self.0) {
+            Ok(inner) => Ok(inner.into_inner().unwrap().rows),
+            Err(table) => Err(RowTable(table))
+        }
+    }
+
+    fn unique_index(&self, column :&str) -> Option<Arc<HashMap<Value, usize>>> {
+        let pos = self.column_position(column).ok()?;
+
+        self.0.lock().unwrap().unique.iter().find(|k| k.pos == pos).map(|k| k.rows.clone())
+    }
+
+    fn group_by(&self, column: &str) -> Result<HashMap<Value, RowTableSlice>, TableError> {
+        // get the position in the row we're concerned with
+        let pos = self.column_position(column)?;
+
+        // group the rows by their value in the column
+        let row_map = parallel::group_indices(&self.0.lock().unwrap().rows, |row| row[pos].clone());
+
+        let column_map :Arc<Vec<(String, usize)>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect());
+
+        Ok(row_map.into_iter().map(|(k, v)| (k, RowTableSlice {
+            column_map: column_map.clone(),
+            rows: Arc::new(v),
+            table: self.0.clone()
+        })).collect())
+    }
+
+    fn group_by_multi(&self, columns :&[&str]) -> Result<HashMap<Vec<Value>, RowTableSlice>, TableError> {
+        if columns.is_empty() {
+            return Err(TableError::new("Grouping needs at least one column"));
+        }
+
+        let positions = columns.iter().map(|c| self.column_position(c)).collect::<Result<Vec<_>, _>>()?;
+
+        // group the rows by their values in the columns
+        let row_map = parallel::group_indices(&self.0.lock().unwrap().rows, |row| positions.iter().map(|&p| row[p].clone()).collect::<Vec<_>>());
+
+        let column_map :Arc<Vec<(String, usize)>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect());
+
+        Ok(row_map.into_iter().map(|(k, v)| (k, RowTableSlice {
+            column_map: column_map.clone(),
+            rows: Arc::new(v),
+            table: self.0.clone()
+        })).collect())
+    }
+
+    fn filter(&self, column :&str, value :&Value) -> Result<RowTableSlice, TableError> {
+        // get the position in the row we're concerned with
+        let pos = self.column_position(column)?;
+
+        let slice_rows = parallel::filter_indices(&self.0.lock().unwrap().rows, |row| row[pos] == *value);
+
+        Ok(RowTableSlice {
+            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
+            rows: Arc::new(slice_rows),
+            table: self.0.clone()
+        })
+    }
+
+    fn filter_by<P: FnMut(&RowSlice<RowTableInner>) -> bool>(&self, mut predicate :P) -> Result<RowTableSlice, TableError> {
+        let mut slice_rows = Vec::new();
+
+        for (i, row) in self.iter().enumerate() {
+            if predicate(&row) {
+                slice_rows.push(i);
+            }
+        }
+
+        Ok(RowTableSlice {
+            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
+            rows: Arc::new(slice_rows),
+            table: self.0.clone()
+        })
+    }
+
+//    fn sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, mut compare: F) -> Result<RowTableSlice, TableError> {
+//        let column_map :Arc<HashMap<String, usize>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i,s)| (s.clone(), i)).collect());
+//
+//        let slice = RowTableSlice {
+//            column_map,
+//            rows: Arc::new((0..self.len()).collect()),
+//            table: self.0.clone()
+//        };
+//
+//        slice.sort_by(compare)
+//    }
+//
+//    fn stable_sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, mut compare: F) -> Result<Self::TableSliceType, TableError> {
+//        let column_map :Arc<HashMap<String, usize>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i,s)| (s.clone(), i)).collect());
+//
+//        let slice = RowTableSlice {
+//            column_map,
+//            rows: Arc::new((0..self.len()).collect()),
+//            table: self.0.clone()
+//        };
+//
+//        slice.stable_sort_by(compare)
+//    }
+
+    fn split_rows_at(&self, mid: usize) -> Result<(Self::TableSliceType, Self::TableSliceType), TableError> {
+        let len = self.len();
+
+        if mid >= len {
+            let err_str = format!("Midpoint too large: {} >= {}", mid, len);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        // build the column map once, the table can't be locked twice in the same expression
+        let column_map :Arc<Vec<(String, usize)>> = Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect());
+
+        Ok( (
+            RowTableSlice {
+                column_map: column_map.clone(),
+                rows: Arc::new((0..mid).collect::<Vec<_>>()),
+                table: self.0.clone()
+            },
+            RowTableSlice {
+                column_map,
+                rows: Arc::new((mid..len).collect::<Vec<_>>()),
+                table: self.0.clone()
+            }
+            )
+        )
+    }
+
+    fn take_rows(&self, indices :&[usize]) -> Result<Self::TableSliceType, TableError> {
+        let len = self.len();
+
+        if let Some(index) = indices.iter().find(|&&i| i >= len) {
+            let err_str = format!("Index {} is beyond table length {}", index, len);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok(RowTableSlice {
+            column_map: Arc::new(self.0.lock().unwrap().columns.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect()),
+            rows: Arc::new(indices.to_vec()),
+            table: self.0.clone()
+        })
+    }
+}
+
+
+impl Row for RowSlice<RowTableInner> {
+    fn try_get(&self, column: &str) -> Result<Value, TableError> {
+        let pos = self.column_map.iter().position(|(c, i)| c == column);
+
+        if pos.is_none() {
+            let err_str = format!("Could not find column in RowSlice: {}", column);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        self.try_at(pos.unwrap())
+    }
+
+    fn try_at(&self, index :usize) -> Result<Value, TableError> {
+        self.with_ref(index, |v| v.into_value())
+    }
+
+    fn with_ref<T, F: FnOnce(ValueRef<'_>) -> T>(&self, index :usize, f :F) -> Result<T, TableError> {
+        if index >= self.column_map.len() {
+            let err_str = format!("Index {} is beyond row width {}", index, self.column_map.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let table = self.table.lock().unwrap();
+
+        match table.rows.get(self.row).and_then(|row| row.get(self.column_map[index].1)) {
+            Some(value) => Ok(f(ValueRef::from(value))),
+            None => {
+                let err_str = format!("Row {} has no value for column {}", self.row, self.column_map[index].0);
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+
+    fn set(&mut self, column :&str, value :Value) -> Result<Value, TableError> {
+        let pos = self.column_map.iter().position(|(c, _)| c == column);
+
+        if pos.is_none() {
+            let err_str = format!("Could not find column in RowSlice: {}", column);
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        let pos = self.column_map[pos.unwrap()].1;
+
+        let mut table = self.table.lock().unwrap();
+
+        table.check_unique(self.row, pos, &value)?;
+        table.check_type(pos, &value);
+        table.stats = None;
+
+        let old = std::mem::replace(&mut table.rows[self.row][pos], value);
+        let new = table.rows[self.row][pos].clone();
+
+        table.reindex(self.row, pos, &old, &new);
+
+        Ok(old)
+    }
+
+    fn columns(&self) -> Vec<String> {
+        self.column_map.iter().map(|(c,i)| c.clone()).collect()
+    }
+}
+
+impl Display for RowSlice<RowTableInner> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        // TODO: Fix this
+        write!(f, "{:?}", self.table.lock().unwrap().rows[self.row])
+    }
+}
+
+
+// Iterator for RowTable
+//https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=856142d55cbca5cfda7bc97a744a0c4e - Iterator/Row Playground
+
+/// `Iterator` for rows in a table; it covers the rows in the table when it was created.
+pub struct RowTableIter {
+    table: Arc<Mutex<RowTableInner>>,
+    column_map: Arc<Vec<(String, usize)>>,
+    cur_pos: usize,
+    end: usize
+}
+
+impl RowTableIter {
+    fn row(&self, row :usize) -> RowSlice<RowTableInner> {
+        RowSlice { table: self.table.clone(), column_map: self.column_map.clone(), row }
+    }
+}
+
+impl Iterator for RowTableIter {
+    type Item=RowSlice<RowTableInner>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur_pos >= self.end {
+            None
+        } else {
+            self.cur_pos += 1;
+            Some(self.row(self.cur_pos-1))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.end - self.cur_pos, Some(self.end - self.cur_pos))
+    }
+}
+
+impl DoubleEndedIterator for RowTableIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.cur_pos >= self.end {
+            None
+        } else {
+            self.end -= 1;
+            Some(self.row(self.end))
+        }
+    }
+}
+
+impl ExactSizeIterator for RowTableIter {}
+
+//impl IntoIterator for RowTable {
+//    type Item=OwnedRow;
+//    type IntoIter=RowTableIntoIter;
+//
+//    fn into_iter(self) -> Self::IntoIter {
+//        let columns = self.0.lock().unwrap().columns.clone();
+//
+//        RowTableIntoIter{ columns: Arc::new(columns), iter: self.0.lock().unwrap().rows.into_iter() }
+//    }
+//}
+
+#[derive(Clone)]
+pub struct RowTableSlice {
+    column_map: Arc<Vec<(String, usize)>>,   // mapping of column names to row offsets
+    rows: Arc<Vec<usize>>,                   // index of the corresponding row in the Table
+    table: Arc<Mutex<RowTableInner>>       // reference to the underlying table
+}
+
+impl RowTableSlice {
+    /// Returns a rayon parallel iterator over the rows of the slice.
+    ///
+    /// It runs in the current rayon thread pool; use it inside [`install`](fn.install.html) to run it in the pool set
+    /// with `set_parallelism`.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item=RowSlice<RowTableInner>> {
+        let (column_map, table, rows) = (self.column_map.clone(), self.table.clone(), self.rows.clone());
+
+        (0..rows.len()).into_par_iter().map(move |i| RowSlice { column_map: column_map.clone(), table: table.clone(), row: rows[i] })
+    }
+
+    /// Calls `f` on the rows of the slice in parallel, like [`RowTable::par_map`](struct.RowTable.html#method.par_map),
+    /// with the values of each row in the order of the slice's `columns`.
+    pub fn par_map<R: Send, F: Fn(&[Value]) -> R + Sync + Send>(&self, f :F) -> Vec<R> {
+        let inner = self.table.lock().unwrap();
+        let positions = self.column_map.iter().map(|(_, p)| *p).collect::<Vec<_>>();
+
+        // the rows are passed as they are when the slice has all the table's columns, in order
+        if positions.iter().copied().eq(0..inner.columns.len()) {
+            return parallel::map(&self.rows, |&row| f(&inner.rows[row]));
+        }
+
+        parallel::map(&self.rows, |&row| f(&positions.iter().map(|&p| inner.rows[row][p].clone()).collect::<Vec<_>>()))
+    }
+}
+
+impl Display for RowTableSlice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        write!(f, "{}", self.preview(DISPLAY_ROWS, DISPLAY_WIDTH))
+    }
+}
+
+
+impl TableOperations for RowTableSlice {
+    type TableSliceType = RowTableSlice;
+    type RowType = RowSlice<RowTableInner>;
+    type Iter = RowTableSliceIter;
+
+    fn iter(&self) -> RowTableSliceIter{
+        RowTableSliceIter {
+            column_map: self.column_map.clone(),
+            rows: self.rows.clone(),
+            table: self.table.clone(),
+            cur_pos: 0,
+            end: self.rows.len()
+        }
+    }
+
+    fn get(&self, index :usize) -> Result<Self::RowType, TableError> {
+        if index >= self.len() {
+            let err_str = format!("Index {} is beyond table length {}", index, self.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok(RowSlice {
+            column_map: self.column_map.clone(),
+            table: self.table.clone(),
+            row: self.rows[index]
+        })
+    }
+
+    #[inline]
+    fn columns(&self) -> Vec<String> {
+        self.column_map.iter().map(|(c,i)| c.clone()).collect()
+    }
+
+    fn filter_by<P: FnMut(&RowSlice<RowTableInner>) -> bool>(&self, mut predicate: P) -> Result<RowTableSlice, TableError> {
+        let mut slice_rows = Vec::new();
+
+        for &row_index in self.rows.iter() {
+            let row = RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: row_index };
+
+            // run the predicate against the row
+            if predicate(&row) {
+                slice_rows.push(row_index);
+            }
+        }
+
+        Ok(RowTableSlice {
+            column_map: self.column_map.clone(),
+            table: self.table.clone(),
+            rows: Arc::new(slice_rows),
+        })
+    }
+
+    fn split_rows_at(&self, mid: usize) -> Result<(Self::TableSliceType, Self::TableSliceType), TableError> {
+        if mid >= self.rows.len() {
+            let err_str = format!("Midpoint too large: {} >= {}", mid, self.rows.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok( (
+            RowTableSlice { column_map: self.column_map.clone(), rows: Arc::new(self.rows[..mid].to_vec()), table: self.table.clone() },
+            RowTableSlice { column_map: self.column_map.clone(), rows: Arc::new(self.rows[mid..].to_vec()), table: self.table.clone() }
+            )
+        )
+    }
+
+    fn take_rows(&self, indices :&[usize]) -> Result<Self::TableSliceType, TableError> {
+        if let Some(index) = indices.iter().find(|&&i| i >= self.rows.len()) {
+            let err_str = format!("Index {} is beyond table length {}", index, self.rows.len());
+            return Err(TableError::new(err_str.as_str()));
+        }
+
+        Ok(RowTableSlice {
+            column_map: self.column_map.clone(),
+            rows: Arc::new(indices.iter().map(|&i| self.rows[i]).collect()),
+            table: self.table.clone()
+        })
+    }
+}
+
+impl TableSlice for RowTableSlice {
+    fn sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, mut compare: F) -> Result<Self::TableSliceType, TableError> {
+        let mut rows = self.rows.iter().cloned().collect::<Vec<_>>();
+
+        rows.sort_unstable_by(|&a, &b| {
+            let a_row = RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: a };
+            let b_row = RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: b };
+
+            compare(a_row, b_row)
+        });
+
+        Ok(RowTableSlice {
+            column_map: self.column_map.clone(),
+            rows: Arc::new(rows),
+            table: self.table.clone()
+        })
+    }
+
+//    fn stable_sort_by<F: FnMut(Self::RowType, Self::RowType) -> Ordering>(&self, compare: F) -> Result<Self::TableSliceType, TableError> {
+//        unimplemented!()
+//        let columns = self.columns.clone();
+//        let table = self.table.clone();
+//
+//        Ok(self.rows.sort_by(|a, b| {
+//            let a_row = RowSlice<RowTableInner> { columns: &columns, row: &table.lock().unwrap().rows[*a] };
+//            let b_row = RowSlice<RowTableInner> { columns: &columns, row: &table.lock().unwrap().rows[*b] };
+//
+//            compare(a_row, b_row)
+//        }))
+//    }
+
+    fn rename_column(&self, old_col: &str, new_col: &str) -> Result<Self::TableSliceType, TableError> {
+        let pos = TableSlice::column_position(self, old_col)?;
+
+        let new_val = (new_col.to_string(), self.column_map[pos].1);
+
+        let mut new_column_map = Vec::with_capacity(self.column_map.len());
+
+        for i in 0..self.column_map.len() {
+            if i == pos {
+                new_column_map.push((new_col.to_string(), self.column_map[i].1));
+            } else {
+                new_column_map.push(self.column_map[i].clone());
+            }
+        }
+
+        Ok( RowTableSlice {
+            column_map: Arc::new(new_column_map),
+            rows: self.rows.clone(),
+            table: self.table.clone()
+        })
+    }
+
+}
+
+/// Reference `Iterator` for rows in a table.
+pub struct RowTableSliceIter {
+    column_map: Arc<Vec<(String, usize)>>,
+    rows: Arc<Vec<usize>>,
+    table: Arc<Mutex<RowTableInner>>,
+    cur_pos: usize,
+    end: usize
+}
+
+impl Iterator for RowTableSliceIter {
+    type Item=RowSlice<RowTableInner>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur_pos >= self.end {
+            None
+        } else {
+            self.cur_pos += 1;
+            let row_index = self.rows[self.cur_pos-1];
+
+            Some(RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: row_index})
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.end - self.cur_pos, Some(self.end - self.cur_pos))
+    }
+}
+
+impl DoubleEndedIterator for RowTableSliceIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.cur_pos >= self.end {
+            None
+        } else {
+            self.end -= 1;
+            let row_index = self.rows[self.end];
+
+            Some(RowSlice { column_map: self.column_map.clone(), table: self.table.clone(), row: row_index})
+        }
+    }
+}
+
+impl ExactSizeIterator for RowTableSliceIter {}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixtures, memory_usage, RowTable, TableOperations, Table, Row, Value, ValueType, ParseErrorPolicy, RaggedRowPolicy, Schema, Keep, Agg, Split, ReadOptions, WriteOptions, Quoting, LineEnding, DateUnit, Calendar, Gap, Fill, Frequency, Bar, TableError, CancelToken};
+    use ordered_float::OrderedFloat;
+
+    fn table_from_str(name :&str, csv :&str) -> RowTable {
+        let path = fixtures::temp_dir().join(name);
+        std::fs::write(&path, csv).unwrap();
+
+        RowTable::from_csv(&path).unwrap()
+    }
+
+    #[test]
+    fn to_from_csv() {
+        let mut table :RowTable = RowTable::new(&["B"]);
+
+        table.filter_by(|r| { r.get("B"); true });
+//        table.find_by(|r| { r.set("B", Value::Integer(7)); true });
+        table.update_by(|r| { r.set("B", Value::Integer(7));} );
+
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+        let path = fixtures::temp_dir().join("row_table_to_csv.csv");
+
+        table.to_csv(&path).unwrap();
+
+        let read = RowTable::from_csv(&path).unwrap();
+        let values = |t :&RowTable| t.iter().map(|row| (0..t.width()).map(|i| row.at(i)).collect::<Vec<_>>()).collect::<Vec<_>>();
+
+        assert_eq!(table.columns(), read.columns());
+        assert_eq!(values(&table), values(&read));
+
+        let slice = table.filter("symbol", &Value::new("IBM")).unwrap();
+        let slice_path = fixtures::temp_dir().join("row_table_slice_to_csv.csv");
+
+        slice.to_csv(&slice_path).unwrap();
+
+        let ibm = RowTable::from_csv(&slice_path).unwrap();
+
+        assert_eq!(table.columns(), ibm.columns());
+        assert_eq!(4, ibm.len());
+        assert_eq!(Value::Integer(2373700), ibm.get(1).unwrap().get("volume"));
+
+        assert!(table.to_csv(fixtures::temp_dir().join("missing").join("table.csv")).is_err());
+    }
+
+    #[test]
+    fn compressed_csv() {
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+        let mut csv = Vec::new();
+
+        table.to_csv_writer(&mut csv).unwrap();
+
+        let check = |bytes :&[u8]| {
+            let read = RowTable::from_csv_bytes(bytes).unwrap();
+
+            assert_eq!(table.columns(), read.columns());
+            assert!(table.iter().zip(read.iter()).all(|(a, b)| table.columns().iter().all(|c| a.get(c) == b.get(c))));
+        };
+
+        check(&csv);
+
+        #[cfg(feature = "gzip")]
+        {
+            use std::io::Read;
+
+            let path = fixtures::temp_dir().join("row_table_compressed.csv.gz");
+
+            table.to_csv_gz(&path, 6, &WriteOptions::default()).unwrap();
+
+            let mut bytes = Vec::new();
+            flate2::read::GzDecoder::new(std::fs::File::open(&path).unwrap()).read_to_end(&mut bytes).unwrap();
+
+            assert_eq!(csv, bytes);
+            assert!(table.to_csv_gz(&path, 10, &WriteOptions::default()).is_err());
+        }
+
+        #[cfg(feature = "zstd")]
+        {
+            let path = fixtures::temp_dir().join("row_table_compressed.csv.zst");
+
+            table.to_csv_zst(&path, 3, &WriteOptions::default()).unwrap();
+
+            let bytes = zstd::decode_all(std::fs::File::open(&path).unwrap()).unwrap();
+
+            check(&bytes);
+            assert_eq!(csv, bytes);
+            assert!(table.to_csv_zst(&path, 100, &WriteOptions::default()).is_err());
+        }
+    }
+
+    #[cfg(feature = "xlsx")]
+    #[test]
+    fn from_xlsx() {
+        use chrono::{NaiveDate, NaiveTime};
+
+        let table = RowTable::from_xlsx(fixtures::path("trades.xlsx"), "Trades").unwrap();
+
+        assert_eq!(vec!["date", "when", "at", "symbol", "close", "volume", "ok", "note"], table.columns());
+        assert_eq!(2, table.len());
+
+        let row = table.get(0).unwrap();
+
+        assert_eq!(Value::Date(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap()), row.get("date"));
+        assert_eq!(Value::DateTime(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap().and_hms_opt(12, 0, 0).unwrap()), row.get("when"));
+        assert_eq!(Value::Time(NaiveTime::from_hms_opt(12, 0, 0).unwrap()), row.get("at"));
+        assert_eq!(Value::String("007".to_string()), row.get("symbol"));
+        assert_eq!(Value::Float(75.09.into()), row.get("close"));
+        assert_eq!(Value::Integer(135480400), row.get("volume"));
+        assert_eq!(Value::Boolean(true), row.get("ok"));
+        assert_eq!(Value::String("#DIV/0!".to_string()), row.get("note"));
+
+        let row = table.get(1).unwrap();
+
+        assert_eq!(Value::Empty, row.get("close"));
+        assert_eq!(Value::String("halted".to_string()), row.get("note"));
+
+        assert_eq!(std::io::ErrorKind::NotFound, RowTable::from_xlsx(fixtures::path("trades.xlsx"), "Missing").unwrap_err().kind());
+        assert!(RowTable::from_xlsx(fixtures::path("stocks.csv"), "Trades").is_err());
+    }
+
+    #[test]
+    fn write_options() {
+        let table = table_from_str("row_table_write_options.csv", "a,b,c\n1,x,2.5\n3,\"y,z\",\n");
+        let write = |options :&WriteOptions| {
+            let mut out = Vec::new();
+
+            table.to_csv_writer_with_options(&mut out, options).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!("a,b,c\n1,x,2.5\n3,\"y,z\",\n", write(&WriteOptions::new()));
+        assert_eq!("c,a\n2.5,1\n,3\n", write(&WriteOptions::new().columns(&["c", "a"])));
+        assert_eq!("1,x,2.5\n3,\"y,z\",\n", write(&WriteOptions::new().header(false)));
+
+        let mut out = Vec::new();
+
+        assert!(table.to_csv_writer_with_options(&mut out, &WriteOptions::new().columns(&["a", "d"])).is_err());
+        assert!(table.to_csv_writer_with_options(&mut out, &WriteOptions::new().columns(&["a", "a"])).is_err());
+
+        assert_eq!("\"a\",\"b\",\"c\"\n\"1\",\"x\",\"2.5\"\n\"3\",\"y,z\",\"\"\n", write(&WriteOptions::new().quoting(Quoting::Always)));
+        assert_eq!("\"a\",\"b\",\"c\"\n1,\"x\",2.5\n3,\"y,z\",\"\"\n", write(&WriteOptions::new().quoting(Quoting::NonNumeric)));
+        assert_eq!("a,b,c\n1,x,2.5\n3,y,z,\n", write(&WriteOptions::new().quoting(Quoting::Never)));
+        assert_eq!("b\nx\n'y,z'\n", write(&WriteOptions::new().columns(&["b"]).quote(b'\'')));
+
+        let quoted = table_from_str("row_table_write_options_quoted.csv", "a\n\"say \"\"hi\"\"\"\n");
+
+        let mut out = Vec::new();
+        quoted.to_csv_writer_with_options(&mut out, &WriteOptions::new().escape(b'\\')).unwrap();
+
+        assert_eq!("a\n\"say \\\"hi\\\"\"\n", String::from_utf8(out).unwrap());
+
+        let path = fixtures::temp_dir().join("row_table_write_options_out.csv");
+
+        table.to_csv_with_options(&path, &WriteOptions::new().columns(&["b"])).unwrap();
+
+        assert_eq!("b\nx\n\"y,z\"\n", std::fs::read_to_string(&path).unwrap());
+
+        let dir = fixtures::temp_dir().join("row_table_write_options_split");
+        let _ = std::fs::remove_dir_all(&dir);
+        let paths = table.to_csv_split(&dir, Split::Rows(1), &WriteOptions::new().columns(&["a"]).header(false)).unwrap();
+
+        assert_eq!("3\n", std::fs::read_to_string(&paths[1]).unwrap());
+
+        assert_eq!("a,b,c\r\n1,x,2.5\r\n3,\"y,z\",\r\n", write(&WriteOptions::new().line_ending(LineEnding::CrLf)));
+        assert_eq!("\u{feff}a\r\n1\r\n3\r\n", write(&WriteOptions::excel().columns(&["a"])));
+
+        // the byte order mark starts every file, and is skipped when it's read back
+        let paths = table.to_csv_split(&dir, Split::Rows(1), &WriteOptions::excel().overwrite(true)).unwrap();
+
+        assert_eq!("\u{feff}a,b,c\r\n3,\"y,z\",\r\n", std::fs::read_to_string(&paths[1]).unwrap());
+        assert_eq!(vec!["a", "b", "c"], RowTable::from_csv(&paths[1]).unwrap().columns());
+    }
+
+    #[test]
+    fn csv_split() {
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+        let dir = fixtures::temp_dir().join("row_table_split");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let read_parts = |paths :&[std::path::PathBuf]| paths.iter().map(|p| RowTable::from_csv(p).unwrap()).collect::<Vec<_>>();
+
+        let paths = table.to_csv_split(&dir, Split::Files(4), &WriteOptions::default()).unwrap();
+        let parts = read_parts(&paths);
+
+        assert_eq!(dir.join("part-00003.csv"), paths[3]);
+        assert_eq!(vec![3, 3, 3, 3], parts.iter().map(|p| p.len()).collect::<Vec<_>>());
+        assert!(parts.iter().all(|p| p.columns() == table.columns()));
+        assert_eq!(table.get(11).unwrap().get("volume"), parts[3].get(2).unwrap().get("volume"));
+
+        // the parts already there aren't replaced without asking
+        let err = table.to_csv_split(&dir, Split::Rows(5), &WriteOptions::default()).unwrap_err();
+
+        assert!(err.to_string().contains("already has part files"), "{}", err);
+
+        let overwrite = WriteOptions::new().overwrite(true);
+        let parts = read_parts(&table.to_csv_split(&dir, Split::Rows(5), &overwrite).unwrap());
+
+        assert_eq!(vec![5, 5, 2], parts.iter().map(|p| p.len()).collect::<Vec<_>>());
+
+        let paths = table.to_csv_split(&dir, Split::Bytes(150), &overwrite).unwrap();
+
+        assert!(paths.len() > 1);
+        assert!(paths.iter().all(|p| std::fs::metadata(p).unwrap().len() <= 150));
+        assert_eq!(table.len(), read_parts(&paths).iter().map(|p| p.len()).sum::<usize>());
+
+        // a row larger than the limit still gets written
+        assert_eq!(table.len(), table.to_csv_split(&dir, Split::Bytes(1), &overwrite).unwrap().len());
+        assert_eq!(1, RowTable::new(&["a"]).to_csv_split(&dir, Split::Files(3), &overwrite).unwrap().len());
+        assert!(table.to_csv_split(&dir, Split::Rows(0), &overwrite).is_err());
+
+        // the parts of the earlier splits, into more files, are gone, while files they didn't write are left
+        table.to_csv_split(&dir, Split::Rows(1), &overwrite).unwrap();
+        std::fs::write(dir.join("notes.txt"), "keep").unwrap();
+        std::fs::write(dir.join("part-99999.csv"), "mine").unwrap();
+
+        assert_eq!(1, table.to_csv_split(&dir, Split::Files(1), &overwrite).unwrap().len());
+        assert_eq!(4, std::fs::read_dir(&dir).unwrap().count());
+        assert_eq!("mine", std::fs::read_to_string(dir.join("part-99999.csv")).unwrap());
+        assert!(dir.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn load() {
+        fixtures::check_load(&RowTable::from_csv(fixtures::path("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn filter() {
+        fixtures::check_filter(&RowTable::from_csv(fixtures::path("stocks.csv")).unwrap());
+    }
+
+    #[cfg(feature = "sampling")]
+    #[test]
+    fn sample() {
+        fixtures::check_sample(&RowTable::from_csv(fixtures::path("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn stats() {
+        let mut table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        table.compute_stats();
+        fixtures::check_stats(&mut table);
+    }
+
+    #[test]
+    fn sort() {
+        fixtures::check_sort(&RowTable::from_csv(fixtures::path("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn group() {
+        fixtures::check_group(&RowTable::from_csv(fixtures::path("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn strings() {
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        fixtures::check_strings(&table);
+        fixtures::check_strings(&table.filter_by(|_| true).unwrap());
+    }
+
+    #[test]
+    fn cancel() {
+        fixtures::check_cancel(&RowTable::from_csv(fixtures::path("stocks.csv")).unwrap());
+
+        let path = fixtures::generated("row_table_cancel.csv", 5_000);
+        let token = CancelToken::new();
+        let table = RowTable::from_csv_with_cancel(&path, &token).unwrap();
+        let mut calls = 0;
+
+        // the token is only checked every few thousand rows
+        let err = table.filter_by_with_cancel(&token, |_| {
+            calls += 1;
+            token.cancel();
+            true
+        }).err().unwrap();
+
+        assert!(err.is_cancelled());
+        assert_eq!(4 * 1024, calls);
+
+        let err = RowTable::from_csv_with_cancel(&path, &token).err().unwrap();
+
+        assert_eq!(std::io::ErrorKind::Interrupted, err.kind());
+        assert!(err.get_ref().unwrap().downcast_ref::<TableError>().unwrap().is_cancelled());
+    }
+
+    #[test]
+    fn index() {
+        fixtures::check_index(&RowTable::from_csv(fixtures::path("stocks.csv")).unwrap());
+    }
+
+    #[test]
+    fn column_handle() {
+        fixtures::check_column_handle(&RowTable::from_csv(fixtures::path("stocks.csv")).unwrap());
+    }
+
+    #[cfg(feature = "datetime-inference")]
+    #[test]
+    fn typed_iter() {
+        use crate::FromRow;
+
+        #[derive(Debug, large_table_derive::FromRow)]
+        struct Stock {
+            symbol: String,
+            #[from_row(rename = "date")]
+            day: chrono::NaiveDate,
+            close: f64,
+            volume: Option<i64>
+        }
+
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+        let stocks = table.typed_iter::<Stock>().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(table.len(), stocks.len());
+
+        let row = table.get(3).unwrap();
+
+        assert_eq!(row.get("symbol").as_string(), stocks[3].symbol);
+        assert_eq!(row.get("date").as_date(), stocks[3].day);
+        assert_eq!(row.get("close").as_float(), stocks[3].close);
+        assert_eq!(Some(row.get("volume").as_integer()), stocks[3].volume);
+        assert_eq!(stocks[3].symbol, Stock::from_row(&row).unwrap().symbol);
+
+        #[derive(Debug, large_table_derive::FromRow)]
+        struct Bad {
+            #[allow(dead_code)]
+            symbol: i64
+        }
+
+        let err = table.typed_iter::<Bad>().next().unwrap().unwrap_err();
+
+        assert!(err.to_string().starts_with("Column symbol: "), "{}", err);
+
+        #[derive(Debug, large_table_derive::FromRow)]
+        struct Missing {
+            #[allow(dead_code)]
+            missing: Option<i64>
+        }
+
+        assert!(table.typed_iter::<Missing>().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn iter_as() {
+        #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+        struct Stock {
+            date: String,
+            symbol: String,
+            open: f64,
+            close: f64,
+            volume: Option<u64>
+        }
+
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+        let stocks = table.iter_as::<Stock>().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(table.len(), stocks.len());
+        assert_eq!(Stock { date: "2020-01-02".to_string(), symbol: "MSFT".to_string(), open: 158.78, close: 160.62, volume: Some(22622100) }, stocks[1]);
+
+        let mut table = table_from_str("row_table_append_struct.csv", "date,symbol,open,close,volume\n2020-01-02,AAPL,74.06,75.09,\n");
+
+        table.append_struct(&stocks[1]).unwrap();
+
+        assert_eq!(2, table.len());
+        assert_eq!(None, table.iter_as::<Stock>().next().unwrap().unwrap().volume);
+        assert_eq!(stocks[1], table.iter_as::<Stock>().nth(1).unwrap().unwrap());
+        assert_eq!(Value::Integer(22622100), table.get(1).unwrap().get("volume"));
+
+        #[derive(Debug, serde::Deserialize, serde::Serialize)]
+        struct Symbol {
+            symbol: String
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Date {
+            #[allow(dead_code)]
+            date: i64
+        }
+
+        // columns without a field are an error, and the value has to deserialize to the field
+        assert!(table.append_struct(&Symbol { symbol: "IBM".to_string() }).is_err());
+        assert_eq!("AAPL", table.iter_as::<Symbol>().next().unwrap().unwrap().symbol);
+        assert!(table.iter_as::<Date>().next().unwrap().is_err());
+    }
+
+    #[cfg(all(feature = "anonymize", feature = "datetime-inference"))]
+    #[test]
+    fn anonymize() {
+        use crate::Anonymizer;
+
+        let mut table = table_from_str("row_table_anonymize.csv", "id,user,amount,price,date,when\n\
+            1,alice,100,1.5,2020-01-17,2020-02-29 09:30:00\n\
+            2,bob,200,2.5,2020-03-01,\n\
+            3,alice,,3.5,,2020-12-31 23:59:59\n");
+
+        table.anonymize("user", &Anonymizer::Hash("salt".to_string()), 0).unwrap();
+
+        let users = table.iter().map(|r| r.get("user").as_string()).collect::<Vec<_>>();
+
+        assert_eq!(64, users[0].len());
+        assert_eq!(users[0], users[2]);
+        assert_ne!(users[0], users[1]);
+
+        let mut other = table_from_str("row_table_anonymize_salt.csv", "user\nalice\n");
+
+        other.anonymize("user", &Anonymizer::Hash("pepper".to_string()), 0).unwrap();
+        assert_ne!(users[0], other.get(0).unwrap().get("user").as_string());
+
+        table.anonymize("amount", &Anonymizer::Jitter(10.0), 7).unwrap();
+
+        let amount = table.get(0).unwrap().get("amount");
+
+        assert!(matches!(amount, Value::Integer(a) if (90..=110).contains(&a)), "{}", amount);
+        assert_eq!(Value::Empty, table.get(2).unwrap().get("amount"));
+
+        table.anonymize("date", &Anonymizer::Month, 0).unwrap();
+        table.anonymize("when", &Anonymizer::Month, 0).unwrap();
+
+        assert_eq!(Value::new("2020-01-01"), table.get(0).unwrap().get("date"));
+        assert_eq!(Value::new("2020-02-01"), table.get(0).unwrap().get("when"));
+        assert_eq!(Value::new("2020-12-01"), table.get(2).unwrap().get("when"));
+
+        // the same seed shuffles the same way, and the values are only moved
+        let mut shuffled = table_from_str("row_table_anonymize_shuffle.csv", "id\n1\n2\n3\n4\n5\n6\n7\n8\n");
+        let mut again = table_from_str("row_table_anonymize_shuffle_again.csv", "id\n1\n2\n3\n4\n5\n6\n7\n8\n");
+
+        shuffled.anonymize("id", &Anonymizer::Shuffle, 42).unwrap();
+        again.anonymize("id", &Anonymizer::Shuffle, 42).unwrap();
+
+        let ids = shuffled.iter().map(|r| r.get("id")).collect::<Vec<_>>();
+        let mut sorted = ids.clone();
+
+        sorted.sort();
+
+        assert_eq!(ids, again.iter().map(|r| r.get("id")).collect::<Vec<_>>());
+        assert_eq!((1..=8).map(Value::Integer).collect::<Vec<_>>(), sorted);
+
+        let mut sparse = table_from_str("row_table_anonymize_shuffle_sparse.csv", "id,x\n1,a\n2,\n3,b\n4,\n5,c\n");
+
+        sparse.anonymize("x", &Anonymizer::Shuffle, 42).unwrap();
+
+        assert_eq!(vec![false, true, false, true, false], sparse.iter().map(|r| r.get("x") == Value::Empty).collect::<Vec<_>>());
+
+        // errors leave the column as it was
+        assert!(table.anonymize("user", &Anonymizer::Jitter(1.0), 0).is_err());
+        assert!(table.anonymize("price", &Anonymizer::Month, 0).is_err());
+        assert!(table.anonymize("price", &Anonymizer::Jitter(-1.0), 0).is_err());
+        assert!(table.anonymize("missing", &Anonymizer::Shuffle, 0).is_err());
+        assert_eq!(Value::Float(1.5.into()), table.get(0).unwrap().get("price"));
+    }
+
+    #[cfg(feature = "privacy")]
+    #[test]
+    fn agg_with_privacy() {
+        use crate::{Noise, Privacy};
+
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        // with a huge budget there's almost no noise
+        let loose = Privacy::new(1e9).bounds("close", 0.0, 200.0).seed(1);
+
+        assert!((table.agg_with_privacy("symbol", Agg::Count, &loose).unwrap() - 12.0).abs() < 1e-3);
+        assert!((table.agg_with_privacy("close", Agg::Sum, &loose).unwrap() - 1472.9).abs() < 1e-3);
+        assert!((table.agg_with_privacy("close", Agg::Mean, &loose).unwrap() - 122.742).abs() < 1e-3);
+
+        // Gaussian noise is only calibrated for an epsilon below 1
+        let gaussian = Privacy::new(0.5).bounds("close", 0.0, 200.0).noise(Noise::Gaussian(1e-5)).seed(1);
+        let mean = table.agg_with_privacy("close", Agg::Mean, &gaussian).unwrap();
+
+        assert!(mean.is_finite());
+        assert_eq!(mean, table.agg_with_privacy("close", Agg::Mean, &gaussian).unwrap());
+        assert!(table.agg_with_privacy("close", Agg::Mean, &loose.clone().noise(Noise::Gaussian(1e-5))).is_err());
+
+        // values are clamped to the bounds
+        let clamped = Privacy::new(1e9).bounds("close", 0.0, 100.0).seed(1);
+
+        assert!((table.agg_with_privacy("close", Agg::Sum, &clamped).unwrap() - 1099.0).abs() < 1e-3);
+
+        // the same seed gives the same noise, and a small budget gives more of it
+        let tight = Privacy::new(0.1).seed(7);
+        let count = table.agg_with_privacy("symbol", Agg::Count, &tight).unwrap();
+
+        assert_eq!(count, table.agg_with_privacy("symbol", Agg::Count, &tight).unwrap());
+        assert_ne!(12.0, count);
+
+        assert!(table.agg_with_privacy("open", Agg::Sum, &loose).is_err());
+        assert!(table.agg_with_privacy("symbol", Agg::Sum, &Privacy::new(1.0).bounds("symbol", 0.0, 1.0)).is_err());
+        assert!(table.agg_with_privacy("close", Agg::Median, &loose).is_err());
+        assert!(table.agg_with_privacy("close", Agg::Count, &Privacy::new(0.0)).is_err());
+        assert!(table.agg_with_privacy("close", Agg::Count, &Privacy::new(1.0).noise(Noise::Gaussian(1.0))).is_err());
+        assert!(table.agg_with_privacy("missing", Agg::Count, &loose).is_err());
+    }
+
+    #[cfg(all(feature = "arrow", feature = "datetime-inference"))]
+    #[test]
+    fn arrow() {
+        use std::sync::Arc;
+        use arrow_array::{Array, RecordBatch, Int32Array, StringArray, TimestampSecondArray};
+        use arrow_schema::{DataType, Field, Schema};
+
+        let table = table_from_str("row_table_arrow.csv", "date,when,symbol,close,volume,ok,empty,wait\n\
+            2020-01-02,2020-01-02 09:30:00,AAPL,75.09,135480400,true,,PT1H\n\
+            2020-01-03,2020-01-03 16:00:00,IBM,,3148600,false,,PT30M\n\
+            2020-01-06,2020-01-06 12:00:00,1,74,,true,,P1D\n");
+
+        let batches = table.to_arrow(2).unwrap();
+
+        assert_eq!(2, batches.len());
+        assert_eq!(vec![2, 1], batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>());
+        assert_eq!(&DataType::Utf8, batches[0].schema().field(2).data_type());
+        assert_eq!(&DataType::Float64, batches[0].schema().field(3).data_type());
+        assert_eq!(&DataType::Null, batches[0].schema().field(6).data_type());
+        assert!(batches[0].column(3).is_null(1));
+
+        let round_trip = RowTable::from_arrow(&batches).unwrap();
+
+        assert_eq!(table.columns(), round_trip.columns());
+
+        for (a, b) in table.iter().zip(round_trip.iter()) {
+            for column in table.columns() {
+                match (a.get(&column), b.get(&column)) {
+                    (Value::Integer(i), v) if column == "symbol" => assert_eq!(Value::String(i.to_string()), v),
+                    (Value::Integer(i), v) if column == "close" => assert_eq!(Value::Float((i as f64).into()), v),
+                    (a, b) => assert_eq!(a, b)
+                }
+            }
+        }
+
+        assert_eq!(1, table.to_arrow(10).unwrap().len());
+        assert!(table.to_arrow(0).is_err());
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("at", DataType::Timestamp(arrow_schema::TimeUnit::Second, None), false)
+        ]));
+        let batch = RecordBatch::try_new(schema, vec![
+            Arc::new(Int32Array::from(vec![Some(1), None])),
+            Arc::new(StringArray::from(vec![None, Some("b")])),
+            Arc::new(TimestampSecondArray::from(vec![0, 86400]))
+        ]).unwrap();
+        let table = RowTable::from_arrow(&[batch.clone(), batch]).unwrap();
+
+        assert_eq!(4, table.len());
+        assert_eq!(Value::Empty, table.get(1).unwrap().get("id"));
+        assert_eq!(Value::Empty, table.get(2).unwrap().get("name"));
+        assert_eq!(Value::String("b".to_string()), table.get(3).unwrap().get("name"));
+        assert_eq!(Value::DateTime(chrono::NaiveDate::from_ymd_opt(1970, 1, 2).unwrap().and_hms_opt(0, 0, 0).unwrap()), table.get(3).unwrap().get("at"));
+        assert!(RowTable::from_arrow(&[]).is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn par_iter() {
+        use rayon::prelude::*;
+
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        let volume :i64 = table.par_iter().map(|row| row.get("volume").as_integer()).sum();
+
+        assert_eq!(table.iter().map(|row| row.get("volume").as_integer()).sum::<i64>(), volume);
+        assert_eq!(volume, crate::install(|| table.par_iter().map(|row| row.get("volume").as_integer()).sum::<i64>()));
+
+        let slice = table.filter("symbol", &Value::String("IBM".to_string())).unwrap();
+        let dates = slice.par_iter().map(|row| row.get("date")).collect::<Vec<_>>();
+
+        assert_eq!(slice.iter().map(|row| row.get("date")).collect::<Vec<_>>(), dates);
+    }
+
+    #[test]
+    fn par_map() {
+        let mut table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+        let volume = table.column_position("volume").unwrap();
+
+        assert_eq!(table.iter().map(|row| row.get("volume").as_integer()).sum::<i64>(), table.par_map(|row| row[volume].as_integer()).into_iter().sum::<i64>());
+
+        // a slice passes its own columns, even when it renames them
+        let slice = crate::TableSlice::rename_column(&table.filter("symbol", &Value::String("IBM".to_string())).unwrap(), "date", "day").unwrap();
+        let day = slice.column_position("day").unwrap();
+
+        assert_eq!(slice.iter().map(|row| row.get("day")).collect::<Vec<_>>(), slice.par_map(|row| row[day].clone()));
+
+        // nor the columns added to the table after it
+        table.add_column_with("one", || Value::Integer(1)).unwrap();
+
+        assert_eq!(table.width() - 1, slice.par_map(|row| row.len())[0]);
+    }
+
+    #[test]
+    fn split_rename() {
+        let mut table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        fixtures::check_split(&table);
+        fixtures::check_rename(&mut table);
+    }
+
+    #[test]
+    fn generated() {
+        fixtures::check_generated(&RowTable::from_csv(fixtures::generated("row_table_generated.csv", 1_000)).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn from_csv_with_progress() {
+        let mut calls = Vec::new();
+        let table = RowTable::from_csv_with_progress(fixtures::generated("row_table_progress.csv", 70_000), |p| calls.push(p)).unwrap();
+
+        // a batch of rows, the rest, and the end
+        assert_eq!(vec![65_536, 70_000, 70_000], calls.iter().map(|p| p.rows).collect::<Vec<_>>());
+        assert!(calls.windows(2).all(|w| w[0].bytes <= w[1].bytes));
+        assert_eq!(calls[2].total_bytes, calls[2].bytes);
+        assert!(calls[0].fraction() > 0.9 && calls[0].fraction() < 1.0);
+        assert_eq!(1.0, calls[2].fraction());
+        assert_eq!(70_000, table.len());
+
+        assert!(RowTable::from_csv_with_progress(fixtures::temp_dir().join("row_table_progress_missing.csv"), |_| ()).is_err());
+
+        // cancelling from the progress callback stops the load after the batch
+        let token = CancelToken::new();
+        let mut calls = 0;
+        let err = RowTable::from_csv_with_progress_and_cancel(fixtures::temp_dir().join("row_table_progress.csv"), &token, |_| {
+            calls += 1;
+            token.cancel();
+        }).err().unwrap();
+
+        assert_eq!(1, calls);
+        assert_eq!(std::io::ErrorKind::Interrupted, err.kind());
+        assert!(err.get_ref().unwrap().downcast_ref::<TableError>().unwrap().is_cancelled());
+    }
+
+    #[test]
+    fn wide() {
+        fixtures::check_wide(&RowTable::from_csv(fixtures::wide("row_table_wide.csv", 500, 20)).unwrap(), 500, 20);
+    }
+
+    #[test]
+    fn from_csv_bytes() {
+        let table = RowTable::from_csv_bytes(b"A,B\n1,hello\n2,world\n").unwrap();
+
+        assert_eq!(vec!["A", "B"], table.columns());
+        assert_eq!(2, table.len());
+        assert_eq!(Value::String("world".to_string()), table.get(1).unwrap().get("B"));
+
+        assert!(RowTable::from_csv_bytes(b"A,A\n1,2\n").is_err());
+    }
+
+    #[test]
+    fn from_csv_chunked() {
+        let path = fixtures::generated("row_table_chunked.csv", 1_000);
+
+        let chunks = RowTable::from_csv_chunked(&path, 300).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(vec![300, 300, 300, 100], chunks.iter().map(|c| c.len()).collect::<Vec<_>>());
+        assert!(chunks.iter().all(|c| c.columns() == vec!["id", "symbol", "price", "date"]));
+
+        let table = RowTable::from_csv(&path).unwrap();
+
+        assert_eq!(table.get(300).unwrap().get("id"), chunks[1].get(0).unwrap().get("id"));
+
+        assert!(RowTable::from_csv_chunked(&path, 0).is_err());
+    }
+
+    #[test]
+    fn from_csv_ragged() {
+        let path = fixtures::temp_dir().join("row_table_ragged.csv");
+
+        std::fs::write(&path, "A,B,C\n1,x,2\n3,y\n4,z,5,6\n7,w,8\n").unwrap();
+
+        assert!(RowTable::from_csv(&path).is_err());
+
+        let err = RowTable::from_csv_ragged(&path, RaggedRowPolicy::Error).unwrap_err();
+
+        assert!(err.to_string().contains("Line 3: expected 3 fields, found 2"), "{}", err);
+
+        let skipped = RowTable::from_csv_ragged(&path, RaggedRowPolicy::Skip).unwrap();
+
+        assert_eq!(2, skipped.len());
+        assert_eq!(Value::Integer(7), skipped.get(1).unwrap().get("A"));
+
+        // a row with extra fields can't be padded
+        assert!(RowTable::from_csv_ragged(&path, RaggedRowPolicy::Pad).unwrap_err().to_string().contains("Line 4"));
+
+        std::fs::write(&path, "A,B,C\n1,x,2\n3,y\n").unwrap();
+
+        let padded = RowTable::from_csv_ragged(&path, RaggedRowPolicy::Pad).unwrap();
+
+        assert_eq!(Value::String("y".to_string()), padded.get(1).unwrap().get("B"));
+        assert_eq!(Value::Empty, padded.get(1).unwrap().get("C"));
+        assert!(padded.get(1).unwrap().try_at(3).is_err());
+    }
+
+    #[test]
+    fn from_csv_headerless() {
+        let path = fixtures::temp_dir().join("row_table_headerless.csv");
+
+        std::fs::write(&path, "2020-01-02,AAPL,300.35\n2020-01-03,MSFT,158.62\n").unwrap();
+
+        let table = RowTable::from_csv_with_options(&path, &ReadOptions::new().has_headers(false)).unwrap();
+
+        assert_eq!(vec!["column_0", "column_1", "column_2"], table.columns());
+        assert_eq!(2, table.len());
+        assert_eq!(Value::String("AAPL".to_string()), table.get(0).unwrap().get("column_1"));
+
+        let named = RowTable::from_csv_with_options(&path, &ReadOptions::new().has_headers(false).column_names(&["date", "symbol", "close"])).unwrap();
+
+        assert_eq!(vec!["date", "symbol", "close"], named.columns());
+        assert_eq!(2, named.len());
+        assert_eq!(Value::String("MSFT".to_string()), named.get(1).unwrap().get("symbol"));
+
+        // names replace a header row
+        let renamed = RowTable::from_csv_with_options(&path, &ReadOptions::new().column_names(&["a", "b", "c"])).unwrap();
+
+        assert_eq!(1, renamed.len());
+        assert_eq!(Value::String("MSFT".to_string()), renamed.get(0).unwrap().get("b"));
+
+        assert!(RowTable::from_csv_with_options(&path, &ReadOptions::new().has_headers(false).column_names(&["a", "b"])).is_err());
+        assert!(RowTable::from_csv_with_options(&path, &ReadOptions::new().has_headers(false).column_names(&["a", "b", "a"])).is_err());
+    }
+
+    #[test]
+    fn from_csv_skip_lines() {
+        let path = fixtures::temp_dir().join("row_table_skip_lines.csv");
+
+        std::fs::write(&path, "Daily report\ngenerated 2020-01-06\ndate,close\n# holiday below\n2020-01-02,75.09\n#2020-01-03,74.36\n2020-01-06,74.95\nTotal: 2\nEnd of report\n").unwrap();
+
+        let options = ReadOptions::new().skip_rows(2).comment(b'#').skip_footer(2);
+        let table = RowTable::from_csv_with_options(&path, &options).unwrap();
+
+        assert_eq!(vec!["date", "close"], table.columns());
+        assert_eq!(2, table.len());
+        assert_eq!(Value::Float(74.95.into()), table.get(1).unwrap().get("close"));
+
+        // without trimming the footer, its short line is an error
+        assert!(RowTable::from_csv_with_options(&path, &ReadOptions::new().skip_rows(2).comment(b'#')).is_err());
+        assert!(RowTable::from_csv_with_options(&path, &options.clone().skip_footer(1)).unwrap_err().to_string().contains("Row 2: expected 2 fields, found 1"));
+
+        let everything = RowTable::from_csv_with_options(&path, &ReadOptions::new().skip_rows(9)).unwrap();
+
+        assert!(everything.columns().is_empty());
+        assert_eq!(0, everything.len());
+    }
+
+    #[test]
+    fn from_csv_null_values() {
+        let path = fixtures::temp_dir().join("row_table_null_values.csv");
+
+        std::fs::write(&path, "symbol,close\nAAPL,75.09\nNULL,74.36\nN/A,-\nAAPL,\n").unwrap();
+
+        let options = ReadOptions::new().null_values(&["NULL", "N/A", "-"]);
+        let table = RowTable::from_csv_with_options(&path, &options).unwrap();
+
+        assert_eq!(2, table.unique("symbol").unwrap().len());
+        assert_eq!(Value::Empty, table.get(2).unwrap().get("close"));
+        assert_eq!(2, table.group_by("symbol").unwrap().len());
+
+        // the tokens don't fail to parse with the column's type
+        let mut schema = Schema::new(&[("close", ValueType::Float)]);
+
+        assert!(RowTable::from_csv_typed(&path, &schema, ParseErrorPolicy::Error).is_err());
+
+        schema.set_null_values(&["NULL", "N/A", "-"]);
+
+        let typed = RowTable::from_csv_typed(&path, &schema, ParseErrorPolicy::Error).unwrap();
+
+        assert_eq!(Value::Empty, typed.get(2).unwrap().get("close"));
+        assert_eq!(Value::Empty, typed.get(1).unwrap().get("symbol"));
+    }
+
+    #[test]
+    fn from_csv_number_format() {
+        let path = fixtures::temp_dir().join("row_table_number_format.csv");
+
+        std::fs::write(&path, "symbol,volume,close\nAAPL,1.234.567,\"75,09\"\nMSFT,980,\"158,62\"\n").unwrap();
+
+        let schema = Schema::new(&[("volume", ValueType::IntegerFormat(".,".to_string())), ("close", ValueType::FloatFormat(".,".to_string()))]);
+        let table = RowTable::from_csv_typed(&path, &schema, ParseErrorPolicy::Error).unwrap();
+
+        assert_eq!(Value::Integer(1234567), table.get(0).unwrap().get("volume"));
+        assert_eq!(Value::Float(OrderedFloat(158.62)), table.get(1).unwrap().get("close"));
+    }
+
+    #[test]
+    fn from_csv_with_schema() {
+        let path = fixtures::temp_dir().join("row_table_schema.csv");
+
+        std::fs::write(&path, "A,B\n1,x\ntwo,y\n3,\n").unwrap();
+
+        let schema = [ValueType::Integer, ValueType::String];
+
+        let err = RowTable::from_csv_with_schema(&path, &schema).unwrap_err();
+
+        assert!(err.to_string().contains("Line 3"));
+
+        let skipped = RowTable::from_csv_with_schema_policy(&path, &schema, ParseErrorPolicy::SkipRow).unwrap();
+
+        assert_eq!(2, skipped.len());
+        assert_eq!(Value::Integer(3), skipped.get(1).unwrap().get("A"));
+        assert_eq!(Value::Empty, skipped.get(1).unwrap().get("B"));
+
+        let emptied = RowTable::from_csv_with_schema_policy(&path, &schema, ParseErrorPolicy::Empty).unwrap();
+
+        assert_eq!(3, emptied.len());
+        assert_eq!(Value::Empty, emptied.get(1).unwrap().get("A"));
+        assert_eq!(Value::String("y".to_string()), emptied.get(1).unwrap().get("B"));
+
+        // only type one column, by name
+        let typed = RowTable::from_csv_typed(&path, &Schema::new(&[("B", ValueType::String)]), ParseErrorPolicy::Error).unwrap();
+
+        assert_eq!(Value::String("two".to_string()), typed.get(1).unwrap().get("A"));
+        assert_eq!(Value::Integer(3), typed.get(2).unwrap().get("A"));
+        assert_eq!(Some(ValueType::String), typed.declared_type("B"));
+        assert_eq!(None, typed.declared_type("A"));
+
+        // values of the declared type keep it, while others drop it
+        typed.get(0).unwrap().set("B", Value::String("z".to_string())).unwrap();
+
+        assert_eq!(Some(ValueType::String), typed.declared_type("B"));
+
+        typed.get(0).unwrap().set("B", Value::Integer(1)).unwrap();
+
+        assert_eq!(None, typed.declared_type("B"));
+
+        let typed = RowTable::from_csv_typed(&path, &Schema::new(&[("A", ValueType::Integer)]), ParseErrorPolicy::SkipRow).unwrap();
+
+        assert_eq!(2, typed.len());
+        assert!(RowTable::from_csv_typed(&path, &Schema::new(&[("C", ValueType::Integer)]), ParseErrorPolicy::Error).is_err());
+    }
+
+    #[test]
+    fn astype() {
+        let mut table = table_from_str("row_table_astype.csv", "A,B,C\n1,1.5,yes\n2,x,no\n,2,yes\n");
+
+        table.astype("A", ValueType::String).unwrap();
+        table.astype("C", ValueType::Boolean).unwrap();
+
+        assert_eq!(Value::String("1".to_string()), table.get(0).unwrap().get("A"));
+        assert_eq!(Value::Empty, table.get(2).unwrap().get("A"));
+        assert_eq!(Value::Boolean(false), table.get(1).unwrap().get("C"));
+
+        // nothing changes when a value can't be converted
+        assert!(table.astype("B", ValueType::Float).is_err());
+        assert_eq!(Value::Integer(2), table.get(2).unwrap().get("B"));
+
+        assert_eq!(vec![1], table.try_astype("B", ValueType::Float).unwrap());
+        assert_eq!(vec![Value::Float(1.5.into()), Value::Empty, Value::Float(2.0.into())], table.iter().map(|r| r.get("B")).collect::<Vec<_>>());
+
+        assert_eq!(vec![
+            ("A".to_string(), ValueType::String),
+            ("B".to_string(), ValueType::Float),
+            ("C".to_string(), ValueType::Boolean)
+        ], table.dtypes());
+
+        assert!(table.astype("missing", ValueType::Integer).is_err());
+    }
+
+    #[test]
+    fn dtypes() {
+        let table = table_from_str("row_table_dtypes.csv", "A,B,C,D\n1,2.5,x,\n2,3,4,\n");
+
+        let dtypes = table.dtypes();
+
+        assert_eq!(vec![
+            ("A".to_string(), ValueType::Integer),
+            ("B".to_string(), ValueType::Number),
+            ("C".to_string(), ValueType::String),
+            ("D".to_string(), ValueType::Empty)
+        ], dtypes);
+
+        // only a sample of the rows of a large table is used
+        let rows = (0..3 * crate::DTYPES_SAMPLE_ROWS).map(|i| vec![Value::Integer(i as i64)]).collect::<Vec<_>>();
+        let large = RowTable::from_rows(vec!["A".to_string()], rows);
+
+        large.get(1).unwrap().set("A", Value::Float(0.5.into())).unwrap();
+        assert_eq!(vec![("A".to_string(), ValueType::Integer)], large.dtypes());
+
+        large.get(3).unwrap().set("A", Value::Float(0.5.into())).unwrap();
+        assert_eq!(vec![("A".to_string(), ValueType::Number)], large.dtypes());
+
+        let info = table.info();
+
+        assert!(info.starts_with("2 rows, 4 columns"));
+        assert!(info.contains("Number"));
+
+        let usage = table.memory_usage();
+
+        assert_eq!(0, usage.mapped);
+        assert_eq!(4, usage.columns.len());
+        assert_eq!(2 * std::mem::size_of::<Value>(), usage.columns[0].1);
+        assert_eq!(memory_usage::column_memory(&table), usage.columns);
+        assert!(usage.to_string().contains("total:"));
+    }
+
+    #[test]
+    fn add_column_from() {
+        let mut table = table_from_str("row_table_add_column_from.csv", "price,quantity\n2.5,4\n1.5,2\n");
+
+        table.add_column_from("total", |row| Value::Float(OrderedFloat(row.get("price").as_float() * row.get("quantity").as_float()))).unwrap();
+
+        assert_eq!(vec!["price", "quantity", "total"], table.columns());
+        assert_eq!(10.0, table.get(0).unwrap().get("total").as_float());
+        assert_eq!(3.0, table.get(1).unwrap().get("total").as_float());
+
+        assert!(table.add_column_from("total", |_| Value::Empty).is_err());
+    }
+
+    #[test]
+    fn par_filter_by() {
+        let table = RowTable::from_csv(fixtures::generated("row_table_par_filter.csv", 20_000)).unwrap();
+
+        let price = table.column_position("price").unwrap();
+        let expected = table.filter_by(|row| row.get("price") > Value::Float(500.0.into())).unwrap();
+        let filtered = table.par_filter_by(|row| row[price] > Value::Float(500.0.into())).unwrap();
+
+        assert!(filtered.len() > 0);
+        assert_eq!(expected.len(), filtered.len());
+        assert!(expected.iter().zip(filtered.iter()).all(|(a, b)| a.get("id") == b.get("id")));
+    }
+
+    #[test]
+    fn enrich() {
+        let mut trades = table_from_str("row_table_enrich.csv", "symbol,qty\nAAPL,1\nMSFT,2\nIBM,3\n,4\nAAPL,5\n");
+        let symbols = table_from_str("row_table_enrich_symbols.csv", "symbol,name,sector\nAAPL,Apple,Tech\nMSFT,Microsoft,Tech\nXOM,Exxon,Energy\n");
+
+        trades.enrich(&symbols, "symbol", &["name", "sector"]).unwrap();
+
+        assert_eq!(vec!["symbol", "qty", "name", "sector"], trades.columns());
+        assert_eq!(Value::String("Apple".to_string()), trades.get(4).unwrap().get("name"));
+        assert_eq!(Value::String("Tech".to_string()), trades.get(1).unwrap().get("sector"));
+        assert_eq!(Value::Empty, trades.get(2).unwrap().get("name"));
+        assert_eq!(Value::Empty, trades.get(3).unwrap().get("sector"));
+
+        assert!(trades.enrich(&symbols, "symbol", &["name"]).is_err());
+        assert!(trades.enrich(&symbols, "qty", &["exchange"]).is_err());
+
+        let repeated = table_from_str("row_table_enrich_repeated.csv", "symbol,exchange\nAAPL,N\nAAPL,Q\n");
+
+        assert!(trades.enrich(&repeated, "symbol", &["exchange"]).unwrap_err().to_string().contains("AAPL repeats"));
+        assert_eq!(4, trades.columns().len());
+    }
+
+    #[test]
+    fn declare_unique() {
+        let mut symbols = table_from_str("row_table_unique.csv", "symbol,name\nAAPL,Apple\nMSFT,Microsoft\n,Unknown\n,Other\n");
+
+        assert!(symbols.loc("symbol", &Value::new("AAPL")).is_err());
+
+        symbols.declare_unique("symbol").unwrap();
+
+        assert_eq!(vec!["symbol"], symbols.unique_columns());
+        assert_eq!(Value::new("Microsoft"), symbols.loc("symbol", &Value::new("MSFT")).unwrap().unwrap().get("name"));
+        assert!(symbols.loc("symbol", &Value::new("IBM")).unwrap().is_none());
+        assert!(symbols.declare_unique("name").is_ok());
+        assert!(symbols.declare_unique("missing").is_err());
+
+        // edits that would repeat a value are rejected
+        let aapl = table_from_str("row_table_unique_aapl.csv", "symbol,name\nAAPL,Apple Inc.\n");
+
+        assert!(symbols.append_row(aapl.get(0).unwrap()).unwrap_err().to_string().contains("already in unique column symbol"));
+        assert!(symbols.get(1).unwrap().set("symbol", Value::new("AAPL")).is_err());
+        assert!(symbols.map_column("symbol", |_| Value::new("X")).is_err());
+        assert_eq!(4, symbols.len());
+
+        symbols.get(1).unwrap().set("symbol", Value::new("MSFT.O")).unwrap();
+
+        assert!(symbols.loc("symbol", &Value::new("MSFT")).unwrap().is_none());
+        assert_eq!(1, symbols.loc("symbol", &Value::new("MSFT.O")).unwrap().unwrap().row);
+
+        symbols.upsert("symbol", table_from_str("row_table_unique_ibm.csv", "symbol,name\nIBM,IBM\n").get(0).unwrap()).unwrap();
+        symbols.upsert("symbol", aapl.get(0).unwrap()).unwrap();
+
+        assert_eq!(5, symbols.len());
+        assert_eq!(Value::new("Apple Inc."), symbols.loc("symbol", &Value::new("AAPL")).unwrap().unwrap().get("name"));
+        assert_eq!(Value::new("IBM"), symbols.loc("name", &Value::new("IBM")).unwrap().unwrap().get("symbol"));
+        assert!(symbols.upsert("name", table_from_str("row_table_unique_dup.csv", "symbol,name\nAAPL,IBM\n").get(0).unwrap()).is_err());
+
+        // a new key is appended while a slice of the table is alive
+        let microsoft = symbols.filter_by(|r| r.get("name") == Value::new("Microsoft")).unwrap();
+
+        symbols.upsert("symbol", table_from_str("row_table_unique_goog.csv", "symbol,name\nGOOG,Alphabet\n").get(0).unwrap()).unwrap();
+
+        assert_eq!(6, symbols.len());
+        assert_eq!(1, microsoft.len());
+        assert_eq!(5, symbols.loc("symbol", &Value::new("GOOG")).unwrap().unwrap().row);
+
+        // joins and lookups with the table on the right use its index
+        let mut trades = table_from_str("row_table_unique_trades.csv", "symbol,qty\nAAPL,1\nMSFT,2\nIBM,3\n,4\n");
+
+        assert_eq!(2, trades.semi_join(&symbols, &["symbol"]).unwrap().len());
+        assert_eq!(2, trades.anti_join(&symbols, &["symbol"]).unwrap().len());
+
+        trades.enrich(&symbols, "symbol", &["name"]).unwrap();
+
+        assert_eq!(Value::new("IBM"), trades.get(2).unwrap().get("name"));
+        assert_eq!(Value::Empty, trades.get(3).unwrap().get("name"));
+    }
+
+    #[test]
+    fn add_column() {
+        let mut table = RowTable::from_csv(fixtures::generated("row_table_add_column.csv", 1_000)).unwrap();
+
+        table.add_column("flag", &Value::Integer(1)).unwrap();
+
+        assert!(table.iter().all(|row| row.get("flag") == Value::Integer(1)));
+        assert!(table.add_column("flag", &Value::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn rolling() {
+        let mut table = table_from_str("row_table_rolling.csv", "A,B\n1,x\n2,x\n,x\n4,x\n5,x\n6,x\n");
+
+        let mean = table.rolling(2).mean("A").unwrap();
+
+        assert_eq!(vec![Value::Empty, Value::Float(OrderedFloat(1.5)), Value::Empty, Value::Empty, Value::Float(OrderedFloat(4.5)), Value::Float(OrderedFloat(5.5))], mean);
+        assert_eq!(Value::Float(OrderedFloat(15.0)), table.rolling(3).sum("A").unwrap()[5]);
+        assert_eq!(Value::Float(OrderedFloat(4.0)), table.rolling(3).min("A").unwrap()[5]);
+        assert_eq!(Value::Float(OrderedFloat(6.0)), table.rolling(3).max("A").unwrap()[5]);
+        assert_eq!(Value::Float(OrderedFloat(1.0)), table.rolling(3).std("A").unwrap()[5]);
+        assert!(table.rolling(0).sum("A").is_err());
+        assert!(table.rolling(1).std("A").is_err());
+        assert!(table.rolling(2).sum("C").is_err());
+
+        table.add_column_values("mean", mean).unwrap();
+
+        assert_eq!(Value::Float(OrderedFloat(5.5)), table.get(5).unwrap().get("mean"));
+        assert!(table.add_column_values("short", vec![Value::Empty]).is_err());
+    }
+
+    #[test]
+    fn window() {
+        let mut table = table_from_str("row_table_window.csv", "symbol,close,volume\nAAPL,75,100\nMSFT,158,5\nAAPL,74,\nAAPL,76,20\nMSFT,159.5,7\n");
+
+        let window = table.window(&["symbol"]).unwrap();
+
+        let ints = |values :&[i64]| values.iter().map(|v| Value::Integer(*v)).collect::<Vec<_>>();
+
+        assert_eq!(vec![Value::Integer(100), Value::Integer(5), Value::Empty, Value::Integer(120), Value::Integer(12)], window.cumsum("volume").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(317.5)), window.cumsum("close").unwrap()[4]);
+        assert_eq!(ints(&[2, 1, 1, 3, 2]), window.rank("close").unwrap());
+        assert_eq!(vec![Value::Empty, Value::Empty, Value::Integer(75), Value::Integer(74), Value::Integer(158)], window.lag("close", 1).unwrap());
+        assert_eq!(vec![Value::Integer(76), Value::Empty, Value::Empty, Value::Empty, Value::Empty], window.lead("close", 2).unwrap());
+        assert!(window.cumsum("symbol").is_err());
+        assert!(window.lag("missing", 1).is_err());
+
+        // without groups, the whole table is one window
+        assert_eq!(ints(&[1, 4, 1, 1, 4]), table.window(&[]).unwrap().rank("symbol").unwrap());
+        assert!(table.window(&["missing"]).is_err());
+
+        let prev = table.window(&["symbol"]).unwrap().lag("close", 1).unwrap();
+
+        table.add_column_values("prev_close", prev).unwrap();
+
+        assert_eq!(Value::Integer(74), table.get(3).unwrap().get("prev_close"));
+    }
+
+    #[test]
+    fn windows() {
+        let table = table_from_str("row_table_windows.csv", "A,B\n1,x\n2,x\n2,y\n5,y\n");
+
+        // keep the rows where A changed from the row before
+        let changed = table.filter_by_indexed(|i, row| i == 0 || table.get(i - 1).unwrap().get("A") != row.get("A")).unwrap();
+
+        assert_eq!(vec![1, 2, 5], changed.iter().map(|row| row.get("A").as_integer()).collect::<Vec<_>>());
+
+        let slice = table.filter("B", &Value::String("y".to_string())).unwrap();
+
+        assert_eq!(1, slice.filter_by_indexed(|i, _| i == 1).unwrap().len());
+
+        let deltas = table.iter_windows(2).map(|w| w[1].get("A").as_integer() - w[0].get("A").as_integer()).collect::<Vec<_>>();
+
+        assert_eq!(vec![1, 0, 3], deltas);
+        assert_eq!(2, table.iter_windows(3).count());
+        assert_eq!(1, slice.iter_windows(2).count());
+        assert_eq!(0, table.iter_windows(5).count());
+        assert_eq!(0, table.iter_windows(0).count());
+    }
+
+    #[cfg(feature = "datetime-inference")]
+    #[test]
+    fn sessionize() {
+        let mut table = table_from_str("row_table_sessionize.csv", "user,time\n\
+            a,2020-01-01T10:00:00\n\
+            b,2020-01-01T10:05:00\n\
+            a,2020-01-01T10:20:00\n\
+            a,2020-01-01T11:00:00\n\
+            b,2020-01-01T10:10:00\n\
+            a,\n");
+
+        table.sessionize("session", "time", chrono::Duration::minutes(30), Some("user")).unwrap();
+
+        let sessions = table.iter().map(|row| row.get("session")).collect::<Vec<_>>();
+
+        assert_eq!(vec![Value::Integer(0), Value::Integer(2), Value::Integer(0), Value::Integer(1), Value::Integer(2), Value::Empty], sessions);
+
+        // without the user column, a and b are the same session
+        table.sessionize("all", "time", chrono::Duration::minutes(30), None).unwrap();
+
+        assert_eq!(Value::Integer(0), table.get(1).unwrap().get("all"));
+        assert_eq!(Value::Integer(1), table.get(3).unwrap().get("all"));
+
+        assert!(table.sessionize("bad", "user", chrono::Duration::minutes(30), None).is_err());
+        assert!(table.sessionize("bad", "missing", chrono::Duration::minutes(30), None).is_err());
+    }
+
+    #[cfg(feature = "datetime-inference")]
+    #[test]
+    fn dt_truncate() {
+        let mut table = table_from_str("row_table_dt_truncate.csv", "time,day,qty\n2020-05-14T10:47:31,2020-05-14,1\n,2020-12-31,2\n");
+        let dt = |s :&str| Value::DateTime(s.parse().unwrap());
+        let date = |s :&str| Value::Date(s.parse().unwrap());
+
+        table.dt_truncate("minute", "time", DateUnit::Minute).unwrap();
+        table.dt_truncate("hour", "time", DateUnit::Hour).unwrap();
+        table.dt_truncate("week", "time", DateUnit::Week).unwrap();
+        table.dt_truncate("quarter", "day", DateUnit::Quarter).unwrap();
+        table.dt_truncate("same_day", "day", DateUnit::Hour).unwrap();
+
+        let row = table.get(0).unwrap();
+
+        assert_eq!(dt("2020-05-14T10:47:00"), row.get("minute"));
+        assert_eq!(dt("2020-05-14T10:00:00"), row.get("hour"));
+        assert_eq!(dt("2020-05-11T00:00:00"), row.get("week"));
+        assert_eq!(date("2020-04-01"), row.get("quarter"));
+        assert_eq!(date("2020-05-14"), row.get("same_day"));
+        assert_eq!(Value::Empty, table.get(1).unwrap().get("hour"));
+        assert_eq!(date("2020-10-01"), table.get(1).unwrap().get("quarter"));
+
+        assert!(table.dt_truncate("bad", "qty", DateUnit::Day).is_err());
+        assert!(table.dt_truncate("bad", "missing", DateUnit::Day).is_err());
+        assert!(table.dt_truncate("hour", "time", DateUnit::Day).is_err());
+    }
+
+    #[cfg(feature = "datetime-inference")]
+    #[test]
+    fn business_days() {
+        let date = |s :&str| s.parse::<chrono::NaiveDate>().unwrap();
+        let calendar = Calendar::new().holidays(&[date("2020-01-01")]);
+
+        // Tue, Wed (holiday), Sat, Fri
+        let mut table = table_from_str("row_table_business_days.csv", "day,close\n2019-12-31,10\n2020-01-01,11\n2020-01-04T16:00:00,12\n2020-01-10,13\n,14\n");
+
+        table.is_business_day("open", "day", &calendar).unwrap();
+        table.add_business_days("next", "day", 1, &calendar).unwrap();
+        table.add_business_days("prev", "day", -1, &calendar).unwrap();
+
+        let column = |c :&str| table.iter().map(|row| row.get(c)).collect::<Vec<_>>();
+
+        assert_eq!(vec![Value::Boolean(true), Value::Boolean(false), Value::Boolean(false), Value::Boolean(true), Value::Empty], column("open"));
+        assert_eq!(vec![Value::new("2020-01-02"), Value::new("2020-01-03"), Value::new("2020-01-07T16:00:00"), Value::new("2020-01-13"), Value::Empty], column("next"));
+        assert_eq!(vec![Value::new("2019-12-30"), Value::new("2019-12-31"), Value::new("2020-01-03T16:00:00"), Value::new("2020-01-09"), Value::Empty], column("prev"));
+
+        assert!(table.is_business_day("bad", "close", &calendar).is_err());
+        assert!(table.add_business_days("bad", "day", 1, &Calendar::new().weekend(&[chrono::Weekday::Mon, chrono::Weekday::Tue, chrono::Weekday::Wed, chrono::Weekday::Thu, chrono::Weekday::Fri, chrono::Weekday::Sat, chrono::Weekday::Sun])).is_err());
+
+        // one row per business day, carrying the last close forward
+        let daily = calendar.resample(&table, "day").unwrap();
+
+        assert_eq!(table.columns(), daily.columns());
+        assert_eq!(Value::new("2019-12-31"), daily.get(0).unwrap().get("day"));
+        assert_eq!(Value::new("2020-01-10"), daily.get(daily.len() - 1).unwrap().get("day"));
+        assert_eq!(vec![10, 11, 11, 12, 12, 12, 12, 13], daily.iter().map(|row| row.get("close").as_integer()).collect::<Vec<_>>());
+        assert_eq!(0, calendar.resample(&table.filter_by(|_| false).unwrap(), "day").unwrap().len());
+        assert!(calendar.resample(&table, "close").is_err());
+    }
+
+    #[cfg(feature = "datetime-inference")]
+    #[test]
+    fn align() {
+        let dt = |s :&str| Value::DateTime(format!("2020-01-02T09:{}:00", s).parse().unwrap());
+        let aapl = table_from_str("row_table_align_aapl.csv", "time,close\n2020-01-02T09:30:00,10\n2020-01-02T09:32:00,12\n2020-01-02T09:32:00,13\n,99\n");
+        let msft = table_from_str("row_table_align_msft.csv", "close,time,volume\n20,2020-01-02T09:31:00,100\n23,2020-01-02T09:33:00,300\n");
+        let closes = |t :&RowTable| t.iter().map(|row| row.get("close")).collect::<Vec<_>>();
+
+        // the union of the times, keeping the last row at 09:32
+        let (a, m) = aapl.align(&msft, "time", Fill::FFill, None).unwrap();
+
+        assert_eq!(aapl.columns(), a.columns());
+        assert_eq!(msft.columns(), m.columns());
+        assert_eq!(vec![dt("30"), dt("31"), dt("32"), dt("33")], a.iter().map(|row| row.get("time")).collect::<Vec<_>>());
+        assert_eq!(vec![dt("30"), dt("31"), dt("32"), dt("33")], m.iter().map(|row| row.get("time")).collect::<Vec<_>>());
+        assert_eq!(vec![Value::Integer(10), Value::Integer(10), Value::Integer(13), Value::Integer(13)], closes(&a));
+        assert_eq!(vec![Value::Empty, Value::Integer(20), Value::Integer(20), Value::Integer(23)], closes(&m));
+
+        let (a, m) = aapl.align(&msft, "time", Fill::BFill, None).unwrap();
+
+        assert_eq!(vec![Value::Integer(10), Value::Integer(13), Value::Integer(13), Value::Empty], closes(&a));
+        assert_eq!(vec![Value::Integer(100), Value::Integer(100), Value::Integer(300), Value::Integer(300)], m.iter().map(|row| row.get("volume")).collect::<Vec<_>>());
+
+        // a regular grid, every 90 seconds
+        let (a, m) = aapl.align(&msft, "time", Fill::Empty, Some(chrono::Duration::seconds(90))).unwrap();
+
+        assert_eq!(3, a.len());
+        assert_eq!(Value::DateTime("2020-01-02T09:31:30".parse().unwrap()), m.get(1).unwrap().get("time"));
+        assert_eq!(vec![Value::Integer(10), Value::Empty, Value::Empty], closes(&a));
+
+        assert!(aapl.align(&msft, "time", Fill::FFill, Some(chrono::Duration::zero())).is_err());
+        assert!(aapl.align(&msft, "volume", Fill::FFill, None).is_err());
+    }
+
+    #[cfg(feature = "datetime-inference")]
+    #[test]
+    fn ohlc() {
+        let dt = |s :&str| Value::DateTime(format!("2020-01-02T{}", s).parse().unwrap());
+        // out of order within the first minute, and a 5 minute bar across two minutes
+        let table = table_from_str("row_table_ohlc.csv", "time,price,size\n2020-01-02T09:30:10,10.5,100\n2020-01-02T09:30:00,10,200\n2020-01-02T09:30:59,11,\n2020-01-02T09:30:30,9.5,50\n2020-01-02T09:31:00,12,10\n2020-01-02T09:36:00,13,5\n,99,1\n");
+
+        let bars = table.ohlc("time", "price", Frequency::Minute(1), Some("size")).unwrap();
+
+        assert_eq!(3, bars.len());
+        assert_eq!(Bar { time: dt("09:30:00"), open: Value::Integer(10), high: Value::Integer(11), low: Value::new("9.5"), close: Value::Integer(11), volume: Value::Integer(350) }, bars.bars()[0]);
+        assert_eq!(vec![dt("09:30:00"), dt("09:31:00"), dt("09:36:00")], bars.bars().iter().map(|b| b.time.clone()).collect::<Vec<_>>());
+
+        let five = table.ohlc("time", "price", Frequency::Minute(5), None).unwrap();
+
+        assert_eq!(vec![(dt("09:30:00"), Value::Integer(12)), (dt("09:35:00"), Value::Integer(13))], five.bars().iter().map(|b| (b.time.clone(), b.close.clone())).collect::<Vec<_>>());
+        assert_eq!(Value::Empty, five.bars()[0].volume);
+        assert_eq!(vec!["time", "open", "high", "low", "close"], five.to_table().columns());
+        assert_eq!(vec!["time", "open", "high", "low", "close", "volume"], bars.to_table().columns());
+
+        // daily bars of dates
+        let daily = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap().ohlc("date", "close", Frequency::Day(1), Some("volume")).unwrap();
+
+        assert_eq!(Value::new("2020-01-02"), daily.bars()[0].time);
+        assert_eq!(Value::new("160.62"), daily.bars()[0].high);
+        assert_eq!(1, table.ohlc("time", "price", Frequency::Day(1), None).unwrap().len());
+
+        assert!(table.ohlc("time", "price", Frequency::Minute(0), None).is_err());
+        assert!(table.ohlc("price", "price", Frequency::Minute(1), None).is_err());
+        assert!(table.ohlc("time", "missing", Frequency::Minute(1), None).is_err());
+    }
+
+    #[test]
+    fn digit_distribution() {
+        // powers of 2 follow Benford's law, and the digits repeated evenly don't
+        let powers = (0..63).map(|p| format!("{},{}", 1i64 << p, p % 9 + 1)).collect::<Vec<_>>().join("\n");
+        let table = table_from_str("row_table_digit_distribution.csv", &format!("power,even\n{}\n0,\n", powers));
+
+        let benford = table.digit_distribution("power").unwrap();
+
+        assert_eq!(63, benford.total());
+        assert_eq!(vec![19, 13, 6, 7, 6, 4, 2, 5, 1], (1..=9).map(|d| benford.count(d)).collect::<Vec<_>>());
+        assert_eq!(19.0 / 63.0, benford.observed(1));
+        assert!((benford.chi_square() - 4.078).abs() < 0.001);
+        assert!(benford.conforms());
+
+        let even = table.digit_distribution("even").unwrap();
+
+        assert!((1..=9).all(|d| even.count(d) == 7));
+        assert!(!even.conforms());
+        assert!(even.chi_square() > benford.chi_square());
+
+        assert_eq!(0.0, table.filter_by(|_| false).unwrap().digit_distribution("power").unwrap().chi_square());
+        assert!(table.digit_distribution("missing").is_err());
+    }
+
+    #[test]
+    fn describe_by() {
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+        let describe = table.describe_by("symbol").unwrap();
+
+        assert_eq!(vec!["open", "close", "volume"], describe.columns());
+        assert_eq!(vec![&Value::new("AAPL"), &Value::new("IBM"), &Value::new("MSFT")], describe.groups());
+
+        let aapl = describe.get(&Value::new("AAPL"), "close").unwrap();
+
+        assert_eq!(4, aapl.count);
+        assert_eq!(74.36, aapl.min);
+        assert_eq!(75.09, aapl.max);
+        assert!((aapl.median - 74.775).abs() < 1e-9);
+        assert!((aapl.q25 - 74.54).abs() < 1e-9);
+        assert!((aapl.mean - 74.75).abs() < 1e-9);
+        assert!(describe.get(&Value::new("TSLA"), "close").is_none());
+
+        let summary = describe.to_table();
+
+        assert_eq!(vec!["symbol", "statistic", "open", "close", "volume"], summary.columns());
+        assert_eq!(24, summary.len());
+        assert_eq!(Value::new("IBM"), summary.get(8).unwrap().get("symbol"));
+        assert_eq!(Value::new("count"), summary.get(8).unwrap().get("statistic"));
+        assert_eq!(Value::Integer(4), summary.get(8).unwrap().get("volume"));
+        assert_eq!(Value::new("max"), summary.get(15).unwrap().get("statistic"));
+        assert_eq!(135.42, summary.get(15).unwrap().get("close").as_float());
+
+        // a group without values in a column has a count of 0, and no other statistics
+        let sparse = table_from_str("row_table_describe_by.csv", "group,x,name\na,1,p\na,3,q\nb,,r\n");
+        let describe = sparse.describe_by("group").unwrap();
+
+        assert_eq!(vec!["x"], describe.columns());
+        assert!(describe.get(&Value::new("b"), "x").is_none());
+
+        let summary = describe.to_table();
+
+        assert_eq!(Value::Integer(0), summary.get(8).unwrap().get("x"));
+        assert_eq!(Value::Empty, summary.get(9).unwrap().get("x"));
+        assert_eq!(Value::Float(2.0.into()), summary.get(1).unwrap().get("x"));
+
+        assert!(table.describe_by("missing").is_err());
+
+        // every row is checked, so a text value that a sample of the rows misses leaves out the column
+        let mut rows = (0..20_000).map(|i| vec![Value::Integer(i % 2), Value::Integer(i)]).collect::<Vec<_>>();
+
+        rows.push(vec![Value::Integer(0), Value::String("n/a".to_string())]);
+
+        let wide = RowTable::from_rows(vec!["group".to_string(), "x".to_string()], rows);
+
+        assert_eq!(ValueType::Integer, wide.dtypes()[1].1);
+        assert!(wide.describe_by("group").unwrap().columns().is_empty());
+    }
+
+    #[test]
+    fn find_near_duplicates() {
+        let table = table_from_str("row_table_find_near_duplicates.csv", "id,name,price,qty\n1,Apple Inc,100.0,5\n2,Microsoft,200.0,3\n1,Apple Inc.,100.5,5\n2,Alphabet,50.0,3\n1,Apple Inc,100.0,5\n3,IBM,10.0,1\n");
+        let duplicates = table.find_near_duplicates(&["id"], 0.15).unwrap();
+
+        assert_eq!(1, duplicates.len());
+        assert_eq!(3, duplicates.rows());
+
+        let cluster = &duplicates.clusters()[0];
+
+        assert_eq!(vec![Value::Integer(1)], cluster.key);
+        assert_eq!(vec![0, 2, 4], cluster.rows);
+        assert_eq!(vec!["name", "price"], cluster.columns);
+        assert!((cluster.similarity - (0.9 + (1.0 - 0.5/100.5) + 1.0) / 3.0).abs() < 1e-9);
+
+        let report = duplicates.to_table();
+
+        assert_eq!(vec!["cluster", "row", "id", "similarity", "columns"], report.columns());
+        assert_eq!(3, report.len());
+        assert_eq!(Value::Integer(2), report.get(1).unwrap().get("row"));
+        assert_eq!(Value::new("name,price"), report.get(1).unwrap().get("columns"));
+
+        // without the typo, only the exact duplicates are a cluster
+        let exact = table.find_near_duplicates(&["id", "qty"], 0.05).unwrap();
+
+        assert_eq!(vec![0, 4], exact.clusters()[0].rows);
+        assert_eq!(1.0, exact.clusters()[0].similarity);
+        assert!(exact.clusters()[0].columns.is_empty());
+
+        assert!(table.find_near_duplicates(&[], 0.1).is_err());
+        assert!(table.find_near_duplicates(&["id"], 1.5).is_err());
+        assert!(table.find_near_duplicates(&["missing"], 0.1).is_err());
+    }
+
+    #[cfg(feature = "datetime-inference")]
+    #[test]
+    fn find_gaps() {
+        let dt = |s :&str| Value::DateTime(s.parse().unwrap());
+        let table = table_from_str("row_table_find_gaps.csv", "sym,time\nA,2020-01-02T09:30:00\nA,2020-01-02T09:31:00\nB,2020-01-02T09:30:00\nA,2020-01-02T09:35:00\nB,2020-01-02T09:32:00\nA,2020-01-02T09:31:00\nB,\n");
+        let minute = chrono::Duration::minutes(1);
+
+        // A is missing 09:32 to 09:34, and B 09:31
+        let gaps = table.find_gaps("time", minute, Some("sym")).unwrap();
+
+        assert_eq!(2, gaps.len());
+        assert_eq!(4, gaps.missing());
+        assert_eq!(Gap { group: Value::new("A"), start: dt("2020-01-02T09:32:00"), end: dt("2020-01-02T09:34:00"), missing: 3 }, gaps.gaps()[0]);
+        assert_eq!(Gap { group: Value::new("B"), start: dt("2020-01-02T09:31:00"), end: dt("2020-01-02T09:31:00"), missing: 1 }, gaps.gaps()[1]);
+        assert_eq!(5.0 / 9.0, gaps.completeness());
+
+        let gap_table = gaps.to_table();
+
+        assert_eq!(vec!["sym", "start", "end", "missing"], gap_table.columns());
+        assert_eq!(vec![3, 1], gap_table.iter().map(|row| row.get("missing").as_integer()).collect::<Vec<_>>());
+
+        // as one series, 09:30 to 09:35 is only missing 09:33 and 09:34
+        let gaps = table.find_gaps("time", minute, None).unwrap();
+
+        assert_eq!(vec![(dt("2020-01-02T09:33:00"), 2)], gaps.gaps().iter().map(|g| (g.start.clone(), g.missing)).collect::<Vec<_>>());
+        assert_eq!(vec!["start", "end", "missing"], gaps.to_table().columns());
+        assert!(table.find_gaps("time", chrono::Duration::minutes(5), None).unwrap().is_empty());
+
+        // more missing intervals than fit in an i32
+        let long = table_from_str("row_table_find_gaps_long.csv", "time\n2020-01-02T09:30:00\n2020-01-02T10:30:00\n");
+        let gaps = long.find_gaps("time", chrono::Duration::microseconds(1), None).unwrap();
+
+        assert_eq!(3_599_999_999, gaps.missing());
+        assert_eq!(dt("2020-01-02T10:29:59.999999"), gaps.gaps()[0].end);
+
+        assert!(table.find_gaps("sym", minute, None).is_err());
+        assert!(table.find_gaps("time", chrono::Duration::zero(), None).is_err());
+        assert!(table.find_gaps("missing", minute, None).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "timezones")]
+    fn dt_convert_tz() {
+        let mut table = table_from_str("row_table_dt_convert_tz.csv", "time\n2020-03-09T03:30:00\n2020-01-02T02:30:00\n2020-03-08T02:30:00\n");
+        let dt = |s :&str| Value::DateTime(s.parse().unwrap());
+
+        table.dt_convert_tz("new_york", "time", "UTC", "America/New_York").unwrap();
+        table.dt_convert_tz("utc", "time", "America/New_York", "UTC").unwrap();
+        table.dt_truncate("local_day", "new_york", DateUnit::Day).unwrap();
+
+        // after the clocks went forward, 4 hours behind; before, 5
+        assert_eq!(dt("2020-03-08T23:30:00"), table.get(0).unwrap().get("new_york"));
+        assert_eq!(dt("2020-01-01T21:30:00"), table.get(1).unwrap().get("new_york"));
+        assert_eq!(dt("2020-01-01T00:00:00"), table.get(1).unwrap().get("local_day"));
+
+        // 2:30 was skipped in New York that night
+        assert_eq!(Value::Empty, table.get(2).unwrap().get("utc"));
+
+        assert!(table.dt_convert_tz("bad", "time", "UTC", "Mars/Olympus_Mons").is_err());
+    }
+
+    #[test]
+    fn map_column() {
+        let mut table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        table.map_column("volume", |v| Value::Integer(v.as_integer() / 100)).unwrap();
+        table.map_column("symbol", |v| Value::String(v.as_string().to_lowercase())).unwrap();
+
+        assert_eq!(Value::Integer(1354804), table.get(0).unwrap().get("volume"));
+        assert_eq!(Value::String("aapl".to_string()), table.get(0).unwrap().get("symbol"));
+        assert!(table.map_column("missing", |v| v.clone()).is_err());
+    }
+
+    #[test]
+    fn append() {
+        let mut t1 = RowTable::new(&["A", "B"]);
+        let mut t2 = table_from_str("row_table_append.csv", "A,B\n1,2.3\n2,hello\n");
+
+        // shared with t2, so the rows are copied
+        t1.append(t2.clone()).unwrap();
+        t1.append(t2.filter_by(|row| row.get("A") == Value::Integer(2)).unwrap()).unwrap();
+
+        // not shared, so the rows are moved
+        t1.append(table_from_str("row_table_append_moved.csv", "A,B\n3,x\n")).unwrap();
+
+        assert_eq!(4, t1.len());
+        assert_eq!(2, t2.len());
+        assert_eq!(Value::new("hello"), t1.get(2).unwrap().get("B"));
+        assert_eq!(Value::new("x"), t1.get(3).unwrap().get("B"));
+
+        assert!(t1.append(table_from_str("row_table_append_swapped.csv", "B,A\n1,2\n")).is_err());
+        assert!(t1.append(table_from_str("row_table_append_wider.csv", "A,B,C\n1,2,3\n")).is_err());
+
+        assert!(t1.declare_unique("A").is_err());
+
+        t2.declare_unique("A").unwrap();
+
+        assert!(t2.append(table_from_str("row_table_append_dup.csv", "A,B\n4,y\n4,z\n")).unwrap_err().to_string().contains("repeats"));
+        assert!(t2.append(table_from_str("row_table_append_dup.csv", "A,B\n5,y\n1,z\n")).unwrap_err().to_string().contains("already in unique column A"));
+
+        t2.append(table_from_str("row_table_append_new.csv", "A,B\n6,y\n")).unwrap();
+
+        assert_eq!(3, t2.len());
+        assert_eq!(Value::new("y"), t2.loc("A", &Value::Integer(6)).unwrap().unwrap().get("B"));
+    }
+
+    #[test]
+    fn concat() {
+        let t1 = table_from_str("row_table_concat_1.csv", "A,B\n1,2\n3,4\n");
+        let t2 = table_from_str("row_table_concat_2.csv", "B,C\n5,6\n");
+
+        let t3 = t1.concat(&t2).unwrap();
+
+        assert_eq!(vec!["A", "B", "C"], t3.columns());
+        assert_eq!(3, t3.len());
+        assert_eq!(Value::Empty, t3.get(0).unwrap().get("C"));
+        assert_eq!(Value::Empty, t3.get(2).unwrap().get("A"));
+        assert_eq!(Value::Integer(5), t3.get(2).unwrap().get("B"));
+
+        let t4 = crate::concat(&[&t1, &t2, &t1]).unwrap();
+
+        assert_eq!(vec!["A", "B", "C"], t4.columns());
+        assert_eq!(5, t4.len());
+        assert_eq!(Value::Integer(3), t4.get(4).unwrap().get("A"));
+
+        // tables of different types
+        let t5 = crate::concat(&[&t1, &t2.filter_by(|_| true).unwrap()]).unwrap();
+
+        assert_eq!(3, t5.len());
+        assert_eq!(Value::Integer(6), t5.get(2).unwrap().get("C"));
+
+        assert!(crate::concat(&[]).is_err());
+    }
+
+    #[test]
+    fn hconcat() {
+        let features = table_from_str("row_table_hconcat_1.csv", "id,score\n1,0.5\n2,0.7\n");
+        let scores = table_from_str("row_table_hconcat_2.csv", "score,label\n0.9,x\n0.1,y\n");
+
+        let table = features.hconcat(&scores, "_right").unwrap();
+
+        assert_eq!(vec!["id", "score", "score_right", "label"], table.columns());
+        assert_eq!(2, table.len());
+        assert_eq!(0.7, table.get(1).unwrap().get("score").as_float());
+        assert_eq!(0.1, table.get(1).unwrap().get("score_right").as_float());
+
+        let short = table_from_str("row_table_hconcat_3.csv", "z\n1\n");
+
+        assert!(features.hconcat(&short, "_right").is_err());
+
+        // an empty suffix can't rename the clashing score column, but is fine without a clash
+        assert!(features.hconcat(&scores, "").unwrap_err().to_string().contains("score"));
+        let labels = table_from_str("row_table_hconcat_4.csv", "label\nx\ny\n");
+
+        assert_eq!(vec!["id", "score", "label"], features.hconcat(&labels, "").unwrap().columns());
+    }
+
+    #[test]
+    fn cross_join() {
+        let rates = table_from_str("row_table_cross_join_1.csv", "rate,name\n0.1,low\n0.5,high\n");
+        let depths = table_from_str("row_table_cross_join_2.csv", "depth,name\n2,a\n4,b\n8,c\n");
+
+        let grid = rates.cross_join(&depths, "_depth", 6).unwrap();
+
+        assert_eq!(vec!["rate", "name", "depth", "name_depth"], grid.columns());
+        assert_eq!(6, grid.len());
+        assert_eq!((0.5, 4), (grid.get(4).unwrap().get("rate").as_float(), grid.get(4).unwrap().get("depth").as_integer()));
+        assert_eq!((Value::new("high"), Value::new("c")), (grid.get(5).unwrap().get("name"), grid.get(5).unwrap().get("name_depth")));
+
+        // a slice works, and an empty side gives no rows
+        let deep = depths.filter_by(|r| r.get("depth").as_integer() > 2).unwrap();
+
+        assert_eq!(4, rates.cross_join(&deep, "_depth", 100).unwrap().len());
+        assert_eq!(0, rates.cross_join(&depths.filter_by(|_| false).unwrap(), "_depth", 0).unwrap().len());
+
+        let err = rates.cross_join(&depths, "_depth", 5).unwrap_err();
+
+        assert!(err.to_string().contains("more than the limit of 5 rows"), "{}", err);
+
+        // both tables have a name column, which an empty suffix can't rename
+        assert!(rates.cross_join(&depths, "", 6).unwrap_err().to_string().contains("name"));
+    }
+
+    #[test]
+    fn dropna_fillna() {
+        let mut table = table_from_str("row_table_dropna_fillna.csv", "A,B,C\n1,,x\n2,3,\n4,5,y\n");
+
+        assert_eq!(1, table.dropna(None).unwrap().len());
+        assert_eq!(2, table.dropna(Some(&["B"])).unwrap().len());
+        assert!(table.dropna(Some(&["Z"])).is_err());
+
+        table.fillna("B", &Value::Integer(0)).unwrap();
+
+        assert_eq!(Value::Integer(0), table.get(0).unwrap().get("B"));
+        assert_eq!(Value::Empty, table.get(1).unwrap().get("C"));
+
+        table.fillna_all(&Value::String("?".to_string())).unwrap();
+
+        assert_eq!(Value::String("?".to_string()), table.get(1).unwrap().get("C"));
+        assert_eq!(3, table.dropna(None).unwrap().len());
+    }
+
+    #[test]
+    fn rank_statistics() {
+        let mut table = table_from_str("row_table_ranks.csv", "g,x,y\na,1,10\na,2,100\na,3,1000\nb,4,\nb,5,0\n");
+
+        assert!((table.spearman_corr("x", "y").unwrap() + 0.2).abs() < 1e-9);
+        assert_eq!(1.0, table.filter("g", &Value::new("a")).unwrap().spearman_corr("x", "y").unwrap());
+        assert!(table.filter("g", &Value::new("b")).unwrap().spearman_corr("x", "y").is_err());
+        assert!(table.spearman_corr("x", "z").is_err());
+
+        table.percent_rank("x_pct", "x", None).unwrap();
+        table.percent_rank("y_pct", "y", Some("g")).unwrap();
+
+        assert_eq!(Value::Float(0.2.into()), table.get(0).unwrap().get("x_pct"));
+        assert_eq!(Value::Float(1.0.into()), table.get(4).unwrap().get("x_pct"));
+        assert_eq!(Value::Float((2.0 / 3.0).into()), table.get(1).unwrap().get("y_pct"));
+        assert_eq!(Value::Empty, table.get(3).unwrap().get("y_pct"));
+        assert_eq!(Value::Float(1.0.into()), table.get(4).unwrap().get("y_pct"));
+    }
+
+    #[test]
+    fn scaling() {
+        let mut table = table_from_str("row_table_scaling.csv", "A,B,C,D\n1,10,5,x\n2,,5,y\n3,30,5,z\n");
+
+        table.standardize(&["A", "C"]).unwrap();
+        table.min_max_scale(&["B"]).unwrap();
+
+        assert_eq!(vec![-1.0, 0.0, 1.0], table.iter().map(|r| r.get("A").as_float()).collect::<Vec<_>>());
+        assert_eq!(Value::Float(0.0.into()), table.get(0).unwrap().get("C"));
+        assert_eq!(Value::Float(0.0.into()), table.get(0).unwrap().get("B"));
+        assert_eq!(Value::Empty, table.get(1).unwrap().get("B"));
+        assert_eq!(Value::Float(1.0.into()), table.get(2).unwrap().get("B"));
+
+        // nothing changes when one of the columns isn't numeric
+        assert!(table.min_max_scale(&["C", "D"]).is_err());
+        assert_eq!(Value::Float(0.0.into()), table.get(0).unwrap().get("C"));
+        assert!(table.standardize(&["E"]).is_err());
+    }
+
+    #[test]
+    fn winsorize() {
+        let values = (1..=10).chain(Some(100)).map(|v| v.to_string()).collect::<Vec<_>>().join(",\n");
+        let mut table = table_from_str("row_table_winsorize.csv", &format!("A,B\n{},\n,\n", values));
+
+        table.winsorize_to("A", 0.1, 0.9, "capped").unwrap();
+        table.winsorize("A", 0.1, 0.9).unwrap();
+
+        let capped = table.iter().map(|r| r.get("A")).collect::<Vec<_>>();
+
+        assert_eq!(Value::Float(2.0.into()), capped[0]);
+        assert_eq!(Value::Integer(5), capped[4]);
+        assert_eq!(Value::Float(10.0.into()), capped[10]);
+        assert_eq!(Value::Empty, capped[11]);
+        assert_eq!(capped, table.iter().map(|r| r.get("capped")).collect::<Vec<_>>());
+
+        // nothing changes for quantiles out of order, or values that aren't numbers
+        assert!(table.winsorize("A", 0.9, 0.1).is_err());
+        assert!(table.winsorize("A", 0.0, 1.5).is_err());
+        table.get(0).unwrap().set("B", Value::new("x")).unwrap();
+        assert!(table.winsorize("B", 0.1, 0.9).is_err());
+        assert!(table.winsorize("missing", 0.1, 0.9).is_err());
+    }
+
+    #[test]
+    fn group_transform() {
+        let mut table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        table.group_transform("symbol", "close", Agg::Mean, "mean_close").unwrap();
+        table.group_transform("symbol", "volume", Agg::Count, "days").unwrap();
+
+        let aapl = table.filter("symbol", &Value::new("AAPL")).unwrap();
+        let mean = aapl.iter().map(|r| r.get("close").as_float()).sum::<f64>() / aapl.len() as f64;
+
+        assert!(aapl.iter().all(|r| r.get("mean_close") == Value::Float(mean.into())));
+        assert!(table.iter().all(|r| r.get("days") == Value::Integer(4)));
+        assert!(table.group_transform("symbol", "date", Agg::Mean, "bad").is_err());
+        assert!(table.group_transform("missing", "close", Agg::Mean, "bad").is_err());
+    }
+
+    #[test]
+    fn where_assign() {
+        let mut table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        table.where_assign(|r| r.get("close").as_float() > 150.0, "flag", &Value::Integer(1), &Value::Integer(0)).unwrap();
+
+        assert_eq!(4, table.filter("flag", &Value::Integer(1)).unwrap().len());
+        assert_eq!(Value::Integer(0), table.get(0).unwrap().get("flag"));
+
+        // overwriting an existing column
+        table.where_assign(|r| r.get("symbol") == Value::new("IBM"), "flag", &Value::Boolean(true), &Value::Empty).unwrap();
+
+        assert_eq!(4, table.filter("flag", &Value::Boolean(true)).unwrap().len());
+        assert_eq!(8, table.filter("flag", &Value::Empty).unwrap().len());
+        assert_eq!(6, table.width());
+    }
+
+    #[test]
+    fn clip_round() {
+        let mut table = table_from_str("row_table_clip_round.csv", "A,B,C\n-5,1.25,x\n3,-1.75,y\n250,,z\n");
+
+        table.clip("A", 0.0, 100.0).unwrap();
+
+        assert_eq!(vec![0, 3, 100], table.iter().map(|r| r.get("A").as_integer()).collect::<Vec<_>>());
+        assert_eq!(Value::Integer(100), table.get(2).unwrap().get("A"));
+
+        table.clip("A", 0.5, 99.5).unwrap();
+
+        assert_eq!(Value::Float(0.5.into()), table.get(0).unwrap().get("A"));
+
+        // clones share their rows, so load copies
+        let mut floor = table_from_str("row_table_floor.csv", "B\n1.25\n-1.75\n");
+        let mut ceil = table_from_str("row_table_ceil.csv", "B\n1.25\n-1.75\n");
+
+        table.round("B", 1).unwrap();
+        floor.floor("B").unwrap();
+        ceil.ceil("B").unwrap();
+
+        assert_eq!(Value::Float(1.3.into()), table.get(0).unwrap().get("B"));
+        assert_eq!(Value::Float((-1.8).into()), table.get(1).unwrap().get("B"));
+        assert_eq!(Value::Empty, table.get(2).unwrap().get("B"));
+        assert_eq!(Value::Float((-2.0).into()), floor.get(1).unwrap().get("B"));
+        assert_eq!(Value::Float(2.0.into()), ceil.get(0).unwrap().get("B"));
+
+        table.round("A", -1).unwrap();
+
+        assert_eq!(Value::Integer(0), table.get(1).unwrap().get("A"));
+        assert!(table.round("C", 0).is_err());
+        assert!(table.clip("C", 0.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn display() {
+        let table = RowTable::from_csv(fixtures::path("stocks.csv")).unwrap();
+
+        let preview = table.to_string();
+        let lines = preview.lines().collect::<Vec<_>>();
+
+        assert_eq!(13, lines.len());
+        assert_eq!("           date  symbol    open   close     volume", lines[0]);
+        assert_eq!("  0  2020-01-02    AAPL   74.06   75.09  135480400", lines[1]);
+        assert_eq!("...         ...     ...     ...     ...        ...", lines[6]);
+        assert!(lines[11].starts_with(" 11"));
+        assert_eq!("[12 rows x 5 columns]", lines[12]);
+
+        let short = table.filter("symbol", &Value::new("IBM")).unwrap().preview(4, 4);
+
+        assert_eq!(vec!["   date  s...  open  c...  v...", "0  2...   IBM  1...  1...  3...", "1  2...   IBM  1...  1...  2..."], short.lines().take(3).collect::<Vec<_>>());
+        assert!(short.ends_with("[4 rows x 5 columns]"));
+        assert_eq!("  A\n[0 rows x 1 columns]", RowTable::new(&["A"]).preview(10, 10));
+    }
+
+    #[test]
+    fn string_ops() {
+        let mut table = table_from_str("row_table_string_ops.csv", "zip,code,name\n501,-7,Alice\n02134,,Bob\n");
+
+        table.zfill("zip", 5).unwrap();
+        table.zfill("code", 3).unwrap();
+
+        assert_eq!(Value::String("00501".to_string()), table.get(0).unwrap().get("zip"));
+        assert_eq!(Value::String("02134".to_string()), table.get(1).unwrap().get("zip"));
+        assert_eq!(Value::String("-07".to_string()), table.get(0).unwrap().get("code"));
+        assert_eq!(Value::Empty, table.get(1).unwrap().get("code"));
+
+        table.substring("zip", 1, 2).unwrap();
+
+        assert_eq!(Value::String("05".to_string()), table.get(0).unwrap().get("zip"));
+
+        table.pad_left("name", 6, '*').unwrap();
+
+        assert_eq!(Value::String("*Alice".to_string()), table.get(0).unwrap().get("name"));
+
+        table.pad_right("name", 7, '.').unwrap();
+
+        assert_eq!(Value::String("***Bob.".to_string()), table.get(1).unwrap().get("name"));
+
+        table.substring("name", 10, 2).unwrap();
+
+        assert_eq!(Value::String(String::new()), table.get(1).unwrap().get("name"));
+        assert!(table.zfill("missing", 3).is_err());
+    }
+
+    #[test]
+    fn drop_duplicates() {
+        let table = table_from_str("row_table_drop_duplicates.csv", "A,B,C\n1,x,a\n2,y,b\n1,x,c\n1,x,a\n");
+
+        let first = table.drop_duplicates(Some(&["A", "B"]), Keep::First).unwrap();
+        let last = table.drop_duplicates(Some(&["A", "B"]), Keep::Last).unwrap();
+
+        assert_eq!(vec!["a", "b"], first.iter().map(|r| r.get("C").as_string()).collect::<Vec<_>>());
+        assert_eq!(vec!["b", "a"], last.iter().map(|r| r.get("C").as_string()).collect::<Vec<_>>());
+        assert_eq!(3, table.drop_duplicates(None, Keep::First).unwrap().len());
+        assert_eq!(1, first.drop_duplicates(Some(&["B"]), Keep::First).unwrap().filter("B", &Value::new("y")).unwrap().len());
+        assert!(table.drop_duplicates(Some(&["Z"]), Keep::First).is_err());
     }
 }
 
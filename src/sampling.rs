@@ -0,0 +1,101 @@
+//! Random samples of the rows of a table, such as for building a training set from a large file.
+//!
+//! The samples are the positions of the chosen rows, in table order; the same seed always chooses the same rows.
+use std::collections::HashMap;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::{index, SliceRandom};
+
+use crate::{TableOperations, TableError, Row, Value};
+
+/// The positions of `n` rows chosen at random out of `len`, or all of them when `n >= len`, in ascending order.
+fn sample_positions(rng :&mut StdRng, len :usize, n :usize) -> Vec<usize> {
+    let mut positions = index::sample(rng, len, n.min(len)).into_vec();
+
+    positions.sort_unstable();
+    positions
+}
+
+pub(crate) fn sample_n<T: TableOperations + ?Sized>(table :&T, n :usize, seed :u64) -> Vec<usize> {
+    sample_positions(&mut StdRng::seed_from_u64(seed), table.len(), n)
+}
+
+pub(crate) fn sample_frac<T: TableOperations + ?Sized>(table :&T, frac :f64, seed :u64) -> Result<Vec<usize>, TableError> {
+    if !(0.0..=1.0).contains(&frac) {
+        let err_str = format!("Sample fraction must be between 0 and 1: {}", frac);
+        return Err(TableError::new(err_str.as_str()));
+    }
+
+    let len = table.len();
+
+    Ok(sample_positions(&mut StdRng::seed_from_u64(seed), len, (frac * len as f64).round() as usize))
+}
+
+pub(crate) fn stratified<T: TableOperations + ?Sized>(table :&T, column :&str, n_per_group :usize, seed :u64) -> Result<Vec<usize>, TableError> {
+    let pos = table.column_position(column)?;
+    let mut groups :HashMap<Value, Vec<usize>> = HashMap::new();
+
+    for (i, row) in table.iter().enumerate() {
+        groups.entry(row.try_at(pos)?).or_default().push(i);
+    }
+
+    // sample the groups in order of their values, so the same seed chooses the same rows
+    let mut groups = groups.into_iter().collect::<Vec<_>>();
+
+    groups.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut positions = Vec::new();
+
+    for (_, rows) in groups {
+        positions.extend(sample_positions(&mut rng, rows.len(), n_per_group).into_iter().map(|i| rows[i]));
+    }
+
+    positions.sort_unstable();
+
+    Ok(positions)
+}
+
+/// The fold of each row, from 0 to `n - 1`, in table order. The rows are shuffled, then dealt out to the folds in turn;
+/// with a `stratify` column the rows of each of its values are dealt out separately, continuing from the fold after
+/// the last row of the value before, so each fold gets its share of every value.
+pub(crate) fn kfold<T: TableOperations + ?Sized>(table :&T, n :usize, seed :u64, stratify :Option<&str>) -> Result<Vec<Value>, TableError> {
+    if n < 2 {
+        let err_str = format!("Cross-validation needs at least 2 folds: {}", n);
+        return Err(TableError::new(err_str.as_str()));
+    }
+
+    let groups = match stratify {
+        Some(column) => {
+            let pos = table.column_position(column)?;
+            let mut groups :HashMap<Value, Vec<usize>> = HashMap::new();
+
+            for (i, row) in table.iter().enumerate() {
+                groups.entry(row.try_at(pos)?).or_default().push(i);
+            }
+
+            // deal out the groups in order of their values, so the same seed gives the same folds
+            let mut groups = groups.into_iter().collect::<Vec<_>>();
+
+            groups.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            groups.into_iter().map(|(_, rows)| rows).collect()
+        },
+        None => vec![(0..table.len()).collect::<Vec<_>>()]
+    };
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut folds = vec![Value::Empty; table.len()];
+    let mut next = 0;
+
+    for mut rows in groups {
+        rows.shuffle(&mut rng);
+
+        for i in rows {
+            folds[i] = Value::Integer(next as i64);
+            next = (next + 1) % n;
+        }
+    }
+
+    Ok(folds)
+}
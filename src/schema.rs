@@ -0,0 +1,122 @@
+//! Column types by name, for loading a CSV file without listing the type of every column.
+use std::io::{Error as IOError, ErrorKind};
+
+use crate::{TableOperations, TableError, Row, Value, ValueType};
+
+/// The most rows [`dtypes`](trait.TableOperations.html#method.dtypes) infers the type of a column from
+pub const DTYPES_SAMPLE_ROWS :usize = 10_000;
+
+/// The types of some of the columns in a CSV file; the types of the other columns are inferred value-by-value.
+///
+/// It can also list tokens, such as `"NULL"`, that are read as `Value::Empty` in any column, instead of failing to
+/// parse with the column's type.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Schema {
+    columns: Vec<(String, ValueType)>,
+    nulls: Vec<String>
+}
+
+impl Schema {
+    /// Creates a schema from `(column, type)` pairs
+    pub fn new<S: ToString>(columns :&[(S, ValueType)]) -> Self {
+        Schema { columns: columns.iter().map(|(c, t)| (c.to_string(), t.clone())).collect(), nulls: Vec::new() }
+    }
+
+    /// Sets the type of a column, replacing any type it already has.
+    pub fn set<S: ToString>(&mut self, column :S, value_type :ValueType) {
+        let column = column.to_string();
+
+        match self.columns.iter_mut().find(|(c, _)| *c == column) {
+            Some(entry) => entry.1 = value_type,
+            None => self.columns.push( (column, value_type) )
+        }
+    }
+
+    /// Sets the tokens read as `Value::Empty`, replacing any the schema already has.
+    pub fn set_null_values<S: ToString>(&mut self, tokens :&[S]) {
+        self.nulls = tokens.iter().map(|t| t.to_string()).collect();
+    }
+
+    /// The tokens read as `Value::Empty`, besides empty fields
+    pub fn null_values(&self) -> &[String] {
+        &self.nulls
+    }
+
+    /// The type of a column, or `None` if it's inferred
+    pub fn get(&self, column :&str) -> Option<&ValueType> {
+        self.columns.iter().find(|(c, _)| c == column).map(|(_, t)| t)
+    }
+
+    /// The type of each of `columns`, returning an error if the schema has a column that isn't one of them.
+    pub(crate) fn types_for(&self, columns :&[String]) -> Result<Vec<Option<ValueType>>, IOError> {
+        if let Some((column, _)) = self.columns.iter().find(|(c, _)| !columns.contains(c)) {
+            let err_str = format!("Schema column not found in the file: {}", column);
+            return Err(IOError::new(ErrorKind::InvalidInput, err_str.as_str()));
+        }
+
+        Ok(columns.iter().map(|c| self.get(c).cloned()).collect())
+    }
+}
+
+/// The type of each column of `table`: its declared type, or else the type of the values of up to `sample` rows spread
+/// evenly through the table, or of every row without a `sample`.
+pub(crate) fn column_types<T: TableOperations + ?Sized>(table :&T, sample :Option<usize>) -> Vec<(String, ValueType)> {
+    let columns = table.columns();
+    let declared = columns.iter().map(|c| table.declared_type(c)).collect::<Vec<_>>();
+    let mut types = vec![ValueType::Empty; columns.len()];
+
+    if declared.iter().any(|t| t.is_none()) {
+        let step = sample.map_or(1, |s| table.len().div_ceil(s.max(1)).max(1));
+
+        for row in table.iter().step_by(step) {
+            for (i, t) in types.iter_mut().enumerate().filter(|(i, _)| declared[*i].is_none()) {
+                *t = std::mem::replace(t, ValueType::Empty).merge(row.at(i).value_type());
+            }
+        }
+    }
+
+    columns.into_iter().zip(declared.into_iter().zip(types)).map(|(c, (d, t))| (c, d.unwrap_or(t))).collect()
+}
+
+/// The values of `column` converted to `value_type`, by parsing their text with it, or an error for each value that
+/// can't be.
+pub(crate) fn convert<T: TableOperations + ?Sized>(table :&T, column :&str, value_type :&ValueType) -> Result<Vec<Result<Value, TableError>>, TableError> {
+    let pos = table.column_position(column)?;
+
+    table.iter().enumerate().map(|(i, row)| {
+        Ok(match row.try_at(pos)? {
+            Value::Empty => Ok(Value::Empty),
+            value => {
+                let text = value.as_string();
+
+                Value::try_with_type(&text, value_type).map_err(|_| conversion_error(i, column, &text, value_type))
+            }
+        })
+    }).collect()
+}
+
+/// The error for a value of `column` that can't be converted to `value_type`
+pub(crate) fn conversion_error(row :usize, column :&str, text :&str, value_type :&ValueType) -> TableError {
+    let err_str = format!("Row {}: cannot convert {:?} in column {} to {}", row, text, column, value_type);
+    TableError::new(err_str.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Schema, ValueType};
+
+    #[test]
+    fn types_for() {
+        let mut schema = Schema::new(&[("price", ValueType::Float), ("date", ValueType::Date)]);
+
+        schema.set("date", ValueType::String);
+
+        let columns = ["id", "date", "price"].iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(vec![None, Some(ValueType::String), Some(ValueType::Float)], schema.types_for(&columns).unwrap());
+
+        schema.set("missing", ValueType::Integer);
+
+        assert!(schema.types_for(&columns).is_err());
+    }
+}
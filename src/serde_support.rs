@@ -0,0 +1,96 @@
+//! Reading rows into, and appending rows from, types that implement serde's traits, using the same mapping of struct
+//! fields to columns by name as the `csv` crate.
+use std::marker::PhantomData;
+
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Row, TableError, Value, ValueRef};
+
+/// Iterator over the rows of a table deserialized to `T`, returned by
+/// [`TableOperations::iter_as`](trait.TableOperations.html#method.iter_as).
+pub struct DeserializeIter<I, T> {
+    iter: I,
+    headers: StringRecord,
+    row_type: PhantomData<fn() -> T>
+}
+
+impl <I, T> DeserializeIter<I, T> {
+    pub(crate) fn new(iter :I, columns :&[String]) -> Self {
+        DeserializeIter { iter, headers: StringRecord::from(columns.to_vec()), row_type: PhantomData }
+    }
+}
+
+impl <I: Iterator, T: DeserializeOwned> Iterator for DeserializeIter<I, T> where I::Item: Row {
+    type Item = Result<T, TableError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.iter.next()?;
+
+        let record = match (0..self.headers.len()).map(|i| row.try_at(i)).collect::<Result<Vec<_>, _>>() {
+            Ok(values) => values.into_iter().map(|v| if v == Value::Empty { String::new() } else { v.as_string() }).collect::<StringRecord>(),
+            Err(e) => return Some(Err(e))
+        };
+
+        Some(record.deserialize(Some(&self.headers)).map_err(|e| TableError::new(e.to_string().as_str())))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// A row serialized from a struct, with its fields parsed like the fields of a CSV file
+pub(crate) struct SerializedRow {
+    columns: Vec<String>,
+    values: Vec<Value>
+}
+
+impl SerializedRow {
+    pub(crate) fn new<T: Serialize>(value :&T) -> Result<SerializedRow, TableError> {
+        let to_err = |e :csv::Error| TableError::new(e.to_string().as_str());
+
+        let mut writer = WriterBuilder::new().from_writer(Vec::new());
+
+        writer.serialize(value).map_err(to_err)?;
+
+        let bytes = writer.into_inner().map_err(|e| TableError::new(e.to_string().as_str()))?;
+        let mut reader = ReaderBuilder::new().from_reader(bytes.as_slice());
+
+        let columns = reader.headers().map_err(to_err)?.iter().map(String::from).collect::<Vec<_>>();
+
+        let values = match reader.records().next() {
+            Some(record) => record.map_err(to_err)?.iter().map(Value::new).collect(),
+            None => return Err(TableError::new("Only structs and maps with named fields can be appended"))
+        };
+
+        Ok(SerializedRow { columns, values })
+    }
+}
+
+impl Row for SerializedRow {
+    fn try_get(&self, column :&str) -> Result<Value, TableError> {
+        match self.columns.iter().position(|c| c == column) {
+            Some(i) => Ok(self.values[i].clone()),
+            None => {
+                let err_str = format!("Field not found: {}", column);
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+
+    fn try_at_ref(&self, index :usize) -> Result<ValueRef<'_>, TableError> {
+        match self.values.get(index) {
+            Some(value) => Ok(ValueRef::from(value)),
+            None => {
+                let err_str = format!("Index {} is beyond row width {}", index, self.values.len());
+                Err(TableError::new(err_str.as_str()))
+            }
+        }
+    }
+
+    fn columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+}
@@ -0,0 +1,170 @@
+//! Index files for memory-mapped tables, written by `MMapTable::save_index`, so re-opening a large CSV file doesn't
+//! have to find every row again.
+//!
+//! An index file is little-endian binary:
+//! ```text
+//! magic        b"LTINDEX2"
+//! file length  u64
+//! fingerprint  u64, FNV-1a of the file length and its contents, 8 bytes at a time
+//! columns      u64 count, then each name as a u64 length and UTF-8 bytes, and its type the same way, empty for none
+//! rows         u64 count, then the u64 offset of each row
+//! ```
+//! The fingerprint hashes the whole file, so any edit is detected, even one that keeps its length; the stale offsets
+//! of the rows would otherwise return the wrong rows.
+use std::convert::{TryFrom, TryInto};
+use std::io::{Error as IOError, ErrorKind, Read, Write, BufReader, BufWriter};
+use std::fs::File;
+use std::path::Path;
+
+use crate::ValueType;
+
+const MAGIC :&[u8; 8] = b"LTINDEX2";
+
+/// The columns, their types, and the offsets of the rows of a memory-mapped CSV file
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Snapshot {
+    pub columns: Vec<String>,
+    pub types: Vec<Option<ValueType>>,
+    pub rows: Vec<usize>
+}
+
+impl Snapshot {
+    /// Writes the index of `data`, the contents of the CSV file, to `path`.
+    pub fn write<P: AsRef<Path>>(&self, path :P, data :&[u8]) -> Result<(), IOError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(MAGIC)?;
+        write_u64(&mut writer, data.len())?;
+        writer.write_all(&fingerprint(data).to_le_bytes())?;
+
+        write_u64(&mut writer, self.columns.len())?;
+
+        for (i, column) in self.columns.iter().enumerate() {
+            write_str(&mut writer, column)?;
+            write_str(&mut writer, self.types.get(i).cloned().flatten().map(|t| t.to_string()).unwrap_or_default().as_str())?;
+        }
+
+        write_u64(&mut writer, self.rows.len())?;
+
+        for &row in self.rows.iter() {
+            write_u64(&mut writer, row)?;
+        }
+
+        writer.flush()
+    }
+
+    /// Reads the index at `path`, returning an error if it isn't the index of `data`.
+    pub fn read<P: AsRef<Path>>(path :P, data :&[u8]) -> Result<Snapshot, IOError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(IOError::new(ErrorKind::InvalidData, "Not an index file, or one from another version"));
+        }
+
+        let len = read_u64(&mut reader)?;
+        let mut hash = [0u8; 8];
+
+        reader.read_exact(&mut hash)?;
+
+        if len != data.len() || u64::from_le_bytes(hash) != fingerprint(data) {
+            return Err(IOError::new(ErrorKind::InvalidData, "Index file does not match the CSV file; it was changed after the index was saved"));
+        }
+
+        let column_count = read_u64(&mut reader)?;
+        let mut columns = Vec::with_capacity(column_count.min(1024));
+        let mut types = Vec::with_capacity(column_count.min(1024));
+
+        for _ in 0..column_count {
+            columns.push(read_str(&mut reader)?);
+
+            let value_type = read_str(&mut reader)?;
+
+            types.push(if value_type.is_empty() {
+                None
+            } else {
+                Some(value_type.parse::<ValueType>().map_err(|_| invalid(format!("Invalid type in index file: {}", value_type)))?)
+            });
+        }
+
+        let row_count = read_u64(&mut reader)?;
+        let mut rows = Vec::with_capacity(row_count.min(len));
+
+        for _ in 0..row_count {
+            let row = read_u64(&mut reader)?;
+
+            if row >= len {
+                return Err(invalid(format!("Row offset beyond the end of the file: {}", row)));
+            }
+
+            rows.push(row);
+        }
+
+        // types are all or nothing, like the ones from a metadata sidecar
+        if types.iter().all(|t| t.is_none()) {
+            types.clear();
+        }
+
+        Ok(Snapshot { columns, types, rows })
+    }
+}
+
+/// FNV-1a hash of the length of `data` and all of its bytes, taken 8 at a time, and rotated so every byte affects
+/// every bit, so hashing a large file is quick
+fn fingerprint(data :&[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut update = |word :u64| {
+        hash = (hash ^ word).wrapping_mul(0x100000001b3).rotate_left(29);
+    };
+
+    update(data.len() as u64);
+
+    let mut words = data.chunks_exact(8);
+
+    for word in &mut words {
+        update(u64::from_le_bytes(word.try_into().unwrap()));
+    }
+
+    let mut last = [0u8; 8];
+
+    last[..words.remainder().len()].copy_from_slice(words.remainder());
+    update(u64::from_le_bytes(last));
+
+    hash
+}
+
+fn invalid(err_str :String) -> IOError {
+    IOError::new(ErrorKind::InvalidData, err_str.as_str())
+}
+
+fn write_u64<W: Write>(writer :&mut W, value :usize) -> Result<(), IOError> {
+    writer.write_all(&(value as u64).to_le_bytes())
+}
+
+fn read_u64<R: Read>(reader :&mut R) -> Result<usize, IOError> {
+    let mut bytes = [0u8; 8];
+
+    reader.read_exact(&mut bytes)?;
+
+    usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| invalid("Value in index file is too large".to_string()))
+}
+
+fn write_str<W: Write>(writer :&mut W, s :&str) -> Result<(), IOError> {
+    write_u64(writer, s.len())?;
+    writer.write_all(s.as_bytes())
+}
+
+fn read_str<R: Read>(reader :&mut R) -> Result<String, IOError> {
+    let len = read_u64(reader)?;
+    let mut bytes = Vec::new();
+
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+
+    if bytes.len() != len {
+        return Err(IOError::new(ErrorKind::UnexpectedEof, "Index file is truncated"));
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid("Invalid UTF-8 in index file".to_string()))
+}
@@ -0,0 +1,330 @@
+//! Statistics shared by the table operations.
+use std::ops::{Bound, RangeBounds};
+
+use crate::{TableOperations, TableError, Row, Value};
+
+/// The non-empty values of a column as numbers, returning an error if any aren't numbers.
+pub(crate) fn numeric_values<T: TableOperations + ?Sized>(table :&T, column :&str) -> Result<Vec<f64>, TableError> {
+    table.column_position(column)?;
+
+    let mut values = Vec::new();
+
+    for row in table.iter() {
+        match row.get(column) {
+            Value::Empty => continue,
+            value => match value.try_as_float() {
+                Some(f) => values.push(f),
+                None => {
+                    let err_str = format!("Non-numeric value in column {}: {}", column, value);
+                    return Err(TableError::new(err_str.as_str()));
+                }
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+/// Mean and sample standard deviation; the deviation is 0 for fewer than two values.
+pub(crate) fn mean_std(values :&[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+
+    let var = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (values.len() - 1) as f64;
+
+    (mean, var.sqrt())
+}
+
+/// The `q` quantile, from 0 to 1, of sorted values, interpolating between the values on either side of it.
+pub(crate) fn quantile(sorted :&[f64], q :f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (pos.floor() as usize, pos.ceil() as usize);
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+/// The `lower_q` and `upper_q` quantiles of the non-empty values of a column, or infinite bounds when it has none;
+/// returns an error if a value isn't a number, or the quantiles aren't in order from 0 to 1.
+pub(crate) fn quantile_bounds<T: TableOperations + ?Sized>(table :&T, column :&str, lower_q :f64, upper_q :f64) -> Result<(f64, f64), TableError> {
+    if !(0.0 <= lower_q && lower_q <= upper_q && upper_q <= 1.0) {
+        let err_str = format!("Quantiles must be from 0 to 1, the lower first: {}, {}", lower_q, upper_q);
+        return Err(TableError::new(err_str.as_str()));
+    }
+
+    let mut values = numeric_values(table, column)?;
+
+    if values.is_empty() {
+        return Ok( (f64::NEG_INFINITY, f64::INFINITY) );
+    }
+
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+
+    Ok( (quantile(&values, lower_q), quantile(&values, upper_q)) )
+}
+
+/// Caps a number at `lower` and `upper`, as a float; numbers between them, and other values, are unchanged.
+pub(crate) fn cap(value :&Value, lower :f64, upper :f64) -> Value {
+    match value.try_as_float() {
+        Some(f) if f < lower => Value::Float(lower.into()),
+        Some(f) if f > upper => Value::Float(upper.into()),
+        _ => value.clone()
+    }
+}
+
+/// The rank of each value, from 1, with tied values getting the average of their ranks.
+pub(crate) fn average_ranks(values :&[Value]) -> Vec<f64> {
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+
+    order.sort_by(|&a, &b| values[a].cmp(&values[b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+
+    while start < order.len() {
+        let mut end = start + 1;
+
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+
+        // positions start..end are ties, ranked start+1..=end
+        let rank = (start + 1 + end) as f64 / 2.0;
+
+        for &i in order[start..end].iter() {
+            ranks[i] = rank;
+        }
+
+        start = end;
+    }
+
+    ranks
+}
+
+/// Pearson correlation coefficient of `x` and `y`, or `None` when either has no variance.
+pub(crate) fn pearson(x :&[f64], y :&[f64]) -> Option<f64> {
+    let n = x.len() as f64;
+    let (mean_x, mean_y) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+
+    for (a, b) in x.iter().zip(y.iter()) {
+        cov += (a - mean_x) * (b - mean_y);
+        var_x += (a - mean_x) * (a - mean_x);
+        var_y += (b - mean_y) * (b - mean_y);
+    }
+
+    if var_x == 0.0 || var_y == 0.0 || x.is_empty() {
+        return None;
+    }
+
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+/// Statistics of a column, stored by `compute_stats` and returned by
+/// [`column_stats`](trait.TableOperations.html#method.column_stats)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnStats {
+    /// The smallest non-empty value, or `None` when there are none, or they aren't all comparable with each other
+    pub min: Option<Value>,
+    /// The largest non-empty value, or `None` when there are none, or they aren't all comparable with each other
+    pub max: Option<Value>,
+    /// The number of non-empty values
+    pub count: usize,
+    /// The number of empty values
+    pub null_count: usize
+}
+
+impl ColumnStats {
+    /// Whether no value of the column can be in `range`, so a filter on the range can skip reading the column.
+    pub(crate) fn excludes<R: RangeBounds<Value>>(&self, range :&R) -> bool {
+        if self.count == 0 {
+            return true;
+        }
+
+        let (min, max) = match (&self.min, &self.max) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return false
+        };
+
+        // a bound that isn't comparable with the values can't be matched by any of them
+        let after_max = match range.start_bound() {
+            Bound::Included(b) => !min.is_comparable(b) || b > max,
+            Bound::Excluded(b) => !min.is_comparable(b) || b >= max,
+            Bound::Unbounded => false
+        };
+        let before_min = match range.end_bound() {
+            Bound::Included(b) => !min.is_comparable(b) || b < min,
+            Bound::Excluded(b) => !min.is_comparable(b) || b <= min,
+            Bound::Unbounded => false
+        };
+
+        after_max || before_min
+    }
+}
+
+/// The statistics of every column of `table`, in a single pass over the rows.
+pub(crate) fn column_stats<T: TableOperations + ?Sized>(table :&T) -> Vec<ColumnStats> {
+    let width = table.width();
+    let mut stats = vec![ColumnStats { min: None, max: None, count: 0, null_count: 0 }; width];
+    let mut comparable = vec![true; width];
+
+    for row in table.iter() {
+        for (i, s) in stats.iter_mut().enumerate() {
+            let value = row.try_at(i).unwrap_or(Value::Empty);
+
+            if value == Value::Empty {
+                s.null_count += 1;
+                continue;
+            }
+
+            s.count += 1;
+
+            if !comparable[i] {
+                continue;
+            }
+
+            match (&s.min, &s.max) {
+                (Some(min), _) if !min.is_comparable(&value) => {
+                    comparable[i] = false;
+                    s.min = None;
+                    s.max = None;
+                },
+                (Some(min), Some(max)) => {
+                    if value < *min {
+                        s.min = Some(value);
+                    } else if value > *max {
+                        s.max = Some(value);
+                    }
+                },
+                _ => {
+                    s.min = Some(value.clone());
+                    s.max = Some(value);
+                }
+            }
+        }
+    }
+
+    stats
+}
+
+/// Aggregations of the values in a group of rows, ignoring empty values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "pipeline", derive(serde::Serialize, serde::Deserialize))]
+pub enum Agg {
+    /// The number of non-empty values
+    Count,
+    /// The sum, which is an integer when all the values are
+    Sum,
+    Mean,
+    Median,
+    /// The sample standard deviation
+    Std,
+    /// The smallest value, using the ordering of `Value`
+    Min,
+    /// The largest value, using the ordering of `Value`
+    Max
+}
+
+impl Agg {
+    /// Aggregates `values`, returning `Value::Empty` when there are none, except for `Count`.
+    ///
+    /// Returns an error if the aggregation needs numbers and a value isn't one.
+    pub fn apply(&self, values :&[Value]) -> Result<Value, TableError> {
+        let values = values.iter().filter(|v| **v != Value::Empty).collect::<Vec<_>>();
+
+        match self {
+            Agg::Count => return Ok(Value::Integer(values.len() as i64)),
+            Agg::Min => return Ok(values.iter().min().map_or(Value::Empty, |v| (*v).clone())),
+            Agg::Max => return Ok(values.iter().max().map_or(Value::Empty, |v| (*v).clone())),
+            _ if values.is_empty() => return Ok(Value::Empty),
+            _ => ()
+        }
+
+        if *self == Agg::Sum && values.iter().all(|v| matches!(v, Value::Integer(_))) {
+            if let Some(sum) = values.iter().try_fold(0i64, |sum, v| sum.checked_add(v.as_integer())) {
+                return Ok(Value::Integer(sum));
+            }
+        }
+
+        let mut numbers = values.iter().map(|v| v.try_as_float().ok_or_else(|| {
+            let err_str = format!("Cannot compute {:?} of a non-numeric value: {}", self, v);
+            TableError::new(err_str.as_str())
+        })).collect::<Result<Vec<_>, _>>()?;
+
+        let ret = match self {
+            Agg::Sum => numbers.iter().sum(),
+            Agg::Mean => mean_std(&numbers).0,
+            Agg::Std if numbers.len() < 2 => return Ok(Value::Empty),
+            Agg::Std => mean_std(&numbers).1,
+            _ => {
+                numbers.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+
+                let mid = numbers.len() / 2;
+
+                if numbers.len() % 2 == 0 { (numbers[mid - 1] + numbers[mid]) / 2.0 } else { numbers[mid] }
+            }
+        };
+
+        Ok(Value::Float(ret.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+    use crate::stats::{average_ranks, pearson, quantile, cap, Agg};
+
+    #[test]
+    fn ranks() {
+        let values = [Value::Integer(10), Value::Integer(30), Value::Integer(20), Value::Integer(30)];
+
+        assert_eq!(vec![1.0, 3.5, 2.0, 3.5], average_ranks(&values));
+        assert!(average_ranks(&[]).is_empty());
+    }
+
+    #[test]
+    fn quantiles() {
+        assert_eq!(1.75, quantile(&[1.0, 2.0, 3.0, 4.0], 0.25));
+        assert_eq!(2.5, quantile(&[1.0, 2.0, 3.0, 4.0], 0.5));
+        assert_eq!(4.0, quantile(&[1.0, 2.0, 3.0, 4.0], 1.0));
+        assert_eq!(5.0, quantile(&[5.0], 0.75));
+
+        assert_eq!(Value::Float(1.5.into()), cap(&Value::Integer(1), 1.5, 3.0));
+        assert_eq!(Value::Integer(2), cap(&Value::Integer(2), 1.5, 3.0));
+        assert_eq!(Value::Float(3.0.into()), cap(&Value::Float(7.0.into()), 1.5, 3.0));
+        assert_eq!(Value::Empty, cap(&Value::Empty, 1.5, 3.0));
+    }
+
+    #[test]
+    fn correlation() {
+        assert_eq!(Some(1.0), pearson(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]));
+        assert_eq!(Some(-1.0), pearson(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]));
+        assert_eq!(None, pearson(&[1.0, 1.0], &[1.0, 2.0]));
+        assert_eq!(None, pearson(&[], &[]));
+    }
+
+    #[test]
+    fn agg() {
+        let values = [Value::Integer(3), Value::Empty, Value::Integer(1), Value::Integer(2), Value::Integer(10)];
+
+        assert_eq!(Value::Integer(4), Agg::Count.apply(&values).unwrap());
+        assert_eq!(Value::Integer(16), Agg::Sum.apply(&values).unwrap());
+        assert_eq!(Value::Float(4.0.into()), Agg::Mean.apply(&values).unwrap());
+        assert_eq!(Value::Float(2.5.into()), Agg::Median.apply(&values).unwrap());
+        assert_eq!(Value::Integer(1), Agg::Min.apply(&values).unwrap());
+        assert_eq!(Value::Integer(10), Agg::Max.apply(&values).unwrap());
+        assert_eq!(Value::Float(1.5.into()), Agg::Sum.apply(&[Value::Integer(1), Value::Float(0.5.into())]).unwrap());
+        assert_eq!(Value::Empty, Agg::Std.apply(&[Value::Integer(1)]).unwrap());
+        assert_eq!(Value::Empty, Agg::Mean.apply(&[Value::Empty]).unwrap());
+        assert_eq!(Value::Integer(0), Agg::Count.apply(&[]).unwrap());
+        assert!(Agg::Mean.apply(&[Value::new("x")]).is_err());
+        assert_eq!(Value::new("x"), Agg::Max.apply(&[Value::new("x")]).unwrap());
+    }
+}
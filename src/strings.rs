@@ -0,0 +1,35 @@
+//! String operations on the values of a column, like Pandas' `df[column].str` accessor.
+//!
+//! They read each value with [`Row::with_text`](trait.Row.html#method.with_text), so a memory-mapped table's fields
+//! are used as they are in the file, without parsing or copying them, while the values of in-memory tables are
+//! formatted; empty values don't match, and map to empty values.
+use crate::{TableOperations, TableError, Row, Value};
+
+/// The rows of `table` whose text in `column` matches `predicate`
+pub(crate) fn filter<T, P>(table :&T, column :&str, mut predicate :P) -> Result<T::TableSliceType, TableError>
+    where T: TableOperations + ?Sized, P: FnMut(&str) -> bool
+{
+    let pos = table.column_position(column)?;
+    let mut ret = Ok( () );
+
+    let slice = table.filter_by(|row| {
+        match row.with_text(pos, |text| text.is_some_and(&mut predicate)) {
+            Ok(matched) => matched,
+            Err(e) => {
+                if ret.is_ok() { ret = Err(e); }
+                false
+            }
+        }
+    })?;
+
+    ret.map(|_| slice)
+}
+
+/// The result of `f` on the text of each value of `column`, in table order, to add as a column
+pub(crate) fn map<T, F>(table :&T, column :&str, mut f :F) -> Result<Vec<Value>, TableError>
+    where T: TableOperations + ?Sized, F: FnMut(&str) -> Value
+{
+    let pos = table.column_position(column)?;
+
+    table.iter().map(|row| row.with_text(pos, |text| text.map_or(Value::Empty, &mut f))).collect()
+}
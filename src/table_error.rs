@@ -1,11 +1,15 @@
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::{Display, Formatter, Error as FmtError};
+use core::fmt::{Display, Formatter, Error as FmtError};
+use alloc::string::String;
 
 #[derive(Debug, Clone)]
 pub struct TableError {
-    reason: String
+    reason: String,
+    cancelled: bool
 }
 
+#[cfg(feature = "std")]
 impl Error for TableError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         // Generic error, underlying cause isn't tracked.
@@ -21,6 +25,16 @@ impl Display for TableError {
 
 impl TableError {
     pub fn new(reason :&str) -> TableError {
-        TableError { reason: String::from(reason) }
+        TableError { reason: String::from(reason), cancelled: false }
+    }
+
+    /// The error returned when an operation is stopped by its [`CancelToken`](struct.CancelToken.html)
+    pub fn cancelled() -> TableError {
+        TableError { reason: String::from("The operation was cancelled"), cancelled: true }
+    }
+
+    /// Whether the operation was stopped by its [`CancelToken`](struct.CancelToken.html), instead of failing
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
     }
 }
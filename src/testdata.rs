@@ -0,0 +1,216 @@
+//! Generators for synthetic CSV files, used by the crate's own tests and benchmarks.
+//!
+//! Only available with the `testdata` feature, or when compiling the crate's tests.
+use std::fs::File;
+use std::io::{BufWriter, Error as IOError, ErrorKind, Write};
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate};
+use csv::WriterBuilder;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+/// The kind of values generated for a column.
+#[derive(Debug, Clone)]
+pub enum ColumnKind {
+    /// Integers in the half-open range `[min, max)`, or always `min` when `min == max`
+    Integer { min :i64, max :i64 },
+    /// Floats in the half-open range `[min, max)`, or always `min` when `min == max`
+    Float { min :f64, max :f64 },
+    /// Strings drawn from `cardinality` distinct values
+    String { cardinality :usize },
+    /// DateTimes within the year starting at 2020-01-01
+    DateTime,
+    /// Dates within the year starting at 2020-01-01
+    Date
+}
+
+/// Generates a CSV file with a header row and `rows` random records.
+///
+/// ```ignore
+/// let csv = CsvGenerator::new(1000)
+///     .column("symbol", ColumnKind::String { cardinality: 10 })
+///     .column("price", ColumnKind::Float { min: 1.0, max: 100.0 })
+///     .na_rate(0.05)
+///     .generate();
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsvGenerator {
+    rows: usize,
+    columns: Vec<(String, ColumnKind)>,
+    na_rate: f64,
+    bad_row_rate: f64,
+    quote_rate: f64,
+    seed: u64
+}
+
+impl CsvGenerator {
+    /// Create a generator for `rows` records, with no columns
+    pub fn new(rows :usize) -> Self {
+        CsvGenerator {
+            rows,
+            columns: Vec::new(),
+            na_rate: 0.0,
+            bad_row_rate: 0.0,
+            quote_rate: 0.0,
+            seed: 0
+        }
+    }
+
+    /// Adds a column named `name` with values of `kind`
+    pub fn column(mut self, name :&str, kind :ColumnKind) -> Self {
+        self.columns.push((name.to_string(), kind));
+        self
+    }
+
+    /// The fraction of cells that are left empty
+    pub fn na_rate(mut self, rate :f64) -> Self {
+        self.na_rate = rate;
+        self
+    }
+
+    /// The fraction of records that have the wrong number of fields
+    pub fn bad_row_rate(mut self, rate :f64) -> Self {
+        self.bad_row_rate = rate;
+        self
+    }
+
+    /// The fraction of string cells that contain delimiters, quotes, or newlines and must be quoted
+    pub fn quote_rate(mut self, rate :f64) -> Self {
+        self.quote_rate = rate;
+        self
+    }
+
+    /// The seed for the random number generator; the same seed always generates the same file
+    pub fn seed(mut self, seed :u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Writes the CSV to `writer`; returns an `InvalidInput` error if a column's `max` is less than its `min`
+    pub fn write<W: Write>(&self, writer :W) -> Result<(), IOError> {
+        for (name, kind) in self.columns.iter() {
+            let valid = match kind {
+                ColumnKind::Integer { min, max } => min <= max,
+                ColumnKind::Float { min, max } => min <= max,
+                _ => true
+            };
+
+            if !valid {
+                let err_str = format!("The range of column {} is empty: {:?}", name, kind);
+                return Err(IOError::new(ErrorKind::InvalidInput, err_str.as_str()));
+            }
+        }
+
+        let mut csv = WriterBuilder::new().flexible(true).from_writer(writer);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        csv.write_record(self.columns.iter().map(|(name, _)| name))?;
+
+        for _ in 0..self.rows {
+            let mut record = self.columns.iter().map(|(_, kind)| self.cell(&mut rng, kind)).collect::<Vec<_>>();
+
+            if self.bad_row_rate > 0.0 && rng.gen_bool(self.bad_row_rate) {
+                // either drop the last field, or add an extra one
+                if rng.gen_bool(0.5) && record.len() > 1 {
+                    record.pop();
+                } else {
+                    record.push(String::from("extra"));
+                }
+            }
+
+            csv.write_record(&record)?;
+        }
+
+        csv.flush()
+    }
+
+    /// Writes the CSV to the file at `path`
+    pub fn write_to_path<P: AsRef<Path>>(&self, path :P) -> Result<(), IOError> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    /// Generates the CSV as a `String`
+    pub fn generate(&self) -> String {
+        let mut buffer = Vec::new();
+
+        self.write(&mut buffer).expect("Error writing CSV to memory");
+
+        String::from_utf8(buffer).expect("Generated CSV is not UTF-8")
+    }
+
+    fn cell(&self, rng :&mut StdRng, kind :&ColumnKind) -> String {
+        if self.na_rate > 0.0 && rng.gen_bool(self.na_rate) {
+            return String::new();
+        }
+
+        let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        match kind {
+            ColumnKind::Integer { min, max } if min == max => min.to_string(),
+            ColumnKind::Integer { min, max } => rng.gen_range(*min, *max).to_string(),
+            ColumnKind::Float { min, max } if min == max => format!("{:.4}", min),
+            ColumnKind::Float { min, max } => format!("{:.4}", rng.gen_range(*min, *max)),
+            ColumnKind::String { cardinality } => {
+                let s = format!("str_{}", rng.gen_range(0, (*cardinality).max(1)));
+
+                if self.quote_rate > 0.0 && rng.gen_bool(self.quote_rate) {
+                    match rng.gen_range(0, 3) {
+                        0 => format!("{}, with comma", s),
+                        1 => format!("{} \"with quotes\"", s),
+                        _ => format!("{}\nwith newline", s)
+                    }
+                } else {
+                    s
+                }
+            },
+            ColumnKind::DateTime => (start + Duration::seconds(rng.gen_range(0, 365 * 24 * 60 * 60))).format("%Y-%m-%d %H:%M:%S").to_string(),
+            ColumnKind::Date => (start + Duration::days(rng.gen_range(0, 365))).format("%Y-%m-%d").to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "rowtable")]
+    use crate::{RowTable, TableOperations};
+    #[cfg(feature = "rowtable")]
+    use crate::testdata::{CsvGenerator, ColumnKind};
+
+    #[cfg(feature = "rowtable")]
+    #[test]
+    fn generate() {
+        let generator = CsvGenerator::new(100)
+            .column("A", ColumnKind::Integer { min: 0, max: 10 })
+            .column("B", ColumnKind::Float { min: 0.0, max: 1.0 })
+            .column("C", ColumnKind::String { cardinality: 5 })
+            .column("D", ColumnKind::DateTime)
+            .na_rate(0.1)
+            .quote_rate(0.2)
+            .seed(7);
+
+        // the same seed generates the same file
+        assert_eq!(generator.generate(), generator.clone().generate());
+
+        let path = crate::fixtures::temp_dir().join("testdata_generate.csv");
+        generator.write_to_path(&path).unwrap();
+
+        let table = RowTable::from_csv(&path).unwrap();
+
+        assert_eq!(100, table.len());
+        assert_eq!(4, table.width());
+        assert!(table.unique("C").unwrap().len() <= 5 * 4 + 1);
+
+        // bad rows can't be loaded
+        CsvGenerator::new(100).column("A", ColumnKind::Integer { min: 0, max: 10 }).column("B", ColumnKind::Date).bad_row_rate(0.5).write_to_path(&path).unwrap();
+
+        assert!(RowTable::from_csv(&path).is_err());
+
+        // an empty range is a constant column, while a reversed one is an error
+        let constant = CsvGenerator::new(3).column("A", ColumnKind::Integer { min: 5, max: 5 }).column("B", ColumnKind::Float { min: 0.5, max: 0.5 });
+
+        assert_eq!("A,B\n5,0.5000\n5,0.5000\n5,0.5000\n", constant.generate());
+        assert!(CsvGenerator::new(3).column("A", ColumnKind::Integer { min: 5, max: 4 }).write(Vec::new()).is_err());
+        assert!(CsvGenerator::new(3).column("B", ColumnKind::Float { min: 1.0, max: f64::NAN }).write(Vec::new()).is_err());
+    }
+}
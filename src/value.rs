@@ -1,32 +1,59 @@
 use chrono::naive::{NaiveDateTime, NaiveDate, NaiveTime};
+use chrono::Duration;
+#[cfg(feature = "datetime-inference")]
 use dtparse::parse;
 use ordered_float::OrderedFloat;
-use std::fmt::{Display, Formatter, Error as FmtError};
+use core::fmt::{Display, Formatter, Error as FmtError};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+
+use crate::TableError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::borrow::Cow;
+#[cfg(feature = "datetime-inference")]
 use chrono::{Datelike, Timelike};
 
 
 /// Various types of values found in the cells of a [`Table`](trait.Table.html)
-#[derive(Debug, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+///
+/// Integers and floats compare by their numeric value, so `Value::Integer(1) == Value::Float(1.0)`;
+/// values of other types are ordered by type: strings, date-times, dates, times, durations, numbers, booleans, then empty.
+#[derive(Debug, Clone)]
 pub enum Value {
     String(String),
     DateTime(NaiveDateTime),
     Date(NaiveDate),
     Time(NaiveTime),
+    Duration(Duration),
     Integer(i64),
     Float(OrderedFloat<f64>),
+    Boolean(bool),
     Empty
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValueType {
     String,
     DateTime,
     DateTimeFormat(String),  // format for the DateTime
+    Date,
     DateFormat(String),      // format for the Date
+    Time,
     TimeFormat(String),      // format for the Time
-    Number,     // try to parse as Float first, then Integer
+    Duration,   // ISO 8601, such as PT90S or P1DT2H
+    Number,     // try to parse as Integer first, then Float
+    NumberFormat(String),    // separators for the Number, see `normalize_number`
     Integer,
+    IntegerFormat(String),   // separators for the Integer
     Float,
+    FloatFormat(String),     // separators for the Float
+    Currency,   // a Float with a currency symbol, such as $1,234.00 or (€12.50) for a negative amount
+    CurrencyFormat(String),  // separators for the Currency, such as ., for 1.234,56 €
+    Percent,    // a Float scaled from a percentage, so 12.5% is 0.125
+    PercentFormat(String),   // separators for the Percent
+    Boolean,    // true/false, 1/0, or yes/no, in any case
     Empty
 }
 
@@ -35,38 +62,26 @@ impl Value {
     ///
     /// The method constructs a [`Value`] using the following:
     /// 1. checks to see if the string is empty, then constructs `Value::Empty`
-    /// 1. if the string contains `-`, `/`, or `:`, then attempts to parse as a [`DateTime`](#https://docs.rs/chrono/*/chrono/struct.DateTime.html)
     /// 1. if the string contains `.`, then attempts to parse as a `f64`
     /// 1. if the string can be parsed as a `i64`, then a `Value::Integer` is constructed
+    /// 1. if the string is `true` or `false`, in any case, then a `Value::Boolean` is constructed
+    /// 1. if the string is an ISO 8601 duration, such as `PT90S`, then a `Value::Duration` is constructed
+    ///    (only with the `duration-inference` feature, as codes such as `P1D` would be read as durations too)
+    /// 1. if the string contains `-`, `/`, or `:`, then attempts to parse as a [`DateTime`](#https://docs.rs/chrono/*/chrono/struct.DateTime.html)
+    ///    (only with the `datetime-inference` feature)
     /// 1. finally a `Value::String` is constructed using the string
     ///
     /// [`Value`]: enum.Value.html
     pub fn new(value :&str) -> Value {
+        Value::infer(value).unwrap_or_else(|| Value::String(String::from(value)))
+    }
+
+    /// Infers the type of `value` as [`new`](#method.new) does, returning `None` for a string, so the caller can
+    /// decide whether to copy it.
+    fn infer(value :&str) -> Option<Value> {
         // first check to see if it's empty
         if value.is_empty() {
-            return Value::Empty;
-        }
-
-        let dt_char_count = value.chars().try_fold(0i64, |sum, c| {
-            if c == '-' || c == '/' || c == ':' {
-                Some(sum + 1)
-            } else if c.is_digit(10) || [' ', 'p', 'P', 'a', 'A', 'm', 'M', 'T', 'Z'].iter().any(|dt_char| c == *dt_char) {
-                Some(sum)
-            } else {
-                None // make sure it's negative
-            }
-        });
-
-        if dt_char_count.is_some() && dt_char_count.unwrap() > 0 {
-            if let Ok((dt, _offset)) = parse(value) {
-                if dt.year() == 0 {
-                    return Value::Time(dt.time());
-                } else if dt.hour() == 0 {
-                    return Value::Date(dt.date());
-                } else {
-                    return Value::DateTime(dt);
-                }
-            }
+            return Some(Value::Empty);
         }
 
         let float_char_count = value.chars().try_fold(0i64, |sum, c| {
@@ -82,45 +97,141 @@ impl Value {
         // next attempt to parse as a float
         if float_char_count.is_some() && float_char_count.unwrap() == 1 {
             if let Ok(f) = value.parse::<f64>() {
-                return Value::Float(OrderedFloat(f));
+                return Some(Value::Float(OrderedFloat(f)));
             }
         }
 
         // next as an integer
         if value.chars().all(|c| c.is_digit(10) || c == '-') {
             if let Ok(i) = value.parse::<i64>() {
-                return Value::Integer(i);
+                return Some(Value::Integer(i));
             }
         }
 
-        // finally, just go with a string
-        Value::String(String::from(value))
-    }
+        // then as a boolean
+        if value.eq_ignore_ascii_case("true") {
+            return Some(Value::Boolean(true));
+        } else if value.eq_ignore_ascii_case("false") {
+            return Some(Value::Boolean(false));
+        }
 
-    pub fn with_type(value :&str, value_type :&ValueType) -> Value {
-        match value_type {
-            ValueType::String => Value::String(value.to_string()),
-            ValueType::DateTime => {
-                let (dt, _offset) = dtparse::parse(value).unwrap();
-                Value::DateTime(dt)
-            },
-            ValueType::DateTimeFormat(format) => Value::DateTime(NaiveDateTime::parse_from_str(value, format).expect(format!("Error parsing DateTime: {} using {}", value, format).as_str())),
-            ValueType::DateFormat(format) => Value::Date(NaiveDate::parse_from_str(value, format).unwrap()),
-            ValueType::TimeFormat(format) => Value::Time(NaiveTime::parse_from_str(value, format).unwrap()),
-            ValueType::Number => {
-                if let Ok(f) = value.parse::<f64>() {
-                    Value::Float(OrderedFloat(f))
+        // then as a duration
+        #[cfg(feature = "duration-inference")]
+        if value.starts_with('P') || value.starts_with("-P") {
+            if let Some(d) = parse_duration(value) {
+                return Some(Value::Duration(d));
+            }
+        }
+
+        #[cfg(feature = "datetime-inference")]
+        {
+            let dt_char_count = value.chars().try_fold(0i64, |sum, c| {
+                if c == '-' || c == '/' || c == ':' {
+                    Some(sum + 1)
+                } else if c.is_digit(10) || [' ', 'p', 'P', 'a', 'A', 'm', 'M', 'T', 'Z'].iter().any(|dt_char| c == *dt_char) {
+                    Some(sum)
                 } else {
-                    if let Ok(i) = value.parse::<i64>() {
-                        Value::Integer(i)
+                    None // make sure it's negative
+                }
+            });
+
+            if dt_char_count.is_some() && dt_char_count.unwrap() > 0 {
+                if let Ok((dt, _offset)) = parse(value) {
+                    if dt.year() == 0 {
+                        return Some(Value::Time(dt.time()));
+                    } else if dt.hour() == 0 {
+                        return Some(Value::Date(dt.date()));
                     } else {
-                        panic!("Error parsing number: {}", value);
+                        return Some(Value::DateTime(dt));
                     }
                 }
-            },
-            ValueType::Integer => Value::Integer(value.parse::<i64>().expect(format!("Error parsing integer: {}", value).as_str())),
+            }
+        }
+
+        // finally, it's a string
+        None
+    }
+
+    /// Parses `value` as `value_type`, panicking if it isn't one, except for `ValueType::Float`, which is 0 when `value`
+    /// doesn't parse; see [`try_with_type`](#method.try_with_type) to handle the errors instead.
+    pub fn with_type(value :&str, value_type :&ValueType) -> Value {
+        match value_type {
             ValueType::Float => Value::Float(OrderedFloat(value.parse::<f64>().unwrap_or_default())),
-            ValueType::Empty => Value::Empty,
+            _ => Value::try_with_type(value, value_type).unwrap_or_else(|e| panic!("{}", e))
+        }
+    }
+
+    /// Parses `value` as `value_type`, returning an error if it isn't one.
+    pub fn try_with_type(value :&str, value_type :&ValueType) -> Result<Value, TableError> {
+        let parsed = match value_type {
+            ValueType::String => Some(Value::String(value.to_string())),
+            #[cfg(feature = "datetime-inference")]
+            ValueType::DateTime => parse(value).ok().map(|(dt, _offset)| Value::DateTime(dt)),
+            #[cfg(not(feature = "datetime-inference"))]
+            ValueType::DateTime => value.parse::<NaiveDateTime>().ok().map(Value::DateTime),
+            ValueType::DateTimeFormat(format) => NaiveDateTime::parse_from_str(value, format).ok().map(Value::DateTime),
+            #[cfg(feature = "datetime-inference")]
+            ValueType::Date => parse(value).ok().map(|(dt, _offset)| Value::Date(dt.date())),
+            #[cfg(not(feature = "datetime-inference"))]
+            ValueType::Date => value.parse::<NaiveDate>().ok().map(Value::Date),
+            ValueType::DateFormat(format) => NaiveDate::parse_from_str(value, format).ok().map(Value::Date),
+            #[cfg(feature = "datetime-inference")]
+            ValueType::Time => parse(value).ok().map(|(dt, _offset)| Value::Time(dt.time())),
+            #[cfg(not(feature = "datetime-inference"))]
+            ValueType::Time => value.parse::<NaiveTime>().ok().map(Value::Time),
+            ValueType::TimeFormat(format) => NaiveTime::parse_from_str(value, format).ok().map(Value::Time),
+            ValueType::Duration => parse_duration(value).map(Value::Duration),
+            ValueType::Number => parse_number(value),
+            ValueType::NumberFormat(format) => normalize_number(value, format).and_then(|v| parse_number(&v)),
+            ValueType::Integer => value.parse::<i64>().ok().map(Value::Integer),
+            ValueType::IntegerFormat(format) => normalize_number(value, format).and_then(|v| v.parse::<i64>().ok()).map(Value::Integer),
+            ValueType::Float => value.parse::<f64>().ok().map(|f| Value::Float(OrderedFloat(f))),
+            ValueType::FloatFormat(format) => normalize_number(value, format).and_then(|v| v.parse::<f64>().ok()).map(|f| Value::Float(OrderedFloat(f))),
+            ValueType::Currency => parse_currency(value, ",.").map(|f| Value::Float(OrderedFloat(f))),
+            ValueType::CurrencyFormat(format) => parse_currency(value, format).map(|f| Value::Float(OrderedFloat(f))),
+            ValueType::Percent => parse_percent(value, ",.").map(|f| Value::Float(OrderedFloat(f))),
+            ValueType::PercentFormat(format) => parse_percent(value, format).map(|f| Value::Float(OrderedFloat(f))),
+            ValueType::Boolean => parse_boolean(value).map(Value::Boolean),
+            ValueType::Empty => Some(Value::Empty),
+        };
+
+        parsed.ok_or_else(|| {
+            let err_str = format!("Error parsing {:?} as {}", value, value_type);
+            TableError::new(err_str.as_str())
+        })
+    }
+
+    /// Parses an empty string, or one of the `nulls` tokens, as `Value::Empty`, otherwise with `value_type`, or inferring
+    /// the type when `None`.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_type_or_infer(value :&str, value_type :Option<&ValueType>, nulls :&[String]) -> Result<Value, TableError> {
+        match value_type {
+            _ if value.is_empty() || nulls.iter().any(|n| n == value) => Ok(Value::Empty),
+            Some(value_type) => Value::try_with_type(value, value_type),
+            None => Ok(Value::new(value))
+        }
+    }
+
+    /// Returns the [`ValueType`](enum.ValueType.html) that describes this value.
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Value::String(_) => ValueType::String,
+            Value::DateTime(_) => ValueType::DateTime,
+            Value::Date(_) => ValueType::Date,
+            Value::Time(_) => ValueType::Time,
+            Value::Duration(_) => ValueType::Duration,
+            Value::Integer(_) => ValueType::Integer,
+            Value::Float(_) => ValueType::Float,
+            Value::Boolean(_) => ValueType::Boolean,
+            Value::Empty => ValueType::Empty
+        }
+    }
+
+    /// Estimated number of bytes used by this value, including any heap allocation.
+    pub fn mem_size(&self) -> usize {
+        match self {
+            Value::String(s) => core::mem::size_of::<Value>() + s.capacity(),
+            _ => core::mem::size_of::<Value>()
         }
     }
 
@@ -168,6 +279,56 @@ impl Value {
         self.try_as_time().unwrap()
     }
 
+    pub fn try_as_duration(&self) -> Option<Duration> {
+        if let Value::Duration(d) = self {
+            Some(*d)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_duration(&self) -> Duration {
+        self.try_as_duration().unwrap()
+    }
+
+    /// Subtracts `other` from this value.
+    ///
+    /// Subtracting two date-times, dates, or times gives the `Value::Duration` between them, and a duration can be
+    /// subtracted from a date-time, date, or another duration. Any other types are an error.
+    pub fn try_sub(&self, other :&Value) -> Result<Value, TableError> {
+        let ret = match (self, other) {
+            (Value::DateTime(a), Value::DateTime(b)) => Some(Value::Duration(a.signed_duration_since(*b))),
+            (Value::Date(a), Value::Date(b)) => Some(Value::Duration(a.signed_duration_since(*b))),
+            (Value::Time(a), Value::Time(b)) => Some(Value::Duration(a.signed_duration_since(*b))),
+            (Value::DateTime(dt), Value::Duration(d)) => dt.checked_sub_signed(*d).map(Value::DateTime),
+            (Value::Date(dt), Value::Duration(d)) => dt.checked_sub_signed(*d).map(Value::Date),
+            (Value::Duration(a), Value::Duration(b)) => a.checked_sub(b).map(Value::Duration),
+            _ => {
+                let err_str = format!("Cannot subtract {} from {}", other.value_type(), self.value_type());
+                return Err(TableError::new(err_str.as_str()));
+            }
+        };
+
+        ret.ok_or_else(|| TableError::new(format!("Overflow subtracting {} from {}", other, self).as_str()))
+    }
+
+    /// Adds `other` to this value.
+    ///
+    /// A `Value::Duration` can be added to a date-time, date, or another duration. Any other types are an error.
+    pub fn try_add(&self, other :&Value) -> Result<Value, TableError> {
+        let ret = match (self, other) {
+            (Value::DateTime(dt), Value::Duration(d)) | (Value::Duration(d), Value::DateTime(dt)) => dt.checked_add_signed(*d).map(Value::DateTime),
+            (Value::Date(dt), Value::Duration(d)) | (Value::Duration(d), Value::Date(dt)) => dt.checked_add_signed(*d).map(Value::Date),
+            (Value::Duration(a), Value::Duration(b)) => a.checked_add(b).map(Value::Duration),
+            _ => {
+                let err_str = format!("Cannot add {} to {}", other.value_type(), self.value_type());
+                return Err(TableError::new(err_str.as_str()));
+            }
+        };
+
+        ret.ok_or_else(|| TableError::new(format!("Overflow adding {} to {}", other, self).as_str()))
+    }
+
     pub fn try_as_integer(&self) -> Option<i64> {
         match self {
             Value::Integer(i) => Some(*i),
@@ -192,6 +353,166 @@ impl Value {
         self.try_as_float().unwrap()
     }
 
+    pub fn try_as_boolean(&self) -> Option<bool> {
+        if let Value::Boolean(b) = self {
+            Some(*b)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_boolean(&self) -> bool {
+        self.try_as_boolean().unwrap()
+    }
+
+}
+
+/// Types a [`Value`](enum.Value.html) can be converted into, for typed access to a column through a
+/// [`ColumnHandle`](struct.ColumnHandle.html).
+///
+/// `Value::Empty` only converts into `Option<T>`, as `None`.
+pub trait FromValue: Sized {
+    fn from_value(value :Value) -> Result<Self, TableError>;
+}
+
+macro_rules! from_value {
+    ($t:ty, $try_as:ident) => {
+        impl FromValue for $t {
+            fn from_value(value :Value) -> Result<Self, TableError> {
+                value.$try_as().ok_or_else(|| {
+                    let err_str = format!("Could not convert {} to {}", value.value_type(), core::any::type_name::<$t>());
+                    TableError::new(err_str.as_str())
+                })
+            }
+        }
+    };
+}
+
+from_value!(i64, try_as_integer);
+from_value!(f64, try_as_float);
+from_value!(bool, try_as_boolean);
+from_value!(NaiveDateTime, try_as_date_time);
+from_value!(NaiveDate, try_as_date);
+from_value!(NaiveTime, try_as_time);
+from_value!(Duration, try_as_duration);
+
+impl FromValue for String {
+    fn from_value(value :Value) -> Result<Self, TableError> {
+        match value {
+            Value::Empty => Err(TableError::new("Could not convert Empty to String")),
+            value => Ok(value.as_string())
+        }
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value :Value) -> Result<Self, TableError> {
+        Ok(value)
+    }
+}
+
+impl <T: FromValue> FromValue for Option<T> {
+    fn from_value(value :Value) -> Result<Self, TableError> {
+        match value {
+            Value::Empty => Ok(None),
+            value => T::from_value(value).map(Some)
+        }
+    }
+}
+
+impl ValueType {
+    /// Combines the type of a column seen so far with the type of the next value in the column.
+    ///
+    /// `Empty` values don't change the type, a mix of integers and floats becomes `Number`,
+    /// and any other mix of types becomes `String`.
+    #[cfg(feature = "std")]
+    pub(crate) fn merge(self, other :ValueType) -> ValueType {
+        match (self, other) {
+            (ValueType::Empty, t) | (t, ValueType::Empty) => t,
+            (ValueType::Integer, ValueType::Integer) => ValueType::Integer,
+            (ValueType::Float, ValueType::Float) => ValueType::Float,
+            (ValueType::Integer, ValueType::Float) |
+            (ValueType::Float, ValueType::Integer) |
+            (ValueType::Number, ValueType::Integer) |
+            (ValueType::Number, ValueType::Float) |
+            (ValueType::Integer, ValueType::Number) |
+            (ValueType::Float, ValueType::Number) |
+            (ValueType::Number, ValueType::Number) => ValueType::Number,
+            (ValueType::DateTime, ValueType::DateTime) => ValueType::DateTime,
+            (ValueType::Date, ValueType::Date) => ValueType::Date,
+            (ValueType::Time, ValueType::Time) => ValueType::Time,
+            (ValueType::Duration, ValueType::Duration) => ValueType::Duration,
+            (ValueType::Boolean, ValueType::Boolean) => ValueType::Boolean,
+            _ => ValueType::String
+        }
+    }
+}
+
+impl Display for ValueType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match self {
+            ValueType::String => write!(f, "String"),
+            ValueType::DateTime => write!(f, "DateTime"),
+            ValueType::DateTimeFormat(format) => write!(f, "DateTime({})", format),
+            ValueType::Date => write!(f, "Date"),
+            ValueType::DateFormat(format) => write!(f, "Date({})", format),
+            ValueType::Time => write!(f, "Time"),
+            ValueType::TimeFormat(format) => write!(f, "Time({})", format),
+            ValueType::Duration => write!(f, "Duration"),
+            ValueType::Number => write!(f, "Number"),
+            ValueType::NumberFormat(format) => write!(f, "Number({})", format),
+            ValueType::Integer => write!(f, "Integer"),
+            ValueType::IntegerFormat(format) => write!(f, "Integer({})", format),
+            ValueType::Float => write!(f, "Float"),
+            ValueType::FloatFormat(format) => write!(f, "Float({})", format),
+            ValueType::Currency => write!(f, "Currency"),
+            ValueType::CurrencyFormat(format) => write!(f, "Currency({})", format),
+            ValueType::Percent => write!(f, "Percent"),
+            ValueType::PercentFormat(format) => write!(f, "Percent({})", format),
+            ValueType::Boolean => write!(f, "Boolean"),
+            ValueType::Empty => write!(f, "Empty")
+        }
+    }
+}
+
+/// Parses the `Display` form of a `ValueType`, such as `Integer` or `Date(%m/%d/%Y)`
+impl FromStr for ValueType {
+    type Err = TableError;
+
+    fn from_str(s :&str) -> Result<Self, Self::Err> {
+        // the formats are in parentheses
+        if let (Some(open), true) = (s.find('('), s.ends_with(')')) {
+            let format = s[open+1..s.len()-1].to_string();
+
+            return match &s[..open] {
+                "DateTime" => Ok(ValueType::DateTimeFormat(format)),
+                "Date" => Ok(ValueType::DateFormat(format)),
+                "Time" => Ok(ValueType::TimeFormat(format)),
+                "Number" if is_number_format(&format) => Ok(ValueType::NumberFormat(format)),
+                "Integer" if is_number_format(&format) => Ok(ValueType::IntegerFormat(format)),
+                "Float" if is_number_format(&format) => Ok(ValueType::FloatFormat(format)),
+                "Currency" if is_number_format(&format) => Ok(ValueType::CurrencyFormat(format)),
+                "Percent" if is_number_format(&format) => Ok(ValueType::PercentFormat(format)),
+                _ => Err(TableError::new(format!("Unknown type: {}", s).as_str()))
+            };
+        }
+
+        match s {
+            "String" => Ok(ValueType::String),
+            "DateTime" => Ok(ValueType::DateTime),
+            "Date" => Ok(ValueType::Date),
+            "Time" => Ok(ValueType::Time),
+            "Duration" => Ok(ValueType::Duration),
+            "Number" => Ok(ValueType::Number),
+            "Integer" => Ok(ValueType::Integer),
+            "Float" => Ok(ValueType::Float),
+            "Currency" => Ok(ValueType::Currency),
+            "Percent" => Ok(ValueType::Percent),
+            "Boolean" => Ok(ValueType::Boolean),
+            "Empty" => Ok(ValueType::Empty),
+            _ => Err(TableError::new(format!("Unknown type: {}", s).as_str()))
+        }
+    }
 }
 
 impl From<Value> for String {
@@ -201,8 +522,10 @@ impl From<Value> for String {
             Value::DateTime(dt) => format!("{}", dt),
             Value::Date(d) => format!("{}", d),
             Value::Time(t) => format!("{}", t),
-            Value::Float(f) => format!("{}", f),
+            Value::Duration(d) => format!("{}", d),
+            Value::Float(f) => format!("{}", Value::Float(f)),
             Value::Integer(i) => format!("{}", i),
+            Value::Boolean(b) => format!("{}", b),
             Value::Empty => String::new(),
         }
     }
@@ -214,9 +537,11 @@ impl From<&Value> for String {
             Value::String(s) => String::from(s),
             Value::DateTime(dt) => format!("{}", dt),
             Value::Time(t) => format!("{}", t),
+            Value::Duration(d) => format!("{}", d),
             Value::Date(d) => format!("{}", d),
-            Value::Float(f) => format!("{}", f),
+            Value::Float(_) => format!("{}", value),
             Value::Integer(i) => format!("{}", i),
+            Value::Boolean(b) => format!("{}", b),
             Value::Empty => String::new(),
         }
     }
@@ -229,19 +554,483 @@ impl Display for Value {
             Value::DateTime(d) => write!(f, "{}", d),
             Value::Date(d) => write!(f, "{}", d),
             Value::Time(t) => write!(f, "{}", t),
+            Value::Duration(d) => write!(f, "{}", d),
             Value::Integer(i) => write!(f, "{}", i),
+            // keep the decimal point on whole numbers, so they parse back as floats
+            Value::Float(of) if is_whole(of.0) => write!(f, "{:.1}", of.0),
             Value::Float(of) => write!(f, "{}", of),
+            Value::Boolean(b) => write!(f, "{}", b),
             Value::Empty => write!(f, "")
         }
     }
 }
 
+/// Parses an ISO 8601 duration: an optional `-`, then `P`, days, then `T` followed by hours, minutes, and seconds,
+/// with each part optional, such as `P1DT2H`, `PT90S`, or `PT0.5S`
+fn parse_duration(value :&str) -> Option<Duration> {
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value)
+    };
+
+    let mut rest = rest.strip_prefix('P')?;
+    let mut in_time = false;
+    let mut total = Duration::zero();
+    let mut parts = 0;
+
+    while !rest.is_empty() {
+        if !in_time && rest.starts_with('T') {
+            in_time = true;
+            rest = &rest[1..];
+            continue;
+        }
+
+        let end = rest.find(|c :char| !c.is_ascii_digit() && c != '.')?;
+        let (number, unit) = (&rest[..end], rest[end..].chars().next()?);
+
+        rest = &rest[end+unit.len_utf8()..];
+
+        let part = match (in_time, unit) {
+            (false, 'D') => Duration::try_days(number.parse().ok()?)?,
+            (true, 'H') => Duration::try_hours(number.parse().ok()?)?,
+            (true, 'M') => Duration::try_minutes(number.parse().ok()?)?,
+            (true, 'S') => {
+                let (secs, nanos) = match number.find('.') {
+                    Some(dot) => (&number[..dot], &number[dot+1..]),
+                    None => (number, "")
+                };
+
+                // the fraction of a second, to nanoseconds
+                if nanos.len() > 9 || !nanos.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+
+                let nanos = if nanos.is_empty() { 0 } else { nanos.parse::<i64>().ok()? * 10i64.pow(9 - nanos.len() as u32) };
+
+                Duration::try_seconds(secs.parse().ok()?)?.checked_add(&Duration::nanoseconds(nanos))?
+            },
+            _ => return None
+        };
+
+        total = total.checked_add(&part)?;
+        parts += 1;
+    }
+
+    if parts == 0 {
+        return None;
+    }
+
+    Some(if negative { -total } else { total })
+}
+
+/// Parses an integer first, so large ones don't lose precision, then a float
+fn parse_number(value :&str) -> Option<Value> {
+    match value.parse::<i64>() {
+        Ok(i) => Some(Value::Integer(i)),
+        Err(_) => value.parse::<f64>().ok().map(|f| Value::Float(OrderedFloat(f)))
+    }
+}
+
+/// Rewrites a number written with the separators in `format`, the thousands separator followed by the decimal mark,
+/// such as `,.` for `1,234,567.89` or `.,` for `1.234,56`, as one Rust can parse. A `format` of only a decimal mark,
+/// such as `,`, is for numbers without thousands separators.
+///
+/// Returns `None` if the thousands separators aren't between groups of 3 digits, so `1.5` isn't read as 15 with `.,`.
+fn normalize_number(value :&str, format :&str) -> Option<String> {
+    if !is_number_format(format) {
+        return None;
+    }
+
+    let mut separators = format.chars();
+
+    let (thousands, decimal) = match (separators.next(), separators.next(), separators.next()) {
+        (Some(decimal), None, None) => (None, decimal),
+        (Some(thousands), Some(decimal), _) => (Some(thousands), decimal),
+        _ => return None
+    };
+
+    let (whole, fraction) = match value.find(decimal) {
+        Some(pos) => (&value[..pos], Some(&value[pos + decimal.len_utf8()..])),
+        None => (value, None)
+    };
+
+    let mut normalized = String::with_capacity(value.len());
+
+    match thousands {
+        Some(thousands) if whole.contains(thousands) => {
+            let digits = whole.trim_start_matches(['-', '+']);
+
+            for (i, group) in digits.split(thousands).enumerate() {
+                let valid_len = if i == 0 { (1..=3).contains(&group.len()) } else { group.len() == 3 };
+
+                if !valid_len || !group.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+            }
+
+            normalized.extend(whole.chars().filter(|c| *c != thousands));
+        },
+        _ => normalized.push_str(whole)
+    }
+
+    if let Some(fraction) = fraction {
+        normalized.push('.');
+        normalized.push_str(fraction);
+    }
+
+    Some(normalized)
+}
+
+/// Parses an amount with an optional currency symbol before or after it, and thousands separators, such as `$1,234.00`,
+/// `-€12.50`, or `12.50 €`; an amount in parentheses, `($12.50)`, is negative, as in accounting.
+fn parse_currency(value :&str, format :&str) -> Option<f64> {
+    let is_symbol = |c :char| ['$', '€', '£', '¥', '₹', '₽', '₩', '₺', '₪', '¢'].contains(&c);
+    let value = value.trim();
+
+    let (negative, amount) = match value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        Some(amount) => (true, amount.trim()),
+        None => (false, value)
+    };
+
+    // the sign can come before or after the symbol
+    let (sign, amount) = match amount.strip_prefix('-') {
+        Some(amount) => ("-", amount),
+        None => ("", amount)
+    };
+
+    // and spaces can come between them and the amount
+    let amount = amount.trim().trim_start_matches(is_symbol).trim_end_matches(is_symbol).trim();
+    let amount = normalize_number(&format!("{}{}", sign, amount), format)?.parse::<f64>().ok()?;
+
+    Some(if negative { -amount } else { amount })
+}
+
+/// Parses a percentage, such as `12.5%` or `-3 %`, written with the separators in `format`, as a fraction
+fn parse_percent(value :&str, format :&str) -> Option<f64> {
+    let amount = value.trim().strip_suffix('%')?.trim_end();
+
+    Some(normalize_number(amount, format)?.parse::<f64>().ok()? / 100.0)
+}
+
+/// Whether `format` is one or two different separators, for `normalize_number`, that can't be part of a number
+fn is_number_format(format :&str) -> bool {
+    let is_separator = |c :char| !c.is_alphanumeric() && c != '-' && c != '+';
+    let mut separators = format.chars();
+
+    match (separators.next(), separators.next(), separators.next()) {
+        (Some(decimal), None, None) => is_separator(decimal),
+        (Some(thousands), Some(decimal), None) => thousands != decimal && is_separator(thousands) && is_separator(decimal),
+        _ => false
+    }
+}
+
+/// Parses true/false, 1/0, or yes/no, in any case
+fn parse_boolean(value :&str) -> Option<bool> {
+    if ["true", "1", "yes"].iter().any(|t| value.eq_ignore_ascii_case(t)) {
+        Some(true)
+    } else if ["false", "0", "no"].iter().any(|f| value.eq_ignore_ascii_case(f)) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Smallest `f64` that's too large for an `i64`, 2^63
+const I64_LIMIT :f64 = 9_223_372_036_854_775_808.0;
+
+/// Whether a float is finite and has no fractional part
+fn is_whole(f :f64) -> bool {
+    // every float this large is whole
+    f.is_finite() && (!(-I64_LIMIT..I64_LIMIT).contains(&f) || (f as i64) as f64 == f)
+}
+
+/// Compares an integer to a float exactly; NaN is above every number, as with `OrderedFloat`.
+fn cmp_integer_float(i :i64, f :f64) -> Ordering {
+    if f.is_nan() || f >= I64_LIMIT {
+        return Ordering::Less;
+    }
+
+    if f < -I64_LIMIT {
+        return Ordering::Greater;
+    }
+
+    // the whole part of f fits in an i64 (casting truncates), so compare that first, then the fraction
+    let whole = f as i64;
+
+    match i.cmp(&whole) {
+        Ordering::Equal => 0.0.partial_cmp(&(f - whole as f64)).unwrap(),
+        ord => ord
+    }
+}
+
+impl Value {
+    /// Position of the value's type in the ordering of values of different types
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::String(_) => 0,
+            Value::DateTime(_) => 1,
+            Value::Date(_) => 2,
+            Value::Time(_) => 3,
+            Value::Duration(_) => 4,
+            Value::Integer(_) | Value::Float(_) => 5,
+            Value::Boolean(_) => 6,
+            Value::Empty => 7
+        }
+    }
+
+    /// Whether the two values are of types that compare by value, such as two numbers, rather than by type.
+    pub fn is_comparable(&self, other :&Value) -> bool {
+        self.type_rank() == other.type_rank()
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other :&Self) -> Ordering {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::DateTime(a), Value::DateTime(b)) => a.cmp(b),
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Time(a), Value::Time(b)) => a.cmp(b),
+            (Value::Duration(a), Value::Duration(b)) => a.cmp(b),
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.cmp(b),
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::Integer(i), Value::Float(f)) => cmp_integer_float(*i, f.0),
+            (Value::Float(f), Value::Integer(i)) => cmp_integer_float(*i, f.0).reverse(),
+            (a, b) => a.type_rank().cmp(&b.type_rank())
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other :&Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other :&Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state :&mut H) {
+        self.type_rank().hash(state);
+
+        match self {
+            Value::String(s) => s.hash(state),
+            Value::DateTime(dt) => dt.hash(state),
+            Value::Date(d) => d.hash(state),
+            Value::Time(t) => t.hash(state),
+            Value::Duration(d) => d.hash(state),
+            Value::Integer(i) => i.hash(state),
+            // whole floats must hash the same as the equal integer
+            Value::Float(f) if is_whole(f.0) && (-I64_LIMIT..I64_LIMIT).contains(&f.0) => (f.0 as i64).hash(state),
+            Value::Float(f) => f.hash(state),
+            Value::Boolean(b) => b.hash(state),
+            Value::Empty => ()
+        }
+    }
+}
+
+/// A borrowed view of a [`Value`](enum.Value.html), returned by [`Row::at_ref`](trait.Row.html#method.at_ref), so a
+/// cell can be compared without copying its string; the other types are copied, as they don't allocate.
+///
+/// It compares, orders, and displays the same as the `Value` it views, and
+/// [`into_value`](#method.into_value) copies the string only when it's borrowed.
+#[derive(Debug, Clone)]
+pub enum ValueRef<'a> {
+    String(Cow<'a, str>),
+    DateTime(NaiveDateTime),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Duration(Duration),
+    Integer(i64),
+    Float(OrderedFloat<f64>),
+    Boolean(bool),
+    Empty
+}
+
+impl <'a> ValueRef<'a> {
+    /// Infers the type of `value` the same as [`Value::new`](enum.Value.html#method.new), borrowing it when it's a string.
+    pub fn new(value :&'a str) -> ValueRef<'a> {
+        match Value::infer(value) {
+            Some(v) => ValueRef::from(v),
+            None => ValueRef::String(Cow::Borrowed(value))
+        }
+    }
+
+    /// Parses `value` like [`Value::with_type_or_infer`](enum.Value.html#method.with_type_or_infer), borrowing it
+    /// when it's a string.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_type_or_infer(value :&'a str, value_type :Option<&ValueType>, nulls :&[String]) -> Result<ValueRef<'a>, TableError> {
+        match value_type {
+            _ if value.is_empty() || nulls.iter().any(|n| n == value) => Ok(ValueRef::Empty),
+            Some(ValueType::String) => Ok(ValueRef::String(Cow::Borrowed(value))),
+            Some(value_type) => Value::try_with_type(value, value_type).map(ValueRef::from),
+            None => Ok(ValueRef::new(value))
+        }
+    }
+
+    /// Returns the string, borrowed, or `None` for any other type.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ValueRef::String(s) => Some(s.as_ref()),
+            _ => None
+        }
+    }
+
+    /// Converts to a `Value`, copying the string if it's borrowed.
+    pub fn into_value(self) -> Value {
+        match self {
+            ValueRef::String(s) => Value::String(s.into_owned()),
+            v => v.scalar()
+        }
+    }
+
+    /// The `Value` of a type other than a string, which is copied; a string becomes an empty `Value::String`, which
+    /// doesn't allocate, and is only used for its type.
+    fn scalar(&self) -> Value {
+        match self {
+            ValueRef::String(_) => Value::String(String::new()),
+            ValueRef::DateTime(dt) => Value::DateTime(*dt),
+            ValueRef::Date(d) => Value::Date(*d),
+            ValueRef::Time(t) => Value::Time(*t),
+            ValueRef::Duration(d) => Value::Duration(*d),
+            ValueRef::Integer(i) => Value::Integer(*i),
+            ValueRef::Float(f) => Value::Float(*f),
+            ValueRef::Boolean(b) => Value::Boolean(*b),
+            ValueRef::Empty => Value::Empty
+        }
+    }
+
+    /// Compares with `other` the same as the `Value`s would compare, without copying either string.
+    pub fn cmp_value(&self, other :&Value) -> Ordering {
+        match (self, other) {
+            (ValueRef::String(a), Value::String(b)) => a.as_ref().cmp(b.as_str()),
+            (a, b) => a.scalar().cmp(b)
+        }
+    }
+}
+
+impl <'a> From<&'a Value> for ValueRef<'a> {
+    fn from(value :&'a Value) -> Self {
+        match value {
+            Value::String(s) => ValueRef::String(Cow::Borrowed(s.as_str())),
+            Value::DateTime(dt) => ValueRef::DateTime(*dt),
+            Value::Date(d) => ValueRef::Date(*d),
+            Value::Time(t) => ValueRef::Time(*t),
+            Value::Duration(d) => ValueRef::Duration(*d),
+            Value::Integer(i) => ValueRef::Integer(*i),
+            Value::Float(f) => ValueRef::Float(*f),
+            Value::Boolean(b) => ValueRef::Boolean(*b),
+            Value::Empty => ValueRef::Empty
+        }
+    }
+}
+
+impl From<Value> for ValueRef<'_> {
+    fn from(value :Value) -> Self {
+        match value {
+            Value::String(s) => ValueRef::String(Cow::Owned(s)),
+            Value::DateTime(dt) => ValueRef::DateTime(dt),
+            Value::Date(d) => ValueRef::Date(d),
+            Value::Time(t) => ValueRef::Time(t),
+            Value::Duration(d) => ValueRef::Duration(d),
+            Value::Integer(i) => ValueRef::Integer(i),
+            Value::Float(f) => ValueRef::Float(f),
+            Value::Boolean(b) => ValueRef::Boolean(b),
+            Value::Empty => ValueRef::Empty
+        }
+    }
+}
+
+impl From<ValueRef<'_>> for Value {
+    fn from(value :ValueRef<'_>) -> Self {
+        value.into_value()
+    }
+}
+
+impl PartialEq<Value> for ValueRef<'_> {
+    fn eq(&self, other :&Value) -> bool {
+        self.cmp_value(other) == Ordering::Equal
+    }
+}
+
+impl PartialEq<ValueRef<'_>> for Value {
+    fn eq(&self, other :&ValueRef<'_>) -> bool {
+        other.cmp_value(self) == Ordering::Equal
+    }
+}
+
+impl PartialOrd<Value> for ValueRef<'_> {
+    fn partial_cmp(&self, other :&Value) -> Option<Ordering> {
+        Some(self.cmp_value(other))
+    }
+}
+
+impl PartialEq for ValueRef<'_> {
+    fn eq(&self, other :&Self) -> bool {
+        match (self, other) {
+            (ValueRef::String(a), ValueRef::String(b)) => a == b,
+            (a, b) => a.scalar() == b.scalar()
+        }
+    }
+}
+
+impl Display for ValueRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match self {
+            ValueRef::String(s) => write!(f, "{}", s),
+            v => write!(f, "{}", v.scalar())
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::Value;
+    use crate::{Value, ValueRef, ValueType};
+    use alloc::borrow::Cow;
+    #[cfg(feature = "datetime-inference")]
     use dtparse::parse;
     use ordered_float::OrderedFloat;
+    use chrono::{NaiveDate, Duration};
+    use proptest::prelude::*;
+    use std::cmp::Ordering;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
 
+    #[test]
+    fn value_ref() {
+        let owned = Value::new("AAPL");
+        let borrowed = ValueRef::from(&owned);
+
+        assert!(matches!(ValueRef::new("AAPL"), ValueRef::String(Cow::Borrowed("AAPL"))));
+        assert!(matches!(ValueRef::from(Value::new("AAPL")), ValueRef::String(Cow::Owned(_))));
+        assert_eq!(Some("AAPL"), borrowed.as_str());
+        assert_eq!(owned, borrowed);
+        assert_eq!(borrowed, owned);
+        assert_eq!(owned, borrowed.clone().into_value());
+
+        // the same inference, comparison, and display as Value
+        for s in ["", "12", "-1.5", "true", "PT90S", "x y"] {
+            assert_eq!(Value::new(s), ValueRef::new(s));
+            assert_eq!(Value::new(s).to_string(), ValueRef::new(s).to_string());
+            assert_eq!(Value::new(s).value_type() == ValueType::String, ValueRef::new(s).as_str().is_some());
+        }
+
+        assert_eq!(ValueRef::Integer(1), ValueRef::Float(OrderedFloat(1.0)));
+        assert_eq!(Value::Float(OrderedFloat(1.0)), ValueRef::Integer(1));
+        assert_ne!(ValueRef::new("1"), Value::new("a"));
+        assert_eq!(Ordering::Less, ValueRef::new("a").cmp_value(&Value::new("b")));
+        assert_eq!(Ordering::Less, ValueRef::new("a").cmp_value(&Value::Integer(1)));
+        assert!(ValueRef::Empty > Value::Boolean(true));
+    }
+
+    #[cfg(feature = "datetime-inference")]
     #[test]
     fn date_time() {
         let val = Value::new("12/23/56 05:07:08PM");
@@ -269,4 +1058,215 @@ mod test {
 //
 //        assert_eq!(Value::DateTime(parse("12/23/56 05:07:08PM").unwrap().0), val);
 //    }
+
+    #[test]
+    fn try_with_type() {
+        assert_eq!(Value::Integer(12), Value::try_with_type("12", &ValueType::Integer).unwrap());
+        assert_eq!(Value::Float(OrderedFloat(1.5)), Value::try_with_type("1.5", &ValueType::Number).unwrap());
+        assert!(Value::try_with_type("1.5", &ValueType::Integer).is_err());
+        assert!(Value::try_with_type("abc", &ValueType::Float).is_err());
+        assert_eq!(Value::Float(OrderedFloat(0.0)), Value::with_type("abc", &ValueType::Float));
+        assert!(Value::try_with_type("maybe", &ValueType::Boolean).is_err());
+        assert!(Value::try_with_type("2020-13-45", &ValueType::DateFormat("%Y-%m-%d".to_string())).is_err());
+    }
+
+    #[test]
+    fn number_format() {
+        let us = ValueType::NumberFormat(",.".to_string());
+        let eu = ValueType::FloatFormat(".,".to_string());
+
+        assert_eq!(Value::Float(OrderedFloat(1234567.89)), Value::try_with_type("1,234,567.89", &us).unwrap());
+        assert_eq!(Value::Integer(-1234), Value::try_with_type("-1,234", &us).unwrap());
+        assert_eq!(Value::Float(OrderedFloat(1234.56)), Value::try_with_type("1.234,56", &eu).unwrap());
+        assert_eq!(Value::Float(OrderedFloat(0.5)), Value::try_with_type("0,5", &eu).unwrap());
+        assert_eq!(Value::Float(OrderedFloat(2.5)), Value::try_with_type("2,5", &ValueType::FloatFormat(",".to_string())).unwrap());
+        assert_eq!(Value::Integer(1234567), Value::try_with_type("1 234 567", &ValueType::IntegerFormat(" ,".to_string())).unwrap());
+
+        // separators must be between groups of 3 digits
+        assert!(Value::try_with_type("1.5", &eu).is_err());
+        assert!(Value::try_with_type("12,34.5", &us).is_err());
+        assert!(Value::try_with_type("1.234,5", &us).is_err());
+        assert!(Value::try_with_type("1,234.5", &ValueType::IntegerFormat(",.".to_string())).is_err());
+        assert!(Value::try_with_type("1", &ValueType::NumberFormat(",.;".to_string())).is_err());
+
+        assert_eq!(eu, "Float(.,)".parse::<ValueType>().unwrap());
+        assert!("Number(,,)".parse::<ValueType>().is_err());
+    }
+
+    #[test]
+    fn currency_percent() {
+        let currency = |s :&str| Value::try_with_type(s, &ValueType::Currency);
+        let percent = |s :&str| Value::try_with_type(s, &ValueType::Percent);
+
+        assert_eq!(Value::Float(OrderedFloat(1234.0)), currency("$1,234.00").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(-12.5)), currency("-€12.50").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(-12.5)), currency("€-12.50").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(-1500.0)), currency("($1,500)").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(99.0)), currency("99 £").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(7.0)), currency("7").unwrap());
+        assert!(currency("$12,34").is_err());
+        assert!(currency("USD").is_err());
+        assert!(currency("$").is_err());
+
+        assert_eq!(Value::Float(OrderedFloat(0.125)), percent("12.5%").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(-0.03)), percent("-3 %").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(12.0)), percent("1,200%").unwrap());
+        assert!(percent("12.5").is_err());
+
+        assert_eq!(ValueType::Percent, "Percent".parse::<ValueType>().unwrap());
+
+        // a space between the sign and the symbol, and the separators of the column's locale
+        let euros = ValueType::CurrencyFormat(".,".to_string());
+
+        assert_eq!(Value::Float(OrderedFloat(-12.0)), currency("- $12").unwrap());
+        assert_eq!(Value::Float(OrderedFloat(1234.56)), Value::try_with_type("1.234,56 €", &euros).unwrap());
+        assert_eq!(Value::Float(OrderedFloat(-0.125)), Value::try_with_type("-12,5 %", &ValueType::PercentFormat(",".to_string())).unwrap());
+        assert!(currency("1.234,56 €").is_err());
+        assert_eq!(euros, euros.to_string().parse::<ValueType>().unwrap());
+    }
+
+    #[test]
+    fn value_type_from_str() {
+        for t in &[ValueType::String, ValueType::DateFormat("%m/%d/%Y".to_string()), ValueType::Number, ValueType::Boolean, ValueType::Empty] {
+            assert_eq!(*t, t.to_string().parse::<ValueType>().unwrap());
+        }
+
+        assert!("Decimal".parse::<ValueType>().is_err());
+        assert!("Integer(%d)".parse::<ValueType>().is_err());
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(Value::Duration(Duration::seconds(90)), Value::with_type("PT90S", &ValueType::Duration));
+        assert_eq!(Value::Duration(Duration::days(1) + Duration::hours(2) + Duration::milliseconds(500)), Value::with_type("P1DT2H0.5S", &ValueType::Duration));
+        assert_eq!(Value::Duration(-Duration::minutes(3)), Value::with_type("-PT3M", &ValueType::Duration));
+        assert!(Value::try_with_type("PT", &ValueType::Duration).is_err());
+        assert!(Value::try_with_type("P1H", &ValueType::Duration).is_err());
+        assert!(Value::try_with_type("P1é", &ValueType::Duration).is_err());
+
+        // durations are only inferred with the feature, as codes such as "P1D" look like them
+        #[cfg(feature = "duration-inference")]
+        assert_eq!(Value::Duration(Duration::seconds(90)), Value::new("PT90S"));
+        #[cfg(not(feature = "duration-inference"))]
+        assert_eq!(Value::String("PT90S".to_string()), Value::new("PT90S"));
+        assert_eq!(Value::String("P1H".to_string()), Value::new("P1H"));
+
+        let bought = Value::DateTime(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap().and_hms_opt(9, 30, 0).unwrap());
+        let sold = Value::DateTime(NaiveDate::from_ymd_opt(2020, 1, 7).unwrap().and_hms_opt(16, 0, 0).unwrap());
+        let held = sold.try_sub(&bought).unwrap();
+
+        assert_eq!(Value::Duration(Duration::days(5) + Duration::minutes(390)), held);
+        assert_eq!(sold, bought.try_add(&held).unwrap());
+        assert_eq!(bought, sold.try_sub(&held).unwrap());
+
+        let start = Value::Date(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap());
+
+        assert_eq!(Value::Duration(Duration::days(-1)), start.try_sub(&Value::Date(NaiveDate::from_ymd_opt(2020, 1, 3).unwrap())).unwrap());
+        assert_eq!(Value::Date(NaiveDate::from_ymd_opt(2020, 1, 9).unwrap()), start.try_add(&Value::Duration(Duration::weeks(1))).unwrap());
+
+        assert!(start.try_sub(&bought).is_err());
+        assert!(Value::Integer(1).try_add(&held).is_err());
+    }
+
+    #[test]
+    fn boolean() {
+        assert_eq!(Value::Boolean(true), Value::new("TRUE"));
+        assert_eq!(Value::Boolean(false), Value::new("False"));
+        assert_eq!(Value::Integer(1), Value::new("1"));
+        assert_eq!(Value::String("yes".to_string()), Value::new("yes"));
+
+        assert_eq!(Value::Boolean(true), Value::with_type("Yes", &ValueType::Boolean));
+        assert_eq!(Value::Boolean(false), Value::with_type("0", &ValueType::Boolean));
+
+        assert_eq!("true", Value::Boolean(true).to_string());
+        assert!(Value::Boolean(false) < Value::Boolean(true));
+        assert!(Value::Integer(1) < Value::Boolean(false));
+    }
+
+    fn hash(value :&Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn any_value() -> impl Strategy<Value = Value> {
+        prop_oneof![
+            any::<String>().prop_map(Value::String),
+            (1i32..3_000_000).prop_map(|d| Value::Date(NaiveDate::from_num_days_from_ce_opt(d).unwrap())),
+            any::<i64>().prop_map(Value::Integer),
+            (-100i64..100).prop_map(Value::Integer),
+            any::<f64>().prop_map(|f| Value::Float(OrderedFloat(f))),
+            (-100i64..100).prop_map(|i| Value::Float(OrderedFloat(i as f64 / 2.0))),
+            any::<bool>().prop_map(Value::Boolean),
+            (-1_000_000_000_000i64..1_000_000_000_000, 0i64..1_000_000_000).prop_map(|(s, n)| Value::Duration(Duration::seconds(s) + Duration::nanoseconds(n))),
+            Just(Value::Empty)
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn ord_consistent(a in any_value(), b in any_value()) {
+            prop_assert_eq!(a.cmp(&b) == Ordering::Equal, a == b);
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+
+            if a == b {
+                prop_assert_eq!(hash(&a), hash(&b));
+            }
+        }
+
+        #[test]
+        fn ord_transitive(a in any_value(), b in any_value(), c in any_value()) {
+            if a <= b && b <= c {
+                prop_assert!(a <= c);
+            }
+        }
+
+        #[test]
+        fn numeric_cross_type(i in -(1i64 << 53)..(1i64 << 53), f in any::<f64>()) {
+            // integers in this range are exact as f64
+            let expected = (i as f64).partial_cmp(&f).unwrap_or(Ordering::Less);
+
+            prop_assert_eq!(expected, Value::Integer(i).cmp(&Value::Float(OrderedFloat(f))));
+            prop_assert_eq!(Value::Integer(i), Value::Float(OrderedFloat(i as f64)));
+        }
+
+        #[test]
+        fn integer_round_trip(i in any::<i64>()) {
+            let value = Value::new(Value::Integer(i).to_string().as_str());
+
+            prop_assert!(matches!(value, Value::Integer(v) if v == i));
+        }
+
+        #[test]
+        fn float_round_trip(f in any::<f64>().prop_filter("finite", |f| f.is_finite())) {
+            let value = Value::new(Value::Float(OrderedFloat(f)).to_string().as_str());
+
+            prop_assert!(matches!(value, Value::Float(v) if v.0 == f));
+        }
+
+        #[test]
+        fn duration_round_trip(s in -1_000_000_000_000i64..1_000_000_000_000, n in 0i64..1_000_000_000) {
+            let duration = Value::Duration(Duration::seconds(s) + Duration::nanoseconds(n));
+
+            prop_assert_eq!(&duration, &Value::with_type(duration.to_string().as_str(), &ValueType::Duration));
+        }
+
+        #[test]
+        fn parse_idempotent(s in "[0-9a-z .]{0,10}") {
+            let value = Value::new(s.as_str());
+
+            prop_assert_eq!(&value, &Value::new(value.to_string().as_str()));
+        }
+
+        #[test]
+        fn typed_agrees_with_inferred(i in any::<i64>(), f in any::<f64>().prop_filter("finite", |f| f.is_finite())) {
+            let i = i.to_string();
+            let f = Value::Float(OrderedFloat(f)).to_string();
+
+            prop_assert_eq!(Value::new(i.as_str()), Value::with_type(i.as_str(), &ValueType::Integer));
+            prop_assert_eq!(Value::new(i.as_str()), Value::with_type(i.as_str(), &ValueType::Number));
+            prop_assert_eq!(Value::new(f.as_str()), Value::with_type(f.as_str(), &ValueType::Float));
+            prop_assert_eq!(Value::new(f.as_str()), Value::with_type(f.as_str(), &ValueType::Number));
+        }
+    }
 }
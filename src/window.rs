@@ -0,0 +1,128 @@
+//! Window functions, such as running totals and ranks, computed within the groups of a table.
+use std::collections::HashMap;
+
+use crate::{TableOperations, TableError, Row, Value};
+
+/// Window functions computed over the rows of each group of a table, created with
+/// [`TableOperations::window`](trait.TableOperations.html#method.window).
+///
+/// Each function returns one `Value` per row of the table, in the order of the table, so the result can be added
+/// with [`add_column_values`](trait.Table.html#method.add_column_values). The rows of a group are used in the order
+/// of the table, so sort it first for time-series data.
+pub struct Window<'a, T: TableOperations> {
+    table: &'a T,
+    groups: Vec<Vec<usize>>  // positions of the rows of each group, in table order
+}
+
+impl <'a, T: TableOperations> Window<'a, T> {
+    pub(crate) fn new(table :&'a T, by :&[&str]) -> Result<Self, TableError> {
+        let positions = by.iter().map(|c| table.column_position(c)).collect::<Result<Vec<_>, _>>()?;
+        let mut group_pos :HashMap<Vec<Value>, usize> = HashMap::new();
+        let mut groups :Vec<Vec<usize>> = Vec::new();
+
+        for (i, row) in table.iter().enumerate() {
+            let key = positions.iter().map(|&p| row.try_at(p)).collect::<Result<Vec<_>, _>>()?;
+            let next = groups.len();
+            let g = *group_pos.entry(key).or_insert(next);
+
+            if g == groups.len() {
+                groups.push(Vec::new());
+            }
+
+            groups[g].push(i);
+        }
+
+        Ok(Window { table, groups })
+    }
+
+    /// The values of `column` of each group, with the positions of their rows
+    fn group_values(&self, column :&str) -> Result<Vec<Vec<(usize, Value)>>, TableError> {
+        let pos = self.table.column_position(column)?;
+        let mut values = self.table.iter().map(|row| row.try_at(pos)).collect::<Result<Vec<_>, _>>()?;
+
+        // each row is in one group, so its value is moved there
+        Ok(self.groups.iter().map(|rows| {
+            rows.iter().map(|&i| (i, std::mem::replace(&mut values[i], Value::Empty))).collect()
+        }).collect())
+    }
+
+    /// Calls `f` with the values of each group, and puts the values it returns back in table order
+    fn apply<F: Fn(&[Value]) -> Result<Vec<Value>, TableError>>(&self, column :&str, f :F) -> Result<Vec<Value>, TableError> {
+        let mut ret = vec![Value::Empty; self.table.len()];
+
+        for group in self.group_values(column)? {
+            let (rows, values) :(Vec<_>, Vec<_>) = group.into_iter().unzip();
+
+            for (i, value) in rows.into_iter().zip(f(&values)?) {
+                ret[i] = value;
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Running total of `column` within each group, skipping empty values, which stay empty; the totals are integers
+    /// when all the values of the group are.
+    pub fn cumsum(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        self.apply(column, |values| {
+            if values.iter().all(|v| matches!(v, Value::Integer(_) | Value::Empty)) {
+                let mut sum = Some(0i64);
+                let totals = values.iter().map(|v| match v {
+                    Value::Empty => Value::Empty,
+                    v => {
+                        sum = sum.and_then(|s| s.checked_add(v.as_integer()));
+                        sum.map_or(Value::Empty, Value::Integer)
+                    }
+                }).collect::<Vec<_>>();
+
+                // fall back to floats when the total overflows
+                if sum.is_some() {
+                    return Ok(totals);
+                }
+            }
+
+            let mut sum = 0.0;
+
+            values.iter().map(|v| match v {
+                Value::Empty => Ok(Value::Empty),
+                v => {
+                    sum += v.try_as_float().ok_or_else(|| {
+                        let err_str = format!("Cannot compute the running total of a non-numeric value: {}", v);
+                        TableError::new(err_str.as_str())
+                    })?;
+
+                    Ok(Value::Float(sum.into()))
+                }
+            }).collect()
+        })
+    }
+
+    /// Rank of the value of `column` within each group, from 1 for the smallest, using the ordering of `Value`; equal
+    /// values get the same rank, and the rank after them skips, like SQL's `RANK()`. Empty values aren't ranked.
+    pub fn rank(&self, column :&str) -> Result<Vec<Value>, TableError> {
+        self.apply(column, |values| {
+            let mut sorted = values.iter().filter(|v| **v != Value::Empty).collect::<Vec<_>>();
+
+            sorted.sort();
+
+            Ok(values.iter().map(|v| match v {
+                Value::Empty => Value::Empty,
+                v => Value::Integer(sorted.partition_point(|s| *s < v) as i64 + 1)
+            }).collect())
+        })
+    }
+
+    /// The value of `column` `n` rows before each row in its group, or `Value::Empty` for the first `n` rows.
+    pub fn lag(&self, column :&str, n :usize) -> Result<Vec<Value>, TableError> {
+        self.apply(column, |values| {
+            Ok((0..values.len()).map(|i| if i >= n { values[i - n].clone() } else { Value::Empty }).collect())
+        })
+    }
+
+    /// The value of `column` `n` rows after each row in its group, or `Value::Empty` for the last `n` rows.
+    pub fn lead(&self, column :&str, n :usize) -> Result<Vec<Value>, TableError> {
+        self.apply(column, |values| {
+            Ok((0..values.len()).map(|i| values.get(i.saturating_add(n)).cloned().unwrap_or(Value::Empty)).collect())
+        })
+    }
+}
@@ -0,0 +1,181 @@
+//! Options for writing tables out as CSV, so an export can match the columns a downstream system expects.
+use std::io::Write;
+
+use csv::{QuoteStyle, Terminator, Writer, WriterBuilder};
+
+use crate::TableError;
+
+/// When fields are quoted in CSV exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quoting {
+    /// Every field, including the header
+    Always,
+    /// Only fields with a delimiter, quote, or line break in them, which must be quoted
+    Necessary,
+    /// Every field that isn't a number
+    NonNumeric,
+    /// No fields, even when that makes the file ambiguous
+    Never
+}
+
+/// The line break written after each record of a CSV export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`, as Windows programs such as Excel expect
+    CrLf
+}
+
+/// How a table is written out as CSV, such as by
+/// [`to_csv_with_options`](trait.TableOperations.html#method.to_csv_with_options).
+///
+/// By default every column is written, in the table's order, after a header row, with `"` quotes around the fields
+/// that need them, quotes in fields doubled, and `\n` line endings:
+/// ```
+/// use large_table::{LineEnding, Quoting, WriteOptions};
+///
+/// let options = WriteOptions::new().columns(&["date", "close"]).header(false).quoting(Quoting::Always);
+/// let windows = WriteOptions::new().line_ending(LineEnding::CrLf).bom(true);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    columns: Option<Vec<String>>,
+    header: bool,
+    quoting: Quoting,
+    quote: u8,
+    escape: Option<u8>,
+    line_ending: LineEnding,
+    bom: bool,
+    overwrite: bool
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions { columns: None, header: true, quoting: Quoting::Necessary, quote: b'"', escape: None, line_ending: LineEnding::Lf, bom: false, overwrite: false }
+    }
+}
+
+impl WriteOptions {
+    pub fn new() -> Self {
+        WriteOptions::default()
+    }
+
+    /// Writes only `columns`, in this order, instead of every column in the table's order.
+    pub fn columns<S: ToString>(mut self, columns :&[S]) -> Self {
+        self.columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    /// Whether the header row is written.
+    pub fn header(mut self, header :bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// When fields are quoted.
+    pub fn quoting(mut self, quoting :Quoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// The character put around quoted fields.
+    pub fn quote(mut self, quote :u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// The character put before quotes in a quoted field, instead of doubling them.
+    pub fn escape(mut self, escape :u8) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    /// The line break written after each record.
+    pub fn line_ending(mut self, line_ending :LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Whether the file starts with a UTF-8 byte order mark, which Excel needs to read non-ASCII text as UTF-8.
+    pub fn bom(mut self, bom :bool) -> Self {
+        self.bom = bom;
+        self
+    }
+
+    /// Whether [`to_csv_split`](trait.TableOperations.html#method.to_csv_split) replaces the part files already in the
+    /// directory, instead of returning an error.
+    pub fn overwrite(mut self, overwrite :bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Options for files that open correctly in Excel on Windows: `\r\n` line endings after a byte order mark.
+    pub fn excel() -> Self {
+        WriteOptions::default().line_ending(LineEnding::CrLf).bom(true)
+    }
+
+    pub fn has_header(&self) -> bool {
+        self.header
+    }
+
+    pub(crate) fn can_overwrite(&self) -> bool {
+        self.overwrite
+    }
+
+    /// The position in `table_columns` of each column to write, in the order they're written.
+    pub(crate) fn positions(&self, table_columns :&[String]) -> Result<Vec<usize>, TableError> {
+        let columns = match &self.columns {
+            Some(columns) => columns,
+            None => return Ok((0..table_columns.len()).collect())
+        };
+
+        let mut positions = Vec::with_capacity(columns.len());
+
+        for column in columns {
+            let pos = table_columns.iter().position(|c| c == column).ok_or_else(|| {
+                let err_str = format!("Column not found: {}", column);
+                TableError::new(err_str.as_str())
+            })?;
+
+            if positions.contains(&pos) {
+                let err_str = format!("Column written more than once: {}", column);
+                return Err(TableError::new(err_str.as_str()));
+            }
+
+            positions.push(pos);
+        }
+
+        Ok(positions)
+    }
+
+    /// A CSV writer for `writer`, configured by the options.
+    pub(crate) fn writer<W: Write>(&self, writer :W) -> Writer<W> {
+        let quote_style = match self.quoting {
+            Quoting::Always => QuoteStyle::Always,
+            Quoting::Necessary => QuoteStyle::Necessary,
+            Quoting::NonNumeric => QuoteStyle::NonNumeric,
+            Quoting::Never => QuoteStyle::Never
+        };
+
+        let terminator = match self.line_ending {
+            LineEnding::Lf => Terminator::Any(b'\n'),
+            LineEnding::CrLf => Terminator::CRLF
+        };
+
+        let mut builder = WriterBuilder::new();
+
+        builder.quote_style(quote_style).quote(self.quote).terminator(terminator);
+
+        if let Some(escape) = self.escape {
+            builder.escape(escape).double_quote(false);
+        }
+
+        builder.from_writer(writer)
+    }
+
+    /// The bytes written at the start of a file, before the header: the byte order mark, if there is one.
+    pub(crate) fn preamble(&self) -> &'static [u8] {
+        if self.bom { b"\xEF\xBB\xBF" } else { b"" }
+    }
+}
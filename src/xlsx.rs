@@ -0,0 +1,60 @@
+//! Reading sheets of Excel (xlsx) workbooks, enabled with the `xlsx` feature.
+//!
+//! Cells keep the type Excel gives them, instead of being inferred from their text, so a string such as `007` stays a
+//! string. Whole numbers are integers, and date-formatted cells are dates, times, date-times, or durations, depending
+//! on their format and value. Error cells, such as `#DIV/0!`, are read as their text.
+use std::fs::File;
+use std::io::{BufReader, Error as IOError, ErrorKind};
+use std::path::Path;
+
+use calamine::{open_workbook, Data, Reader, Xlsx, XlsxError};
+
+use crate::Value;
+
+/// Converts a cell to a value
+fn cell_value(cell :&Data) -> Value {
+    match cell {
+        Data::Empty => Value::Empty,
+        Data::Int(i) => Value::Integer(*i),
+        Data::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Value::Integer(*f as i64),
+        Data::Float(f) => Value::Float((*f).into()),
+        Data::String(s) => Value::String(s.clone()),
+        Data::Bool(b) => Value::Boolean(*b),
+        Data::DateTime(dt) if dt.is_duration() => dt.as_duration().map(Value::Duration).unwrap_or(Value::Empty),
+        Data::DateTime(dt) => match dt.as_datetime() {
+            Some(date_time) if dt.as_f64() < 1.0 => Value::Time(date_time.time()),
+            Some(date_time) if dt.as_f64().fract() == 0.0 => Value::Date(date_time.date()),
+            Some(date_time) => Value::DateTime(date_time),
+            None => Value::Empty
+        },
+        Data::DateTimeIso(s) | Data::DurationIso(s) => Value::new(s),
+        Data::Error(e) => Value::String(e.to_string())
+    }
+}
+
+/// Reads the columns, from the first row, and the rest of the rows of a sheet.
+pub(crate) fn read_sheet<P: AsRef<Path>>(path :P, sheet :&str) -> Result<(Vec<String>, Vec<Vec<Value>>), IOError> {
+    let xlsx_err = |e :XlsxError| IOError::new(ErrorKind::InvalidData, e.to_string());
+    let mut workbook :Xlsx<BufReader<File>> = open_workbook(path).map_err(xlsx_err)?;
+
+    if !workbook.sheet_names().iter().any(|s| s == sheet) {
+        let err_str = format!("Sheet not found: {}", sheet);
+        return Err(IOError::new(ErrorKind::NotFound, err_str.as_str()));
+    }
+
+    let range = workbook.worksheet_range(sheet).map_err(xlsx_err)?;
+    let mut rows = range.rows();
+
+    let columns = match rows.next() {
+        Some(header) => header.iter().enumerate().map(|(i, cell)| match cell {
+            Data::Empty => {
+                let err_str = format!("Column {} has no name in sheet {}", i + 1, sheet);
+                Err(IOError::new(ErrorKind::InvalidData, err_str.as_str()))
+            },
+            cell => Ok(cell.to_string())
+        }).collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new()
+    };
+
+    Ok((columns, rows.map(|row| row.iter().map(cell_value).collect()).collect()))
+}